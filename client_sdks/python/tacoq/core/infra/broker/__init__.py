@@ -3,11 +3,12 @@ from tacoq.core.infra.broker.client import (
     WorkerBrokerClient,
     BaseBrokerClient,
 )
-from tacoq.core.infra.broker.config import BrokerConfig
+from tacoq.core.infra.broker.config import BrokerConfig, QueueType
 
 __all__ = [
     "PublisherBrokerClient",
     "WorkerBrokerClient",
     "BaseBrokerClient",
     "BrokerConfig",
+    "QueueType",
 ]
@@ -5,8 +5,8 @@ refer to the `PublisherClient` and `WorkerClient` to publish tasks and consume
 results, respectively.
 """
 
-from typing import AsyncGenerator, Optional, Self
-from aio_pika import Message, connect_robust
+from typing import Any, AsyncGenerator, Optional, Self
+from aio_pika import DeliveryMode, Message, connect_robust
 from pydantic import BaseModel
 
 from aio_pika.abc import (
@@ -17,11 +17,15 @@ from aio_pika.abc import (
     AbstractIncomingMessage,
 )
 
-from tacoq.core.infra.broker.config import BrokerConfig
+from tacoq.core.infra.broker.config import BrokerConfig, QueueType
 from tacoq.core.models import (
+    TaskAcknowledgedUpdate,
     TaskAssignmentUpdate,
     TaskRunningUpdate,
     TaskCompletedUpdate,
+    TaskBatchCompletedUpdate,
+    WorkerHeartbeatUpdate,
+    WorkerRegistrationUpdate,
 )
 
 # =========================================
@@ -34,15 +38,28 @@ TASK_EXCHANGE = "tacoq_task_exchange"
 """ Single exchange for all task-related messages. """
 
 RELAY_QUEUE = "tacoq_relay_queue"
-""" Queue for relay to receive ALL tasks. """
+""" Queue for relay to receive task events. """
 
 RELAY_ROUTING_KEY = "#"  # Wildcard to receive all messages
-""" Relay receives all messages. """
+""" Relay receives all messages, unless it routes some to dedicated queues. """
 
 WORKER_ROUTING_KEY = "tasks.{worker_kind}"
 """ Workers only receive tasks for their kind. """
 
 
+def queue_arguments(queue_type: QueueType) -> dict[str, Any]:
+    """The arguments every TacoQ queue is declared with. Quorum queues don't
+    accept `x-max-priority`, so only classic queues deliver urgent tasks first.
+
+    ### Arguments:
+    - queue_type: The kind of queue to declare.
+    """
+
+    if queue_type == QueueType.QUORUM:
+        return {"x-queue-type": QueueType.QUORUM.value}
+    return {"x-max-priority": 255}
+
+
 # =========================================
 # Errors
 # =========================================
@@ -101,6 +118,14 @@ class BaseBrokerClient(BaseModel):
     _task_exchange: Optional[AbstractExchange] = None
     """ The exchange for task assignments. """
 
+    @property
+    def _delivery_mode(self: Self) -> DeliveryMode:
+        """The delivery mode of published tasks and task events."""
+
+        if self.config.persistent_messages:
+            return DeliveryMode.PERSISTENT
+        return DeliveryMode.NOT_PERSISTENT
+
     async def connect(self: Self) -> None:
         """Establish connection to RabbitMQ server and setup channel."""
 
@@ -120,10 +145,12 @@ class BaseBrokerClient(BaseModel):
         relay_queue = await self._channel.declare_queue(
             RELAY_QUEUE,
             durable=True,
-            arguments={"x-max-priority": 255},
+            arguments=queue_arguments(self.config.queue_type),
         )
 
-        await relay_queue.bind(self._task_exchange, routing_key=RELAY_ROUTING_KEY)
+        # A relay routing events to dedicated queues binds its queues itself
+        if self.config.bind_relay_queue:
+            await relay_queue.bind(self._task_exchange, routing_key=RELAY_ROUTING_KEY)
 
     async def disconnect(self: Self) -> None:
         """Close the RabbitMQ connection.
@@ -172,7 +199,7 @@ class PublisherBrokerClient(BaseBrokerClient):
         worker_queue = await self._channel.declare_queue(
             worker_kind,
             durable=True,  # Survive broker restarts
-            arguments={"x-max-priority": 255},
+            arguments=queue_arguments(self.config.queue_type),
         )
         await worker_queue.bind(
             self._task_exchange,
@@ -184,7 +211,7 @@ class PublisherBrokerClient(BaseBrokerClient):
         clone_queue = await self._channel.declare_queue(
             f"{worker_kind}_cloned",
             durable=True,
-            arguments={"x-max-priority": 255},
+            arguments=queue_arguments(self.config.queue_type),
         )
         await clone_queue.bind(
             self._task_exchange,
@@ -212,7 +239,7 @@ class PublisherBrokerClient(BaseBrokerClient):
         queue = await self._channel.declare_queue(
             worker_kind,
             durable=True,
-            arguments={"x-max-priority": 255},
+            arguments=queue_arguments(self.config.queue_type),
         )
 
         # Purge the queue
@@ -237,6 +264,7 @@ class PublisherBrokerClient(BaseBrokerClient):
             headers={"message_type": "TaskAssignment"},
             body=task_assignment_update.avro_bytes,
             priority=task_assignment_update.priority,
+            delivery_mode=self._delivery_mode,
         )
         routing_key = WORKER_ROUTING_KEY.format(
             worker_kind=task_assignment_update.worker_kind
@@ -287,7 +315,7 @@ class WorkerBrokerClient(BaseBrokerClient):
         self._queue = await self._channel.declare_queue(
             self.worker_kind,
             durable=True,
-            arguments={"x-max-priority": 255},
+            arguments=queue_arguments(self.config.queue_type),
         )
         await self._queue.bind(self._task_exchange, routing_key=routing_key)
 
@@ -308,6 +336,28 @@ class WorkerBrokerClient(BaseBrokerClient):
                 task_assignment = TaskAssignmentUpdate.from_avro_bytes(message.body)
                 yield (task_assignment, message)
 
+    async def publish_task_acknowledged(
+        self: Self, task_acknowledged_update: TaskAcknowledgedUpdate
+    ) -> None:
+        """Publish a task acknowledged update to the shared results queue.
+
+        ### Arguments:
+        - task_acknowledged_update: The task acknowledged update to publish.
+        """
+
+        if self._task_exchange is None:
+            raise ExchangeNotDeclaredError(
+                "Tried to publish task acknowledged update, but exchange was not declared."
+            )
+
+        message = Message(
+            headers={"message_type": "TaskAcknowledged"},
+            body=task_acknowledged_update.avro_bytes,
+            delivery_mode=self._delivery_mode,
+        )
+
+        await self._task_exchange.publish(message, routing_key=TASK_EXCHANGE)
+
     async def publish_task_running(
         self: Self, task_running_update: TaskRunningUpdate
     ) -> None:
@@ -325,6 +375,7 @@ class WorkerBrokerClient(BaseBrokerClient):
         message = Message(
             headers={"message_type": "TaskRunning"},
             body=task_running_update.avro_bytes,
+            delivery_mode=self._delivery_mode,
         )
 
         await self._task_exchange.publish(message, routing_key=TASK_EXCHANGE)
@@ -346,6 +397,75 @@ class WorkerBrokerClient(BaseBrokerClient):
         message = Message(
             headers={"message_type": "TaskCompleted"},
             body=task_completed_update.avro_bytes,
+            delivery_mode=self._delivery_mode,
+        )
+
+        await self._task_exchange.publish(message, routing_key=TASK_EXCHANGE)
+
+    async def publish_task_batch_completed(
+        self: Self, task_batch_completed_update: TaskBatchCompletedUpdate
+    ) -> None:
+        """Publish several task completed updates to the shared results queue
+        in a single message.
+
+        ### Arguments:
+        - task_batch_completed_update: The batch of task completed updates to publish.
+        """
+
+        if self._task_exchange is None:
+            raise ExchangeNotDeclaredError(
+                "Tried to publish task batch completed update, but exchange was not declared."
+            )
+
+        message = Message(
+            headers={"message_type": "TaskBatchCompleted"},
+            body=task_batch_completed_update.avro_bytes,
+            delivery_mode=self._delivery_mode,
+        )
+
+        await self._task_exchange.publish(message, routing_key=TASK_EXCHANGE)
+
+    async def publish_worker_registration(
+        self: Self, worker_registration_update: WorkerRegistrationUpdate
+    ) -> None:
+        """Announce this worker and the task kinds it handles to the relay.
+
+        ### Arguments:
+        - worker_registration_update: The worker registration to publish.
+        """
+
+        if self._task_exchange is None:
+            raise ExchangeNotDeclaredError(
+                "Tried to publish worker registration, but exchange was not declared."
+            )
+
+        message = Message(
+            headers={"message_type": "WorkerRegistration"},
+            body=worker_registration_update.avro_bytes,
+            delivery_mode=self._delivery_mode,
+        )
+
+        await self._task_exchange.publish(message, routing_key=TASK_EXCHANGE)
+
+    async def publish_worker_heartbeat(
+        self: Self, worker_heartbeat_update: WorkerHeartbeatUpdate
+    ) -> None:
+        """Let the relay know this worker is still alive.
+
+        ### Arguments:
+        - worker_heartbeat_update: The worker heartbeat to publish.
+        """
+
+        if self._task_exchange is None:
+            raise ExchangeNotDeclaredError(
+                "Tried to publish worker heartbeat, but exchange was not declared."
+            )
+
+        message = Message(
+            headers={"message_type": "WorkerHeartbeat"},
+            body=worker_heartbeat_update.avro_bytes,
+            # A heartbeat is worthless once the broker restarted
+            delivery_mode=DeliveryMode.NOT_PERSISTENT,
         )
 
         await self._task_exchange.publish(message, routing_key=TASK_EXCHANGE)
@@ -4,9 +4,23 @@ This configuration is used to connect to the broker and declare the necessary
 queues and exchanges.
 """
 
+from enum import Enum
+
 from pydantic import BaseModel
 
 
+class QueueType(str, Enum):
+    """Kind of queue the broker clients declare their queues as. It must match
+    the queue type the relay is configured with, since the broker refuses to
+    declare an existing queue with different arguments."""
+
+    CLASSIC = "classic"
+    """ Queue stored on a single node, supporting priorities. """
+
+    QUORUM = "quorum"
+    """ Queue replicated across the cluster, without priorities. """
+
+
 class BrokerConfig(BaseModel):
     """Configuration for a RabbitMQ broker.
 
@@ -15,6 +29,10 @@ class BrokerConfig(BaseModel):
     - confirm_delivery: Whether to confirm delivery of messages using [publisher confirms](https://www.rabbitmq.com/docs/confirms#publisher-confirms).
     - test_mode: Whether the worker is running in a test environment. If it is, certain
       dangerous operations are allowed, such as deleting all tasks in the queue.
+    - bind_relay_queue: Whether the relay queue is bound to every task event.
+    - queue_type: The kind of queue the relay and worker queues are declared as.
+    - persistent_messages: Whether tasks and task events are published as
+      persistent messages, which the broker keeps across restarts.
 
     ### Usage:
     ```python
@@ -31,3 +49,20 @@ class BrokerConfig(BaseModel):
     test_mode: bool = False
     """ Whether the worker is running in a test environment. If it is, certain 
     dangerous operations are allowed, such as deleting all tasks in the queue. """
+
+    bind_relay_queue: bool = True
+    """ Whether the relay queue is bound to every task event, so the relay
+    receives the events published before it first started. Disable it when the
+    relay routes events to dedicated queues, since it binds its queues itself
+    then and the catch-all binding would undo that. """
+
+    queue_type: QueueType = QueueType.CLASSIC
+    """ The kind of queue the relay and worker queues are declared as. It must
+    match `TACOQ_RELAY_QUEUE_TYPE` of the relay. Tasks keep their priority
+    on classic queues only. """
+
+    persistent_messages: bool = True
+    """ Whether tasks and task events are published as persistent messages,
+    which the broker keeps across restarts. Transient messages are faster to
+    publish but lost if the broker restarts before they are consumed. Matches
+    `TACOQ_RELAY_PUBLISH_PERSISTENT` of the relay. """
@@ -1,8 +1,12 @@
 from tacoq.core.models.task import Task, TaskRawInput, TaskRawOutput, TaskStatus
 from tacoq.core.models.exception import SerializedException
+from tacoq.core.models.task_acknowledged_update import TaskAcknowledgedUpdate
 from tacoq.core.models.task_assignment_update import TaskAssignmentUpdate
+from tacoq.core.models.task_batch_completed_update import TaskBatchCompletedUpdate
 from tacoq.core.models.task_completed_update import TaskCompletedUpdate
 from tacoq.core.models.task_running_update import TaskRunningUpdate
+from tacoq.core.models.worker_heartbeat_update import WorkerHeartbeatUpdate
+from tacoq.core.models.worker_registration_update import WorkerRegistrationUpdate
 
 __all__ = [
     "Task",
@@ -10,7 +14,11 @@ __all__ = [
     "TaskRawOutput",
     "TaskStatus",
     "SerializedException",
+    "TaskAcknowledgedUpdate",
     "TaskAssignmentUpdate",
+    "TaskBatchCompletedUpdate",
     "TaskCompletedUpdate",
     "TaskRunningUpdate",
+    "WorkerHeartbeatUpdate",
+    "WorkerRegistrationUpdate",
 ]
@@ -64,12 +64,16 @@ class Task(AvroSerializableBaseModel):
     - created_at: The time the task was created at.
     - started_at: The time the task was started at.
     - completed_at: The time the task was completed at.
+    - scheduled_for: The time the task may start at the earliest, if it was scheduled.
+    - acknowledged_at: The time a worker received the task, before starting it.
     - input_data: The input data of the task.
+    - input_content_type: The MIME type of the input data, if the publisher declared one.
     - output_data: The data output by the task.
+    - output_content_type: The MIME type of the output data, if the worker declared one.
     - is_error: Whether the task failed. Used primarly for the dead letter queue.
     - status: The current status of the task at the time of retrieval. See `TaskStatus` for more details.
     - priority: The priority of the task, ranging from 0 (lowest) to 255 (highest). For best practices on using the priority, see RabbitMQ's.
-    - ttl_duration: An optional determining for how long a task should stay alive after it has been completed.
+    - ttl_duration: An optional determining for how long a task should stay alive after it has been completed, in seconds.
     - otel_ctx_carrier: The OpenTelemetry context carrier for the task.
 
     ### Usage:
@@ -100,6 +104,12 @@ class Task(AvroSerializableBaseModel):
     completed_at: Optional[datetime] = Field(default=None)
     """ The time the task was completed at. """
 
+    scheduled_for: Optional[datetime] = Field(default=None)
+    """ The time the task may start at the earliest, if it was scheduled. """
+
+    acknowledged_at: Optional[datetime] = Field(default=None)
+    """ The time a worker received the task, before starting it. """
+
     updated_at: datetime = Field(default_factory=lambda: datetime.now())
     """ The last time the task object was updated in the database. """
 
@@ -111,10 +121,16 @@ class Task(AvroSerializableBaseModel):
     input_data: Optional[TaskRawInput] = Field(default=None)
     """ The raw input data of the task. To decode it, use the `get_decoded_input_data` method. """
 
+    input_content_type: Optional[str] = Field(default=None)
+    """ The MIME type of the input data, if the publisher declared one. """
+
     output_data: Optional[TaskRawOutput] = Field(default=None)
     """ The raw output data of the task. To decode it, use the `get_decoded_output_data` method. """
 
-    is_error: Optional[int] = Field(default=None)
+    output_content_type: Optional[str] = Field(default=None)
+    """ The MIME type of the output data, if the worker declared one. """
+
+    is_error: Optional[bool] = Field(default=None)
     """ Whether the task failed. Used primarly for the dead letter queue."""
 
     # Metadata
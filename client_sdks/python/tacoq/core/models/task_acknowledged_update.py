@@ -0,0 +1,24 @@
+from datetime import datetime
+from uuid import UUID
+
+from pydantic import Field
+
+from tacoq.core.models.avro_serializable_base_model import (
+    AvroSerializableBaseModel,
+    avro_schema_path,
+)
+
+
+@avro_schema_path("schemas/avro/task_acknowledged_update.json")
+class TaskAcknowledgedUpdate(AvroSerializableBaseModel):
+    """Update of a task being received by a worker, before it starts running."""
+
+    id: UUID
+    """The unique ID of the task. Generated by the client so that it can be 
+    communicated to the relay and the workers directly."""
+
+    acknowledged_at: datetime = Field(default_factory=lambda: datetime.now())
+    """ The time the worker received the task at. """
+
+    update_type: str = Field(default="Acknowledged")
+    """ The type of update. """
@@ -1,4 +1,5 @@
 from datetime import datetime
+from typing import Optional
 from uuid import UUID
 
 from pydantic import Field
@@ -37,6 +38,13 @@ class TaskAssignmentUpdate(AvroSerializableBaseModel):
     otel_ctx_carrier: dict[str, str]
     """ The OpenTelemetry context carrier for the task. """
 
+    input_content_type: Optional[str] = Field(default=None)
+    """ The MIME type of the input data (e.g. `application/json`), if known. """
+
+    scheduled_for: Optional[datetime] = Field(default=None)
+    """ The time the task may start at the earliest. Only tasks submitted
+    through the relay API are held back until then. """
+
     id: UUID
     """The unique ID of the task. Generated by the client so that it can be 
     communicated to the relay and the workers directly."""
@@ -0,0 +1,18 @@
+from pydantic import Field
+
+from tacoq.core.models.avro_serializable_base_model import (
+    AvroSerializableBaseModel,
+    avro_schema_path,
+)
+from tacoq.core.models.task_completed_update import TaskCompletedUpdate
+
+
+@avro_schema_path("schemas/avro/task_batch_completed_update.json")
+class TaskBatchCompletedUpdate(AvroSerializableBaseModel):
+    """Several task completions sent by a worker in a single message."""
+
+    completions: list[TaskCompletedUpdate] = Field()
+    """ The completed tasks, applied by the relay in order. """
+
+    update_type: str = Field(default="BatchCompleted")
+    """ The type of update. """
@@ -1,5 +1,6 @@
 import uuid
 from datetime import datetime
+from typing import Optional
 from uuid import UUID
 
 from pydantic import Field
@@ -24,8 +25,11 @@ class TaskCompletedUpdate(AvroSerializableBaseModel):
     output_data: TaskRawOutput = Field()
     """ The data output by the task."""
 
-    is_error: int = Field()
+    is_error: bool = Field()
     """ Whether the task failed. Used primarly for the dead letter queue."""
 
+    output_content_type: Optional[str] = Field(default=None)
+    """ The MIME type of the output data (e.g. `image/png`), if known. """
+
     update_type: str = Field(default="Completed")
     """ The type of update. """
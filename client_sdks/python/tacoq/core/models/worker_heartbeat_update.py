@@ -0,0 +1,26 @@
+from datetime import datetime
+
+from pydantic import Field
+
+from tacoq.core.models.avro_serializable_base_model import (
+    AvroSerializableBaseModel,
+    avro_schema_path,
+)
+
+
+@avro_schema_path("schemas/avro/worker_heartbeat_update.json")
+class WorkerHeartbeatUpdate(AvroSerializableBaseModel):
+    """Published periodically by a running worker, so the relay knows it is
+    alive even while it isn't running any task."""
+
+    worker_name: str
+    """ The name of the worker. """
+
+    worker_kind: str
+    """ The kind of the worker. """
+
+    heartbeat_at: datetime = Field(default_factory=lambda: datetime.now())
+    """ The time the heartbeat was sent at. """
+
+    update_type: str = Field(default="Heartbeat")
+    """ The type of update. """
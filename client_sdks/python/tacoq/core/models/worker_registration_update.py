@@ -0,0 +1,32 @@
+from datetime import datetime
+
+from pydantic import Field
+
+from tacoq.core.models.avro_serializable_base_model import (
+    AvroSerializableBaseModel,
+    avro_schema_path,
+)
+
+
+@avro_schema_path("schemas/avro/worker_registration_update.json")
+class WorkerRegistrationUpdate(AvroSerializableBaseModel):
+    """Announcement published by a worker when it starts, so the relay knows
+    about it before any task is assigned."""
+
+    worker_name: str
+    """ The name of the worker. """
+
+    worker_kind: str
+    """ The kind of the worker. """
+
+    task_kinds: list[str]
+    """ The task kinds the worker has handlers for. """
+
+    version: str
+    """ The version of the TacoQ SDK the worker runs. """
+
+    registered_at: datetime
+    """ The time the worker registered at. """
+
+    update_type: str = Field(default="Registration")
+    """ The type of update. """
@@ -72,6 +72,7 @@ class PublisherClient(BaseModel):
         priority: int = 0,
         ttl_duration: int = 60 * 60 * 24 * 7,
         otel_ctx_carrier: Optional[Dict[str, str]] = None,
+        input_content_type: Optional[str] = None,
     ) -> Task:
         """Publish a task to the broker.
 
@@ -87,12 +88,14 @@ class PublisherClient(BaseModel):
         - task_id: The ID of the task. If not provided, a new UUID will be
           generated.
         - priority: The priority of the task.
-        - ttl_duration: For how long the task should live after its done.
+        - ttl_duration: For how long the task should live after its done, in seconds.
           Default value of 7 days.
         - otel_ctx_carrier: The OpenTelemetry context carrier to be added to
           the task. This will track the entire task's lifecycle. If none is
           provided, a new one will be created. If one is provided, the context
           is expected to already be injected.
+        - input_content_type: The MIME type of the encoded input data. Inputs
+          declared as `application/json` can be searched for in the relay.
 
         ### Returns
         - `Task`: The task instance.
@@ -168,6 +171,7 @@ class PublisherClient(BaseModel):
                 ttl_duration=ttl_duration,
                 otel_ctx_carrier=otel_ctx_carrier,
                 created_at=created_at,
+                input_content_type=input_content_type,
             )
 
             # Set the attributes of the span so it can be identified
@@ -8,6 +8,7 @@ import asyncio
 import inspect
 import json
 from datetime import datetime
+from importlib.metadata import PackageNotFoundError, version
 from typing import (
     Any,
     Awaitable,
@@ -36,16 +37,28 @@ from tacoq.core.encoding import Decoder, Encoder
 from tacoq.core.infra.broker import WorkerBrokerClient
 from tacoq.core.models import (
     SerializedException,
+    TaskAcknowledgedUpdate,
     TaskAssignmentUpdate,
     TaskCompletedUpdate,
     TaskRawInput,
     TaskRawOutput,
     TaskRunningUpdate,
+    WorkerHeartbeatUpdate,
+    WorkerRegistrationUpdate,
 )
 from tacoq.core.telemetry import LoggerManager, TracerManager
 from tacoq.core.telemetry import StructuredMessage as _
 from tacoq.worker.config import WorkerApplicationConfig
 
+
+def _sdk_version() -> str:
+    """The installed version of the TacoQ SDK, announced on registration."""
+    try:
+        return version("tacoq")
+    except PackageNotFoundError:
+        return "unknown"
+
+
 # =========================================
 # Errors
 # =========================================
@@ -194,6 +207,9 @@ class WorkerApplication(BaseModel):
     _active_tasks: set[asyncio.Task[None]] = set()
     """ The set of active tasks that this worker application is processing. """
 
+    _heartbeat_task: Optional[asyncio.Task[None]] = None
+    """ The background task publishing the heartbeats of this worker. """
+
     def __init__(self: Self, config: WorkerApplicationConfig) -> None:
         super().__init__(config=config)
         self._registered_tasks = {}  # This must be set here so that the dictionary is per-instance
@@ -392,6 +408,11 @@ class WorkerApplication(BaseModel):
                 await message.nack()
                 return
 
+            # Let the relay know the task reached a worker that can run it
+            await self._broker_client.publish_task_acknowledged(
+                TaskAcknowledgedUpdate(id=task_assignment_update.id)
+            )
+
             # Task Execution ================================
             # TODO - Improve exception serialization
 
@@ -512,11 +533,63 @@ class WorkerApplication(BaseModel):
             )
             raise e
 
+    async def _register(self: Self) -> None:
+        """Announce this worker and its registered task kinds to the relay."""
+
+        if self._broker_client is None:
+            raise RuntimeError("Broker client not initialized")
+
+        await self._broker_client.publish_worker_registration(
+            WorkerRegistrationUpdate(
+                worker_name=self.config.name,
+                worker_kind=self.config.kind,
+                task_kinds=list(self._registered_tasks.keys()),
+                version=_sdk_version(),
+                registered_at=datetime.now(),
+            )
+        )
+
+    async def _send_heartbeats(self: Self) -> None:
+        """Publish a heartbeat every `heartbeat_interval` seconds until the
+        worker shuts down. Failed heartbeats are logged and retried on the next
+        interval, since a missed heartbeat only matters if they keep failing."""
+
+        if self._broker_client is None:
+            raise RuntimeError("Broker client not initialized")
+
+        logger = LoggerManager.get_logger()
+        while not self._shutdown_event.is_set():
+            try:
+                await self._broker_client.publish_worker_heartbeat(
+                    WorkerHeartbeatUpdate(
+                        worker_name=self.config.name,
+                        worker_kind=self.config.kind,
+                    )
+                )
+            except Exception as e:
+                logger.warning(
+                    _(
+                        message="Error publishing worker heartbeat",
+                        attributes={"error": str(e)},
+                    )
+                )
+
+            try:
+                await asyncio.wait_for(
+                    self._shutdown_event.wait(),
+                    timeout=self.config.heartbeat_interval,
+                )
+            except asyncio.TimeoutError:
+                continue
+
     async def entrypoint(self: Self) -> None:
         """Initialize and start listening for tasks."""
 
         # Initialize the broker client
         await self._init_broker_client()
+        await self._register()
+        if self.config.heartbeat_interval > 0:
+            self._heartbeat_task = asyncio.create_task(self._send_heartbeats())
 
         logger = LoggerManager.get_logger()
         logger.info(
@@ -644,6 +717,16 @@ class WorkerApplication(BaseModel):
 
         await asyncio.gather(*self._active_tasks)
 
+        # Stop the heartbeats before the broker connection goes away
+
+        if self._heartbeat_task is not None:
+            self._heartbeat_task.cancel()
+            try:
+                await self._heartbeat_task
+            except asyncio.CancelledError:
+                pass
+            self._heartbeat_task = None
+
         # Disconnect from broker
 
         logger.info(
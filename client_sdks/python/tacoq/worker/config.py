@@ -17,6 +17,9 @@ class WorkerApplicationConfig(BaseModel):
       This also dictates how many asynchronous tasks can be executed at once.
       This purposefully does not have a default as it is *very* important to set
       it correctly.
+    - heartbeat_interval: Seconds between two heartbeats, which tell the relay
+      the worker is alive while it isn't running any task. Keep it well below
+      the stale worker threshold of the relay. Set it to 0 to disable them.
 
     ### Usage
     ```python
@@ -54,3 +57,6 @@ class WorkerApplicationConfig(BaseModel):
     broker_prefetch_count: int
     """ The number of tasks to prefetch from the broker. This also dictates how 
     many asynchronous tasks can be executed at once. """
+
+    heartbeat_interval: float = 30.0
+    """ Seconds between two heartbeats. Heartbeats are disabled when 0. """
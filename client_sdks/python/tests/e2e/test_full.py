@@ -271,7 +271,7 @@ async def test_delayed_instrumented_task_e2e(
         assert completed_task.status == TaskStatus.COMPLETED, (
             f"Task {task.id} is not completed"
         )
-        assert completed_task.is_error == 0
+        assert completed_task.is_error is False
         assert completed_task.output_data is not None
 
         assert (
@@ -356,7 +356,7 @@ async def test_error_task_e2e(
         assert task_status.status == TaskStatus.COMPLETED, (
             f"Task {task.id} is not completed"
         )
-        assert task_status.is_error == 1, f"Task {task.id} is not an error"
+        assert task_status.is_error is True, f"Task {task.id} is not an error"
         assert task_status.output_data is not None
         assert "Task failed successfully" in task_status.output_data.decode(
             "utf-8"
@@ -34,7 +34,7 @@ async def test_get_task_success(mock_relay_client: RelayClient):
         input_data=json.dumps({"foo": "bar"}).encode("utf-8"),
         priority=5,
         output_data=None,
-        is_error=0,
+        is_error=False,
         started_at=None,
         completed_at=None,
         executed_by=None,
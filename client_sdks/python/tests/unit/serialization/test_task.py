@@ -17,7 +17,7 @@ def test_task_avro_serde():
         executed_by="test_worker",
         input_data=b"test input",
         output_data=b"test output",
-        is_error=0,
+        is_error=False,
         priority=128,
         ttl_duration=3600,
         otel_ctx_carrier={"trace_id": "123"},
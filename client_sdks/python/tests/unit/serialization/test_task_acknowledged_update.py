@@ -0,0 +1,26 @@
+import uuid
+from datetime import datetime, timezone
+
+import pytest
+from tacoq.core.models.task_acknowledged_update import TaskAcknowledgedUpdate
+
+
+@pytest.mark.unit
+def test_task_acknowledged_update_avro_serde():
+    update = TaskAcknowledgedUpdate(
+        id=uuid.uuid4(),
+        acknowledged_at=datetime.now(timezone.utc),
+    )
+
+    # Convert to Avro bytes
+    avro_bytes = update.avro_bytes
+
+    # Convert back from Avro bytes
+    deserialized = TaskAcknowledgedUpdate.from_avro_bytes(avro_bytes)
+
+    # Check all fields match
+    assert update.id == deserialized.id
+    assert (
+        update.acknowledged_at.timestamp() == deserialized.acknowledged_at.timestamp()
+    )
+    assert update.update_type == deserialized.update_type
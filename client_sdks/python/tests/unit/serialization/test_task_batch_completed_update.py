@@ -0,0 +1,43 @@
+import uuid
+from datetime import datetime, timezone
+
+import pytest
+from tacoq.core.models.task_batch_completed_update import TaskBatchCompletedUpdate
+from tacoq.core.models.task_completed_update import TaskCompletedUpdate
+
+
+@pytest.mark.unit
+def test_task_batch_completed_update_avro_serde():
+    update = TaskBatchCompletedUpdate(
+        completions=[
+            TaskCompletedUpdate(
+                id=uuid.uuid4(),
+                completed_at=datetime.now(timezone.utc),
+                output_data=b"first",
+                is_error=False,
+            ),
+            TaskCompletedUpdate(
+                id=uuid.uuid4(),
+                completed_at=datetime.now(timezone.utc),
+                output_data=b"second",
+                is_error=True,
+                output_content_type="text/plain",
+            ),
+        ]
+    )
+
+    # Convert to Avro bytes
+    avro_bytes = update.avro_bytes
+
+    # Convert back from Avro bytes
+    deserialized = TaskBatchCompletedUpdate.from_avro_bytes(avro_bytes)
+
+    # Check all fields match
+    assert update.update_type == deserialized.update_type
+    assert len(update.completions) == len(deserialized.completions)
+    for completion, other in zip(update.completions, deserialized.completions):
+        assert completion.id == other.id
+        assert completion.completed_at.timestamp() == other.completed_at.timestamp()
+        assert completion.output_data == other.output_data
+        assert completion.is_error == other.is_error
+        assert completion.output_content_type == other.output_content_type
@@ -11,7 +11,7 @@ def test_task_completed_update_avro_serde():
         id=uuid.uuid4(),
         completed_at=datetime.now(timezone.utc),
         output_data=b"test output",
-        is_error=0,
+        is_error=False,
     )
 
     # Convert to Avro bytes
@@ -0,0 +1,25 @@
+from datetime import datetime, timezone
+
+import pytest
+from tacoq.core.models.worker_heartbeat_update import WorkerHeartbeatUpdate
+
+
+@pytest.mark.unit
+def test_worker_heartbeat_update_avro_serde():
+    update = WorkerHeartbeatUpdate(
+        worker_name="test_worker_1",
+        worker_kind="test_worker",
+        heartbeat_at=datetime.now(timezone.utc),
+    )
+
+    # Convert to Avro bytes
+    avro_bytes = update.avro_bytes
+
+    # Convert back from Avro bytes
+    deserialized = WorkerHeartbeatUpdate.from_avro_bytes(avro_bytes)
+
+    # Check all fields match
+    assert update.worker_name == deserialized.worker_name
+    assert update.worker_kind == deserialized.worker_kind
+    assert update.heartbeat_at.timestamp() == deserialized.heartbeat_at.timestamp()
+    assert deserialized.update_type == "Heartbeat"
@@ -0,0 +1,29 @@
+from datetime import datetime, timezone
+
+import pytest
+from tacoq.core.models.worker_registration_update import WorkerRegistrationUpdate
+
+
+@pytest.mark.unit
+def test_worker_registration_update_avro_serde():
+    update = WorkerRegistrationUpdate(
+        worker_name="test_worker_1",
+        worker_kind="test_worker",
+        task_kinds=["task_a", "task_b"],
+        version="0.4.0",
+        registered_at=datetime.now(timezone.utc),
+    )
+
+    # Convert to Avro bytes
+    avro_bytes = update.avro_bytes
+
+    # Convert back from Avro bytes
+    deserialized = WorkerRegistrationUpdate.from_avro_bytes(avro_bytes)
+
+    # Check all fields match
+    assert update.worker_name == deserialized.worker_name
+    assert update.worker_kind == deserialized.worker_kind
+    assert update.task_kinds == deserialized.task_kinds
+    assert update.version == deserialized.version
+    assert update.registered_at.timestamp() == deserialized.registered_at.timestamp()
+    assert deserialized.update_type == "Registration"
@@ -5,6 +5,7 @@ These tests verify that the worker application can correctly register task handl
 execute tasks, and manage its lifecycle.
 """
 
+import asyncio
 import datetime
 import json
 from unittest import mock
@@ -169,6 +170,7 @@ async def test_execute_registered_task(
         mock.create_autospec(AbstractIncomingMessage, instance=True),
     )
     assert executed
+    worker_app._broker_client.publish_task_acknowledged.assert_awaited_once()  # type: ignore
 
 
 @pytest.mark.unit
@@ -184,7 +186,8 @@ async def test_execute_unregistered_task(
         mock.create_autospec(AbstractIncomingMessage, instance=True),
     )
 
-    # Verify that the task wasn't published
+    # Verify that the task was neither acknowledged nor published
+    worker_app._broker_client.publish_task_acknowledged.assert_not_called()  # type: ignore
     worker_app._broker_client.publish_task_completed.assert_not_called()  # type: ignore
 
 
@@ -223,3 +226,23 @@ async def test_broker_not_initialized(worker_app: WorkerApplication):
     worker_app._broker_client = None
     with pytest.raises(RuntimeError, match="Broker client not initialized"):
         await worker_app._listen()
+
+
+@pytest.mark.unit
+@pytest.mark.asyncio
+async def test_send_heartbeats_until_shutdown(worker_app: WorkerApplication):
+    """Test that heartbeats are published until the worker shuts down."""
+    worker_app.config.heartbeat_interval = 0.01
+    worker_app._shutdown_event = asyncio.Event()
+    worker_app._broker_client = mock.create_autospec(WorkerBrokerClient, instance=True)
+
+    heartbeats = asyncio.create_task(worker_app._send_heartbeats())
+    await asyncio.sleep(0.05)
+    worker_app.issue_shutdown()
+    await asyncio.wait_for(heartbeats, timeout=1.0)
+
+    publish = worker_app._broker_client.publish_worker_heartbeat  # type: ignore
+    assert publish.await_count >= 2
+    heartbeat = publish.await_args.args[0]
+    assert heartbeat.worker_name == "test_worker"
+    assert heartbeat.worker_kind == "test_kind"
@@ -0,0 +1,77 @@
+//! Measures encoding and decoding a task with Avro, the format TacoQ
+//! services exchange, and with JSON for comparison. Each is run with an
+//! empty, a 1 KiB and a 1 MiB input.
+//!
+//! Run with `cargo bench -p tacoq-client`.
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use tacoq_client::models::AvroSerializable;
+use tacoq_client::Task;
+use uuid::Uuid;
+
+/// Input sizes the task is measured with, in bytes
+const INPUT_SIZES: [(&str, usize); 3] = [("empty", 0), ("1KiB", 1024), ("1MiB", 1024 * 1024)];
+
+/// Builds a completed task whose input holds `input_size` bytes.
+fn task_with_input(input_size: usize) -> Task {
+    let now = Utc::now().naive_utc();
+    Task {
+        id: Uuid::new_v4(),
+        task_kind: Some("benchmark_task".to_string()),
+        worker_kind: Some("benchmark_worker".to_string()),
+        created_at: now,
+        started_at: Some(now),
+        completed_at: Some(now),
+        scheduled_for: None,
+        acknowledged_at: None,
+        input_content_type: None,
+        output_content_type: None,
+        updated_at: now,
+        input_data: Some(vec![0xAB; input_size]),
+        output_data: Some(vec![0xCD; 64]),
+        is_error: Some(false),
+        priority: Some(5),
+        ttl_duration: Some(3600),
+        executed_by: Some("worker-1".to_string()),
+        otel_ctx_carrier: None,
+    }
+}
+
+fn bench_serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("task_serialize");
+    for (name, size) in INPUT_SIZES {
+        let task = task_with_input(size);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("avro", name), &task, |b, task| {
+            b.iter(|| black_box(task).try_into_avro_bytes().unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("json", name), &task, |b, task| {
+            b.iter(|| serde_json::to_vec(black_box(task)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_deserialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("task_deserialize");
+    for (name, size) in INPUT_SIZES {
+        let task = task_with_input(size);
+        let avro = task.try_into_avro_bytes().unwrap();
+        let json = serde_json::to_vec(&task).unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("avro", name), &avro, |b, bytes| {
+            b.iter(|| Task::try_from_avro_bytes(black_box(bytes)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("json", name), &json, |b, bytes| {
+            b.iter(|| serde_json::from_slice::<Task>(black_box(bytes)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_serialize, bench_deserialize);
+criterion_main!(benches);
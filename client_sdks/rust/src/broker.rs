@@ -0,0 +1,346 @@
+use lapin::options::{
+    BasicPublishOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions,
+};
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind};
+use std::collections::HashSet;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::error::ClientError;
+use crate::models::{AvroSerializable, TaskAssignmentUpdate, WorkerHeartbeatUpdate};
+
+// These must match the topology declared by the other TacoQ SDKs.
+
+/// Single exchange for all task-related messages
+pub const TASK_EXCHANGE: &str = "tacoq_task_exchange";
+/// Queue the relay receives task events from
+pub const RELAY_QUEUE: &str = "tacoq_relay_queue";
+/// The relay receives every message published to the exchange, unless it
+/// routes some events to dedicated queues
+const RELAY_ROUTING_KEY: &str = "#";
+
+/// Routing key of the events workers publish to the relay
+const EVENT_ROUTING_KEY: &str = TASK_EXCHANGE;
+
+/// Routing key of the tasks for a worker kind
+fn worker_routing_key(worker_kind: &str) -> String {
+    format!("tasks.{}", worker_kind)
+}
+
+/// AMQP delivery mode of messages the broker drops on restart
+const TRANSIENT_DELIVERY_MODE: u8 = 1;
+/// AMQP delivery mode of messages the broker keeps across restarts
+const PERSISTENT_DELIVERY_MODE: u8 = 2;
+
+/// Kind of queue the client declares the relay and worker queues as. It
+/// must match the queue type the relay is configured with, since the broker
+/// refuses to declare an existing queue with different arguments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueueType {
+    /// Queue stored on a single node, supporting priorities
+    #[default]
+    Classic,
+    /// Queue replicated across the cluster, without priorities
+    Quorum,
+}
+
+/// Arguments every TacoQ queue is declared with. Quorum queues don't accept
+/// `x-max-priority`, so only classic queues deliver urgent tasks first.
+fn queue_arguments(queue_type: QueueType) -> FieldTable {
+    let mut arguments = FieldTable::default();
+    match queue_type {
+        QueueType::Classic => arguments.insert("x-max-priority".into(), 255.into()),
+        QueueType::Quorum => arguments.insert(
+            "x-queue-type".into(),
+            AMQPValue::LongString("quorum".into()),
+        ),
+    }
+    arguments
+}
+
+/// Builds the properties shared by every published message. The
+/// `message_type` header tells the relay and the workers how to decode it.
+///
+/// # Arguments
+///
+/// * `message_type` - The type of the published message
+/// * `persistent` - Whether the broker keeps the message across restarts
+fn message_properties(message_type: &str, persistent: bool) -> BasicProperties {
+    let mut headers = FieldTable::default();
+    headers.insert(
+        "message_type".into(),
+        AMQPValue::LongString(message_type.into()),
+    );
+    let delivery_mode = if persistent {
+        PERSISTENT_DELIVERY_MODE
+    } else {
+        TRANSIENT_DELIVERY_MODE
+    };
+    BasicProperties::default()
+        .with_headers(headers)
+        .with_content_type("application/avro".into())
+        .with_delivery_mode(delivery_mode)
+}
+
+/// Builds the properties of a published assignment.
+///
+/// # Arguments
+///
+/// * `assignment` - The published assignment
+/// * `persistent` - Whether the broker keeps the message across restarts
+fn assignment_properties(assignment: &TaskAssignmentUpdate, persistent: bool) -> BasicProperties {
+    message_properties("TaskAssignment", persistent)
+        .with_priority(assignment.priority.clamp(0, 255) as u8)
+}
+
+/// How the publisher declares its queues and publishes its messages.
+///
+/// # Fields
+///
+/// * `persistent` - Whether assignments are published as persistent
+///   messages, which survive a broker restart
+/// * `bind_relay_queue` - Whether the relay queue is bound to every task
+///   event. The relay binds its queues itself when it routes events to
+///   dedicated queues, and the catch-all binding would undo that
+/// * `queue_type` - The kind of queue the relay and worker queues are
+///   declared as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BrokerSettings {
+    pub persistent: bool,
+    pub bind_relay_queue: bool,
+    pub queue_type: QueueType,
+}
+
+/// Publishes task assignments to the broker, declaring the queues of the
+/// relay and of the worker kinds it publishes to.
+pub(crate) struct BrokerPublisher {
+    _connection: Connection,
+    channel: Channel,
+    declared_worker_kinds: Mutex<HashSet<String>>,
+    settings: BrokerSettings,
+}
+
+impl BrokerPublisher {
+    /// Connects to the broker and declares the task exchange and the relay
+    /// queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The broker URL
+    /// * `settings` - How queues are declared and messages published
+    pub async fn connect(url: &str, settings: BrokerSettings) -> Result<Self, ClientError> {
+        info!("Connecting to the broker");
+        let connection = Connection::connect(url, ConnectionProperties::default()).await?;
+        let channel = connection.create_channel().await?;
+
+        channel
+            .exchange_declare(
+                TASK_EXCHANGE,
+                ExchangeKind::Topic,
+                ExchangeDeclareOptions {
+                    durable: true,
+                    ..ExchangeDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        if settings.bind_relay_queue {
+            declare_bound_queue(
+                &channel,
+                RELAY_QUEUE,
+                RELAY_ROUTING_KEY,
+                settings.queue_type,
+            )
+            .await?;
+        } else {
+            declare_queue(&channel, RELAY_QUEUE, settings.queue_type).await?;
+        }
+
+        Ok(Self {
+            _connection: connection,
+            channel,
+            declared_worker_kinds: Mutex::new(HashSet::new()),
+            settings,
+        })
+    }
+
+    /// Publishes a task assignment, routed to the workers of its kind and to
+    /// the relay.
+    ///
+    /// # Arguments
+    ///
+    /// * `assignment` - The assignment to publish
+    pub async fn publish_assignment(
+        &self,
+        assignment: &TaskAssignmentUpdate,
+    ) -> Result<(), ClientError> {
+        let routing_key = worker_routing_key(&assignment.worker_kind);
+
+        // Declare the worker queue so tasks aren't dropped before a worker
+        // of that kind ever started
+        {
+            let mut declared = self.declared_worker_kinds.lock().await;
+            if !declared.contains(&assignment.worker_kind) {
+                declare_bound_queue(
+                    &self.channel,
+                    &assignment.worker_kind,
+                    &routing_key,
+                    self.settings.queue_type,
+                )
+                .await?;
+                declared.insert(assignment.worker_kind.clone());
+            }
+        }
+
+        let payload = assignment
+            .try_into_avro_bytes()
+            .map_err(ClientError::Avro)?;
+
+        let properties = assignment_properties(assignment, self.settings.persistent);
+
+        debug!(task_id = %assignment.id, routing_key = %routing_key, "Publishing task assignment");
+        self.channel
+            .basic_publish(
+                TASK_EXCHANGE,
+                &routing_key,
+                BasicPublishOptions::default(),
+                &payload,
+                properties,
+            )
+            .await?
+            .await?;
+        Ok(())
+    }
+
+    /// Publishes a worker heartbeat to the relay. Heartbeats are always
+    /// transient, since a stale heartbeat is worthless after a broker restart.
+    ///
+    /// # Arguments
+    ///
+    /// * `heartbeat` - The heartbeat to publish
+    pub async fn publish_heartbeat(
+        &self,
+        heartbeat: &WorkerHeartbeatUpdate,
+    ) -> Result<(), ClientError> {
+        let payload = heartbeat.try_into_avro_bytes().map_err(ClientError::Avro)?;
+
+        debug!(worker_name = %heartbeat.worker_name, "Publishing worker heartbeat");
+        self.channel
+            .basic_publish(
+                TASK_EXCHANGE,
+                EVENT_ROUTING_KEY,
+                BasicPublishOptions::default(),
+                &payload,
+                message_properties("WorkerHeartbeat", false),
+            )
+            .await?
+            .await?;
+        Ok(())
+    }
+}
+
+/// Declares a durable queue of the given type.
+async fn declare_queue(
+    channel: &Channel,
+    queue: &str,
+    queue_type: QueueType,
+) -> Result<(), ClientError> {
+    channel
+        .queue_declare(
+            queue,
+            QueueDeclareOptions {
+                durable: true,
+                ..QueueDeclareOptions::default()
+            },
+            queue_arguments(queue_type),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Declares a durable queue of the given type and binds it to the task
+/// exchange.
+async fn declare_bound_queue(
+    channel: &Channel,
+    queue: &str,
+    routing_key: &str,
+    queue_type: QueueType,
+) -> Result<(), ClientError> {
+    declare_queue(channel, queue, queue_type).await?;
+    channel
+        .queue_bind(
+            queue,
+            TASK_EXCHANGE,
+            routing_key,
+            QueueBindOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignment_properties() {
+        let assignment = TaskAssignmentUpdate {
+            id: uuid::Uuid::new_v4(),
+            task_kind: "resize".to_string(),
+            worker_kind: "image_worker".to_string(),
+            created_at: chrono::Utc::now().naive_utc(),
+            input_data: vec![],
+            priority: 300,
+            ttl_duration: 60,
+            otel_ctx_carrier: Default::default(),
+            input_content_type: None,
+            scheduled_for: None,
+            update_type: "Assignment".to_string(),
+        };
+
+        let properties = assignment_properties(&assignment, true);
+        assert_eq!(properties.delivery_mode(), &Some(PERSISTENT_DELIVERY_MODE));
+        assert_eq!(properties.priority(), &Some(255));
+        assert_eq!(
+            properties
+                .content_type()
+                .as_ref()
+                .map(|value| value.as_str()),
+            Some("application/avro")
+        );
+
+        let properties = assignment_properties(&assignment, false);
+        assert_eq!(properties.delivery_mode(), &Some(TRANSIENT_DELIVERY_MODE));
+    }
+
+    #[test]
+    fn test_message_properties() {
+        let properties = message_properties("WorkerHeartbeat", false);
+        assert_eq!(properties.delivery_mode(), &Some(TRANSIENT_DELIVERY_MODE));
+        assert_eq!(properties.priority(), &None);
+        assert_eq!(
+            properties
+                .headers()
+                .as_ref()
+                .and_then(|headers| headers.inner().get("message_type")),
+            Some(&AMQPValue::LongString("WorkerHeartbeat".into()))
+        );
+    }
+
+    #[test]
+    fn test_queue_arguments() {
+        let arguments = queue_arguments(QueueType::Classic);
+        assert_eq!(arguments.inner().len(), 1);
+        assert!(arguments.inner().contains_key("x-max-priority"));
+
+        // Quorum queues refuse the priority argument
+        let arguments = queue_arguments(QueueType::Quorum);
+        assert_eq!(arguments.inner().len(), 1);
+        assert_eq!(
+            arguments.inner().get("x-queue-type"),
+            Some(&AMQPValue::LongString("quorum".into()))
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use reqwest::StatusCode;
+use std::error::Error;
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Errors returned by the [`Client`](crate::Client).
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Invalid client configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("No broker URL configured, tasks can't be submitted")]
+    NoBroker,
+
+    #[error("No worker kind given for task kind {0} and no default worker kind configured")]
+    NoWorkerKind(String),
+
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Relay responded with {status}: {body}")]
+    UnexpectedStatus { status: StatusCode, body: String },
+
+    #[error("Broker operation failed: {0}")]
+    Broker(#[from] lapin::Error),
+
+    #[error("Failed to encode or decode an Avro message: {0}")]
+    Avro(Box<dyn Error + Send + Sync>),
+
+    #[error("Task {id} did not complete within {timeout:?}")]
+    Timeout { id: Uuid, timeout: Duration },
+}
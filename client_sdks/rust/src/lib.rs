@@ -0,0 +1,15 @@
+//! Client for TacoQ, submitting tasks through the broker and querying them
+//! through the relay API.
+//!
+//! Messages are encoded with the Avro schemas shared by every TacoQ service,
+//! so the client stays compatible with the relay and the other SDKs.
+
+mod broker;
+mod client;
+mod error;
+pub mod models;
+
+pub use broker::QueueType;
+pub use client::{Client, ClientBuilder, WireFormat};
+pub use error::ClientError;
+pub use models::{Task, TaskSpec};
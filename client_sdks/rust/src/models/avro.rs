@@ -0,0 +1,94 @@
+use apache_avro::{from_avro_datum, from_value, to_avro_datum, types::Value, Schema};
+use serde::{de::DeserializeOwned, Serialize};
+use std::error::Error;
+
+/// Trait for types that can be serialized to and deserialized from Avro format.
+///
+/// This mirrors the trait of the same name in the relay. The schemas are
+/// copied from `schemas/avro` by `dev/sync_schemas.sh`, so the client encodes
+/// exactly what the relay and the other SDKs expect.
+///
+/// Fields of implementing types must be declared in the same order as the
+/// fields of their schema.
+pub trait AvroSerializable: Sized + Serialize + DeserializeOwned {
+    /// Returns the Avro schema for this type
+    fn schema() -> &'static Schema;
+
+    /// Serializes the implementing type into Avro binary format
+    ///
+    /// # Returns
+    /// A vector of bytes containing the Avro-encoded data
+    fn try_into_avro_bytes(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let value = apache_avro::to_value(self)?;
+        if !matches!(value, Value::Record(_)) {
+            return Err("Expected Record type".into());
+        }
+        Ok(to_avro_datum(Self::schema(), value)?)
+    }
+
+    /// Deserializes Avro binary data into an instance of the implementing type
+    ///
+    /// # Arguments
+    /// * `data` - Slice of bytes containing Avro-encoded data
+    ///
+    /// # Returns
+    /// An instance of the implementing type
+    fn try_from_avro_bytes(data: &[u8]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut reader = data;
+        let value = from_avro_datum(Self::schema(), &mut reader, None)?;
+        Ok(from_value::<Self>(&value)?)
+    }
+}
+
+/// Helper functions for serializing and deserializing datetime values as
+/// microseconds since the epoch, like the Avro `timestamp-micros` type.
+pub mod serde_avro_datetime {
+    use chrono::{DateTime, NaiveDateTime};
+    use serde::{de::Deserializer, Deserialize, Serializer};
+
+    pub fn serialize<S>(dt: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i64(dt.and_utc().timestamp_micros())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ts = i64::deserialize(deserializer)?;
+        DateTime::from_timestamp_micros(ts)
+            .map(|dt| dt.naive_utc())
+            .ok_or_else(|| serde::de::Error::custom("Invalid timestamp"))
+    }
+}
+
+/// Helper functions for serializing and deserializing optional datetime
+/// values as microseconds since the epoch.
+pub mod serde_avro_datetime_opt {
+    use chrono::{DateTime, NaiveDateTime};
+    use serde::{de::Deserializer, Deserialize, Serializer};
+
+    pub fn serialize<S>(dt: &Option<NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match dt {
+            Some(dt) => serializer.serialize_some(&dt.and_utc().timestamp_micros()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<NaiveDateTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::deserialize(deserializer)? {
+            Some(ts) => DateTime::from_timestamp_micros(ts)
+                .map(|dt| Some(dt.naive_utc()))
+                .ok_or_else(|| serde::de::Error::custom("Invalid timestamp")),
+            None => Ok(None),
+        }
+    }
+}
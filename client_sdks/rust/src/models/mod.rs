@@ -0,0 +1,11 @@
+mod avro;
+mod task;
+mod task_assignment;
+mod task_spec;
+mod worker_heartbeat;
+
+pub use avro::*;
+pub use task::*;
+pub use task_assignment::*;
+pub use task_spec::*;
+pub use worker_heartbeat::*;
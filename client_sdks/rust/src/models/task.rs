@@ -0,0 +1,85 @@
+use apache_avro::{serde_avro_bytes_opt, Schema};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::{serde_avro_datetime, serde_avro_datetime_opt, AvroSerializable};
+
+/// A task as stored by the relay.
+///
+/// Fields are declared in the same order as the Avro schema.
+///
+/// # Fields
+/// * `id` - The id of the task
+/// * `task_kind` - The kind of the task
+/// * `worker_kind` - The kind of worker executing the task
+/// * `created_at` - When the task was created
+/// * `started_at` - When a worker started executing the task
+/// * `completed_at` - When the task completed, successfully or not
+/// * `updated_at` - When the task was last updated
+/// * `input_data` - The input data of the task
+/// * `output_data` - The output data of the task, once completed
+/// * `is_error` - Whether the task failed
+/// * `priority` - The priority of the task
+/// * `ttl_duration` - How long the task is kept after completing, in seconds
+/// * `executed_by` - The name of the worker that executed the task
+/// * `otel_ctx_carrier` - OpenTelemetry context of the trace that originated the task
+/// * `scheduled_for` - When the task may start at the earliest, if it was scheduled
+/// * `acknowledged_at` - When a worker received the task, before starting it
+/// * `input_content_type` - The MIME type of the input data, if the publisher declared one
+/// * `output_content_type` - The MIME type of the output data, if the worker declared one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    pub id: Uuid,
+    pub task_kind: Option<String>,
+    pub worker_kind: Option<String>,
+    #[serde(with = "serde_avro_datetime")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "serde_avro_datetime_opt")]
+    pub started_at: Option<NaiveDateTime>,
+    #[serde(with = "serde_avro_datetime_opt")]
+    pub completed_at: Option<NaiveDateTime>,
+    #[serde(with = "serde_avro_datetime")]
+    pub updated_at: NaiveDateTime,
+    #[serde(with = "serde_avro_bytes_opt")]
+    pub input_data: Option<Vec<u8>>,
+    #[serde(with = "serde_avro_bytes_opt")]
+    pub output_data: Option<Vec<u8>>,
+    pub is_error: Option<bool>,
+    pub priority: Option<i32>,
+    pub ttl_duration: Option<i64>,
+    pub executed_by: Option<String>,
+    pub otel_ctx_carrier: Option<HashMap<String, String>>,
+    #[serde(default, with = "serde_avro_datetime_opt")]
+    pub scheduled_for: Option<NaiveDateTime>,
+    #[serde(default, with = "serde_avro_datetime_opt")]
+    pub acknowledged_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub input_content_type: Option<String>,
+    #[serde(default)]
+    pub output_content_type: Option<String>,
+}
+
+impl Task {
+    /// Whether the task completed, successfully or not.
+    pub fn is_completed(&self) -> bool {
+        self.completed_at.is_some()
+    }
+
+    /// Whether the task completed with an error.
+    pub fn is_error(&self) -> bool {
+        self.is_error.unwrap_or(false)
+    }
+}
+
+impl AvroSerializable for Task {
+    fn schema() -> &'static Schema {
+        lazy_static::lazy_static! {
+            static ref AVRO_SCHEMA: Schema = Schema::parse_str(
+                include_str!("schemas/avro/task.json")
+            ).expect("Failed to parse Avro schema");
+        }
+        &AVRO_SCHEMA
+    }
+}
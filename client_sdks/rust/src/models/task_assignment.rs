@@ -0,0 +1,53 @@
+use apache_avro::{serde_avro_bytes, Schema};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::{serde_avro_datetime, serde_avro_datetime_opt, AvroSerializable};
+
+/// Published to the broker to submit a task. Workers of the task's worker
+/// kind execute it and the relay records it.
+///
+/// # Fields
+/// * `id` - The id of the task
+/// * `task_kind` - The kind of the task
+/// * `worker_kind` - The kind of worker that can execute the task
+/// * `created_at` - When the task was created
+/// * `input_data` - The input data of the task
+/// * `priority` - The priority of the task
+/// * `ttl_duration` - How long the task is kept after completing, in seconds
+/// * `otel_ctx_carrier` - OpenTelemetry context carrier map
+/// * `update_type` - Always `Assignment`, checked by consumers
+/// * `input_content_type` - The MIME type of the input data, if known
+/// * `scheduled_for` - When the task may start at the earliest. Only tasks
+///   submitted through the relay API are held back until then
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskAssignmentUpdate {
+    pub id: Uuid,
+    pub task_kind: String,
+    pub worker_kind: String,
+    #[serde(with = "serde_avro_datetime")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "serde_avro_bytes")]
+    pub input_data: Vec<u8>,
+    pub priority: i32,
+    pub ttl_duration: i64,
+    pub otel_ctx_carrier: HashMap<String, String>,
+    pub update_type: String,
+    #[serde(default)]
+    pub input_content_type: Option<String>,
+    #[serde(default, with = "serde_avro_datetime_opt")]
+    pub scheduled_for: Option<NaiveDateTime>,
+}
+
+impl AvroSerializable for TaskAssignmentUpdate {
+    fn schema() -> &'static Schema {
+        lazy_static::lazy_static! {
+            static ref AVRO_SCHEMA: Schema = Schema::parse_str(
+                include_str!("schemas/avro/task_assignment_update.json")
+            ).expect("Failed to parse Avro schema");
+        }
+        &AVRO_SCHEMA
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Default time a task is kept after completing, in seconds (7 days), like
+/// the other TacoQ SDKs.
+pub const DEFAULT_TTL_DURATION_SECS: i64 = 60 * 60 * 24 * 7;
+
+/// Describes a task to submit.
+///
+/// # Fields
+/// * `task_kind` - The kind of the task
+/// * `worker_kind` - The kind of worker executing the task, defaults to the
+///   worker kind configured on the client
+/// * `input_data` - The input data of the task
+/// * `id` - The id of the task, generated if unset
+/// * `priority` - The priority of the task, from 0 to 255
+/// * `ttl_duration` - How long the task is kept after completing, in seconds
+/// * `otel_ctx_carrier` - OpenTelemetry context to propagate to the workers
+/// * `input_content_type` - The MIME type of the input data, if known. The
+///   relay indexes `application/json` inputs so tasks can be searched by them
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskSpec {
+    pub task_kind: String,
+    pub worker_kind: Option<String>,
+    pub input_data: Vec<u8>,
+    pub id: Option<Uuid>,
+    pub priority: i32,
+    pub ttl_duration: i64,
+    pub otel_ctx_carrier: HashMap<String, String>,
+    pub input_content_type: Option<String>,
+}
+
+impl TaskSpec {
+    /// Creates a task of a kind with default settings.
+    ///
+    /// # Arguments
+    /// * `task_kind` - The kind of the task
+    /// * `input_data` - The input data of the task
+    pub fn new(task_kind: &str, input_data: Vec<u8>) -> Self {
+        Self {
+            task_kind: task_kind.to_string(),
+            worker_kind: None,
+            input_data,
+            id: None,
+            priority: 0,
+            ttl_duration: DEFAULT_TTL_DURATION_SECS,
+            otel_ctx_carrier: HashMap::new(),
+            input_content_type: None,
+        }
+    }
+
+    /// Sets the worker kind executing the task.
+    pub fn with_worker_kind(mut self, worker_kind: &str) -> Self {
+        self.worker_kind = Some(worker_kind.to_string());
+        self
+    }
+
+    /// Sets the id of the task.
+    pub fn with_id(mut self, id: Uuid) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Sets the priority of the task.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets how long the task is kept after completing, in seconds.
+    pub fn with_ttl_duration(mut self, ttl_duration: i64) -> Self {
+        self.ttl_duration = ttl_duration;
+        self
+    }
+
+    /// Sets the OpenTelemetry context propagated to the workers.
+    pub fn with_otel_ctx_carrier(mut self, otel_ctx_carrier: HashMap<String, String>) -> Self {
+        self.otel_ctx_carrier = otel_ctx_carrier;
+        self
+    }
+
+    /// Sets the MIME type of the input data.
+    pub fn with_input_content_type(mut self, input_content_type: &str) -> Self {
+        self.input_content_type = Some(input_content_type.to_string());
+        self
+    }
+}
@@ -0,0 +1,33 @@
+use apache_avro::Schema;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{serde_avro_datetime, AvroSerializable};
+
+/// Published periodically by a running worker, so the relay knows it is
+/// alive even while it isn't running any task.
+///
+/// # Fields
+/// * `worker_name` - The name of the worker
+/// * `worker_kind` - The kind of the worker
+/// * `heartbeat_at` - When the heartbeat was sent
+/// * `update_type` - Always `Heartbeat`, checked by consumers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerHeartbeatUpdate {
+    pub worker_name: String,
+    pub worker_kind: String,
+    #[serde(with = "serde_avro_datetime")]
+    pub heartbeat_at: NaiveDateTime,
+    pub update_type: String,
+}
+
+impl AvroSerializable for WorkerHeartbeatUpdate {
+    fn schema() -> &'static Schema {
+        lazy_static::lazy_static! {
+            static ref AVRO_SCHEMA: Schema = Schema::parse_str(
+                include_str!("schemas/avro/worker_heartbeat_update.json")
+            ).expect("Failed to parse Avro schema");
+        }
+        &AVRO_SCHEMA
+    }
+}
@@ -0,0 +1,164 @@
+//! Runs the client against a stub relay API serving tasks like the relay
+//! does, in JSON or Avro depending on the Accept header.
+
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tacoq_client::models::AvroSerializable;
+use tacoq_client::{Client, ClientError, Task, TaskSpec, WireFormat};
+use uuid::Uuid;
+
+#[derive(Clone, Default)]
+struct StubRelay {
+    tasks: Arc<HashMap<Uuid, Task>>,
+    /// Number of requests after which the tasks are reported completed
+    completes_after: u32,
+    requests: Arc<AtomicU32>,
+}
+
+async fn get_task(
+    State(relay): State<StubRelay>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Response {
+    let requests = relay.requests.fetch_add(1, Ordering::SeqCst) + 1;
+    let Some(mut task) = relay.tasks.get(&id).cloned() else {
+        return (StatusCode::NOT_FOUND, "Task not found").into_response();
+    };
+    if requests >= relay.completes_after {
+        task.completed_at = Some(Utc::now().naive_utc());
+        task.output_data = Some(vec![4, 5, 6]);
+    }
+
+    let wants_avro = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("application/avro"));
+    if wants_avro {
+        (
+            [(header::CONTENT_TYPE, "application/avro")],
+            task.try_into_avro_bytes().unwrap(),
+        )
+            .into_response()
+    } else {
+        Json(task).into_response()
+    }
+}
+
+/// Serves the stub relay on a random port and returns its base URL.
+async fn serve(relay: StubRelay) -> String {
+    let app = Router::new()
+        .route("/tasks/{id}", get(get_task))
+        .with_state(relay);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", address)
+}
+
+fn pending_task() -> Task {
+    let now = Utc::now().naive_utc();
+    Task {
+        id: Uuid::new_v4(),
+        task_kind: Some("resize".to_string()),
+        worker_kind: Some("image_worker".to_string()),
+        created_at: now,
+        updated_at: now,
+        input_data: Some(vec![1, 2, 3]),
+        is_error: Some(false),
+        priority: Some(0),
+        ttl_duration: Some(3600),
+        ..Task::default()
+    }
+}
+
+fn stub_relay(task: &Task, completes_after: u32) -> StubRelay {
+    StubRelay {
+        tasks: Arc::new(HashMap::from([(task.id, task.clone())])),
+        completes_after,
+        ..StubRelay::default()
+    }
+}
+
+#[tokio::test]
+async fn test_get_task_in_every_format() {
+    let task = pending_task();
+    let base_url = serve(stub_relay(&task, u32::MAX)).await;
+
+    for format in [WireFormat::Avro, WireFormat::Json] {
+        let client = Client::builder()
+            .base_url(&base_url)
+            .format(format)
+            .build()
+            .unwrap();
+
+        let fetched = client.get_task(task.id).await.unwrap().unwrap();
+        assert_eq!(fetched.id, task.id);
+        assert_eq!(fetched.input_data, Some(vec![1, 2, 3]));
+        assert!(!fetched.is_completed());
+
+        assert!(client.get_task(Uuid::new_v4()).await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_wait_for_polls_until_completed() {
+    let task = pending_task();
+    let base_url = serve(stub_relay(&task, 3)).await;
+    let client = Client::builder()
+        .base_url(&base_url)
+        .poll_interval(Duration::from_millis(10))
+        .build()
+        .unwrap();
+
+    let completed = client
+        .wait_for(task.id, Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(completed.is_completed());
+    assert_eq!(completed.output_data, Some(vec![4, 5, 6]));
+}
+
+#[tokio::test]
+async fn test_wait_for_times_out() {
+    let task = pending_task();
+    let base_url = serve(stub_relay(&task, u32::MAX)).await;
+    let client = Client::builder()
+        .base_url(&base_url)
+        .poll_interval(Duration::from_millis(10))
+        .build()
+        .unwrap();
+
+    let result = client.wait_for(task.id, Duration::from_millis(50)).await;
+    assert!(matches!(result, Err(ClientError::Timeout { id, .. }) if id == task.id));
+}
+
+#[tokio::test]
+async fn test_submit_task_requires_broker() {
+    let client = Client::builder()
+        .base_url("http://localhost:3000")
+        .default_worker_kind("image_worker")
+        .build()
+        .unwrap();
+
+    let result = client.submit_task(TaskSpec::new("resize", vec![])).await;
+    assert!(matches!(result, Err(ClientError::NoBroker)));
+}
+
+#[tokio::test]
+async fn test_send_heartbeat_requires_broker() {
+    let client = Client::builder()
+        .base_url("http://localhost:3000")
+        .build()
+        .unwrap();
+
+    let result = client.send_heartbeat("worker-1", "image_worker").await;
+    assert!(matches!(result, Err(ClientError::NoBroker)));
+}
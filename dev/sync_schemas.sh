@@ -9,6 +9,7 @@ SCHEMA_SOURCE="schemas"
 TARGET_DIRS=(
   "client_sdks/python/tacoq/core/models/"
   "server/relay/src/models/"
+  "client_sdks/rust/src/models/"
 )
 
 echo "Syncing Avro schema to services..."
@@ -0,0 +1,13 @@
+-- The task status is derived from the lifecycle timestamps. Storing it as a
+-- generated column keeps it in sync with every update and backfills existing
+-- rows, while allowing it to be indexed and filtered on.
+ALTER TABLE tasks
+ADD COLUMN status TEXT NOT NULL GENERATED ALWAYS AS (
+    CASE
+        WHEN completed_at IS NOT NULL THEN 'Completed'
+        WHEN started_at IS NOT NULL THEN 'Processing'
+        ELSE 'Pending'
+    END
+) STORED;
+
+CREATE INDEX tasks_status_idx ON tasks (status);
@@ -0,0 +1,9 @@
+-- Workers known to the relay, kept alive by the heartbeats they publish
+CREATE TABLE
+    workers (
+        name TEXT PRIMARY KEY,
+        worker_kind_name TEXT NOT NULL,
+        last_heartbeat_at TIMESTAMP NOT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT NOW (),
+        updated_at TIMESTAMP NOT NULL DEFAULT NOW ()
+    );
@@ -0,0 +1,3 @@
+-- MIME type of the task output, as declared by the worker that completed it
+ALTER TABLE tasks
+ADD COLUMN output_content_type TEXT;
@@ -0,0 +1,2 @@
+-- Keyset pagination walks the tasks by creation date, newest first
+CREATE INDEX tasks_created_at_id_idx ON tasks (created_at DESC, id DESC);
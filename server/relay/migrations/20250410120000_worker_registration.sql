@@ -0,0 +1,15 @@
+-- Worker kinds that have had at least one worker register
+CREATE TABLE
+    worker_kinds (
+        name TEXT PRIMARY KEY,
+        created_at TIMESTAMP NOT NULL DEFAULT NOW (),
+        updated_at TIMESTAMP NOT NULL DEFAULT NOW ()
+    );
+
+-- Capabilities announced by workers when they register on startup
+ALTER TABLE workers
+ADD COLUMN task_kinds TEXT[] NOT NULL DEFAULT '{}',
+ADD COLUMN version TEXT,
+ADD COLUMN registered_at TIMESTAMP;
+
+CREATE INDEX workers_worker_kind_name_idx ON workers (worker_kind_name);
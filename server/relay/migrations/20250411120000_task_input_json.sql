@@ -0,0 +1,6 @@
+-- JSON copy of the task input, kept when the input is declared as JSON so
+-- tasks can be searched by the content of their input
+ALTER TABLE tasks
+ADD COLUMN input_json JSONB;
+
+CREATE INDEX tasks_input_json_idx ON tasks USING GIN (input_json jsonb_path_ops);
@@ -0,0 +1,13 @@
+-- History of the events applied to each task, kept alongside the task row
+-- which only holds the latest state
+CREATE TABLE
+    task_events (
+        id BIGSERIAL PRIMARY KEY,
+        task_id UUID NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        event_type TEXT NOT NULL,
+        payload JSONB NOT NULL,
+        occurred_at TIMESTAMP NOT NULL,
+        recorded_at TIMESTAMP NOT NULL DEFAULT NOW ()
+    );
+
+CREATE INDEX task_events_task_id_idx ON task_events (task_id, occurred_at);
@@ -0,0 +1,3 @@
+-- MIME type of the task input, as declared by the publisher that assigned it
+ALTER TABLE tasks
+ADD COLUMN input_content_type TEXT;
@@ -0,0 +1,10 @@
+-- Defaults applied to the tasks of a kind submitted without them. Task kinds
+-- are still derived from the tasks, this only holds their settings
+CREATE TABLE
+    task_kinds (
+        name TEXT PRIMARY KEY,
+        default_priority INT,
+        default_ttl_duration BIGINT,
+        created_at TIMESTAMP NOT NULL DEFAULT NOW (),
+        updated_at TIMESTAMP NOT NULL DEFAULT NOW ()
+    );
@@ -0,0 +1,18 @@
+-- Store the task status as an enum so the database only ever holds statuses
+-- the relay knows about, instead of free text that has to be parsed back.
+CREATE TYPE task_status AS ENUM ('Pending', 'Processing', 'Completed');
+
+DROP INDEX tasks_status_idx;
+
+ALTER TABLE tasks DROP COLUMN status;
+
+ALTER TABLE tasks
+ADD COLUMN status task_status NOT NULL GENERATED ALWAYS AS (
+    CASE
+        WHEN completed_at IS NOT NULL THEN 'Completed'::task_status
+        WHEN started_at IS NOT NULL THEN 'Processing'::task_status
+        ELSE 'Pending'::task_status
+    END
+) STORED;
+
+CREATE INDEX tasks_status_idx ON tasks (status);
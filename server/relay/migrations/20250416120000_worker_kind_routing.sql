@@ -0,0 +1,5 @@
+-- Where the assignments of a worker kind are routed. Kinds created by a
+-- worker registration leave them unset and follow the SDK conventions.
+ALTER TABLE worker_kinds
+ADD COLUMN routing_key TEXT,
+ADD COLUMN queue_name TEXT;
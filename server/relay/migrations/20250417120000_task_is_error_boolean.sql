@@ -0,0 +1,4 @@
+-- Store whether a task failed as a boolean instead of an integer only ever
+-- holding 0 or 1. Any non-zero value counts as an error.
+ALTER TABLE tasks
+ALTER COLUMN is_error TYPE BOOLEAN USING is_error <> 0;
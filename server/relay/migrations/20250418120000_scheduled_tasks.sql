@@ -0,0 +1,12 @@
+-- Tasks can be scheduled to start no earlier than a given time. The
+-- assignments of tasks submitted through the API are held in
+-- scheduled_tasks until then, and published by the scheduled task job.
+ALTER TABLE tasks
+ADD COLUMN scheduled_for TIMESTAMP;
+
+CREATE TABLE scheduled_tasks (
+    task_id UUID PRIMARY KEY REFERENCES tasks (id) ON DELETE CASCADE,
+    scheduled_for TIMESTAMP NOT NULL
+);
+
+CREATE INDEX scheduled_tasks_scheduled_for_idx ON scheduled_tasks (scheduled_for);
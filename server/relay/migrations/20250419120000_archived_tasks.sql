@@ -0,0 +1,10 @@
+-- Expired tasks moved out of tasks by the cleanup job in archive mode, kept
+-- for audit. The columns are the ones of tasks, with the status frozen as it
+-- was when archived. Task IDs can be reused, so an ID may be archived twice.
+CREATE TABLE
+    archived_tasks (
+        LIKE tasks INCLUDING DEFAULTS,
+        archived_at TIMESTAMP NOT NULL DEFAULT NOW ()
+    );
+
+CREATE INDEX archived_tasks_id_idx ON archived_tasks (id, archived_at DESC);
@@ -0,0 +1,4 @@
+-- JSON Schema the inputs of a task kind declared as JSON are validated
+-- against when submitted
+ALTER TABLE task_kinds
+ADD COLUMN input_schema JSONB;
@@ -0,0 +1,22 @@
+-- Incremental exports pull the tasks changed since their last run, so every
+-- write to a task must move its updated_at forward, including the upserts of
+-- the consumer that don't set it themselves. The time of the write is used
+-- rather than the start of its transaction, so updated_at is only behind the
+-- commit by as long as the rest of the transaction takes.
+CREATE FUNCTION set_tasks_updated_at () RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = clock_timestamp();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tasks_set_updated_at BEFORE
+UPDATE ON tasks FOR EACH ROW
+EXECUTE FUNCTION set_tasks_updated_at ();
+
+ALTER TABLE tasks
+ALTER COLUMN updated_at
+SET DEFAULT clock_timestamp();
+
+-- The change feed walks the tasks by update date, oldest first
+CREATE INDEX tasks_updated_at_id_idx ON tasks (updated_at, id);
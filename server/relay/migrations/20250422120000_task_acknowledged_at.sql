@@ -0,0 +1,7 @@
+-- Workers acknowledge the tasks they receive before starting them, which
+-- tells a task that never reached a worker apart from one a worker stalled on.
+ALTER TABLE tasks
+ADD COLUMN acknowledged_at TIMESTAMP;
+
+ALTER TABLE archived_tasks
+ADD COLUMN acknowledged_at TIMESTAMP;
@@ -0,0 +1,285 @@
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, StatusCode},
+    routing::get,
+    Json, Router,
+};
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::constants::BROKER_HEALTH_CHECK_TIMEOUT_SECS;
+use crate::jobs::CleanupStats;
+use crate::lifecycle::AppState;
+use crate::models::QueueDepth;
+
+pub fn routes() -> Router<AppState> {
+    debug!("Setting up admin API routes");
+    Router::new()
+        .route("/cleanup-status", get(get_cleanup_status))
+        .route("/queues", get(get_queue_depths))
+}
+
+/// Extractor guarding admin endpoints. The request must carry the configured
+/// admin token as a bearer token. Admin endpoints are disabled when no token
+/// is configured.
+pub struct AdminGuard;
+
+impl FromRequestParts<AppState> for AdminGuard {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(expected) = state.admin_token.as_deref() else {
+            warn!(path = %parts.uri.path(), "Admin endpoint called but no admin token is configured");
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Admin endpoints are disabled".to_string(),
+            ));
+        };
+
+        let provided = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match provided {
+            // Compared in constant time, so response times don't leak how
+            // much of the token was guessed right
+            Some(token) if bool::from(token.as_bytes().ct_eq(expected.as_bytes())) => {
+                Ok(AdminGuard)
+            }
+            Some(_) => {
+                warn!(path = %parts.uri.path(), "Admin endpoint called with an invalid token");
+                Err((StatusCode::FORBIDDEN, "Invalid admin token".to_string()))
+            }
+            None => Err((StatusCode::UNAUTHORIZED, "Missing admin token".to_string())),
+        }
+    }
+}
+
+/// Get the status of the task cleanup job
+///
+/// # Returns
+/// Returns the configured interval, when the job last ran and how many
+/// expired tasks it deleted
+#[utoipa::path(
+    get,
+    description = "Get the status of the task cleanup job. Requires the admin token.",
+    path = "/admin/cleanup-status",
+    responses(
+        (status = 200, description = "Cleanup job status", body = CleanupStats, content_type = "application/json"),
+        (status = 401, description = "Missing admin token", content_type = "text/plain"),
+        (status = 403, description = "Invalid admin token or admin endpoints disabled", content_type = "text/plain"),
+        (status = 404, description = "Task cleanup job disabled", content_type = "text/plain")
+    ),
+    security(("admin_token" = [])),
+    tag = "admin"
+)]
+#[instrument(skip(state, _admin))]
+async fn get_cleanup_status(
+    _admin: AdminGuard,
+    State(state): State<AppState>,
+) -> Result<Json<CleanupStats>, (StatusCode, String)> {
+    info!("API request: Get cleanup status");
+
+    match state.cleanup_stats {
+        Some(stats) => Ok(Json(
+            stats.lock().expect("Cleanup stats lock poisoned").clone(),
+        )),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            "Task cleanup job is disabled".to_string(),
+        )),
+    }
+}
+
+/// Get the depth of the consumed queues
+///
+/// # Returns
+/// Returns the number of messages waiting in each consumed queue and in its
+/// dead letter queue, read from the broker
+#[utoipa::path(
+    get,
+    description = "Get the number of messages waiting in each consumed queue. Requires the admin token.",
+    path = "/admin/queues",
+    responses(
+        (status = 200, description = "Queue depths", body = Vec<QueueDepth>, content_type = "application/json"),
+        (status = 401, description = "Missing admin token", content_type = "text/plain"),
+        (status = 403, description = "Invalid admin token or admin endpoints disabled", content_type = "text/plain"),
+        (status = 404, description = "Task event consumer disabled", content_type = "text/plain"),
+        (status = 503, description = "Broker unreachable", content_type = "text/plain")
+    ),
+    security(("admin_token" = [])),
+    tag = "admin"
+)]
+#[instrument(skip(state, _admin))]
+async fn get_queue_depths(
+    _admin: AdminGuard,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<QueueDepth>>, (StatusCode, String)> {
+    info!("API request: Get queue depths");
+
+    let Some(broker) = state.broker else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Task event consumer is disabled".to_string(),
+        ));
+    };
+
+    let timeout = Duration::from_secs(BROKER_HEALTH_CHECK_TIMEOUT_SECS);
+    match tokio::time::timeout(timeout, broker.queue_depths()).await {
+        Ok(Ok(depths)) => {
+            debug!(queues = depths.len(), "Successfully read queue depths");
+            Ok(Json(depths))
+        }
+        Ok(Err(e)) => {
+            error!(error = %e, "Failed to read queue depths from the broker");
+            Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Broker is unavailable: {}", e),
+            ))
+        }
+        Err(_) => {
+            error!("Broker did not answer the queue depth lookup in time");
+            Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!(
+                    "Broker is unavailable: no answer after {} seconds",
+                    timeout.as_secs()
+                ),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Mutex};
+
+    use crate::health_probe::Readiness;
+    use crate::jobs::CleanupStats;
+    use crate::lifecycle::setup_app;
+    use crate::repo::{PgRepositoryCore, TaskRepository};
+    use crate::server::RequestLimits;
+    use crate::testing::test::{get_test_server, init_test_logger, StubBroker, TEST_ADMIN_TOKEN};
+
+    // This runs before any test in this module
+    #[ctor::ctor]
+    fn init() {
+        init_test_logger();
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_cleanup_status(db_pools: PgPool) {
+        let mut stats = CleanupStats {
+            interval_seconds: 300,
+            ..Default::default()
+        };
+        stats.record_run(3);
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            None,
+            None,
+            &RequestLimits::default(),
+            Some(TEST_ADMIN_TOKEN.to_string()),
+            false,
+            Some(Arc::new(Mutex::new(stats.clone()))),
+            Readiness::new(0),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/admin/cleanup-status").await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        // Tokens of the same length and prefixes of the token are refused
+        let wrong_token = "x".repeat(TEST_ADMIN_TOKEN.len());
+        for token in [wrong_token.as_str(), &TEST_ADMIN_TOKEN[..4]] {
+            let response = server
+                .get("/admin/cleanup-status")
+                .authorization_bearer(token)
+                .await;
+            assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+        }
+
+        let response = server
+            .get("/admin/cleanup-status")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<CleanupStats>(), stats);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_cleanup_status_when_disabled(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let response = server
+            .get("/admin/cleanup-status")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_queue_depths(db_pools: PgPool) {
+        let broker = Arc::new(StubBroker::default());
+        broker.connected.store(true, Ordering::SeqCst);
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            Some(broker.clone()),
+            None,
+            &RequestLimits::default(),
+            Some(TEST_ADMIN_TOKEN.to_string()),
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+
+        let response = server
+            .get("/admin/queues")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.json::<serde_json::Value>(),
+            json!([{
+                "queue": "tacoq_relay_queue",
+                "messages": 3,
+                "consumers": 1,
+                "dead_letter_messages": 0
+            }])
+        );
+
+        broker.connected.store(false, Ordering::SeqCst);
+        let response = server
+            .get("/admin/queues")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_queue_depths_when_consumer_disabled(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let response = server
+            .get("/admin/queues")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+}
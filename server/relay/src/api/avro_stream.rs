@@ -0,0 +1,155 @@
+use axum::body::Bytes;
+use futures::channel::mpsc::Sender;
+use futures::{SinkExt, Stream, StreamExt};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, error};
+
+use crate::models::AvroSerializable;
+
+/// Buffer shared between the Avro writer and the task draining it into the
+/// response body.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().expect("Avro buffer lock poisoned"))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .expect("Avro buffer lock poisoned")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sends the bytes written so far, if any.
+///
+/// # Returns
+/// `false` if the receiving end was dropped, meaning the client went away
+async fn send_written(
+    buffer: &SharedBuffer,
+    sender: &mut Sender<Result<Bytes, io::Error>>,
+) -> bool {
+    let chunk = buffer.take();
+    if chunk.is_empty() {
+        return true;
+    }
+    sender.send(Ok(Bytes::from(chunk))).await.is_ok()
+}
+
+/// Encodes records into an Avro Object Container File, sending each block to
+/// `sender` as soon as the writer emits it. Only a single block is buffered
+/// at a time, so memory stays flat regardless of the number of records.
+///
+/// Errors are sent down the channel, which aborts the response body.
+///
+/// # Arguments
+///
+/// * `records` - The records to encode
+/// * `sender` - The channel feeding the response body
+pub async fn write_avro_container<T, E, S>(records: S, mut sender: Sender<Result<Bytes, io::Error>>)
+where
+    T: AvroSerializable,
+    E: std::fmt::Display,
+    S: Stream<Item = Result<T, E>>,
+{
+    let buffer = SharedBuffer::default();
+    let mut writer = apache_avro::Writer::new(T::schema(), buffer.clone());
+    let mut records = std::pin::pin!(records);
+    let mut count: u64 = 0;
+
+    while let Some(record) = records.next().await {
+        let appended = record
+            .map_err(|e| io::Error::other(e.to_string()))
+            .and_then(|record| {
+                record
+                    .try_into_avro_value()
+                    .map_err(|e| io::Error::other(e.to_string()))
+            })
+            .and_then(|value| {
+                writer
+                    .append(value)
+                    .map_err(|e| io::Error::other(e.to_string()))
+            });
+
+        if let Err(e) = appended {
+            error!(error = %e, "Failed to write record to Avro container");
+            let _ = sender.send(Err(e)).await;
+            return;
+        }
+        count += 1;
+
+        if !send_written(&buffer, &mut sender).await {
+            debug!("Client disconnected, stopping Avro stream");
+            return;
+        }
+    }
+
+    // Writes the header if there were no records, and the last block
+    if let Err(e) = writer.into_inner() {
+        error!(error = %e, "Failed to finish Avro container");
+        let _ = sender.send(Err(io::Error::other(e.to_string()))).await;
+        return;
+    }
+    send_written(&buffer, &mut sender).await;
+    debug!(records = count, "Finished streaming Avro container");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Task;
+
+    async fn encode(tasks: Vec<Result<Task, String>>) -> Vec<Result<Bytes, io::Error>> {
+        let (sender, receiver) = futures::channel::mpsc::channel(16);
+        write_avro_container(futures::stream::iter(tasks), sender).await;
+        receiver.collect().await
+    }
+
+    #[tokio::test]
+    async fn test_container_round_trip() {
+        let tasks: Vec<Task> = (0..3)
+            .map(|i| Task::new("TaskKindName", &format!("Worker{}", i), i, 0))
+            .collect();
+
+        let chunks = encode(tasks.iter().cloned().map(Ok).collect()).await;
+        let bytes: Vec<u8> = chunks.into_iter().flat_map(|c| c.unwrap()).collect();
+
+        let reader = apache_avro::Reader::new(&bytes[..]).unwrap();
+        let decoded: Vec<Task> = reader
+            .map(|value| apache_avro::from_value::<Task>(&value.unwrap()).unwrap())
+            .collect();
+        let ids: Vec<_> = decoded.iter().map(|task| task.id).collect();
+        assert_eq!(ids, tasks.iter().map(|task| task.id).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_empty_container_has_header() {
+        let chunks = encode(vec![]).await;
+        let bytes: Vec<u8> = chunks.into_iter().flat_map(|c| c.unwrap()).collect();
+
+        let reader = apache_avro::Reader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_aborts_body() {
+        let chunks = encode(vec![
+            Ok(Task::new("TaskKindName", "Worker", 0, 0)),
+            Err("database went away".to_string()),
+        ])
+        .await;
+
+        assert!(chunks.last().unwrap().is_err());
+    }
+}
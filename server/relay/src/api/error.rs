@@ -0,0 +1,179 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Error returned by the API handlers, sent as a JSON [`ErrorResponse`] so
+/// clients can tell errors apart by their code instead of parsing messages.
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+/// Body of every error response.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+}
+
+/// What went wrong.
+///
+/// # Fields
+/// * `code` - Machine-readable kind of the error, such as `not_found`
+/// * `message` - Human-readable description of the error
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorDetail {
+    pub code: String,
+    pub message: String,
+}
+
+/// Code of the errors that don't set one, after their status
+fn default_code(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::NOT_ACCEPTABLE => "not_acceptable",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::UNPROCESSABLE_ENTITY => "unprocessable_entity",
+        StatusCode::TOO_MANY_REQUESTS => "too_many_requests",
+        StatusCode::SERVICE_UNAVAILABLE => "service_unavailable",
+        status if status.is_client_error() => "client_error",
+        _ => "internal_error",
+    }
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code: default_code(status),
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn not_acceptable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_ACCEPTABLE, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+
+    /// A failure to encode or decode Avro.
+    pub fn avro(error: impl Display) -> Self {
+        Self {
+            code: "avro_error",
+            ..Self::internal(format!("Avro serialization failed: {}", error))
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse {
+            error: ErrorDetail {
+                code: self.code.to_string(),
+                message: self.message,
+            },
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        Self {
+            code: "database_error",
+            ..Self::internal(format!("Database error: {}", error))
+        }
+    }
+}
+
+impl From<apache_avro::Error> for ApiError {
+    fn from(error: apache_avro::Error) -> Self {
+        Self::avro(error)
+    }
+}
+
+/// Lets handlers returning an [`ApiError`] use the helpers that still
+/// report errors as a status and a plain-text message.
+impl From<(StatusCode, String)> for ApiError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        Self::new(status, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use axum::http::header;
+    use serde_json::json;
+
+    async fn response_parts(error: ApiError) -> (StatusCode, String, serde_json::Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let content_type = response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, content_type, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_not_found_envelope() {
+        let (status, content_type, body) =
+            response_parts(ApiError::not_found("Task with ID 42 not found")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(content_type, "application/json");
+        assert_eq!(
+            body,
+            json!({ "error": { "code": "not_found", "message": "Task with ID 42 not found" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_database_error_envelope() {
+        let (status, content_type, body) =
+            response_parts(ApiError::from(sqlx::Error::PoolTimedOut)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(content_type, "application/json");
+        assert_eq!(body["error"]["code"], "database_error");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Database error: "));
+    }
+
+    #[tokio::test]
+    async fn test_codes_follow_status() {
+        let code =
+            |error: ApiError| async move { response_parts(error).await.2["error"]["code"].clone() };
+        assert_eq!(code(ApiError::bad_request("")).await, "bad_request");
+        assert_eq!(
+            code(ApiError::from((StatusCode::NOT_ACCEPTABLE, String::new()))).await,
+            "not_acceptable"
+        );
+        assert_eq!(
+            code(ApiError::new(StatusCode::IM_A_TEAPOT, "")).await,
+            "client_error"
+        );
+        assert_eq!(code(ApiError::avro("bad schema")).await, "avro_error");
+    }
+}
@@ -1,13 +1,79 @@
 use axum::extract::State;
 use axum::http::StatusCode;
-use axum::{routing::get, Router};
+use axum::{routing::get, Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tracing::{debug, error, info, instrument};
+use utoipa::ToSchema;
 
 use crate::lifecycle::AppState;
+use crate::models::message_schema_fingerprints;
 
 pub fn routes() -> Router<AppState> {
     debug!("Setting up health API routes");
-    Router::new().route("/", get(health))
+    Router::new()
+        .route("/", get(health))
+        .route("/ready", get(ready))
+        .route("/schema", get(schema))
+}
+
+/// Fingerprints of the Avro schemas the relay encodes and decodes messages
+/// with. Another service using the same schemas reports the same ones.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SchemaFingerprints {
+    /// How the fingerprints are computed
+    pub algorithm: String,
+    /// Hex fingerprint of each schema, keyed by message type
+    pub schemas: BTreeMap<String, String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/schema",
+    description = "Fingerprints of the Avro schemas used for messages, to detect schemas drifting between services",
+    responses(
+        (status = 200, description = "Schema fingerprints", body = SchemaFingerprints, content_type = "application/json"),
+        (status = 500, description = "A schema is invalid", content_type = "text/plain")
+    ),
+    tag = "health"
+)]
+#[instrument]
+async fn schema() -> Result<Json<SchemaFingerprints>, (StatusCode, String)> {
+    let schemas = message_schema_fingerprints().map_err(|e| {
+        error!(error = %e, "Failed to fingerprint Avro schemas");
+        (StatusCode::INTERNAL_SERVER_ERROR, e)
+    })?;
+
+    Ok(Json(SchemaFingerprints {
+        algorithm: "CRC-64-AVRO of the Parsing Canonical Form".to_string(),
+        schemas: schemas
+            .into_iter()
+            .map(|(name, fingerprint)| (name.to_string(), fingerprint))
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    description = "Whether the service finished setting up its dependencies and can serve traffic",
+    responses(
+        (status = 200, description = "Service is ready"),
+        (status = 503, description = "Service is still starting up")
+    ),
+    tag = "health"
+)]
+#[instrument(skip(state))]
+async fn ready(State(state): State<AppState>) -> Result<String, (StatusCode, String)> {
+    if !state.readiness.is_ready() {
+        debug!("Readiness check failed, setup is still in progress");
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Service is not ready\n".to_string(),
+        ));
+    }
+
+    Ok("Service is ready\n".to_string())
 }
 
 #[utoipa::path(
@@ -43,3 +109,103 @@ async fn health(State(state): State<AppState>) -> Result<String, (StatusCode, St
     debug!("Health check successful");
     Ok("Service is healthy\n".to_string())
 }
+
+#[cfg(test)]
+mod test {
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use sqlx::PgPool;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use super::SchemaFingerprints;
+    use crate::health_probe::Readiness;
+    use crate::lifecycle::setup_app;
+    use crate::models::{AvroSerializable, TaskAssignmentUpdate};
+    use crate::repo::{PgRepositoryCore, TaskRepository};
+    use crate::server::RequestLimits;
+    use crate::testing::test::{get_test_server, init_test_logger, StubBroker};
+
+    // This runs before any test in this module
+    #[ctor::ctor]
+    fn init() {
+        init_test_logger();
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_health_follows_broker_reconnection(db_pools: PgPool) {
+        let broker = Arc::new(StubBroker::default());
+        broker.connected.store(true, Ordering::SeqCst);
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            Some(broker.clone()),
+            None,
+            &RequestLimits::default(),
+            None,
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/health").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        // The connection dies
+        broker.connected.store(false, Ordering::SeqCst);
+        let response = server.get("/health").await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.text().contains("broker: "));
+
+        // The consumer reconnects
+        broker.connected.store(true, Ordering::SeqCst);
+        let response = server.get("/health").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_ready_only_after_setup_completes(db_pools: PgPool) {
+        let readiness = Readiness::new(2);
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            None,
+            None,
+            &RequestLimits::default(),
+            None,
+            false,
+            None,
+            readiness.clone(),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/health/ready").await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        readiness.complete_step("migrations");
+        let response = server.get("/health/ready").await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        readiness.complete_step("queue");
+        let response = server.get("/health/ready").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_schema_fingerprints(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let response = server.get("/health/schema").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let fingerprints = response.json::<SchemaFingerprints>();
+        assert_eq!(fingerprints.schemas.len(), 8);
+        assert_eq!(
+            fingerprints.schemas["TaskAssignmentUpdate"],
+            TaskAssignmentUpdate::schema_fingerprint().unwrap()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use std::fmt::Write;
+use tracing::{debug, instrument};
+
+use crate::lifecycle::AppState;
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+pub fn routes() -> Router<AppState> {
+    debug!("Setting up metrics API routes");
+    Router::new().route("/", get(metrics))
+}
+
+/// Writes a gauge in the Prometheus text exposition format.
+fn write_gauge(output: &mut String, name: &str, help: &str, value: f64) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} gauge", name);
+    let _ = writeln!(output, "{} {}", name, value);
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    description = "Relay metrics in the Prometheus text format. The same metrics are exported over OpenTelemetry. \
+        The consumer lag is left out until the consumer processed an event.",
+    responses(
+        (status = 200, description = "Relay metrics", content_type = "text/plain")
+    ),
+    tag = "health"
+)]
+#[instrument(skip(state))]
+async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let mut output = String::new();
+
+    if let Some(lag) = state
+        .broker
+        .as_ref()
+        .and_then(|broker| broker.consumer_lag())
+    {
+        write_gauge(
+            &mut output,
+            "tacoq_relay_consumer_lag_seconds",
+            "Time the task event processed last by the consumer waited to be processed",
+            lag.as_secs_f64(),
+        );
+    }
+
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], output)
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::{header, StatusCode};
+    use axum_test::TestServer;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::health_probe::Readiness;
+    use crate::lifecycle::setup_app;
+    use crate::repo::{PgRepositoryCore, TaskRepository};
+    use crate::server::RequestLimits;
+    use crate::testing::test::{get_test_server, init_test_logger, StubBroker};
+
+    // This runs before any test in this module
+    #[ctor::ctor]
+    fn init() {
+        init_test_logger();
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_metrics_expose_consumer_lag(db_pools: PgPool) {
+        let broker = Arc::new(StubBroker {
+            lag: Some(Duration::from_millis(2500)),
+            ..Default::default()
+        });
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            Some(broker),
+            None,
+            &RequestLimits::default(),
+            None,
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/metrics").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain; version=0.0.4"
+        );
+        assert_eq!(
+            response.text(),
+            "# HELP tacoq_relay_consumer_lag_seconds Time the task event processed last by the consumer waited to be processed\n\
+             # TYPE tacoq_relay_consumer_lag_seconds gauge\n\
+             tacoq_relay_consumer_lag_seconds 2.5\n"
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_metrics_without_consumer(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let response = server.get("/metrics").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(!response.text().contains("tacoq_relay_consumer_lag_seconds"));
+    }
+}
@@ -2,13 +2,23 @@ use axum::Router;
 
 use crate::lifecycle::AppState;
 
+mod admin;
+mod avro_stream;
+mod error;
 mod health;
+mod metrics;
 mod openapi_docs;
 mod task;
+mod task_kind;
+mod worker_kind;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
+        .nest("/admin", admin::routes())
         .nest("/api-docs", openapi_docs::routes())
         .nest("/health", health::routes())
+        .nest("/metrics", metrics::routes())
         .nest("/tasks", task::routes())
+        .nest("/task-kinds", task_kind::routes())
+        .nest("/worker-kinds", worker_kind::routes())
 }
@@ -1,13 +1,68 @@
 use axum::{routing::get, Json, Router};
 use tracing::{debug, instrument};
-use utoipa::OpenApi;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 
 use crate::lifecycle::AppState;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(openapi, crate::api::task::get_task_by_id),
-    components(schemas(crate::models::Task)),
+    paths(
+        openapi,
+        crate::api::health::ready,
+        crate::api::health::schema,
+        crate::api::metrics::metrics,
+        crate::api::task::get_task_by_id,
+        crate::api::task::get_task_input,
+        crate::api::task::get_task_result,
+        crate::api::task::get_task_worker,
+        crate::api::task::get_task_history,
+        crate::api::task::requeue_task,
+        crate::api::task::get_task_stats,
+        crate::api::task::batch_get_tasks,
+        crate::api::task::submit_tasks,
+        crate::api::task::validate_tasks,
+        crate::api::task::submit_task_sync,
+        crate::api::task::list_tasks,
+        crate::api::task::list_task_changes,
+        crate::api::task::delete_tasks,
+        crate::api::task_kind::list_task_kinds,
+        crate::api::task_kind::get_task_kind,
+        crate::api::task_kind::set_task_kind_defaults,
+        crate::api::task_kind::set_task_kind_input_schema,
+        crate::api::worker_kind::list_worker_kinds,
+        crate::api::worker_kind::get_worker_kind,
+        crate::api::worker_kind::create_worker_kind,
+        crate::api::worker_kind::delete_worker_kind,
+        crate::api::worker_kind::list_workers_of_kind,
+        crate::api::admin::get_cleanup_status,
+        crate::api::admin::get_queue_depths
+    ),
+    components(schemas(
+        crate::models::Task,
+        crate::models::TaskStats,
+        crate::models::TaskKind,
+        crate::models::TaskKindDefaults,
+        crate::models::TaskEvent,
+        crate::models::Worker,
+        crate::models::WorkerKind,
+        crate::models::NewWorkerKind,
+        crate::models::TaskPage,
+        crate::models::TaskChanges,
+        crate::models::TaskSpec,
+        crate::models::QueueDepth,
+        crate::jobs::CleanupStats,
+        crate::api::error::ErrorResponse,
+        crate::api::error::ErrorDetail,
+        crate::api::health::SchemaFingerprints,
+        crate::api::task::DeleteTasksResponse,
+        crate::api::task::BatchGetResponse,
+        crate::api::task::BatchSubmitResponse,
+        crate::api::task::SubmittedTask,
+        crate::api::task::SubmissionStatus,
+        crate::api::task::ValidationResponse
+    )),
+    modifiers(&SecurityAddon),
     info(
         title = "TacoQ Relay API",
         version = "0.4.0",
@@ -16,6 +71,19 @@ use crate::lifecycle::AppState;
 )]
 struct ApiDoc;
 
+/// Registers the bearer token scheme used by the admin endpoints.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "admin_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
 pub fn routes() -> Router<AppState> {
     debug!("Setting up OpenAPI documentation routes");
     Router::new().route("/openapi.json", get(openapi))
@@ -1,345 +1,3703 @@
 use axum::{
-    extract::{Path, State},
-    http::{header, HeaderMap, StatusCode},
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
-    routing::get,
-    Router,
+    routing::{get, post},
+    Json, Router,
 };
-use tracing::{debug, error, info, instrument};
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::api::admin::AdminGuard;
+use crate::api::avro_stream::write_avro_container;
+use crate::api::error::{ApiError, ErrorResponse};
+use crate::constants::{
+    AVRO_STREAM_CHUNK_BYTES, AVRO_STREAM_THRESHOLD_BYTES, DEFAULT_SYNC_TASK_TIMEOUT_SECS,
+    DEFAULT_TASK_PAGE_SIZE, MAX_BATCH_GET_SIZE, MAX_SYNC_TASK_POLL_INTERVAL_MS,
+    MAX_SYNC_TASK_TIMEOUT_SECS, MAX_TASK_PAGE_SIZE, SYNC_TASK_POLL_INTERVAL_MS,
+    TASK_CHANGES_LAG_SECS,
+};
 use crate::lifecycle::AppState;
-use crate::models::{AvroSerializable, Task};
+use crate::models::{
+    deserialize_timestamp_opt, inject_context, AvroSerializable, Task, TaskAssignmentUpdate,
+    TaskChanges, TaskCursor, TaskEvent, TaskPage, TaskResult, TaskSpec, TaskStats, Worker,
+};
+use crate::task_event_consumer::Event;
 
 pub fn routes() -> Router<AppState> {
     debug!("Setting up task API routes");
-    Router::new().route("/{id}", get(get_task_by_id))
+    Router::new()
+        .route("/", get(list_tasks).delete(delete_tasks))
+        .route("/stats", get(get_task_stats))
+        .route("/changes", get(list_task_changes))
+        .route("/batch", post(submit_tasks))
+        .route("/validate", post(validate_tasks))
+        .route("/sync", post(submit_task_sync))
+        .route("/batch-get", post(batch_get_tasks))
+        .route("/{id}", get(get_task_by_id))
+        .route("/{id}/input", get(get_task_input))
+        .route("/{id}/result", get(get_task_result))
+        .route("/{id}/worker", get(get_task_worker))
+        .route("/{id}/history", get(get_task_history))
+        .route("/{id}/requeue", post(requeue_task))
 }
 
-/// Get a task by its UUID
+/// Time window applied to the stats on the task creation date
+#[derive(Debug, Deserialize, IntoParams)]
+struct TaskStatsQuery {
+    /// Only count tasks created at or after this time
+    #[serde(default, deserialize_with = "deserialize_timestamp_opt")]
+    since: Option<NaiveDateTime>,
+    /// Only count tasks created before this time
+    #[serde(default, deserialize_with = "deserialize_timestamp_opt")]
+    until: Option<NaiveDateTime>,
+}
+
+/// Get aggregate task counts
 ///
 /// # Arguments
-/// * `id` - UUID of the task to retrieve
+/// * `since` - Optional lower bound (inclusive) on the task creation date
+/// * `until` - Optional upper bound (exclusive) on the task creation date
 ///
 /// # Returns
-/// Returns a response containing the task if found, either in JSON or Avro format based on Accept header
+/// Returns the task counts grouped by status and by worker kind
 #[utoipa::path(
     get,
-    description = "Get a task by its UUID",
-    path = "/tasks/{id}",
-    params(
-        ("id" = Uuid, Path, description = "Task ID to get")
-    ),
+    description = "Get task counts grouped by status and worker kind",
+    path = "/tasks/stats",
+    params(TaskStatsQuery),
     responses(
-        (status = 200, description = "Task found", body = Task, content_type = "application/json"),
-        (status = 200, description = "Task found (Avro format)", content_type = "application/avro"),
-        (status = 404, description = "Task not found", content_type = "text/plain"),
+        (status = 200, description = "Task stats", body = TaskStats, content_type = "application/json"),
         (status = 500, description = "Internal server error", content_type = "text/plain")
     ),
     tag = "tasks"
 )]
-#[instrument(skip(state, headers), fields(task_id = %id))]
-async fn get_task_by_id(
+#[instrument(skip(state))]
+async fn get_task_stats(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
-    headers: HeaderMap,
-) -> Result<TaskResponse, (StatusCode, String)> {
-    info!(task_id = %id, "API request: Get task by ID");
-
-    let result: Result<Option<Task>, sqlx::Error> =
-        match state.task_repository.get_task_by_id(&id).await {
-            Ok(task) => Ok(task),
-            Err(e) => {
-                error!(
-                    task_id = %id,
-                    error = %e,
-                    "Database error while fetching task"
-                );
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Failed to get task: {}", e),
-                ));
-            }
-        };
-
-    match result {
-        Ok(Some(task)) => {
-            info!(
-                task_id = %id,
-                task_kind = %task.clone().task_kind.unwrap_or("None".to_string()),
-                "Successfully retrieved task"
-            );
+    Query(query): Query<TaskStatsQuery>,
+) -> Result<Json<TaskStats>, (StatusCode, String)> {
+    info!(since = ?query.since, until = ?query.until, "API request: Get task stats");
 
-            // Determine response format based on Accept header
-            let format = determine_response_format(&headers);
-            debug!(task_id = %id, format = ?format, "Determined response format");
+    let status_counts = state
+        .task_repository
+        .count_tasks_by_status(query.since, query.until)
+        .await;
+    let worker_kind_counts = state
+        .task_repository
+        .count_tasks_by_worker_kind(query.since, query.until)
+        .await;
 
-            Ok(TaskResponse { task, format })
+    match (status_counts, worker_kind_counts) {
+        (Ok(status_counts), Ok(worker_kind_counts)) => {
+            let stats = TaskStats::from_counts(status_counts, worker_kind_counts);
+            debug!(total = stats.total, "Successfully computed task stats");
+            Ok(Json(stats))
         }
-        Ok(None) => {
-            debug!(task_id = %id, "Task not found");
+        (Err(e), _) | (_, Err(e)) => {
+            error!(error = %e, "Database error while computing task stats");
             Err((
-                StatusCode::NOT_FOUND,
-                format!("Task with ID {} not found", id),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get task stats: {}", e),
             ))
         }
-        Err(_) => unreachable!(), // We handled this above
     }
 }
 
-/// Determines the response format based on the Accept header
-fn determine_response_format(headers: &HeaderMap) -> ResponseFormat {
-    // Default to JSON if no Accept header is present
-    let accept = match headers.get(header::ACCEPT) {
-        Some(value) => match value.to_str() {
-            Ok(s) => s,
-            Err(_) => return ResponseFormat::Json,
-        },
-        None => return ResponseFormat::Json,
-    };
+/// Filters applied when listing tasks
+#[derive(Debug, Deserialize, IntoParams)]
+struct ListTasksQuery {
+    /// Only list tasks of this worker kind
+    worker_kind: Option<String>,
+    /// Only list tasks whose JSON input contains this JSON value. Only inputs
+    /// published as `application/json` can match.
+    input_contains: Option<String>,
+    /// Cursor returned as `next_cursor` by the previous page
+    after: Option<String>,
+    /// Maximum number of tasks in the page, 100 by default
+    limit: Option<i64>,
+}
+
+/// List tasks ordered by creation date
+///
+/// # Arguments
+/// * `worker_kind` - Optional worker kind the tasks must have
+/// * `input_contains` - Optional JSON value the task input must contain
+/// * `after` - Optional cursor of the page to list
+/// * `limit` - Optional size of the page to list
+///
+/// # Returns
+/// Returns a page of tasks, newest first, with the cursor of the next page.
+/// Pages hold 100 tasks unless `limit` says otherwise. When Avro is requested
+/// without `after` or `limit`, returns every task as an Avro Object Container
+/// File streamed from the database instead.
+#[utoipa::path(
+    get,
+    description = "List tasks ordered by creation date, newest first. Pass the returned `next_cursor` as `after` to page through the tasks. Avro requests without `after` or `limit` stream every task instead.",
+    path = "/tasks",
+    params(ListTasksQuery),
+    responses(
+        (status = 200, description = "Page of tasks", body = TaskPage, content_type = "application/json"),
+        (status = 200, description = "Every task (Avro Object Container File)", content_type = "application/avro"),
+        (status = 400, description = "Invalid cursor, limit or input filter", content_type = "text/plain"),
+        (status = 406, description = "No supported format is acceptable", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state, headers))]
+async fn list_tasks(
+    State(state): State<AppState>,
+    Query(query): Query<ListTasksQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    info!(
+        worker_kind = ?query.worker_kind,
+        input_contains = ?query.input_contains,
+        "API request: List tasks"
+    );
 
-    // Parse accept header parts
-    let mut json_quality = 0.0;
-    let mut avro_quality = 0.0;
+    let input_contains = query
+        .input_contains
+        .as_deref()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .transpose()
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("The input filter must be valid JSON: {}", e),
+            )
+        })?;
 
-    // Check for wildcard
-    if accept.contains("*/*") {
-        json_quality = 1.0;
+    // Only the Avro export lists every task, as it never holds them all
+    let format = determine_response_format(&headers, state.allow_avro)?;
+    if format != ResponseFormat::Avro || query.after.is_some() || query.limit.is_some() {
+        return list_task_page(state, query, input_contains, format).await;
     }
 
-    // Look for quality values or defaults
-    for part in accept.split(',').map(|s| s.trim()) {
-        if part.starts_with("application/json") {
-            json_quality = extract_quality(part).unwrap_or(1.0);
-        } else if part.starts_with("application/avro") {
-            avro_quality = extract_quality(part).unwrap_or(1.0);
-        }
+    // Records are encoded as they are read from the database, so a database
+    // error past this point aborts the body instead
+    let (sender, receiver) = futures::channel::mpsc::channel(16);
+    let task_repository = state.task_repository.clone();
+    tokio::spawn(async move {
+        let tasks =
+            task_repository.stream_tasks(query.worker_kind.as_deref(), input_contains.as_ref());
+        write_avro_container(tasks, sender).await;
+    });
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/avro")],
+        Body::from_stream(receiver),
+    )
+        .into_response())
+}
+
+/// Lists a page of tasks, fetching one extra task to know whether another
+/// page follows. Pages are encoded as JSON unless MessagePack is requested.
+async fn list_task_page(
+    state: AppState,
+    query: ListTasksQuery,
+    input_contains: Option<serde_json::Value>,
+    format: ResponseFormat,
+) -> Result<Response, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(DEFAULT_TASK_PAGE_SIZE);
+    if !(1..=MAX_TASK_PAGE_SIZE).contains(&limit) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("The limit must be between 1 and {}", MAX_TASK_PAGE_SIZE),
+        ));
     }
+    let after = query
+        .after
+        .as_deref()
+        .map(str::parse::<TaskCursor>)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let mut tasks = state
+        .task_repository
+        .list_tasks_after(
+            after,
+            query.worker_kind.as_deref(),
+            input_contains.as_ref(),
+            limit + 1,
+        )
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Database error while listing a page of tasks");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to list tasks: {}", e),
+            )
+        })?;
 
-    // Choose format based on quality values
-    if avro_quality > 0.0 && avro_quality >= json_quality {
-        ResponseFormat::Avro
-    } else if json_quality > 0.0 {
-        ResponseFormat::Json
+    let next_cursor = if tasks.len() as i64 > limit {
+        tasks.truncate(limit as usize);
+        tasks.last().map(|task| TaskCursor::after(task).encode())
     } else {
-        // Default to JSON if no matching media type
-        ResponseFormat::Json
+        None
+    };
+    debug!(
+        count = tasks.len(),
+        has_next = next_cursor.is_some(),
+        "Successfully listed a page of tasks"
+    );
+
+    let page = TaskPage { tasks, next_cursor };
+    match format {
+        ResponseFormat::MessagePack => Ok(msgpack_response(&page)),
+        _ => Ok(Json(page).into_response()),
     }
 }
 
-/// Extracts the quality value (q parameter) from an Accept header part
-fn extract_quality(part: &str) -> Option<f32> {
-    if let Some(q_idx) = part.find(";q=") {
-        let q_value = &part[(q_idx + 3)..];
-        if let Some(end_idx) = q_value.find(';') {
-            q_value[..end_idx].parse::<f32>().ok()
-        } else {
-            q_value.parse::<f32>().ok()
-        }
-    } else {
-        None
+/// Position and size of the changes to list
+#[derive(Debug, Deserialize, IntoParams)]
+struct TaskChangesQuery {
+    /// Only list tasks updated after this date, usually the `cursor` returned
+    /// by the previous call. Every task is listed if not given.
+    #[serde(default, deserialize_with = "deserialize_timestamp_opt")]
+    since: Option<NaiveDateTime>,
+    /// Maximum number of tasks to list, besides the ones updated at the same
+    /// time as the last one
+    limit: Option<i64>,
+}
+
+/// List the tasks changed since a date
+///
+/// # Arguments
+/// * `since` - Optional date the tasks must have been updated after
+/// * `limit` - Optional number of tasks to list
+///
+/// # Returns
+/// Returns the changed tasks, oldest update first, with the latest update
+/// date among them as the cursor of the next call. Deleted and archived
+/// tasks are never reported, as explained on
+/// [`crate::repo::TaskRepository::list_changed_since`].
+#[utoipa::path(
+    get,
+    description = "List the tasks updated after `since`, oldest update first, to export them incrementally. \
+        Pass the returned `cursor` as `since` to get the next changes. \
+        Tasks updated at the same time as the last listed task are always listed with it, so a page may exceed `limit`. \
+        Updates are listed a few seconds after they happen, once every update before them has been committed. \
+        Deleted and archived tasks aren't listed, not even as removals. \
+        An export following the feed keeps them until it is compared against a full listing of `/tasks`.",
+    path = "/tasks/changes",
+    params(TaskChangesQuery),
+    responses(
+        (status = 200, description = "Changed tasks", body = TaskChanges, content_type = "application/json"),
+        (status = 400, description = "Invalid date or limit", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state))]
+async fn list_task_changes(
+    State(state): State<AppState>,
+    Query(query): Query<TaskChangesQuery>,
+) -> Result<Json<TaskChanges>, (StatusCode, String)> {
+    info!(since = ?query.since, "API request: List task changes");
+
+    let limit = query.limit.unwrap_or(DEFAULT_TASK_PAGE_SIZE);
+    if !(1..=MAX_TASK_PAGE_SIZE).contains(&limit) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("The limit must be between 1 and {}", MAX_TASK_PAGE_SIZE),
+        ));
     }
+    let since = query.since.unwrap_or(DateTime::UNIX_EPOCH.naive_utc());
+
+    let tasks = state
+        .task_repository
+        .list_changed_since(since, limit, TASK_CHANGES_LAG_SECS)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Database error while listing task changes");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to list task changes: {}", e),
+            )
+        })?;
+
+    let cursor = tasks.last().map_or(since, |task| task.updated_at);
+    debug!(count = tasks.len(), cursor = %cursor, "Successfully listed task changes");
+
+    Ok(Json(TaskChanges { tasks, cursor }))
 }
 
-/// Response format enum
-#[derive(Debug)]
-enum ResponseFormat {
-    Json,
-    Avro,
+/// Filters selecting the tasks to delete. A task must match all of them.
+#[derive(Debug, Deserialize, IntoParams)]
+struct DeleteTasksQuery {
+    /// Only delete tasks of this worker kind
+    worker_kind: Option<String>,
+    /// Only delete tasks created before this time
+    #[serde(default, deserialize_with = "deserialize_timestamp_opt")]
+    before: Option<NaiveDateTime>,
 }
 
-/// Task response wrapper that handles content negotiation
-struct TaskResponse {
-    task: Task,
-    format: ResponseFormat,
+/// Number of tasks removed by a delete.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeleteTasksResponse {
+    pub deleted: u64,
 }
 
-impl IntoResponse for TaskResponse {
-    fn into_response(self) -> Response {
-        match self.format {
-            ResponseFormat::Json => {
-                // Return JSON response
-                (
-                    StatusCode::OK,
-                    [(header::CONTENT_TYPE, "application/json")],
-                    axum::Json(self.task),
-                )
-                    .into_response()
-            }
-            ResponseFormat::Avro => {
-                // Convert task to Avro binary format using the convenience method
-                match self.task.try_into_avro_bytes() {
-                    Ok(avro_bytes) => (
-                        StatusCode::OK,
-                        [(header::CONTENT_TYPE, "application/avro")],
-                        avro_bytes,
-                    )
-                        .into_response(),
-                    Err(e) => {
-                        error!(
-                            task_id = %self.task.id,
-                            error = %e,
-                            "Failed to convert task to Avro bytes"
-                        );
-                        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
-                    }
-                }
-            }
+/// Delete all the tasks matching a filter
+///
+/// # Arguments
+/// * `worker_kind` - Optional worker kind the tasks must have
+/// * `before` - Optional upper bound (exclusive) on the task creation date
+///
+/// # Returns
+/// Returns the number of deleted tasks. At least one filter is required.
+#[utoipa::path(
+    delete,
+    description = "Delete all the tasks matching a filter. Requires the admin token.",
+    path = "/tasks",
+    params(DeleteTasksQuery),
+    responses(
+        (status = 200, description = "Tasks deleted", body = DeleteTasksResponse, content_type = "application/json"),
+        (status = 400, description = "No filter supplied", content_type = "text/plain"),
+        (status = 401, description = "Missing admin token", content_type = "text/plain"),
+        (status = 403, description = "Invalid admin token or admin endpoints disabled", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    security(("admin_token" = [])),
+    tag = "tasks"
+)]
+#[instrument(skip(state, _admin))]
+async fn delete_tasks(
+    _admin: AdminGuard,
+    State(state): State<AppState>,
+    Query(query): Query<DeleteTasksQuery>,
+) -> Result<Json<DeleteTasksResponse>, (StatusCode, String)> {
+    if query.worker_kind.is_none() && query.before.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "At least one filter (worker_kind, before) is required".to_string(),
+        ));
+    }
+
+    warn!(
+        worker_kind = ?query.worker_kind,
+        before = ?query.before,
+        "API request: Deleting tasks by filter"
+    );
+
+    match state
+        .task_repository
+        .delete_tasks_by_filter(query.worker_kind.as_deref(), query.before)
+        .await
+    {
+        Ok(deleted) => {
+            warn!(
+                worker_kind = ?query.worker_kind,
+                before = ?query.before,
+                deleted = deleted,
+                "Deleted tasks by filter"
+            );
+            Ok(Json(DeleteTasksResponse { deleted }))
+        }
+        Err(e) => {
+            error!(error = %e, "Database error while deleting tasks");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to delete tasks: {}", e),
+            ))
         }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use crate::models::{AvroSerializable, Task};
-    use axum::http::{HeaderMap, HeaderValue, StatusCode};
-    use sqlx::PgPool;
-    use uuid::Uuid;
+/// Tasks found by a batch lookup, and the requested IDs that have no task.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchGetResponse {
+    pub tasks: Vec<Task>,
+    pub missing: Vec<Uuid>,
+}
 
-    use crate::{
-        repo::{PgRepositoryCore, TaskRepository},
-        testing::test::{get_test_server, init_test_logger},
-    };
+/// Get several tasks by their UUIDs
+///
+/// # Arguments
+/// * `ids` - JSON array of the UUIDs of the tasks to retrieve
+///
+/// # Returns
+/// Returns the tasks found, in the order they were requested, along with the
+/// IDs that have no task. When Avro is requested, the found tasks are
+/// returned as an Avro Object Container File instead.
+#[utoipa::path(
+    post,
+    description = "Get several tasks by their UUIDs in a single request",
+    path = "/tasks/batch-get",
+    request_body(content = Vec<Uuid>, description = "IDs of the tasks to get", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Tasks found and missing IDs", body = BatchGetResponse, content_type = "application/json"),
+        (status = 200, description = "Tasks found (Avro Object Container File)", content_type = "application/avro"),
+        (status = 400, description = "Too many IDs requested", content_type = "text/plain"),
+        (status = 406, description = "No supported format is acceptable", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state, headers, ids), fields(count = ids.len()))]
+async fn batch_get_tasks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(ids): Json<Vec<Uuid>>,
+) -> Result<Response, (StatusCode, String)> {
+    info!(count = ids.len(), "API request: Batch get tasks");
 
-    // This runs before any test in this module
-    #[ctor::ctor]
-    fn init() {
-        init_test_logger();
+    if ids.len() > MAX_BATCH_GET_SIZE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "At most {} task IDs can be requested at once",
+                MAX_BATCH_GET_SIZE
+            ),
+        ));
     }
+    let format = determine_response_format(&headers, state.allow_avro)?;
 
-    fn get_test_task() -> Task {
-        Task::new("TaskKindName", "WorkerKindName", 0, 0)
-            .with_input_data(vec![1, 2, 3])
-            .with_output_data(vec![4, 5, 6])
-            .with_error(false)
-    }
+    let mut found: HashMap<Uuid, Task> = state
+        .task_repository
+        .find_by_ids(&ids)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Database error while fetching tasks by ID");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get tasks: {}", e),
+            )
+        })?
+        .into_iter()
+        .map(|task| (task.id, task))
+        .collect();
 
-    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn test_non_existent_task_by_id(db_pools: PgPool) {
-        let server = get_test_server(db_pools).await;
+    // Answer in request order, listing each ID once
+    let mut seen = HashSet::new();
+    let mut tasks = Vec::with_capacity(found.len());
+    let mut missing = Vec::new();
+    for id in ids.into_iter().filter(|id| seen.insert(*id)) {
+        match found.remove(&id) {
+            Some(task) => tasks.push(task),
+            None => missing.push(id),
+        }
+    }
+    debug!(
+        found = tasks.len(),
+        missing = missing.len(),
+        "Successfully fetched tasks by ID"
+    );
 
-        let response = server.get(&format!("/tasks/{}", Uuid::new_v4())).await;
+    match format {
+        ResponseFormat::Json => Ok(Json(BatchGetResponse { tasks, missing }).into_response()),
+        ResponseFormat::MessagePack => Ok(msgpack_response(&BatchGetResponse { tasks, missing })),
+        ResponseFormat::Avro => {
+            let (sender, receiver) = futures::channel::mpsc::channel(16);
+            let records = futures::stream::iter(tasks.into_iter().map(Ok::<_, sqlx::Error>));
+            tokio::spawn(write_avro_container(records, sender));
 
-        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+            Ok((
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/avro")],
+                Body::from_stream(receiver),
+            )
+                .into_response())
+        }
     }
+}
 
-    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn test_get_existing_task_by_id(db_pools: PgPool) {
-        let server = get_test_server(db_pools.clone()).await;
-        let core = PgRepositoryCore::new(db_pools.clone());
-        let task_repository = TaskRepository::new(core.clone());
-
-        let test_task = get_test_task();
+/// Whether the assignment of a submitted task reached the broker, or is
+/// held until the task is scheduled to start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    Published,
+    Scheduled,
+    Failed,
+}
 
-        task_repository.create_task(&test_task).await.unwrap();
+/// Outcome of one task of a batch submission. Failed tasks are stored but
+/// stay pending until their assignment is published again.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SubmittedTask {
+    pub id: Uuid,
+    pub status: SubmissionStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
 
-        let response = server.get(&format!("/tasks/{}", test_task.id)).await;
-        assert_eq!(response.status_code(), StatusCode::OK);
-        assert_eq!(
-            response
-                .headers()
-                .get(axum::http::header::CONTENT_TYPE)
-                .unwrap(),
-            "application/json"
-        );
+/// Outcome of every task of a batch submission, in request order.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchSubmitResponse {
+    pub tasks: Vec<SubmittedTask>,
+}
 
-        let response_body = response.json::<Task>();
-        assert_eq!(response_body.id, test_task.id);
+/// Checks a batch of tasks can be submitted. Shared by the submission and
+/// its dry run, so both accept exactly the same batches.
+///
+/// # Returns
+/// Every problem found, prefixed with the index of its task, empty if the
+/// batch is valid
+fn validate_submission(state: &AppState, specs: &[TaskSpec]) -> Vec<String> {
+    if specs.is_empty() {
+        return vec!["No tasks to submit".to_string()];
+    }
+    if specs.len() > state.max_submit_batch_size {
+        return vec![format!(
+            "At most {} tasks can be submitted at once",
+            state.max_submit_batch_size
+        )];
     }
 
-    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn test_get_existing_task_by_id_avro(db_pools: PgPool) {
-        let server = get_test_server(db_pools.clone()).await;
-        let core = PgRepositoryCore::new(db_pools.clone());
-        let task_repository = TaskRepository::new(core.clone());
+    specs
+        .iter()
+        .enumerate()
+        .flat_map(|(index, spec)| {
+            spec.validate(state.max_payload_bytes)
+                .into_iter()
+                .map(move |problem| format!("Task {}: {}", index, problem))
+        })
+        .collect()
+}
 
-        let test_task = get_test_task();
+/// Finds the worker kinds of a batch no worker registered with yet, in
+/// alphabetical order.
+async fn unknown_worker_kinds<'a>(
+    state: &AppState,
+    specs: &'a [TaskSpec],
+) -> Result<Vec<&'a str>, (StatusCode, String)> {
+    let worker_kinds: BTreeSet<&str> = specs
+        .iter()
+        .map(|spec| spec.worker_kind.as_str())
+        .filter(|worker_kind| !worker_kind.trim().is_empty())
+        .collect();
 
-        task_repository.create_task(&test_task).await.unwrap();
+    let mut unknown = Vec::new();
+    for worker_kind in worker_kinds {
+        let exists = state
+            .worker_repository
+            .worker_kind_exists(worker_kind)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Database error while checking worker kinds");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to check worker kinds: {}", e),
+                )
+            })?;
+        if !exists {
+            unknown.push(worker_kind);
+        }
+    }
+    Ok(unknown)
+}
 
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            axum::http::header::ACCEPT,
-            HeaderValue::from_static("application/avro"),
-        );
+/// Why a batch is rejected in strict worker kind mode
+fn unknown_worker_kind_error(worker_kind: &str) -> String {
+    format!(
+        "Unknown worker kind {}, no worker of this kind has registered",
+        worker_kind
+    )
+}
 
-        let response = server
-            .get(&format!("/tasks/{}", test_task.id))
-            .add_header(
-                axum::http::header::ACCEPT,
-                HeaderValue::from_static("application/avro"),
+/// Validates the inputs declared as JSON against the schema of their task
+/// kind. Tasks of kinds without a schema and other inputs are left alone.
+///
+/// # Returns
+/// Every way the inputs don't conform, empty if they all do
+async fn input_schema_errors(
+    state: &AppState,
+    specs: &[TaskSpec],
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let task_kinds: Vec<String> = specs
+        .iter()
+        .map(|spec| spec.task_kind.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let schemas = state
+        .task_repository
+        .get_task_kind_input_schemas(&task_kinds)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Database error while fetching task kind input schemas");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get task kind input schemas: {}", e),
             )
-            .await;
-
-        assert_eq!(response.status_code(), StatusCode::OK);
-        assert_eq!(
-            response
-                .headers()
-                .get(axum::http::header::CONTENT_TYPE)
-                .unwrap(),
-            "application/avro"
-        );
+        })?;
 
-        let response_body = response.as_bytes().to_vec();
-        let expected_task = Task::try_from_avro_bytes(&response_body).unwrap();
-        assert_eq!(expected_task.id, test_task.id);
+    // Schemas are checked when they are set, so they only fail to compile
+    // if they were written to the database directly
+    let mut validators = HashMap::with_capacity(schemas.len());
+    for (task_kind, schema) in &schemas {
+        let validator = jsonschema::validator_for(schema).map_err(|e| {
+            error!(task_kind = %task_kind, error = %e, "Invalid task kind input schema");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Invalid input schema of task kind {}: {}", task_kind, e),
+            )
+        })?;
+        validators.insert(task_kind.as_str(), validator);
     }
 
-    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn test_content_negotiation_quality_values(db_pools: PgPool) {
-        let server = get_test_server(db_pools.clone()).await;
-        let core = PgRepositoryCore::new(db_pools.clone());
-        let task_repository = TaskRepository::new(core.clone());
+    let mut problems = Vec::new();
+    for (index, spec) in specs.iter().enumerate() {
+        let Some(validator) = validators.get(spec.task_kind.as_str()) else {
+            continue;
+        };
+        match spec.input_json() {
+            Ok(Some(input)) => problems.extend(
+                validator
+                    .iter_errors(&input)
+                    .map(|e| format!("Task {}: input_data{}: {}", index, e.instance_path, e)),
+            ),
+            Ok(None) => {}
+            Err(e) => problems.push(format!(
+                "Task {}: input_data is not valid JSON: {}",
+                index, e
+            )),
+        }
+    }
+    Ok(problems)
+}
 
-        let test_task = get_test_task();
-        task_repository.create_task(&test_task).await.unwrap();
+/// Outcome of the dry run of a batch submission.
+///
+/// # Fields
+/// * `valid` - Whether the batch would be accepted
+/// * `errors` - Why the batch would be rejected
+/// * `warnings` - Problems that don't prevent submitting, such as a worker
+///   kind no worker registered with yet
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ValidationResponse {
+    pub valid: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
 
-        // Test with higher quality for JSON
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            axum::http::header::ACCEPT,
-            HeaderValue::from_static("application/json;q=0.9, application/avro;q=0.8"),
+/// Validate a batch of tasks without submitting it
+///
+/// Runs the checks of `POST /tasks/batch`, without storing or publishing
+/// anything. Tasks of a worker kind no worker registered with are still
+/// valid, as they wait in the queue until one does, but are warned about.
+/// With `TACOQ_STRICT_WORKER_KIND` set, they are invalid instead.
+///
+/// # Arguments
+/// * `specs` - JSON array of the tasks to validate
+///
+/// # Returns
+/// Returns whether the batch is valid, with the problems found
+#[utoipa::path(
+    post,
+    description = "Validate a batch of tasks as if submitted, without storing or publishing it",
+    path = "/tasks/validate",
+    request_body = Vec<TaskSpec>,
+    responses(
+        (status = 200, description = "The batch would be accepted", body = ValidationResponse, content_type = "application/json"),
+        (status = 422, description = "The batch would be rejected", body = ValidationResponse, content_type = "application/json"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state, specs), fields(count = specs.len()))]
+async fn validate_tasks(
+    State(state): State<AppState>,
+    Json(specs): Json<Vec<TaskSpec>>,
+) -> Result<(StatusCode, Json<ValidationResponse>), (StatusCode, String)> {
+    info!(count = specs.len(), "API request: Validate tasks");
+
+    let mut errors = validate_submission(&state, &specs);
+    if errors.is_empty() {
+        errors = input_schema_errors(&state, &specs).await?;
+    }
+
+    let mut warnings = Vec::new();
+    for worker_kind in unknown_worker_kinds(&state, &specs).await? {
+        if state.strict_worker_kind {
+            errors.push(unknown_worker_kind_error(worker_kind));
+        } else {
+            warnings.push(format!(
+                "No worker of kind {} has registered yet, its tasks will wait until one does",
+                worker_kind
+            ));
+        }
+    }
+
+    debug!(
+        errors = errors.len(),
+        warnings = warnings.len(),
+        "Validated tasks"
+    );
+    let status = if errors.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+    Ok((
+        status,
+        Json(ValidationResponse {
+            valid: errors.is_empty(),
+            errors,
+            warnings,
+        }),
+    ))
+}
+
+/// Submit several tasks at once
+///
+/// The tasks are stored in a single transaction, so either all of them are
+/// or none is, then their assignments are published one by one. Tasks
+/// without a priority or TTL get the defaults of their kind. The broker
+/// can't take part in the transaction, so a publish failing midway is
+/// reported per task instead of undoing the batch. Tasks scheduled for later
+/// aren't published, the scheduled task job publishes them once they're due.
+/// With `TACOQ_STRICT_WORKER_KIND` set, batches with a task of a worker kind
+/// no worker registered with are rejected. Inputs declared as JSON must
+/// conform to the input schema of their task kind, if it has one.
+///
+/// # Arguments
+/// * `specs` - JSON array of the tasks to submit
+///
+/// # Returns
+/// Returns the id and publish status of every task, with `201 Created` if
+/// they were all published and `207 Multi-Status` otherwise
+#[utoipa::path(
+    post,
+    description = "Store a batch of tasks atomically and publish their assignments",
+    path = "/tasks/batch",
+    request_body = Vec<TaskSpec>,
+    responses(
+        (status = 201, description = "Every task stored and published", body = BatchSubmitResponse, content_type = "application/json"),
+        (status = 207, description = "Every task stored, some not published", body = BatchSubmitResponse, content_type = "application/json"),
+        (status = 400, description = "Empty or too large batch, invalid task, or unknown worker kind in strict mode", content_type = "text/plain"),
+        (status = 422, description = "Input not conforming to the schema of its task kind", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain"),
+        (status = 503, description = "Task event publisher disabled", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state, specs), fields(count = specs.len()))]
+async fn submit_tasks(
+    State(state): State<AppState>,
+    Json(specs): Json<Vec<TaskSpec>>,
+) -> Result<(StatusCode, Json<BatchSubmitResponse>), (StatusCode, String)> {
+    info!(count = specs.len(), "API request: Submit tasks");
+
+    let tasks = submit_specs(&state, specs).await?;
+
+    let failed = tasks
+        .iter()
+        .filter(|task| task.status == SubmissionStatus::Failed)
+        .count();
+    info!(count = tasks.len(), failed = failed, "Submitted tasks");
+    let status = if failed == 0 {
+        StatusCode::CREATED
+    } else {
+        StatusCode::MULTI_STATUS
+    };
+    Ok((status, Json(BatchSubmitResponse { tasks })))
+}
+
+/// Validates, stores and publishes a batch of tasks. Shared by the batch
+/// submission and the synchronous one.
+///
+/// # Returns
+/// The outcome of every task, in request order
+async fn submit_specs(
+    state: &AppState,
+    specs: Vec<TaskSpec>,
+) -> Result<Vec<SubmittedTask>, (StatusCode, String)> {
+    let Some(publisher) = state.task_event_publisher.clone() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Task event publisher is disabled".to_string(),
+        ));
+    };
+    let mut problems = validate_submission(state, &specs);
+    // Catches typos in the worker kind before the tasks sit unrouted
+    if problems.is_empty() && state.strict_worker_kind {
+        problems = unknown_worker_kinds(state, &specs)
+            .await?
+            .into_iter()
+            .map(unknown_worker_kind_error)
+            .collect();
+    }
+    if !problems.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, problems.join("\n")));
+    }
+    let problems = input_schema_errors(state, &specs).await?;
+    if !problems.is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, problems.join("\n")));
+    }
+
+    // Settings left out of a task come from its kind
+    let task_kinds: Vec<String> = specs
+        .iter()
+        .map(|spec| spec.task_kind.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let defaults = state
+        .task_repository
+        .get_task_kind_defaults(&task_kinds)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Database error while fetching task kind defaults");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get task kind defaults: {}", e),
+            )
+        })?;
+
+    // Assignments are published with the routing key stored for their worker kind
+    let mut routing_keys = HashMap::new();
+    for spec in &specs {
+        if routing_keys.contains_key(&spec.worker_kind) {
+            continue;
+        }
+        let routing_key = state
+            .worker_repository
+            .routing_key_of(&spec.worker_kind)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Database error while fetching worker kind routing keys");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to get worker kind routing keys: {}", e),
+                )
+            })?;
+        routing_keys.insert(spec.worker_kind.clone(), routing_key);
+    }
+
+    // Tasks submitted without a trace context continue the trace of the request
+    let request_context = inject_context(&Span::current().context());
+    let created_at = chrono::Utc::now().naive_utc();
+    let assignments: Vec<TaskAssignmentUpdate> = specs
+        .into_iter()
+        .map(|mut spec| {
+            if spec.otel_ctx_carrier.is_empty() {
+                spec.otel_ctx_carrier = request_context.clone();
+            }
+            let kind_defaults = defaults.get(&spec.task_kind).copied().unwrap_or_default();
+            spec.into_assignment(created_at, &kind_defaults)
+        })
+        .collect();
+
+    state
+        .task_repository
+        .create_tasks_from_assignments(&assignments)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Database error while storing submitted tasks");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to store tasks: {}", e),
+            )
+        })?;
+
+    let mut tasks = Vec::with_capacity(assignments.len());
+    for assignment in assignments {
+        let id = assignment.id;
+        if assignment.held_until().is_some() {
+            tasks.push(SubmittedTask {
+                id,
+                status: SubmissionStatus::Scheduled,
+                error: None,
+            });
+            continue;
+        }
+        let routing_key = &routing_keys[&assignment.worker_kind];
+        let outcome = publisher
+            .publish(&Event::Assignment(assignment), routing_key)
+            .await;
+        tasks.push(match outcome {
+            Ok(()) => SubmittedTask {
+                id,
+                status: SubmissionStatus::Published,
+                error: None,
+            },
+            Err(e) => {
+                error!(task_id = %id, error = %e, "Failed to publish submitted task assignment");
+                SubmittedTask {
+                    id,
+                    status: SubmissionStatus::Failed,
+                    error: Some(e.to_string()),
+                }
+            }
+        });
+    }
+    Ok(tasks)
+}
+
+/// Options of a synchronous task submission
+#[derive(Debug, Deserialize, IntoParams)]
+struct SubmitSyncQuery {
+    /// Seconds to wait for the task to complete, from 1 to 60 and below the
+    /// request timeout of the relay. Defaults to 10.
+    timeout: Option<u64>,
+}
+
+/// Longest a synchronous submission may wait for its task. It stays a second
+/// below the request timeout, so the relay answers before the request is
+/// aborted.
+///
+/// # Arguments
+/// * `request_timeout` - The time after which the relay aborts requests
+fn max_sync_timeout_secs(request_timeout: Duration) -> u64 {
+    MAX_SYNC_TASK_TIMEOUT_SECS
+        .min(request_timeout.as_secs().saturating_sub(1))
+        .max(1)
+}
+
+/// Submit a task and wait for its result
+///
+/// The task is submitted like with `POST /tasks/batch`, then the request
+/// waits for a worker to complete it. The task isn't cancelled when the wait
+/// times out, its result can still be fetched from `GET /tasks/{id}/result`.
+/// The wait is capped below the request timeout of the relay.
+///
+/// # Arguments
+/// * `timeout` - Seconds to wait for the task to complete
+/// * `spec` - The task to submit
+///
+/// # Returns
+/// Returns the raw output of the task with the content type declared by the
+/// worker, flagged with the `x-task-error` header if the task failed, or
+/// `408 Request Timeout` if it didn't complete in time
+#[utoipa::path(
+    post,
+    description = "Submit a task and wait for a worker to complete it, returning its output",
+    path = "/tasks/sync",
+    params(SubmitSyncQuery),
+    request_body = TaskSpec,
+    responses(
+        (status = 200, description = "Task output", content_type = "application/octet-stream",
+            headers(("x-task-error" = bool, description = "Set to true when the task failed and the output is its error"))),
+        (status = 400, description = "Invalid task or timeout, or unknown worker kind in strict mode", content_type = "text/plain"),
+        (status = 408, description = "Task not completed in time", content_type = "text/plain"),
+        (status = 422, description = "Input not conforming to the schema of its task kind", content_type = "text/plain"),
+        (status = 500, description = "Internal server error or task not published", content_type = "text/plain"),
+        (status = 503, description = "Task event publisher disabled", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state, spec), fields(task_kind = %spec.task_kind))]
+async fn submit_task_sync(
+    State(state): State<AppState>,
+    Query(query): Query<SubmitSyncQuery>,
+    Json(spec): Json<TaskSpec>,
+) -> Result<Response, (StatusCode, String)> {
+    let max_timeout_secs = max_sync_timeout_secs(state.request_timeout);
+    let timeout_secs = query
+        .timeout
+        .unwrap_or(DEFAULT_SYNC_TASK_TIMEOUT_SECS.min(max_timeout_secs));
+    info!(
+        timeout_secs = timeout_secs,
+        "API request: Submit task synchronously"
+    );
+    if !(1..=max_timeout_secs).contains(&timeout_secs) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("timeout must be between 1 and {} seconds", max_timeout_secs),
+        ));
+    }
+
+    let submitted = submit_specs(&state, vec![spec]).await?.remove(0);
+    let id = submitted.id;
+    if let Some(e) = submitted.error {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to publish task with ID {}: {}", id, e),
+        ));
+    }
+
+    // The task row is polled rather than watched, so completions recorded by
+    // the consumer of another relay instance are seen too. The polls back off
+    // so that long waits don't keep querying the database
+    let completion = async {
+        let mut poll_interval = Duration::from_millis(SYNC_TASK_POLL_INTERVAL_MS);
+        loop {
+            tokio::time::sleep(poll_interval).await;
+            match state.task_repository.get_task_result(&id).await {
+                Ok(Some(result)) if result.is_completed() => return Ok(result),
+                Ok(_) => {}
+                Err(e) => return Err(e),
+            }
+            poll_interval =
+                (poll_interval * 2).min(Duration::from_millis(MAX_SYNC_TASK_POLL_INTERVAL_MS));
+        }
+    };
+    match tokio::time::timeout(Duration::from_secs(timeout_secs), completion).await {
+        Ok(Ok(result)) => {
+            debug!(task_id = %id, "Task completed");
+            Ok(task_output_response(result))
+        }
+        Ok(Err(e)) => {
+            error!(task_id = %id, error = %e, "Database error while waiting for task");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get result of task with ID {}: {}", id, e),
+            ))
+        }
+        Err(_) => {
+            warn!(task_id = %id, timeout_secs = timeout_secs, "Task did not complete in time");
+            Err((
+                StatusCode::REQUEST_TIMEOUT,
+                format!(
+                    "Task with ID {} did not complete within {} seconds",
+                    id, timeout_secs
+                ),
+            ))
+        }
+    }
+}
+
+/// Options of a task request
+#[derive(Debug, Deserialize, IntoParams)]
+struct GetTaskQuery {
+    /// Format of the response, `json`, `avro` or `msgpack`. Takes precedence
+    /// over the Accept header.
+    format: Option<String>,
+    /// Whether to look the task up in the archive when it isn't in the
+    /// tasks anymore
+    #[serde(default)]
+    include_archived: bool,
+}
+
+/// Get a task by its UUID
+///
+/// # Arguments
+/// * `id` - UUID of the task to retrieve
+/// * `format` - Optional format overriding the Accept header
+/// * `include_archived` - Whether to fall back to the archived tasks
+///
+/// # Returns
+/// Returns a response containing the task if found, in JSON, Avro or
+/// MessagePack format based on the `format` parameter or the Accept header.
+/// The response carries an `ETag`, and `304 Not Modified` is returned instead
+/// when it matches the `If-None-Match` header
+#[utoipa::path(
+    get,
+    description = "Get a task by its UUID",
+    path = "/tasks/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Task ID to get"),
+        GetTaskQuery
+    ),
+    responses(
+        (status = 200, description = "Task found", body = Task, content_type = "application/json",
+            headers(("traceparent" = String, description = "W3C trace context of the trace that originated the task, if any"))),
+        (status = 200, description = "Task found (Avro format)", content_type = "application/avro",
+            headers(("traceparent" = String, description = "W3C trace context of the trace that originated the task, if any"))),
+        (status = 200, description = "Task found (MessagePack format)", content_type = "application/msgpack",
+            headers(("traceparent" = String, description = "W3C trace context of the trace that originated the task, if any"))),
+        (status = 304, description = "Task unchanged since the version in If-None-Match",
+            headers(("ETag" = String, description = "Version of the task in the requested format"))),
+        (status = 400, description = "Unknown format", body = ErrorResponse, content_type = "application/json"),
+        (status = 404, description = "Task not found", body = ErrorResponse, content_type = "application/json"),
+        (status = 406, description = "No supported format is acceptable", body = ErrorResponse, content_type = "application/json"),
+        (status = 500, description = "Internal server error", body = ErrorResponse, content_type = "application/json")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state, query, headers), fields(task_id = %id))]
+async fn get_task_by_id(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<GetTaskQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    info!(task_id = %id, format = ?query.format, "API request: Get task by ID");
+
+    let format_override = query
+        .format
+        .as_deref()
+        .map(ResponseFormat::from_str)
+        .transpose()
+        .map_err(ApiError::bad_request)?;
+    if format_override == Some(ResponseFormat::Avro) && !state.allow_avro {
+        return Err(ApiError::not_acceptable(
+            "Avro responses are disabled in the JSON wire format",
+        ));
+    }
+
+    let task = state
+        .task_repository
+        .get_task_by_id(&id)
+        .await
+        .inspect_err(|e| error!(task_id = %id, error = %e, "Database error while fetching task"))?;
+    // Expired tasks moved to the archive by the cleanup job
+    let task = match task {
+        None if query.include_archived => state
+            .task_repository
+            .get_archived_task_by_id(&id)
+            .await
+            .inspect_err(
+                |e| error!(task_id = %id, error = %e, "Database error while fetching archived task"),
+            )?,
+        task => task,
+    };
+    let Some(task) = task else {
+        debug!(task_id = %id, "Task not found");
+        return Err(ApiError::not_found(format!(
+            "Task with ID {} not found",
+            id
+        )));
+    };
+    info!(
+        task_id = %id,
+        task_kind = %task.clone().task_kind.unwrap_or("None".to_string()),
+        "Successfully retrieved task"
+    );
+
+    // The format parameter takes precedence over the Accept header
+    let format = match format_override {
+        Some(format) => format,
+        None => determine_response_format(&headers, state.allow_avro)?,
+    };
+    debug!(task_id = %id, format = ?format, "Determined response format");
+
+    let etag = task_etag(&task, format);
+    if etag_matches(&headers, &etag) {
+        debug!(task_id = %id, etag = %etag, "Task not modified");
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+
+    let traceparent = task.traceparent();
+    if traceparent.is_none() {
+        debug!(task_id = %id, "Task has no trace context, skipping traceparent");
+    }
+
+    Ok(TaskResponse {
+        task,
+        format,
+        traceparent,
+        etag,
+    }
+    .into_response())
+}
+
+/// Get the input of a task
+///
+/// # Arguments
+/// * `id` - UUID of the task whose input to retrieve
+///
+/// # Returns
+/// Returns the raw input data with the content type declared by the
+/// publisher, or `application/octet-stream` if it didn't declare one
+#[utoipa::path(
+    get,
+    description = "Get the raw input of a task, served with the content type declared by its publisher",
+    path = "/tasks/{id}/input",
+    params(
+        ("id" = Uuid, Path, description = "Task ID to get the input of")
+    ),
+    responses(
+        (status = 200, description = "Task input", content_type = "application/octet-stream"),
+        (status = 404, description = "Task not found or without input", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state), fields(task_id = %id))]
+async fn get_task_input(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Response, (StatusCode, String)> {
+    info!(task_id = %id, "API request: Get task input");
+
+    let input = match state.task_repository.get_task_input(&id).await {
+        Ok(Some(input)) => input,
+        Ok(None) => {
+            debug!(task_id = %id, "Task not found");
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("Task with ID {} not found", id),
+            ));
+        }
+        Err(e) => {
+            error!(task_id = %id, error = %e, "Database error while fetching task input");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get task input: {}", e),
+            ));
+        }
+    };
+
+    // Tasks only get their input once their assignment is received
+    let Some(input_data) = input.input_data else {
+        debug!(task_id = %id, "Task has no input");
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Task with ID {} has no input", id),
+        ));
+    };
+
+    // Fall back to raw bytes if the declared content type isn't a valid header
+    let content_type = input
+        .input_content_type
+        .as_deref()
+        .and_then(|content_type| HeaderValue::from_str(content_type).ok())
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, content_type)],
+        input_data,
+    )
+        .into_response())
+}
+
+/// Get the output of a task
+///
+/// # Arguments
+/// * `id` - UUID of the task whose output to retrieve
+///
+/// # Returns
+/// Returns the raw output data with the content type declared by the worker,
+/// or `application/octet-stream` if it didn't declare one. The output of a
+/// failed task is flagged with the `x-task-error` header
+#[utoipa::path(
+    get,
+    description = "Get the raw output of a task, served with the content type declared by the worker",
+    path = "/tasks/{id}/result",
+    params(
+        ("id" = Uuid, Path, description = "Task ID to get the output of")
+    ),
+    responses(
+        (status = 200, description = "Task output", content_type = "application/octet-stream",
+            headers(("x-task-error" = bool, description = "Set to true when the task failed and the output is its error"))),
+        (status = 202, description = "Task not completed yet", content_type = "text/plain"),
+        (status = 404, description = "Task not found", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state), fields(task_id = %id))]
+async fn get_task_result(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Response, (StatusCode, String)> {
+    info!(task_id = %id, "API request: Get task result");
+
+    let result = match state.task_repository.get_task_result(&id).await {
+        Ok(Some(result)) => result,
+        Ok(None) => {
+            debug!(task_id = %id, "Task not found");
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("Task with ID {} not found", id),
+            ));
+        }
+        Err(e) => {
+            error!(task_id = %id, error = %e, "Database error while fetching task result");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get task result: {}", e),
+            ));
+        }
+    };
+
+    if !result.is_completed() {
+        debug!(task_id = %id, "Task not completed yet");
+        return Ok((
+            StatusCode::ACCEPTED,
+            format!("Task with ID {} is not completed yet", id),
+        )
+            .into_response());
+    }
+
+    Ok(task_output_response(result))
+}
+
+/// Header flagging the output of a failed task, which is the error the task
+/// failed with rather than its result
+pub const TASK_ERROR_HEADER: &str = "x-task-error";
+
+/// Serves the output of a completed task with the content type declared by
+/// the worker, flagged with [`TASK_ERROR_HEADER`] if the task failed.
+fn task_output_response(result: TaskResult) -> Response {
+    // Fall back to raw bytes if the declared content type isn't a valid header
+    let content_type = result
+        .output_content_type
+        .as_deref()
+        .and_then(|content_type| HeaderValue::from_str(content_type).ok())
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+
+    let is_failed = result.is_failed();
+    let mut response = (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, content_type)],
+        result.output_data.unwrap_or_default(),
+    )
+        .into_response();
+    if is_failed {
+        response
+            .headers_mut()
+            .insert(TASK_ERROR_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// Get the worker that executed a task
+///
+/// # Arguments
+/// * `id` - UUID of the task whose worker to retrieve
+///
+/// # Returns
+/// Returns the worker named in the task's `executed_by`, with its kind and
+/// latest heartbeat
+#[utoipa::path(
+    get,
+    description = "Get the worker that executed a task, with its kind and latest heartbeat",
+    path = "/tasks/{id}/worker",
+    params(
+        ("id" = Uuid, Path, description = "Task ID to get the worker of")
+    ),
+    responses(
+        (status = 200, description = "Worker of the task", body = Worker, content_type = "application/json"),
+        (status = 404, description = "Task not found, not picked up by a worker yet, or worker unknown", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state), fields(task_id = %id))]
+async fn get_task_worker(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Worker>, (StatusCode, String)> {
+    info!(task_id = %id, "API request: Get task worker");
+
+    match state.worker_repository.get_worker_of_task(&id).await {
+        Ok(Some(worker)) => Ok(Json(worker)),
+        Ok(None) => {
+            debug!(task_id = %id, "No worker found for task");
+            Err((
+                StatusCode::NOT_FOUND,
+                format!("No worker found for task with ID {}", id),
+            ))
+        }
+        Err(e) => {
+            error!(task_id = %id, error = %e, "Database error while fetching task worker");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get task worker: {}", e),
+            ))
+        }
+    }
+}
+
+/// Get the history of a task
+///
+/// # Arguments
+/// * `id` - UUID of the task whose history to retrieve
+///
+/// # Returns
+/// Returns every assignment, running and completed event applied to the task,
+/// in the order they happened, including the ones that didn't change it
+#[utoipa::path(
+    get,
+    description = "Get the events applied to a task, in the order they happened",
+    path = "/tasks/{id}/history",
+    params(
+        ("id" = Uuid, Path, description = "Task ID to get the history of")
+    ),
+    responses(
+        (status = 200, description = "Task history", body = Vec<TaskEvent>, content_type = "application/json"),
+        (status = 404, description = "Task not found", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "tasks"
+)]
+#[instrument(skip(state), fields(task_id = %id))]
+async fn get_task_history(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<TaskEvent>>, (StatusCode, String)> {
+    info!(task_id = %id, "API request: Get task history");
+
+    let database_error = |e: sqlx::Error| {
+        error!(task_id = %id, error = %e, "Database error while fetching task history");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to get task history: {}", e),
+        )
+    };
+
+    let history = state
+        .task_repository
+        .get_task_history(&id)
+        .await
+        .map_err(database_error)?;
+
+    // Tasks created before the history was recorded have none
+    if history.is_empty()
+        && state
+            .task_repository
+            .get_task_status(&id)
+            .await
+            .map_err(database_error)?
+            .is_none()
+    {
+        debug!(task_id = %id, "Task not found");
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Task with ID {} not found", id),
+        ));
+    }
+
+    debug!(task_id = %id, count = history.len(), "Successfully retrieved task history");
+    Ok(Json(history))
+}
+
+/// Requeue a task whose worker stopped executing it
+///
+/// # Arguments
+/// * `id` - UUID of the task to requeue
+///
+/// # Returns
+/// Returns the task back in `Pending` after publishing its assignment again
+/// to the workers of its kind. Completed tasks can't be requeued.
+#[utoipa::path(
+    post,
+    description = "Put a started task back in Pending and publish its assignment again. Requires the admin token.",
+    path = "/tasks/{id}/requeue",
+    params(
+        ("id" = Uuid, Path, description = "Task ID to requeue")
+    ),
+    responses(
+        (status = 200, description = "Task requeued", body = Task, content_type = "application/json"),
+        (status = 401, description = "Missing admin token", content_type = "text/plain"),
+        (status = 403, description = "Invalid admin token or admin endpoints disabled", content_type = "text/plain"),
+        (status = 404, description = "Task not found", content_type = "text/plain"),
+        (status = 409, description = "Task completed or never assigned", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain"),
+        (status = 503, description = "Task event publisher disabled", content_type = "text/plain")
+    ),
+    security(("admin_token" = [])),
+    tag = "tasks"
+)]
+#[instrument(skip(state, _admin), fields(task_id = %id))]
+async fn requeue_task(
+    _admin: AdminGuard,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Task>, (StatusCode, String)> {
+    warn!(task_id = %id, "API request: Requeue task");
+
+    let database_error = |e: sqlx::Error| {
+        error!(task_id = %id, error = %e, "Database error while requeuing task");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to requeue task: {}", e),
+        )
+    };
+    let completed = || {
+        (
+            StatusCode::CONFLICT,
+            format!("Task with ID {} is already completed", id),
+        )
+    };
+
+    let Some(publisher) = state.task_event_publisher.clone() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Task event publisher is disabled".to_string(),
+        ));
+    };
+
+    let Some(task) = state
+        .task_repository
+        .get_task_by_id(&id)
+        .await
+        .map_err(database_error)?
+    else {
+        debug!(task_id = %id, "Task not found");
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Task with ID {} not found", id),
+        ));
+    };
+    if task.completed_at.is_some() {
+        return Err(completed());
+    }
+    let Some(assignment) = TaskAssignmentUpdate::from_task(&task) else {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Task with ID {} was never assigned", id),
+        ));
+    };
+
+    let routing_key = state
+        .worker_repository
+        .routing_key_of(&assignment.worker_kind)
+        .await
+        .map_err(database_error)?;
+
+    // The task may have completed since it was read
+    let requeued = state
+        .task_repository
+        .requeue_task(&id)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(completed)?;
+
+    if let Err(e) = publisher
+        .publish(&Event::Assignment(assignment), &routing_key)
+        .await
+    {
+        // The task stays pending, so the requeue can simply be retried
+        error!(task_id = %id, error = %e, "Failed to publish requeued task assignment");
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to publish task assignment: {}", e),
+        ));
+    }
+
+    warn!(
+        task_id = %id,
+        executed_by = ?task.executed_by,
+        routing_key = %routing_key,
+        "Requeued task"
+    );
+    Ok(Json(requeued))
+}
+
+/// Determines the response format based on the Accept header
+///
+/// JSON is served when the client has no preference, which is when the
+/// header is missing or accepts any type. A header that only accepts types
+/// the relay can't produce is rejected with `406 Not Acceptable`, which
+/// includes Avro when `allow_avro` is false.
+fn determine_response_format(
+    headers: &HeaderMap,
+    allow_avro: bool,
+) -> Result<ResponseFormat, (StatusCode, String)> {
+    // Default to JSON if no Accept header is present
+    let accept = match headers.get(header::ACCEPT) {
+        Some(value) => match value.to_str() {
+            Ok(s) => s,
+            Err(_) => return Ok(ResponseFormat::Json),
+        },
+        None => return Ok(ResponseFormat::Json),
+    };
+
+    negotiate_format(accept, allow_avro).ok_or_else(|| {
+        debug!(accept = %accept, "No acceptable response format");
+        let supported = if allow_avro {
+            "application/json, application/avro and application/msgpack"
+        } else {
+            "application/json and application/msgpack"
+        };
+        (
+            StatusCode::NOT_ACCEPTABLE,
+            format!("Supported formats are {}", supported),
+        )
+    })
+}
+
+/// Picks the format with the highest quality in an Accept header, or `None`
+/// if the header accepts none of the formats. Wildcards count towards JSON, as
+/// the default format. Avro is only picked when `allow_avro` is true.
+fn negotiate_format(accept: &str, allow_avro: bool) -> Option<ResponseFormat> {
+    let mut json_quality = None;
+    let mut avro_quality = None;
+    let mut msgpack_quality = None;
+    let mut wildcard_quality = None;
+
+    // An explicit media type takes precedence over a wildcard
+    for part in accept.split(',').map(|s| s.trim()) {
+        let media_type = part.split(';').next().unwrap_or_default().trim();
+        let quality = extract_quality(part).unwrap_or(1.0);
+        match media_type {
+            "application/json" => json_quality = Some(quality),
+            "application/avro" if allow_avro => avro_quality = Some(quality),
+            "application/msgpack" | "application/x-msgpack" => msgpack_quality = Some(quality),
+            "*/*" | "application/*" => wildcard_quality = Some(quality),
+            _ => {}
+        }
+    }
+
+    // Choose format based on quality values, binary formats winning ties
+    let candidates = [
+        (ResponseFormat::Avro, avro_quality.unwrap_or(0.0)),
+        (ResponseFormat::MessagePack, msgpack_quality.unwrap_or(0.0)),
+        (
+            ResponseFormat::Json,
+            json_quality.or(wildcard_quality).unwrap_or(0.0),
+        ),
+    ];
+    let mut best: Option<(ResponseFormat, f32)> = None;
+    for (format, quality) in candidates {
+        if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((format, quality));
+        }
+    }
+    best.map(|(format, _)| format)
+}
+
+/// Extracts the quality value (q parameter) from an Accept header part
+fn extract_quality(part: &str) -> Option<f32> {
+    part.split(';')
+        .skip(1)
+        .map(|param| param.trim())
+        .find_map(|param| param.strip_prefix("q="))
+        .and_then(|q_value| q_value.parse::<f32>().ok())
+}
+
+/// Response format enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResponseFormat {
+    Json,
+    Avro,
+    MessagePack,
+}
+
+impl FromStr for ResponseFormat {
+    type Err = String;
+
+    /// Parses the name of a format, as given in the `format` query parameter
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Ok(ResponseFormat::Json),
+            "avro" => Ok(ResponseFormat::Avro),
+            "msgpack" => Ok(ResponseFormat::MessagePack),
+            _ => Err(format!(
+                "Unknown format {}. Supported formats are json, avro and msgpack",
+                value
+            )),
+        }
+    }
+}
+
+/// Entity tag of a task in a response format. It is a SHA-256 of every field
+/// of the task, so it changes whenever the task does and stays the same
+/// across relay versions and replicas, and names the format, so a client
+/// switching formats doesn't reuse a body in the wrong one.
+fn task_etag(task: &Task, format: ResponseFormat) -> String {
+    let digest = Sha256::digest(serde_json::to_vec(task).unwrap_or_default());
+    let hash: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("\"{hash}-{format:?}\"").to_ascii_lowercase()
+}
+
+/// Whether the `If-None-Match` header lists `etag`, or `*`. Tags are compared
+/// weakly, ignoring the `W/` prefix, as required for `If-None-Match`.
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Serializes a response body as MessagePack, keeping field names like the
+/// JSON body does.
+fn msgpack_response<T: Serialize>(body: &T) -> Response {
+    match rmp_serde::to_vec_named(body) {
+        Ok(bytes) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/msgpack")],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => {
+            error!(error = %e, "Failed to serialize response as MessagePack");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Builds the response of an Avro encoded task, with its `Content-Length`
+/// set. Payloads past [`AVRO_STREAM_THRESHOLD_BYTES`] are streamed in chunks
+/// sharing the encoded buffer, so large results aren't copied into the body.
+fn avro_response(avro_bytes: Vec<u8>) -> Response {
+    let content_length = avro_bytes.len();
+    let body = if content_length > AVRO_STREAM_THRESHOLD_BYTES {
+        debug!(content_length, "Streaming large Avro task response");
+        let bytes = Bytes::from(avro_bytes);
+        let chunks = (0..content_length)
+            .step_by(AVRO_STREAM_CHUNK_BYTES)
+            .map(move |start| {
+                let end = (start + AVRO_STREAM_CHUNK_BYTES).min(content_length);
+                Ok::<_, Infallible>(bytes.slice(start..end))
+            });
+        Body::from_stream(futures::stream::iter(chunks))
+    } else {
+        Body::from(avro_bytes)
+    };
+
+    (
+        StatusCode::OK,
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/avro"),
+            ),
+            (header::CONTENT_LENGTH, HeaderValue::from(content_length)),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// OpenTelemetry information attached to the JSON task body
+#[derive(Debug, Serialize)]
+struct OtelInfo {
+    traceparent: String,
+}
+
+/// JSON body of a task response. The task fields are flattened so the body
+/// remains a valid `Task` for clients that ignore the `otel` field.
+#[derive(Debug, Serialize)]
+struct TaskJsonBody {
+    #[serde(flatten)]
+    task: Task,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    otel: Option<OtelInfo>,
+}
+
+/// Task response wrapper that handles content negotiation
+struct TaskResponse {
+    task: Task,
+    format: ResponseFormat,
+    traceparent: Option<String>,
+    etag: String,
+}
+
+impl IntoResponse for TaskResponse {
+    fn into_response(self) -> Response {
+        let traceparent_header = self
+            .traceparent
+            .as_deref()
+            .and_then(|traceparent| HeaderValue::from_str(traceparent).ok());
+        let etag_header = HeaderValue::from_str(&self.etag).ok();
+
+        let mut response = match self.format {
+            ResponseFormat::Json => {
+                // Return JSON response
+                let body = TaskJsonBody {
+                    task: self.task,
+                    otel: self.traceparent.map(|traceparent| OtelInfo { traceparent }),
+                };
+                (
+                    StatusCode::OK,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    axum::Json(body),
+                )
+                    .into_response()
+            }
+            ResponseFormat::MessagePack => msgpack_response(&TaskJsonBody {
+                task: self.task,
+                otel: self.traceparent.map(|traceparent| OtelInfo { traceparent }),
+            }),
+            ResponseFormat::Avro => {
+                // Convert task to Avro binary format using the convenience method
+                match self.task.try_into_avro_bytes() {
+                    Ok(avro_bytes) => avro_response(avro_bytes),
+                    Err(e) => {
+                        error!(
+                            task_id = %self.task.id,
+                            error = %e,
+                            "Failed to convert task to Avro bytes"
+                        );
+                        return ApiError::avro(e).into_response();
+                    }
+                }
+            }
+        };
+
+        // Expose the originating trace so clients can correlate the task
+        if let Some(traceparent) = traceparent_header {
+            response.headers_mut().insert("traceparent", traceparent);
+        }
+        if let Some(etag) = etag_header.filter(|_| response.status().is_success()) {
+            response.headers_mut().insert(header::ETAG, etag);
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        etag_matches, max_sync_timeout_secs, BatchGetResponse, BatchSubmitResponse,
+        DeleteTasksResponse, SubmissionStatus, ValidationResponse, TASK_ERROR_HEADER,
+    };
+    use crate::constants::{DEFAULT_TASK_PAGE_SIZE, MAX_SYNC_TASK_TIMEOUT_SECS};
+    use crate::models::{
+        AvroSerializable, Task, TaskAssignmentUpdate, TaskChanges, TaskCompletedUpdate, TaskEvent,
+        TaskKindDefaults, TaskPage, TaskRunningUpdate, TaskStats, TaskStatus, TtlPolicy, Worker,
+        WorkerHeartbeatUpdate,
+    };
+    use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+    use axum_test::TestServer;
+    use chrono::Local;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    use crate::{
+        health_probe::Readiness,
+        lifecycle::setup_app,
+        repo::{PgRepositoryCore, TaskRepository, WorkerRepository},
+        server::RequestLimits,
+        testing::test::{get_test_server, init_test_logger, RecordingPublisher, TEST_ADMIN_TOKEN},
+    };
+
+    // This runs before any test in this module
+    #[ctor::ctor]
+    fn init() {
+        init_test_logger();
+    }
+
+    fn get_test_task() -> Task {
+        Task::new("TaskKindName", "WorkerKindName", 0, 0)
+            .with_input_data(vec![1, 2, 3])
+            .with_output_data(vec![4, 5, 6])
+            .with_error(false)
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_non_existent_task_by_id(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let id = Uuid::new_v4();
+        let response = server.get(&format!("/tasks/{}", id)).await;
+
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(response.header(header::CONTENT_TYPE), "application/json");
+        assert_eq!(
+            response.json::<serde_json::Value>(),
+            json!({
+                "error": {
+                    "code": "not_found",
+                    "message": format!("Task with ID {} not found", id)
+                }
+            })
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_archived_task_by_id(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+        let mut test_task = get_test_task();
+        test_task.completed_at = Some(Local::now().naive_local() - chrono::Duration::days(1));
+        task_repository.create_task(&test_task).await.unwrap();
+        task_repository
+            .archive_expired_tasks(10, std::time::Duration::ZERO)
+            .await
+            .unwrap();
+
+        // Archived tasks are only served when asked for
+        let response = server.get(&format!("/tasks/{}", test_task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        let response = server
+            .get(&format!("/tasks/{}?include_archived=true", test_task.id))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let task = response.json::<Task>();
+        assert_eq!(task.id, test_task.id);
+        assert_eq!(task.input_data, test_task.input_data);
+
+        let response = server
+            .get(&format!("/tasks/{}?include_archived=true", Uuid::new_v4()))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_by_id_database_error(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        db_pools.close().await;
+
+        let response = server.get(&format!("/tasks/{}", Uuid::new_v4())).await;
+
+        assert_eq!(response.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = response.json::<serde_json::Value>();
+        assert_eq!(body["error"]["code"], "database_error");
+        assert!(body["error"]["message"].is_string());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_existing_task_by_id(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let core = PgRepositoryCore::new(db_pools.clone());
+        let task_repository = TaskRepository::new(core.clone());
+
+        let test_task = get_test_task();
+
+        task_repository.create_task(&test_task).await.unwrap();
+
+        let response = server.get(&format!("/tasks/{}", test_task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(axum::http::header::CONTENT_TYPE)
+                .unwrap(),
+            "application/json"
+        );
+
+        let response_body = response.json::<Task>();
+        assert_eq!(response_body.id, test_task.id);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_exposes_traceparent(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let core = PgRepositoryCore::new(db_pools.clone());
+        let task_repository = TaskRepository::new(core.clone());
+
+        let traceparent = "00-3f4a168998a20c019615e558ec12d985-47d2075b594ffe86-01";
+        let test_task = get_test_task().with_otel_context(json!({ "traceparent": traceparent }));
+        task_repository.create_task(&test_task).await.unwrap();
+
+        let response = server.get(&format!("/tasks/{}", test_task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.headers().get("traceparent").unwrap(), traceparent);
+
+        let response_body = response.json::<serde_json::Value>();
+        assert_eq!(response_body["otel"]["traceparent"], traceparent);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_without_context_skips_traceparent(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let core = PgRepositoryCore::new(db_pools.clone());
+        let task_repository = TaskRepository::new(core.clone());
+
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+
+        let response = server.get(&format!("/tasks/{}", test_task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(response.headers().get("traceparent").is_none());
+
+        let response_body = response.json::<serde_json::Value>();
+        assert!(response_body.get("otel").is_none());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_if_none_match(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+
+        let response = server.get(&format!("/tasks/{}", test_task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+
+        // A matching tag skips the body
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(header::IF_NONE_MATCH, etag.clone())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), &etag);
+        assert!(response.as_bytes().is_empty());
+
+        // Any other tag gets the full task
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(header::IF_NONE_MATCH, HeaderValue::from_static("\"stale\""))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), &etag);
+        assert_eq!(response.json::<Task>().id, test_task.id);
+
+        // The same task in another format has another tag
+        let response = server
+            .get(&format!("/tasks/{}?format=avro", test_task.id))
+            .add_header(header::IF_NONE_MATCH, etag.clone())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let avro_etag = response.headers().get(header::ETAG).unwrap().clone();
+        assert_ne!(avro_etag, etag);
+
+        let response = server
+            .get(&format!("/tasks/{}?format=avro", test_task.id))
+            .add_header(header::IF_NONE_MATCH, avro_etag)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_etag_changes_with_task(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+
+        let response = server.get(&format!("/tasks/{}", test_task.id)).await;
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+
+        task_repository
+            .update_task_from_running_update(&TaskRunningUpdate::new(
+                test_task.id,
+                Local::now().naive_local(),
+                "worker-1".to_string(),
+            ))
+            .await
+            .unwrap();
+
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(header::IF_NONE_MATCH, etag.clone())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_ne!(response.headers().get(header::ETAG).unwrap(), &etag);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_worker(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let core = PgRepositoryCore::new(db_pools);
+        let task_repository = TaskRepository::new(core.clone());
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+
+        let heartbeat_at = Local::now().naive_local();
+        WorkerRepository::new(core)
+            .save_heartbeat(&WorkerHeartbeatUpdate::new(
+                "worker-1",
+                "WorkerKindName",
+                heartbeat_at,
+            ))
+            .await
+            .unwrap();
+        task_repository
+            .update_task_from_running_update(&TaskRunningUpdate::new(
+                test_task.id,
+                Local::now().naive_local(),
+                "worker-1".to_string(),
+            ))
+            .await
+            .unwrap();
+
+        let response = server.get(&format!("/tasks/{}/worker", test_task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let worker = response.json::<Worker>();
+        assert_eq!(worker.name, "worker-1");
+        assert_eq!(worker.worker_kind, "WorkerKindName");
+        assert_eq!(
+            worker.last_heartbeat_at.and_utc().timestamp_micros(),
+            heartbeat_at.and_utc().timestamp_micros()
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_unassigned_task_worker(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let test_task = get_test_task();
+        TaskRepository::new(PgRepositoryCore::new(db_pools))
+            .create_task(&test_task)
+            .await
+            .unwrap();
+
+        // No worker picked the task up yet
+        let response = server.get(&format!("/tasks/{}/worker", test_task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        let response = server
+            .get(&format!("/tasks/{}/worker", Uuid::new_v4()))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = "\"0123456789abcdef-json\"";
+        let if_none_match = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static(value));
+            etag_matches(&headers, etag)
+        };
+
+        assert!(if_none_match("\"0123456789abcdef-json\""));
+        assert!(if_none_match("W/\"0123456789abcdef-json\""));
+        assert!(if_none_match("\"other\", \"0123456789abcdef-json\""));
+        assert!(if_none_match("*"));
+        assert!(!if_none_match("\"0123456789abcdef-avro\""));
+        assert!(!etag_matches(&HeaderMap::new(), etag));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_result(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let response = server
+            .get(&format!("/tasks/{}/result", Uuid::new_v4()))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        let task = get_test_task();
+        task_repository.create_task(&task).await.unwrap();
+
+        let response = server.get(&format!("/tasks/{}/result", task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::ACCEPTED);
+
+        let completed =
+            TaskCompletedUpdate::new(task.id, Local::now().naive_local(), vec![4, 5, 6], false);
+        task_repository
+            .update_task_from_completed_update(&completed)
+            .await
+            .unwrap();
+
+        let response = server.get(&format!("/tasks/{}/result", task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        assert_eq!(response.as_bytes().to_vec(), vec![4, 5, 6]);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_result_with_declared_content_type(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let id = Uuid::new_v4();
+        let completed =
+            TaskCompletedUpdate::new(id, Local::now().naive_local(), vec![0x89, 0x50], false)
+                .with_output_content_type("image/png");
+        task_repository
+            .update_task_from_completed_update(&completed)
+            .await
+            .unwrap();
+
+        let response = server.get(&format!("/tasks/{}/result", id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+        assert_eq!(response.as_bytes().to_vec(), vec![0x89, 0x50]);
+        assert!(response.headers().get(TASK_ERROR_HEADER).is_none());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_failed_task_result(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let id = Uuid::new_v4();
+        let completed =
+            TaskCompletedUpdate::new(id, Local::now().naive_local(), b"boom".to_vec(), true);
+        task_repository
+            .update_task_from_completed_update(&completed)
+            .await
+            .unwrap();
+
+        let response = server.get(&format!("/tasks/{}/result", id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.headers().get(TASK_ERROR_HEADER).unwrap(), "true");
+        assert_eq!(response.as_bytes().to_vec(), b"boom".to_vec());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_input(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let response = server
+            .get(&format!("/tasks/{}/input", Uuid::new_v4()))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        let task = get_test_task();
+        task_repository.create_task(&task).await.unwrap();
+
+        let response = server.get(&format!("/tasks/{}/input", task.id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        assert_eq!(response.as_bytes().to_vec(), vec![1, 2, 3]);
+
+        let id = Uuid::new_v4();
+        task_repository
+            .update_task_from_assignment_update(&TaskAssignmentUpdate {
+                id,
+                task_kind: "TaskKindName".to_string(),
+                worker_kind: "WorkerKindName".to_string(),
+                created_at: Local::now().naive_local(),
+                input_data: br#"{"n":1}"#.to_vec(),
+                input_content_type: Some("application/json".to_string()),
+                ..TaskAssignmentUpdate::default()
+            })
+            .await
+            .unwrap();
+
+        let response = server.get(&format!("/tasks/{}/input", id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        assert_eq!(response.as_bytes().to_vec(), br#"{"n":1}"#.to_vec());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_input_before_assignment(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        // The task is known from its running event, but its input isn't
+        let id = Uuid::new_v4();
+        task_repository
+            .update_task_from_running_update(&TaskRunningUpdate::new(
+                id,
+                Local::now().naive_local(),
+                "worker-1".to_string(),
+            ))
+            .await
+            .unwrap();
+
+        let response = server.get(&format!("/tasks/{}/input", id)).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_history(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let response = server
+            .get(&format!("/tasks/{}/history", Uuid::new_v4()))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        let id = Uuid::new_v4();
+        let now = Local::now().naive_local();
+        task_repository
+            .update_task_from_assignment_update(&TaskAssignmentUpdate {
+                id,
+                task_kind: "TaskKindName".to_string(),
+                worker_kind: "WorkerKindName".to_string(),
+                created_at: now,
+                ..TaskAssignmentUpdate::default()
+            })
+            .await
+            .unwrap();
+        task_repository
+            .update_task_from_running_update(&TaskRunningUpdate::new(
+                id,
+                now,
+                "worker-1".to_string(),
+            ))
+            .await
+            .unwrap();
+        task_repository
+            .update_task_from_completed_update(&TaskCompletedUpdate::new(id, now, vec![1], false))
+            .await
+            .unwrap();
+
+        let response = server.get(&format!("/tasks/{}/history", id)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let history = response.json::<Vec<TaskEvent>>();
+        let event_types: Vec<_> = history
+            .iter()
+            .map(|event| event.event_type.as_str())
+            .collect();
+        assert_eq!(
+            event_types,
+            vec!["TaskAssignment", "TaskRunning", "TaskCompleted"]
+        );
+        assert!(history.iter().all(|event| event.task_id == id));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_stats(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let core = PgRepositoryCore::new(db_pools.clone());
+        let task_repository = TaskRepository::new(core.clone());
+
+        let mut completed_task = get_test_task();
+        completed_task.completed_at = Some(completed_task.created_at);
+        task_repository.create_task(&get_test_task()).await.unwrap();
+        task_repository.create_task(&completed_task).await.unwrap();
+
+        let response = server.get("/tasks/stats").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let stats = response.json::<TaskStats>();
+        assert_eq!(stats.total, 2);
+        assert_eq!(stats.by_status.pending, 1);
+        assert_eq!(stats.by_status.processing, 0);
+        assert_eq!(stats.by_status.completed, 1);
+        assert_eq!(stats.by_worker_kind.len(), 1);
+        assert_eq!(stats.by_worker_kind[0].count, 2);
+
+        // A window in the future contains no tasks but keeps the same shape
+        let response = server
+            .get("/tasks/stats")
+            .add_query_param("since", "2999-01-01T00:00:00")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let stats = response.json::<serde_json::Value>();
+        assert_eq!(
+            stats,
+            json!({
+                "total": 0,
+                "by_status": { "pending": 0, "processing": 0, "completed": 0 },
+                "by_worker_kind": []
+            })
+        );
+
+        // Timestamps with an offset are accepted and converted to UTC
+        let response = server
+            .get("/tasks/stats")
+            .add_query_param("until", "2999-01-01T00:00:00+02:00")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<TaskStats>().total, 2);
+
+        let response = server
+            .get("/tasks/stats")
+            .add_query_param("since", "2999-01-01")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_existing_task_by_id_avro(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let core = PgRepositoryCore::new(db_pools.clone());
+        let task_repository = TaskRepository::new(core.clone());
+
+        let test_task = get_test_task();
+
+        task_repository.create_task(&test_task).await.unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::ACCEPT,
+            HeaderValue::from_static("application/avro"),
+        );
+
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(
+                axum::http::header::ACCEPT,
+                HeaderValue::from_static("application/avro"),
+            )
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(axum::http::header::CONTENT_TYPE)
+                .unwrap(),
+            "application/avro"
+        );
+
+        let response_body = response.as_bytes().to_vec();
+        let expected_task = Task::try_from_avro_bytes(&response_body).unwrap();
+        assert_eq!(expected_task.id, test_task.id);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_large_task_avro(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        // Past the threshold, so the body is streamed
+        let output: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let test_task = get_test_task().with_output_data(output.clone());
+        task_repository.create_task(&test_task).await.unwrap();
+
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(header::ACCEPT, HeaderValue::from_static("application/avro"))
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let body = response.as_bytes();
+        assert!(body.len() > AVRO_STREAM_THRESHOLD_BYTES);
+        assert_eq!(
+            response.headers().get(header::CONTENT_LENGTH).unwrap(),
+            &body.len().to_string()
+        );
+        let task = Task::try_from_avro_bytes(body).unwrap();
+        assert_eq!(task.id, test_task.id);
+        assert_eq!(task.output_data, Some(output));
+
+        // Small payloads are sent in one piece, with the same header
+        let small_task = get_test_task();
+        task_repository.create_task(&small_task).await.unwrap();
+        let response = server
+            .get(&format!("/tasks/{}", small_task.id))
+            .add_header(header::ACCEPT, HeaderValue::from_static("application/avro"))
+            .await;
+        assert_eq!(
+            response.headers().get(header::CONTENT_LENGTH).unwrap(),
+            &response.as_bytes().len().to_string()
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_format_parameter(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+
+        // The parameter takes precedence over the Accept header
+        let response = server
+            .get(&format!("/tasks/{}?format=json", test_task.id))
+            .add_header(header::ACCEPT, HeaderValue::from_static("application/avro"))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<Task>().id, test_task.id);
+
+        let response = server
+            .get(&format!("/tasks/{}?format=avro", test_task.id))
+            .add_header(header::ACCEPT, HeaderValue::from_static("application/json"))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/avro"
+        );
+        let task = Task::try_from_avro_bytes(response.as_bytes()).unwrap();
+        assert_eq!(task.id, test_task.id);
+
+        let response = server
+            .get(&format!("/tasks/{}?format=msgpack", test_task.id))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/msgpack"
+        );
+        let task: Task = rmp_serde::from_slice(response.as_bytes()).unwrap();
+        assert_eq!(task.id, test_task.id);
+
+        let response = server
+            .get(&format!("/tasks/{}?format=xml", test_task.id))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_content_negotiation_quality_values(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let core = PgRepositoryCore::new(db_pools.clone());
+        let task_repository = TaskRepository::new(core.clone());
+
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+
+        // Test with higher quality for JSON
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::ACCEPT,
+            HeaderValue::from_static("application/json;q=0.9, application/avro;q=0.8"),
+        );
+
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(
+                axum::http::header::ACCEPT,
+                HeaderValue::from_static("application/json;q=0.9, application/avro;q=0.8"),
+            )
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(axum::http::header::CONTENT_TYPE)
+                .unwrap(),
+            "application/json"
+        );
+
+        // Test with higher quality for Avro
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::ACCEPT,
+            HeaderValue::from_static("application/json;q=0.7, application/avro;q=0.8"),
+        );
+
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(
+                axum::http::header::ACCEPT,
+                HeaderValue::from_static("application/json;q=0.7, application/avro;q=0.8"),
+            )
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(axum::http::header::CONTENT_TYPE)
+                .unwrap(),
+            "application/avro"
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_content_negotiation_not_acceptable(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+
+        // Only unsupported types are acceptable
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(header::ACCEPT, HeaderValue::from_static("application/xml"))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_ACCEPTABLE);
+
+        let response = server
+            .get("/tasks")
+            .add_header(header::ACCEPT, HeaderValue::from_static("application/xml"))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_ACCEPTABLE);
+
+        // JSON is explicitly refused
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(
+                header::ACCEPT,
+                HeaderValue::from_static("application/json;q=0"),
+            )
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_ACCEPTABLE);
+
+        // No preference defaults to JSON
+        for accept in [None, Some("*/*"), Some("application/xml, */*;q=0.1")] {
+            let mut request = server.get(&format!("/tasks/{}", test_task.id));
+            if let Some(accept) = accept {
+                request = request.add_header(header::ACCEPT, HeaderValue::from_static(accept));
+            }
+            let response = request.await;
+
+            assert_eq!(
+                response.status_code(),
+                StatusCode::OK,
+                "Accept: {:?}",
+                accept
+            );
+            assert_eq!(
+                response.headers().get(header::CONTENT_TYPE).unwrap(),
+                "application/json"
+            );
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_avro_responses_disabled(db_pools: PgPool) {
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            None,
+            None,
+            &RequestLimits {
+                allow_avro: false,
+                ..RequestLimits::default()
+            },
+            None,
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+
+        // Avro is refused whether it is asked for by header or parameter
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(header::ACCEPT, HeaderValue::from_static("application/avro"))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_ACCEPTABLE);
+
+        let response = server
+            .get(&format!("/tasks/{}?format=avro", test_task.id))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_ACCEPTABLE);
+
+        // A preference for Avro falls back to JSON
+        let response = server
+            .get(&format!("/tasks/{}", test_task.id))
+            .add_header(
+                header::ACCEPT,
+                HeaderValue::from_static("application/json;q=0.7, application/avro;q=0.8"),
+            )
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_delete_tasks_by_filter(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools.clone()));
+
+        let test_task = get_test_task();
+        let other_task = Task::new("TaskKindName", "OtherWorkerKind", 0, 0);
+        task_repository.create_task(&test_task).await.unwrap();
+        task_repository.create_task(&other_task).await.unwrap();
+
+        let response = server
+            .delete("/tasks")
+            .add_query_param("worker_kind", "WorkerKindName")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<DeleteTasksResponse>().deleted, 1);
+
+        assert!(task_repository
+            .get_task_by_id(&test_task.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(task_repository
+            .get_task_by_id(&other_task.id)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_delete_tasks_requires_filter(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+
+        let response = server
+            .delete("/tasks")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_delete_tasks_requires_admin_token(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+
+        let response = server
+            .delete("/tasks")
+            .add_query_param("worker_kind", "WorkerKindName")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .delete("/tasks")
+            .add_query_param("worker_kind", "WorkerKindName")
+            .authorization_bearer("wrong-token")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_tasks_json(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools.clone()));
+
+        let test_task = get_test_task();
+        task_repository.create_task(&test_task).await.unwrap();
+        task_repository
+            .create_task(&Task::new("TaskKindName", "OtherWorkerKind", 0, 0))
+            .await
+            .unwrap();
+
+        let response = server
+            .get("/tasks")
+            .add_query_param("worker_kind", "WorkerKindName")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let page = response.json::<TaskPage>();
+        assert_eq!(page.tasks.len(), 1);
+        assert_eq!(page.tasks[0].id, test_task.id);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_tasks_by_input(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools.clone()));
+
+        let mut ids = Vec::new();
+        for input in [r#"{"user": "alice", "plan": "pro"}"#, r#"{"user": "bob"}"#] {
+            let assignment = TaskAssignmentUpdate {
+                id: Uuid::new_v4(),
+                created_at: Local::now().naive_local(),
+                input_data: input.as_bytes().to_vec(),
+                input_content_type: Some("application/json".to_string()),
+                ..Default::default()
+            };
+            task_repository
+                .update_task_from_assignment_update(&assignment)
+                .await
+                .unwrap();
+            ids.push(assignment.id);
+        }
+
+        let response = server
+            .get("/tasks")
+            .add_query_param("input_contains", r#"{"plan": "pro"}"#)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let page = response.json::<TaskPage>();
+        assert_eq!(page.tasks.len(), 1);
+        assert_eq!(page.tasks[0].id, ids[0]);
+
+        let response = server
+            .get("/tasks")
+            .add_query_param("input_contains", r#"{"user": "bob"}"#)
+            .add_query_param("limit", 10)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let page = response.json::<TaskPage>();
+        assert_eq!(page.tasks.len(), 1);
+        assert_eq!(page.tasks[0].id, ids[1]);
+
+        let response = server
+            .get("/tasks")
+            .add_query_param("input_contains", "{not json")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_tasks_avro_container(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools.clone()));
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let task = get_test_task();
+            task_repository.create_task(&task).await.unwrap();
+            ids.push(task.id);
+        }
+
+        let response = server
+            .get("/tasks")
+            .add_header(
+                axum::http::header::ACCEPT,
+                HeaderValue::from_static("application/avro"),
+            )
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(axum::http::header::CONTENT_TYPE)
+                .unwrap(),
+            "application/avro"
+        );
+
+        let body = response.as_bytes();
+        let reader = apache_avro::Reader::new(&body[..]).unwrap();
+        let mut decoded: Vec<Uuid> = reader
+            .map(|value| apache_avro::from_value::<Task>(&value.unwrap()).unwrap().id)
+            .collect();
+        decoded.sort();
+        ids.sort();
+        assert_eq!(decoded, ids);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_batch_get_tasks(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let first = get_test_task();
+        let second = get_test_task();
+        task_repository.create_task(&first).await.unwrap();
+        task_repository.create_task(&second).await.unwrap();
+        let unknown = Uuid::new_v4();
+
+        let ids = vec![second.id, unknown, first.id, second.id];
+        let response = server.post("/tasks/batch-get").json(&ids).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let body = response.json::<BatchGetResponse>();
+        let found: Vec<Uuid> = body.tasks.iter().map(|task| task.id).collect();
+        assert_eq!(found, vec![second.id, first.id]);
+        assert_eq!(body.missing, vec![unknown]);
+
+        // The found tasks can also be read as an Avro container
+        let response = server
+            .post("/tasks/batch-get")
+            .add_header(header::ACCEPT, HeaderValue::from_static("application/avro"))
+            .json(&ids)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/avro"
         );
 
+        let body = response.as_bytes();
+        let reader = apache_avro::Reader::new(&body[..]).unwrap();
+        let decoded: Vec<Uuid> = reader
+            .map(|value| apache_avro::from_value::<Task>(&value.unwrap()).unwrap().id)
+            .collect();
+        assert_eq!(decoded, vec![second.id, first.id]);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_batch_get_tasks_rejects_too_many_ids(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let ids: Vec<Uuid> = (0..501).map(|_| Uuid::new_v4()).collect();
+        let response = server.post("/tasks/batch-get").json(&ids).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let response = server.post("/tasks/batch-get").json(&ids[..500]).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<BatchGetResponse>().missing.len(), 500);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_tasks_pages(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools.clone()));
+
+        // Tasks share creation dates three by three, so pages must break ties on the id
+        let now = Local::now().naive_local();
+        let mut expected = Vec::new();
+        for i in 0..250 {
+            let mut task = get_test_task();
+            task.created_at = now - chrono::Duration::seconds(i / 3);
+            task_repository.create_task(&task).await.unwrap();
+            expected.push((task.created_at, task.id));
+        }
+        expected.sort();
+        expected.reverse();
+
+        // Pages are bounded even when no limit is given
+        let response = server.get("/tasks").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let page = response.json::<TaskPage>();
+        assert_eq!(page.tasks.len() as i64, DEFAULT_TASK_PAGE_SIZE);
+        assert!(page.next_cursor.is_some());
+
+        let mut listed = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut request = server.get("/tasks").add_query_param("limit", 40);
+            if let Some(cursor) = &cursor {
+                request = request.add_query_param("after", cursor);
+            }
+            let response = request.await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+
+            let page = response.json::<TaskPage>();
+            assert!(page.tasks.len() <= 40);
+            listed.extend(page.tasks.iter().map(|task| task.id));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let expected: Vec<Uuid> = expected.into_iter().map(|(_, id)| id).collect();
+        assert_eq!(listed, expected);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_tasks_rejects_invalid_page(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
         let response = server
-            .get(&format!("/tasks/{}", test_task.id))
-            .add_header(
-                axum::http::header::ACCEPT,
-                HeaderValue::from_static("application/json;q=0.9, application/avro;q=0.8"),
-            )
+            .get("/tasks")
+            .add_query_param("after", "not-a-cursor")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let response = server.get("/tasks").add_query_param("limit", 0).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_task_changes_pages(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools.clone()));
+
+        // Tasks share update dates three by three, so pages must not split them
+        let start = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc();
+        let mut expected = Vec::new();
+        for i in 0..100 {
+            let mut task = get_test_task();
+            task.updated_at = start + chrono::Duration::seconds(i / 3);
+            task_repository.create_task(&task).await.unwrap();
+            expected.push((task.updated_at, task.id));
+        }
+        expected.sort();
+
+        let list_changes = |since: Option<String>| {
+            let mut request = server.get("/tasks/changes").add_query_param("limit", 10);
+            if let Some(since) = since {
+                request = request.add_query_param("since", since);
+            }
+            async move {
+                let response = request.await;
+                assert_eq!(response.status_code(), StatusCode::OK);
+                response.json::<TaskChanges>()
+            }
+        };
+
+        let mut listed = Vec::new();
+        let mut since = None;
+        loop {
+            let changes = list_changes(since.clone()).await;
+            if changes.tasks.is_empty() {
+                assert_eq!(Some(changes.cursor.to_string()), since);
+                break;
+            }
+            assert!(changes.tasks.len() <= 12);
+            assert_eq!(changes.cursor, changes.tasks.last().unwrap().updated_at);
+            listed.extend(changes.tasks.iter().map(|task| (task.updated_at, task.id)));
+            since = Some(changes.cursor.to_string());
+        }
+        assert_eq!(listed, expected);
+
+        // A task updated just now isn't listed until its update settles
+        task_repository
+            .update_task_from_running_update(&TaskRunningUpdate::new(
+                expected[42].1,
+                Local::now().naive_local(),
+                "worker-1".to_string(),
+            ))
+            .await
+            .unwrap();
+
+        let changes = list_changes(since.clone()).await;
+        assert!(changes.tasks.is_empty());
+        assert_eq!(Some(changes.cursor.to_string()), since);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_task_changes_rejects_invalid_query(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let response = server
+            .get("/tasks/changes")
+            .add_query_param("since", "yesterday")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let response = server
+            .get("/tasks/changes")
+            .add_query_param("limit", 0)
             .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_requeue_task(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            None,
+            Some(publisher.clone()),
+            &RequestLimits::default(),
+            Some(TEST_ADMIN_TOKEN.to_string()),
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let task = Task::new("TaskKindName", "WorkerKindName", 3, 0).with_input_data(vec![1, 2, 3]);
+        task_repository.create_task(&task).await.unwrap();
+        task_repository
+            .update_task_from_running_update(&TaskRunningUpdate::new(
+                task.id,
+                Local::now().naive_local(),
+                "worker-1".to_string(),
+            ))
+            .await
+            .unwrap();
+        let path = format!("/tasks/{}/requeue", task.id);
+
+        let response = server.post(&path).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
 
+        let response = server
+            .post(&path)
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
         assert_eq!(response.status_code(), StatusCode::OK);
+        let requeued = response.json::<Task>();
+        assert_eq!(requeued.started_at, None);
+        assert_eq!(requeued.executed_by, None);
         assert_eq!(
-            response
-                .headers()
-                .get(axum::http::header::CONTENT_TYPE)
+            task_repository.get_task_status(&task.id).await.unwrap(),
+            Some(TaskStatus::Pending)
+        );
+        assert_eq!(
+            *publisher.published.lock().unwrap(),
+            vec![("tasks.WorkerKindName".to_string(), task.id)]
+        );
+
+        // Completed tasks stay completed
+        task_repository
+            .update_task_from_completed_update(&TaskCompletedUpdate::new(
+                task.id,
+                Local::now().naive_local(),
+                vec![],
+                false,
+            ))
+            .await
+            .unwrap();
+        let response = server
+            .post(&path)
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+        let response = server
+            .post(&format!("/tasks/{}/requeue", Uuid::new_v4()))
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(publisher.published.lock().unwrap().len(), 1);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_requeue_task_requires_publisher(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let response = server
+            .post(&format!("/tasks/{}/requeue", Uuid::new_v4()))
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    /// Test server publishing to `publisher`
+    async fn get_publishing_test_server(
+        db_pools: PgPool,
+        publisher: Arc<RecordingPublisher>,
+    ) -> TestServer {
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            None,
+            Some(publisher),
+            &RequestLimits {
+                max_submit_batch_size: 3,
+                max_payload_bytes: 8,
+                ..RequestLimits::default()
+            },
+            Some(TEST_ADMIN_TOKEN.to_string()),
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        TestServer::new(app).unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_tasks(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let server = get_publishing_test_server(db_pools.clone(), publisher.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools.clone()));
+        WorkerRepository::new(PgRepositoryCore::new(db_pools))
+            .create_worker_kind("other_worker", "other.tasks", "other_queue")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let id = Uuid::new_v4();
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([
+                { "id": id, "task_kind": "resize", "worker_kind": "image_worker", "input_data": [1, 2, 3], "priority": 5 },
+                { "task_kind": "crop", "worker_kind": "other_worker" }
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        let submitted = response.json::<BatchSubmitResponse>().tasks;
+        assert_eq!(submitted.len(), 2);
+        assert_eq!(submitted[0].id, id);
+        assert!(submitted
+            .iter()
+            .all(|task| task.status == SubmissionStatus::Published && task.error.is_none()));
+        assert_eq!(
+            *publisher.published.lock().unwrap(),
+            vec![
+                ("tasks.image_worker".to_string(), id),
+                ("other.tasks".to_string(), submitted[1].id)
+            ]
+        );
+
+        let task = task_repository.get_task_by_id(&id).await.unwrap().unwrap();
+        assert_eq!(task.task_kind.as_deref(), Some("resize"));
+        assert_eq!(task.input_data, Some(vec![1, 2, 3]));
+        assert_eq!(task.priority, Some(5));
+        assert_eq!(
+            task_repository
+                .get_task_status(&submitted[1].id)
+                .await
                 .unwrap(),
-            "application/json"
+            Some(TaskStatus::Pending)
         );
+    }
 
-        // Test with higher quality for Avro
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            axum::http::header::ACCEPT,
-            HeaderValue::from_static("application/json;q=0.7, application/avro;q=0.8"),
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_tasks_applies_ttl_policy(db_pools: PgPool) {
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools.clone()))
+            .with_ttl_policy(TtlPolicy {
+                default_secs: 600,
+                min_secs: 60,
+            });
+        let app = setup_app(
+            &db_pools,
+            task_repository.clone(),
+            None,
+            Some(Arc::new(RecordingPublisher::default())),
+            &RequestLimits::default(),
+            None,
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([
+                { "task_kind": "resize", "worker_kind": "image_worker" },
+                { "task_kind": "resize", "worker_kind": "image_worker", "ttl_duration": 5 }
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        // Unset TTLs get the default, and short ones are raised to the minimum
+        let submitted = response.json::<BatchSubmitResponse>().tasks;
+        let ttls = [
+            task_repository
+                .get_task_by_id(&submitted[0].id)
+                .await
+                .unwrap(),
+            task_repository
+                .get_task_by_id(&submitted[1].id)
+                .await
+                .unwrap(),
+        ]
+        .map(|task| task.unwrap().ttl_duration);
+        assert_eq!(ttls, [Some(600), Some(60)]);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_scheduled_tasks(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let server = get_publishing_test_server(db_pools.clone(), publisher.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let later = Uuid::new_v4();
+        let overdue = Uuid::new_v4();
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([
+                { "id": later, "task_kind": "resize", "worker_kind": "image_worker", "scheduled_for": "2999-01-01T00:00:00Z" },
+                { "id": overdue, "task_kind": "resize", "worker_kind": "image_worker", "scheduled_for": "2000-01-01T00:00:00Z" }
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        // Only the task whose time has come is published
+        let submitted = response.json::<BatchSubmitResponse>().tasks;
+        assert_eq!(submitted[0].status, SubmissionStatus::Scheduled);
+        assert_eq!(submitted[1].status, SubmissionStatus::Published);
+        assert_eq!(
+            *publisher.published.lock().unwrap(),
+            vec![("tasks.image_worker".to_string(), overdue)]
         );
 
+        let task = task_repository
+            .get_task_by_id(&later)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            task.scheduled_for.map(|at| at.to_string()).as_deref(),
+            Some("2999-01-01 00:00:00")
+        );
+        let due = task_repository
+            .find_due_scheduled_tasks(chrono::Utc::now().naive_utc(), 10)
+            .await
+            .unwrap();
+        assert!(due.is_empty());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_tasks_inherits_task_kind_defaults(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let server = get_publishing_test_server(db_pools.clone(), publisher).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+        task_repository
+            .set_task_kind_defaults(
+                "resize",
+                &TaskKindDefaults {
+                    default_priority: Some(7),
+                    default_ttl_duration: Some(600),
+                },
+            )
+            .await
+            .unwrap();
+
         let response = server
-            .get(&format!("/tasks/{}", test_task.id))
-            .add_header(
-                axum::http::header::ACCEPT,
-                HeaderValue::from_static("application/json;q=0.7, application/avro;q=0.8"),
+            .post("/tasks/batch")
+            .json(&json!([
+                { "task_kind": "resize", "worker_kind": "image_worker" },
+                { "task_kind": "resize", "worker_kind": "image_worker", "priority": 2 },
+                { "task_kind": "crop", "worker_kind": "image_worker" }
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+        let submitted = response.json::<BatchSubmitResponse>().tasks;
+
+        let mut tasks = Vec::new();
+        for task in &submitted {
+            tasks.push(
+                task_repository
+                    .get_task_by_id(&task.id)
+                    .await
+                    .unwrap()
+                    .unwrap(),
+            );
+        }
+        assert_eq!(tasks[0].priority, Some(7));
+        assert_eq!(tasks[0].ttl_duration, Some(600));
+        assert_eq!(tasks[1].priority, Some(2));
+        assert_eq!(tasks[2].priority, Some(0));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_tasks_reports_failed_publishes(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher {
+            fail_after: Some(1),
+            ..RecordingPublisher::default()
+        });
+        let server = get_publishing_test_server(db_pools.clone(), publisher.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([
+                { "task_kind": "resize", "worker_kind": "image_worker" },
+                { "task_kind": "resize", "worker_kind": "image_worker" },
+                { "task_kind": "resize", "worker_kind": "image_worker" }
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::MULTI_STATUS);
+
+        let submitted = response.json::<BatchSubmitResponse>().tasks;
+        let statuses: Vec<_> = submitted.iter().map(|task| task.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                SubmissionStatus::Published,
+                SubmissionStatus::Failed,
+                SubmissionStatus::Failed
+            ]
+        );
+        assert_eq!(submitted[1].error.as_deref(), Some("Broker unavailable"));
+
+        // Every task is stored, published or not
+        for task in &submitted {
+            assert_eq!(
+                task_repository.get_task_status(&task.id).await.unwrap(),
+                Some(TaskStatus::Pending)
+            );
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_tasks_rejects_invalid_batches(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let server = get_publishing_test_server(db_pools.clone(), publisher.clone()).await;
+
+        let task = json!({ "task_kind": "resize", "worker_kind": "image_worker" });
+        let response = server.post("/tasks/batch").json(&json!([])).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([task, task, task, task]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        // An invalid task stores none of the batch
+        let id = Uuid::new_v4();
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([
+                { "id": id, "task_kind": "resize", "worker_kind": "image_worker" },
+                { "task_kind": "resize", "worker_kind": "image_worker", "ttl_duration": i64::MAX }
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        assert!(TaskRepository::new(PgRepositoryCore::new(db_pools))
+            .get_task_by_id(&id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(publisher.published.lock().unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_tasks_requires_publisher(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([{ "task_kind": "resize", "worker_kind": "image_worker" }]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_validate_tasks(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let server = get_publishing_test_server(db_pools.clone(), publisher.clone()).await;
+        WorkerRepository::new(PgRepositoryCore::new(db_pools.clone()))
+            .save_heartbeat(&WorkerHeartbeatUpdate::new(
+                "worker-1",
+                "image_worker",
+                Local::now().naive_local(),
+            ))
+            .await
+            .unwrap();
+
+        let id = Uuid::new_v4();
+        let response = server
+            .post("/tasks/validate")
+            .json(&json!([
+                { "id": id, "task_kind": "resize", "worker_kind": "image_worker", "input_data": [1, 2, 3] }
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.json::<serde_json::Value>(),
+            json!({ "valid": true })
+        );
+
+        // Nothing is stored nor published
+        assert!(TaskRepository::new(PgRepositoryCore::new(db_pools))
+            .get_task_by_id(&id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(publisher.published.lock().unwrap().is_empty());
+
+        // A worker kind without workers is only warned about
+        let response = server
+            .post("/tasks/validate")
+            .json(&json!([{ "task_kind": "resize", "worker_kind": "unknown_worker" }]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let validation = response.json::<ValidationResponse>();
+        assert!(validation.valid);
+        assert_eq!(validation.warnings.len(), 1);
+        assert!(validation.warnings[0].contains("unknown_worker"));
+    }
+
+    /// Validates a batch expected to be invalid, returning its errors
+    async fn validation_errors(server: &TestServer, body: serde_json::Value) -> Vec<String> {
+        let response = server.post("/tasks/validate").json(&body).await;
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation = response.json::<ValidationResponse>();
+        assert!(!validation.valid);
+        validation.errors
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_validate_tasks_reports_every_problem(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let server = get_publishing_test_server(db_pools, publisher).await;
+
+        assert_eq!(
+            validation_errors(&server, json!([])).await,
+            vec!["No tasks to submit"]
+        );
+
+        let task = json!({ "task_kind": "resize", "worker_kind": "image_worker" });
+        assert_eq!(
+            validation_errors(&server, json!([task, task, task, task])).await,
+            vec!["At most 3 tasks can be submitted at once"]
+        );
+
+        let errors = validation_errors(
+            &server,
+            json!([
+                { "task_kind": "", "worker_kind": " ", "input_data": [0, 0, 0, 0, 0, 0, 0, 0, 0] },
+                { "task_kind": "resize", "worker_kind": "image_worker", "priority": 300 },
+                { "task_kind": "resize", "worker_kind": "image_worker", "ttl_duration": i64::MAX }
+            ]),
+        )
+        .await;
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert_eq!(errors[0], "Task 0: task_kind must not be blank");
+        assert_eq!(errors[1], "Task 0: worker_kind must not be blank");
+        assert!(errors[2].starts_with("Task 0: input_data of 9 bytes"));
+        assert_eq!(errors[3], "Task 1: priority must be between 0 and 255");
+        assert!(errors[4].starts_with("Task 2: Invalid ttl_duration"));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_tasks_strict_worker_kind(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            None,
+            Some(publisher.clone()),
+            &RequestLimits::default(),
+            None,
+            true,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        let strict_server = TestServer::new(app).unwrap();
+        WorkerRepository::new(PgRepositoryCore::new(db_pools.clone()))
+            .save_heartbeat(&WorkerHeartbeatUpdate::new(
+                "worker-1",
+                "image_worker",
+                Local::now().naive_local(),
+            ))
+            .await
+            .unwrap();
+
+        // A typo in the worker kind rejects the whole batch
+        let response = strict_server
+            .post("/tasks/batch")
+            .json(&json!([
+                { "task_kind": "resize", "worker_kind": "image_worker" },
+                { "task_kind": "resize", "worker_kind": "imgae_worker" }
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.text(),
+            "Unknown worker kind imgae_worker, no worker of this kind has registered"
+        );
+        assert!(publisher.published.lock().unwrap().is_empty());
+
+        let response = strict_server
+            .post("/tasks/validate")
+            .json(&json!([{ "task_kind": "resize", "worker_kind": "imgae_worker" }]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation = response.json::<ValidationResponse>();
+        assert_eq!(validation.errors.len(), 1);
+        assert!(validation.warnings.is_empty());
+
+        let response = strict_server
+            .post("/tasks/batch")
+            .json(&json!([{ "task_kind": "resize", "worker_kind": "image_worker" }]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        // Without strict mode, the tasks wait for a worker of their kind
+        let lenient_server = get_publishing_test_server(db_pools, publisher.clone()).await;
+        let response = lenient_server
+            .post("/tasks/batch")
+            .json(&json!([{ "task_kind": "resize", "worker_kind": "imgae_worker" }]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+        assert_eq!(publisher.published.lock().unwrap().len(), 2);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_tasks_input_schema(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        // Without the payload limit of the publishing test server, which
+        // JSON inputs exceed
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            None,
+            Some(publisher.clone()),
+            &RequestLimits::default(),
+            None,
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
+        let server = TestServer::new(app).unwrap();
+        TaskRepository::new(PgRepositoryCore::new(db_pools))
+            .set_task_kind_input_schema(
+                "resize",
+                Some(&json!({
+                    "type": "object",
+                    "required": ["width"],
+                    "properties": { "width": { "type": "integer" } }
+                })),
             )
+            .await
+            .unwrap();
+        let task = |input: serde_json::Value| {
+            json!({
+                "task_kind": "resize",
+                "worker_kind": "image_worker",
+                "input_data": serde_json::to_vec(&input).unwrap(),
+                "input_content_type": "application/json"
+            })
+        };
+
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([task(json!({ "width": 640 }))]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([
+                task(json!({ "width": 640 })),
+                task(json!({ "width": "640" }))
+            ]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(response.text().starts_with("Task 1: input_data/width: "));
+        assert_eq!(publisher.published.lock().unwrap().len(), 1);
+
+        let response = server
+            .post("/tasks/validate")
+            .json(&json!([task(json!({ "height": 480 }))]))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.json::<ValidationResponse>().errors.len(), 1);
+
+        // Inputs not declared as JSON are left opaque
+        let response = server
+            .post("/tasks/batch")
+            .json(&json!([{
+                "task_kind": "resize",
+                "worker_kind": "image_worker",
+                "input_data": [0, 159, 146, 150]
+            }]))
             .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_task_sync(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let server = get_publishing_test_server(db_pools.clone(), publisher.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        // Stands in for a worker completing the task once it was published
+        let worker = {
+            let publisher = publisher.clone();
+            tokio::spawn(async move {
+                let id = loop {
+                    if let Some((_, id)) = publisher.published.lock().unwrap().first() {
+                        break *id;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                };
+                let mut completed =
+                    TaskCompletedUpdate::new(id, Local::now().naive_local(), vec![4, 5, 6], false);
+                completed.output_content_type = Some("application/x-resized".to_string());
+                task_repository
+                    .update_task_from_completed_update(&completed)
+                    .await
+                    .unwrap();
+            })
+        };
 
+        let response = server
+            .post("/tasks/sync?timeout=5")
+            .json(&json!({ "task_kind": "resize", "worker_kind": "image_worker" }))
+            .await;
         assert_eq!(response.status_code(), StatusCode::OK);
         assert_eq!(
-            response
-                .headers()
-                .get(axum::http::header::CONTENT_TYPE)
-                .unwrap(),
-            "application/avro"
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-resized"
+        );
+        assert_eq!(response.as_bytes().to_vec(), vec![4, 5, 6]);
+        assert!(response.headers().get(TASK_ERROR_HEADER).is_none());
+        worker.await.unwrap();
+    }
+
+    #[test]
+    fn test_max_sync_timeout_stays_below_request_timeout() {
+        assert_eq!(
+            max_sync_timeout_secs(std::time::Duration::from_secs(30)),
+            MAX_SYNC_TASK_TIMEOUT_SECS.min(29)
+        );
+        assert_eq!(
+            max_sync_timeout_secs(std::time::Duration::from_secs(600)),
+            MAX_SYNC_TASK_TIMEOUT_SECS
+        );
+        assert_eq!(
+            max_sync_timeout_secs(std::time::Duration::from_millis(500)),
+            1
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_submit_task_sync_timeout(db_pools: PgPool) {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let server = get_publishing_test_server(db_pools.clone(), publisher.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+        let task = json!({ "task_kind": "resize", "worker_kind": "image_worker" });
+
+        let response = server.post("/tasks/sync?timeout=0").json(&task).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        // The default request timeout of 30 seconds would abort the wait
+        let response = server.post("/tasks/sync?timeout=30").json(&task).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        assert!(publisher.published.lock().unwrap().is_empty());
+
+        // No worker picks the task up, which stays pending after the wait
+        let response = server.post("/tasks/sync?timeout=1").json(&task).await;
+        assert_eq!(response.status_code(), StatusCode::REQUEST_TIMEOUT);
+        let id = publisher.published.lock().unwrap()[0].1;
+        assert!(response.text().contains(&id.to_string()));
+        assert_eq!(
+            task_repository.get_task_status(&id).await.unwrap(),
+            Some(TaskStatus::Pending)
         );
     }
 }
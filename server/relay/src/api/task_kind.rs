@@ -0,0 +1,390 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, put},
+    Json, Router,
+};
+use tracing::{debug, error, info, instrument};
+
+use crate::api::admin::AdminGuard;
+use crate::constants::MAX_TTL_DURATION_SECS;
+use crate::lifecycle::AppState;
+use crate::models::{TaskKind, TaskKindDefaults};
+
+pub fn routes() -> Router<AppState> {
+    debug!("Setting up task kind API routes");
+    Router::new()
+        .route("/", get(list_task_kinds))
+        .route("/{name}", get(get_task_kind))
+        .route("/{name}/defaults", put(set_task_kind_defaults))
+        .route("/{name}/input-schema", put(set_task_kind_input_schema))
+}
+
+/// List the task kinds submitted to the relay
+///
+/// # Returns
+/// Returns every task kind ordered by name, with the worker kind it was most
+/// recently submitted to
+#[utoipa::path(
+    get,
+    description = "List the task kinds submitted to the relay",
+    path = "/task-kinds",
+    responses(
+        (status = 200, description = "Task kinds found", body = Vec<TaskKind>, content_type = "application/json"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "task-kinds"
+)]
+#[instrument(skip(state))]
+async fn list_task_kinds(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TaskKind>>, (StatusCode, String)> {
+    info!("API request: List task kinds");
+
+    match state.task_repository.list_task_kinds().await {
+        Ok(task_kinds) => {
+            debug!(count = task_kinds.len(), "Successfully listed task kinds");
+            Ok(Json(task_kinds))
+        }
+        Err(e) => {
+            error!(error = %e, "Database error while listing task kinds");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to list task kinds: {}", e),
+            ))
+        }
+    }
+}
+
+/// Get a task kind by its name
+///
+/// # Arguments
+/// * `name` - Name of the task kind to retrieve
+///
+/// # Returns
+/// Returns the task kind if any task of that kind was submitted
+#[utoipa::path(
+    get,
+    description = "Get a task kind by its name",
+    path = "/task-kinds/{name}",
+    params(
+        ("name" = String, Path, description = "Task kind name to get")
+    ),
+    responses(
+        (status = 200, description = "Task kind found", body = TaskKind, content_type = "application/json"),
+        (status = 404, description = "Task kind not found", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "task-kinds"
+)]
+#[instrument(skip(state))]
+async fn get_task_kind(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<TaskKind>, (StatusCode, String)> {
+    info!(task_kind = %name, "API request: Get task kind");
+
+    match state.task_repository.get_task_kind(&name).await {
+        Ok(Some(task_kind)) => Ok(Json(task_kind)),
+        Ok(None) => {
+            debug!(task_kind = %name, "Task kind not found");
+            Err((
+                StatusCode::NOT_FOUND,
+                format!("Task kind {} not found", name),
+            ))
+        }
+        Err(e) => {
+            error!(task_kind = %name, error = %e, "Database error while fetching task kind");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get task kind: {}", e),
+            ))
+        }
+    }
+}
+
+/// Set the defaults of a task kind
+///
+/// # Arguments
+/// * `name` - Name of the task kind, which doesn't need to have any task yet
+/// * `defaults` - The priority and TTL of the tasks submitted without one.
+///   Unset defaults are cleared
+///
+/// # Returns
+/// Returns the stored defaults
+#[utoipa::path(
+    put,
+    description = "Set the priority and TTL applied to the tasks of a kind submitted without them. Requires the admin token.",
+    path = "/task-kinds/{name}/defaults",
+    params(
+        ("name" = String, Path, description = "Task kind name to set the defaults of")
+    ),
+    request_body = TaskKindDefaults,
+    responses(
+        (status = 200, description = "Defaults stored", body = TaskKindDefaults, content_type = "application/json"),
+        (status = 400, description = "Priority or TTL out of range", content_type = "text/plain"),
+        (status = 401, description = "Missing admin token", content_type = "text/plain"),
+        (status = 403, description = "Invalid admin token or admin endpoints disabled", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    security(("admin_token" = [])),
+    tag = "task-kinds"
+)]
+#[instrument(skip(state, _admin))]
+async fn set_task_kind_defaults(
+    _admin: AdminGuard,
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(defaults): Json<TaskKindDefaults>,
+) -> Result<Json<TaskKindDefaults>, (StatusCode, String)> {
+    info!(task_kind = %name, defaults = ?defaults, "API request: Set task kind defaults");
+
+    if defaults
+        .default_priority
+        .is_some_and(|priority| !(0..=255).contains(&priority))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "default_priority must be between 0 and 255".to_string(),
+        ));
+    }
+    if defaults
+        .default_ttl_duration
+        .is_some_and(|ttl| !(0..=MAX_TTL_DURATION_SECS).contains(&ttl))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "default_ttl_duration must be between 0 and {} seconds",
+                MAX_TTL_DURATION_SECS
+            ),
+        ));
+    }
+
+    match state
+        .task_repository
+        .set_task_kind_defaults(&name, &defaults)
+        .await
+    {
+        Ok(()) => Ok(Json(defaults)),
+        Err(e) => {
+            error!(task_kind = %name, error = %e, "Database error while setting task kind defaults");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to set task kind defaults: {}", e),
+            ))
+        }
+    }
+}
+
+/// Set the input schema of a task kind
+///
+/// # Arguments
+/// * `name` - Name of the task kind, which doesn't need to have any task yet
+/// * `schema` - The JSON Schema the inputs of the kind declared as JSON must
+///   conform to, or `null` to clear it
+///
+/// # Returns
+/// Returns the stored schema
+#[utoipa::path(
+    put,
+    description = "Set the JSON Schema that inputs of a task kind declared as `application/json` are validated against on submission. `null` clears it. Requires the admin token.",
+    path = "/task-kinds/{name}/input-schema",
+    params(
+        ("name" = String, Path, description = "Task kind name to set the input schema of")
+    ),
+    request_body(content = Option<Object>, description = "JSON Schema of the inputs", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Input schema stored", body = Option<Object>, content_type = "application/json"),
+        (status = 400, description = "Invalid JSON Schema", content_type = "text/plain"),
+        (status = 401, description = "Missing admin token", content_type = "text/plain"),
+        (status = 403, description = "Invalid admin token or admin endpoints disabled", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    security(("admin_token" = [])),
+    tag = "task-kinds"
+)]
+#[instrument(skip(state, _admin, schema))]
+async fn set_task_kind_input_schema(
+    _admin: AdminGuard,
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(schema): Json<Option<serde_json::Value>>,
+) -> Result<Json<Option<serde_json::Value>>, (StatusCode, String)> {
+    info!(task_kind = %name, "API request: Set task kind input schema");
+
+    if let Some(schema) = &schema {
+        jsonschema::validator_for(schema).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid JSON Schema: {}", e),
+            )
+        })?;
+    }
+
+    match state
+        .task_repository
+        .set_task_kind_input_schema(&name, schema.as_ref())
+        .await
+    {
+        Ok(()) => Ok(Json(schema)),
+        Err(e) => {
+            error!(task_kind = %name, error = %e, "Database error while setting task kind input schema");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to set task kind input schema: {}", e),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::{Task, TaskKind, TaskKindDefaults};
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use crate::{
+        repo::{PgRepositoryCore, TaskRepository},
+        testing::test::{get_test_server, init_test_logger, TEST_ADMIN_TOKEN},
+    };
+
+    // This runs before any test in this module
+    #[ctor::ctor]
+    fn init() {
+        init_test_logger();
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_task_kinds(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        task_repository
+            .create_task(&Task::new("resize_image", "WorkerKindName", 0, 0))
+            .await
+            .unwrap();
+
+        let response = server.get("/task-kinds").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let task_kinds = response.json::<Vec<TaskKind>>();
+        assert_eq!(task_kinds.len(), 1);
+        assert_eq!(task_kinds[0].name, "resize_image");
+        assert_eq!(task_kinds[0].worker_kind, "WorkerKindName");
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_task_kind(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        task_repository
+            .create_task(&Task::new("resize_image", "WorkerKindName", 0, 0))
+            .await
+            .unwrap();
+
+        let response = server.get("/task-kinds/resize_image").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<TaskKind>().name, "resize_image");
+
+        let response = server.get("/task-kinds/missing").await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_set_task_kind_defaults(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+        let path = "/task-kinds/resize_image/defaults";
+        let defaults = json!({ "default_priority": 9, "default_ttl_duration": 3600 });
+
+        let response = server.put(path).json(&defaults).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .put(path)
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&defaults)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            response.json::<TaskKindDefaults>(),
+            TaskKindDefaults {
+                default_priority: Some(9),
+                default_ttl_duration: Some(3600),
+            }
+        );
+
+        // The defaults show on the kind once it has tasks
+        task_repository
+            .create_task(&Task::new("resize_image", "WorkerKindName", 0, 0))
+            .await
+            .unwrap();
+        let task_kind = server
+            .get("/task-kinds/resize_image")
+            .await
+            .json::<TaskKind>();
+        assert_eq!(task_kind.default_priority, Some(9));
+        assert_eq!(task_kind.default_ttl_duration, Some(3600));
+
+        let response = server
+            .put(path)
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&json!({ "default_priority": 256 }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_set_task_kind_input_schema(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+        let path = "/task-kinds/resize_image/input-schema";
+        let schema = json!({
+            "type": "object",
+            "required": ["width"],
+            "properties": { "width": { "type": "integer" } }
+        });
+
+        let response = server.put(path).json(&schema).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .put(path)
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&schema)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        task_repository
+            .create_task(&Task::new("resize_image", "WorkerKindName", 0, 0))
+            .await
+            .unwrap();
+        let task_kind = server
+            .get("/task-kinds/resize_image")
+            .await
+            .json::<TaskKind>();
+        assert_eq!(task_kind.input_schema, Some(schema));
+
+        let response = server
+            .put(path)
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&json!({ "type": "integr" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let response = server
+            .put(path)
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&serde_json::Value::Null)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let task_kind = server
+            .get("/task-kinds/resize_image")
+            .await
+            .json::<TaskKind>();
+        assert_eq!(task_kind.input_schema, None);
+    }
+}
@@ -0,0 +1,469 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use tracing::{debug, error, info, instrument};
+
+use crate::api::admin::AdminGuard;
+use crate::lifecycle::AppState;
+use crate::models::{NewWorkerKind, Worker, WorkerKind};
+use crate::repo::WorkerKindDeletion;
+use crate::task_event_publisher::worker_routing_key;
+
+pub fn routes() -> Router<AppState> {
+    debug!("Setting up worker kind API routes");
+    Router::new()
+        .route("/", get(list_worker_kinds).post(create_worker_kind))
+        .route("/{name}", get(get_worker_kind).delete(delete_worker_kind))
+        .route("/{name}/workers", get(list_workers_of_kind))
+}
+
+/// List the worker kinds known to the relay
+///
+/// # Returns
+/// Returns every worker kind ordered by name, with where its task assignments
+/// are routed
+#[utoipa::path(
+    get,
+    description = "List the worker kinds known to the relay",
+    path = "/worker-kinds",
+    responses(
+        (status = 200, description = "Worker kinds found", body = Vec<WorkerKind>, content_type = "application/json"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "worker-kinds"
+)]
+#[instrument(skip(state))]
+async fn list_worker_kinds(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<WorkerKind>>, (StatusCode, String)> {
+    info!("API request: List worker kinds");
+
+    match state.worker_repository.find_all_worker_kinds().await {
+        Ok(worker_kinds) => {
+            debug!(
+                count = worker_kinds.len(),
+                "Successfully listed worker kinds"
+            );
+            Ok(Json(worker_kinds))
+        }
+        Err(e) => {
+            error!(error = %e, "Database error while listing worker kinds");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to list worker kinds: {}", e),
+            ))
+        }
+    }
+}
+
+/// Get a worker kind by its name
+///
+/// # Arguments
+/// * `name` - Name of the worker kind to retrieve
+///
+/// # Returns
+/// Returns the worker kind if it was created or one of its workers registered
+#[utoipa::path(
+    get,
+    description = "Get a worker kind by its name",
+    path = "/worker-kinds/{name}",
+    params(
+        ("name" = String, Path, description = "Worker kind name to get")
+    ),
+    responses(
+        (status = 200, description = "Worker kind found", body = WorkerKind, content_type = "application/json"),
+        (status = 404, description = "Worker kind not found", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "worker-kinds"
+)]
+#[instrument(skip(state))]
+async fn get_worker_kind(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<WorkerKind>, (StatusCode, String)> {
+    info!(worker_kind = %name, "API request: Get worker kind");
+
+    match state.worker_repository.get_worker_kind(&name).await {
+        Ok(Some(worker_kind)) => Ok(Json(worker_kind)),
+        Ok(None) => {
+            debug!(worker_kind = %name, "Worker kind not found");
+            Err((
+                StatusCode::NOT_FOUND,
+                format!("Worker kind {} not found", name),
+            ))
+        }
+        Err(e) => {
+            error!(worker_kind = %name, error = %e, "Database error while fetching worker kind");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get worker kind: {}", e),
+            ))
+        }
+    }
+}
+
+/// Create a worker kind
+///
+/// # Arguments
+/// * `new_worker_kind` - The name of the kind, and optionally where its task
+///   assignments are routed
+///
+/// # Returns
+/// Returns the created worker kind
+#[utoipa::path(
+    post,
+    description = "Create a worker kind. Unset routing settings follow the SDK conventions. Requires the admin token.",
+    path = "/worker-kinds",
+    request_body = NewWorkerKind,
+    responses(
+        (status = 201, description = "Worker kind created", body = WorkerKind, content_type = "application/json"),
+        (status = 400, description = "Blank name, routing key or queue name, or wildcard in the routing key", content_type = "text/plain"),
+        (status = 401, description = "Missing admin token", content_type = "text/plain"),
+        (status = 403, description = "Invalid admin token or admin endpoints disabled", content_type = "text/plain"),
+        (status = 409, description = "Worker kind already exists", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    security(("admin_token" = [])),
+    tag = "worker-kinds"
+)]
+#[instrument(skip(state, _admin))]
+async fn create_worker_kind(
+    _admin: AdminGuard,
+    State(state): State<AppState>,
+    Json(new_worker_kind): Json<NewWorkerKind>,
+) -> Result<(StatusCode, Json<WorkerKind>), (StatusCode, String)> {
+    info!(worker_kind = %new_worker_kind.name, "API request: Create worker kind");
+
+    let name = new_worker_kind.name.trim();
+    let routing_key = new_worker_kind
+        .routing_key
+        .unwrap_or_else(|| worker_routing_key(name));
+    let queue_name = new_worker_kind
+        .queue_name
+        .unwrap_or_else(|| name.to_string());
+    for (field, value) in [
+        ("name", name),
+        ("routing_key", routing_key.as_str()),
+        ("queue_name", queue_name.as_str()),
+    ] {
+        if value.trim().is_empty() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("{} must not be blank", field),
+            ));
+        }
+    }
+    // Assignments are published with the routing key, which must match one
+    // binding exactly rather than be a pattern
+    if routing_key.contains(['*', '#']) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "routing_key must not contain the wildcards * or #".to_string(),
+        ));
+    }
+
+    match state
+        .worker_repository
+        .create_worker_kind(name, &routing_key, &queue_name)
+        .await
+    {
+        Ok(Some(worker_kind)) => Ok((StatusCode::CREATED, Json(worker_kind))),
+        Ok(None) => {
+            debug!(worker_kind = %name, "Worker kind already exists");
+            Err((
+                StatusCode::CONFLICT,
+                format!("Worker kind {} already exists", name),
+            ))
+        }
+        Err(e) => {
+            error!(worker_kind = %name, error = %e, "Database error while creating worker kind");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to create worker kind: {}", e),
+            ))
+        }
+    }
+}
+
+/// Delete a worker kind
+///
+/// # Arguments
+/// * `name` - Name of the worker kind to delete
+///
+/// # Returns
+/// Returns `204 No Content` once deleted. Kinds still referenced by workers
+/// or tasks are kept
+#[utoipa::path(
+    delete,
+    description = "Delete a worker kind that no worker or task references. Requires the admin token.",
+    path = "/worker-kinds/{name}",
+    params(
+        ("name" = String, Path, description = "Worker kind name to delete")
+    ),
+    responses(
+        (status = 204, description = "Worker kind deleted"),
+        (status = 401, description = "Missing admin token", content_type = "text/plain"),
+        (status = 403, description = "Invalid admin token or admin endpoints disabled", content_type = "text/plain"),
+        (status = 404, description = "Worker kind not found", content_type = "text/plain"),
+        (status = 409, description = "Workers or tasks reference the worker kind", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    security(("admin_token" = [])),
+    tag = "worker-kinds"
+)]
+#[instrument(skip(state, _admin))]
+async fn delete_worker_kind(
+    _admin: AdminGuard,
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    info!(worker_kind = %name, "API request: Delete worker kind");
+
+    match state.worker_repository.delete_worker_kind(&name).await {
+        Ok(WorkerKindDeletion::Deleted) => Ok(StatusCode::NO_CONTENT),
+        Ok(WorkerKindDeletion::NotFound) => Err((
+            StatusCode::NOT_FOUND,
+            format!("Worker kind {} not found", name),
+        )),
+        Ok(WorkerKindDeletion::InUse) => {
+            debug!(worker_kind = %name, "Worker kind is still referenced");
+            Err((
+                StatusCode::CONFLICT,
+                format!(
+                    "Worker kind {} is still referenced by workers or tasks",
+                    name
+                ),
+            ))
+        }
+        Err(e) => {
+            error!(worker_kind = %name, error = %e, "Database error while deleting worker kind");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to delete worker kind: {}", e),
+            ))
+        }
+    }
+}
+
+/// List the workers of a worker kind
+///
+/// # Arguments
+/// * `name` - Name of the worker kind
+///
+/// # Returns
+/// Returns every worker of the kind ordered by name, with its latest heartbeat
+#[utoipa::path(
+    get,
+    description = "List the workers of a worker kind",
+    path = "/worker-kinds/{name}/workers",
+    params(
+        ("name" = String, Path, description = "Worker kind name to list the workers of")
+    ),
+    responses(
+        (status = 200, description = "Workers found", body = Vec<Worker>, content_type = "application/json"),
+        (status = 404, description = "Worker kind not found", content_type = "text/plain"),
+        (status = 500, description = "Internal server error", content_type = "text/plain")
+    ),
+    tag = "worker-kinds"
+)]
+#[instrument(skip(state))]
+async fn list_workers_of_kind(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<Worker>>, (StatusCode, String)> {
+    info!(worker_kind = %name, "API request: List workers of kind");
+
+    let database_error = |e: sqlx::Error| {
+        error!(worker_kind = %name, error = %e, "Database error while listing workers");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to list workers: {}", e),
+        )
+    };
+
+    if !state
+        .worker_repository
+        .worker_kind_exists(&name)
+        .await
+        .map_err(database_error)?
+    {
+        debug!(worker_kind = %name, "Worker kind not found");
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Worker kind {} not found", name),
+        ));
+    }
+
+    let workers = state
+        .worker_repository
+        .find_workers_by_kind(&name)
+        .await
+        .map_err(database_error)?;
+    debug!(worker_kind = %name, count = workers.len(), "Successfully listed workers");
+    Ok(Json(workers))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::{Task, Worker, WorkerHeartbeatUpdate, WorkerKind};
+    use axum::http::StatusCode;
+    use chrono::Local;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use crate::{
+        repo::{PgRepositoryCore, TaskRepository, WorkerRepository},
+        testing::test::{get_test_server, init_test_logger, TEST_ADMIN_TOKEN},
+    };
+
+    // This runs before any test in this module
+    #[ctor::ctor]
+    fn init() {
+        init_test_logger();
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_workers_of_kind(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let worker_repository = WorkerRepository::new(PgRepositoryCore::new(db_pools.clone()));
+
+        let response = server.get("/worker-kinds/TestWorker/workers").await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        worker_repository
+            .save_heartbeat(&WorkerHeartbeatUpdate::new(
+                "worker-1",
+                "TestWorker",
+                Local::now().naive_local(),
+            ))
+            .await
+            .unwrap();
+
+        let response = server.get("/worker-kinds/TestWorker/workers").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let workers = response.json::<Vec<Worker>>();
+        assert_eq!(workers.len(), 1);
+        assert_eq!(workers[0].name, "worker-1");
+
+        // A kind stays known once its workers are gone
+        sqlx::query!("INSERT INTO worker_kinds (name) VALUES ('IdleWorker')")
+            .execute(&db_pools)
+            .await
+            .unwrap();
+        let response = server.get("/worker-kinds/IdleWorker/workers").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(response.json::<Vec<Worker>>().is_empty());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_create_and_list_worker_kinds(db_pools: PgPool) {
+        let server = get_test_server(db_pools).await;
+
+        let response = server
+            .post("/worker-kinds")
+            .json(&json!({ "name": "GpuWorker" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .post("/worker-kinds")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&json!({ "name": "GpuWorker" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+        let created = response.json::<WorkerKind>();
+        assert_eq!(created.routing_key, "tasks.GpuWorker");
+        assert_eq!(created.queue_name, "GpuWorker");
+
+        let response = server
+            .post("/worker-kinds")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&json!({ "name": "CpuWorker", "routing_key": "cpu.tasks", "queue_name": "cpu" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        let response = server
+            .post("/worker-kinds")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&json!({ "name": "AnyWorker", "routing_key": "tasks.#" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let response = server
+            .post("/worker-kinds")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&json!({ "name": "GpuWorker" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+        let response = server
+            .post("/worker-kinds")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .json(&json!({ "name": " " }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let response = server.get("/worker-kinds").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let names: Vec<String> = response
+            .json::<Vec<WorkerKind>>()
+            .into_iter()
+            .map(|kind| kind.name)
+            .collect();
+        assert_eq!(names, vec!["CpuWorker", "GpuWorker"]);
+
+        let response = server.get("/worker-kinds/CpuWorker").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<WorkerKind>().routing_key, "cpu.tasks");
+
+        let response = server.get("/worker-kinds/missing").await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_delete_worker_kind(db_pools: PgPool) {
+        let server = get_test_server(db_pools.clone()).await;
+        let task_repository = TaskRepository::new(PgRepositoryCore::new(db_pools));
+
+        for name in ["GpuWorker", "BusyWorker"] {
+            let response = server
+                .post("/worker-kinds")
+                .authorization_bearer(TEST_ADMIN_TOKEN)
+                .json(&json!({ "name": name }))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CREATED);
+        }
+        task_repository
+            .create_task(&Task::new("resize_image", "BusyWorker", 0, 0))
+            .await
+            .unwrap();
+
+        let response = server.delete("/worker-kinds/GpuWorker").await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        // Kinds referenced by tasks are kept
+        let response = server
+            .delete("/worker-kinds/BusyWorker")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+        let response = server
+            .delete("/worker-kinds/GpuWorker")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+        let response = server
+            .delete("/worker-kinds/GpuWorker")
+            .authorization_bearer(TEST_ADMIN_TOKEN)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        let response = server.get("/worker-kinds/BusyWorker").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+}
@@ -1,4 +1,154 @@
 // This is the file for all the project constants
 
-/// Queue for the manager to receive all tasks
-pub static RELAY_QUEUE: &str = "relay_queue";
+use std::net::{IpAddr, Ipv4Addr};
+
+/// Exchange task assignments are published to when none is configured,
+/// shared with the SDKs
+pub static TASK_EXCHANGE: &str = "tacoq_task_exchange";
+
+/// Queue the relay consumes task events from when none are configured
+pub static DEFAULT_RELAY_QUEUE: &str = "tacoq_relay_queue";
+
+/// Headers exchange routing task events to the queues dedicated to their type
+pub static RELAY_EVENT_EXCHANGE: &str = "tacoq_relay_event_exchange";
+
+/// Interface the HTTP server binds to when none is configured, all of them
+pub static DEFAULT_HTTP_HOST: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+/// Port the HTTP server listens on when none is configured
+pub static DEFAULT_HTTP_PORT: u16 = 3000;
+
+/// Length of the window API requests are counted in when rate limiting is
+/// enabled and no window is configured
+pub static DEFAULT_RATE_LIMIT_WINDOW_SECS: u64 = 60;
+
+/// Maximum size of a request body accepted by the API when none is configured
+pub static DEFAULT_MAX_REQUEST_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Time after which an API request is aborted when none is configured
+pub static DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Largest task input or output stored when no limit is configured
+pub static DEFAULT_MAX_PAYLOAD_BYTES: usize = 16 * 1024 * 1024;
+
+/// Largest delivery the consumer decodes when no limit is configured, leaving
+/// room for the rest of an event around a payload of the maximum size
+pub static DEFAULT_MAX_MESSAGE_BYTES: usize = 32 * 1024 * 1024;
+
+/// Number of tasks in a page of the task listing when no limit is given
+pub static DEFAULT_TASK_PAGE_SIZE: i64 = 100;
+
+/// Largest page of the task listing a client may request
+pub static MAX_TASK_PAGE_SIZE: i64 = 1000;
+
+/// Age a task update must reach before the change feed lists it, so the
+/// transactions that wrote the updates before it have committed
+pub static TASK_CHANGES_LAG_SECS: f64 = 5.0;
+
+/// Largest number of task IDs a client may look up in a single batch
+pub static MAX_BATCH_GET_SIZE: usize = 500;
+
+/// Time a synchronous submission waits for its task when no timeout is given
+pub static DEFAULT_SYNC_TASK_TIMEOUT_SECS: u64 = 10;
+
+/// Longest a synchronous submission may wait for its task
+pub static MAX_SYNC_TASK_TIMEOUT_SECS: u64 = 60;
+
+/// Time between the first two checks of whether the task of a synchronous
+/// submission completed. It doubles after every check, so long waits don't
+/// keep querying the database
+pub static SYNC_TASK_POLL_INTERVAL_MS: u64 = 100;
+
+/// Longest time between two checks of whether the task of a synchronous
+/// submission completed
+pub static MAX_SYNC_TASK_POLL_INTERVAL_MS: u64 = 1000;
+
+/// Size past which an Avro task response is streamed in chunks instead of
+/// being handed to the response body as a single buffer
+pub static AVRO_STREAM_THRESHOLD_BYTES: usize = 1024 * 1024;
+
+/// Size of the chunks a streamed Avro task response is sent in
+pub static AVRO_STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Largest number of tasks a client may submit in a single batch when none
+/// is configured
+pub static DEFAULT_MAX_SUBMIT_BATCH_SIZE: usize = 100;
+
+/// Time after which the health check gives up on the broker, such as while
+/// the consumer is still reconnecting
+pub static BROKER_HEALTH_CHECK_TIMEOUT_SECS: u64 = 5;
+
+/// Maximum number of database connections when none is configured
+pub static DEFAULT_DB_MAX_CONNECTIONS: u32 = 20;
+
+/// Number of database connections kept open when none is configured
+pub static DEFAULT_DB_MIN_CONNECTIONS: u32 = 0;
+
+/// Time to wait for a free database connection when none is configured
+pub static DEFAULT_DB_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+
+/// Largest accepted task `ttl_duration` (ten years, in seconds). Anything
+/// bigger almost certainly came from a publisher sending another unit.
+pub static MAX_TTL_DURATION_SECS: i64 = 10 * 365 * 24 * 60 * 60;
+
+/// Task `ttl_duration` applied when the publisher doesn't set one (7 days, in
+/// seconds), like the default of the SDKs
+pub static DEFAULT_TASK_TTL_SECS: i64 = 60 * 60 * 24 * 7;
+
+/// Smallest task `ttl_duration` kept, in seconds. Shorter TTLs would delete
+/// tasks as soon as they complete, before anyone can read their result.
+pub static DEFAULT_MIN_TASK_TTL_SECS: i64 = 60;
+
+/// Number of recently handled task events remembered to skip their
+/// redeliveries when none is configured
+pub static DEFAULT_DEDUP_WINDOW: usize = 10_000;
+
+/// Seconds between runs of the expired task cleanup when none is configured
+pub static DEFAULT_CLEANUP_INTERVAL_SECS: u64 = 300;
+
+/// Most expired tasks deleted per statement of a cleanup run when none is
+/// configured. Smaller chunks hold their row locks for less time.
+pub static DEFAULT_CLEANUP_CHUNK_SIZE: i64 = 1000;
+
+/// Milliseconds between two delete statements of a cleanup run when none is
+/// configured, leaving room for concurrent writes to the tasks table
+pub static DEFAULT_CLEANUP_CHUNK_DELAY_MS: u64 = 100;
+
+/// Maximum number of connections of the cleanup job's own pool when none is
+/// configured. Kept small so a sweep can't compete with ingest for the
+/// database.
+pub static DEFAULT_CLEANUP_DB_MAX_CONNECTIONS: u32 = 2;
+
+/// Age of the latest heartbeat after which a worker is considered lost when
+/// none is configured, in seconds
+pub static DEFAULT_STALE_WORKER_THRESHOLD_SECS: u64 = 300;
+
+/// Seconds between two checks for tasks of lost workers
+pub static STALE_TASK_CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Seconds between two checks for scheduled tasks that are due, which is
+/// also how late they may be published
+pub static SCHEDULED_TASK_CHECK_INTERVAL_SECS: u64 = 5;
+
+/// Most scheduled tasks published per check
+pub static SCHEDULED_TASK_BATCH_SIZE: i64 = 100;
+
+/// Consecutive batches failing on an unreachable database after which
+/// consumption pauses when none is configured
+pub static DEFAULT_CIRCUIT_BREAKER_THRESHOLD: u32 = 5;
+
+/// Seconds consumption pauses before probing the database again when none
+/// is configured
+pub static DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS: u64 = 30;
+
+/// Seconds a consumed queue may go without deliveries before its channel is
+/// checked and reconnected if dead, when none is configured
+pub static DEFAULT_CONSUMER_IDLE_TIMEOUT_SECS: u64 = 60;
+
+/// Attempts made to publish a task event before giving up when none is
+/// configured
+pub static DEFAULT_PUBLISH_MAX_ATTEMPTS: u32 = 3;
+
+/// Seconds the consumer is given to finish handling its in-flight deliveries
+/// on shutdown, before the database pool is closed anyway
+pub static CONSUMER_DRAIN_TIMEOUT_SECS: u64 = 30;
@@ -1,6 +1,84 @@
+use crate::constants::BROKER_HEALTH_CHECK_TIMEOUT_SECS;
+use crate::models::QueueDepth;
 use crate::repo::PgRepositoryCore;
-use crate::task_event_consumer::{RabbitMQTaskEventCore, TaskEventCore};
+use crate::task_event_consumer::{RabbitMQTaskEventConsumer, TaskEventConsumer, TaskEventCore};
+use futures::future::BoxFuture;
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// Tracks the setup steps that must finish before the relay serves traffic,
+/// such as running the migrations and declaring the consumed queues. Clones
+/// share the same state.
+#[derive(Clone, Debug)]
+pub struct Readiness {
+    pending_steps: Arc<AtomicUsize>,
+}
+
+impl Readiness {
+    /// Creates a gate waiting for a number of setup steps.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - The number of steps to complete before being ready
+    pub fn new(steps: usize) -> Self {
+        Self {
+            pending_steps: Arc::new(AtomicUsize::new(steps)),
+        }
+    }
+
+    /// Marks a setup step as completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `step` - Name of the step, used in logs
+    pub fn complete_step(&self, step: &str) {
+        let previous =
+            self.pending_steps
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |pending| {
+                    pending.checked_sub(1)
+                });
+        if previous == Ok(1) {
+            info!(step = %step, "Last setup step completed, service is ready");
+        } else {
+            info!(step = %step, "Setup step completed");
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.pending_steps.load(Ordering::SeqCst) == 0
+    }
+}
+
+/// Source of the broker state, queried on every health check so that the
+/// result follows reconnections instead of a connection captured at startup.
+pub trait BrokerHealthSource: Send + Sync {
+    /// Checks the broker connection currently in use
+    fn check_broker(&self) -> BoxFuture<'_, Result<(), Box<dyn Error + Send + Sync>>>;
+
+    /// Reads how many messages wait in each consumed queue
+    fn queue_depths(&self) -> BoxFuture<'_, Result<Vec<QueueDepth>, Box<dyn Error + Send + Sync>>>;
+
+    /// Time the event the consumer processed last waited to be processed, `None`
+    /// until it processed one
+    fn consumer_lag(&self) -> Option<Duration>;
+}
+
+impl BrokerHealthSource for RabbitMQTaskEventConsumer {
+    fn check_broker(&self) -> BoxFuture<'_, Result<(), Box<dyn Error + Send + Sync>>> {
+        Box::pin(async move { self.core().await?.health_check().await })
+    }
+
+    fn queue_depths(&self) -> BoxFuture<'_, Result<Vec<QueueDepth>, Box<dyn Error + Send + Sync>>> {
+        Box::pin(RabbitMQTaskEventConsumer::queue_depths(self))
+    }
+
+    fn consumer_lag(&self) -> Option<Duration> {
+        self.lag().lag()
+    }
+}
 
 /// Represents the health status of an individual service component
 ///
@@ -22,17 +100,28 @@ pub struct ServiceHealth {
 #[derive(Clone)]
 pub struct ServiceHealthProbe {
     repository_core: PgRepositoryCore,
-    broker_core: Option<Arc<RabbitMQTaskEventCore>>,
+    broker: Option<Arc<dyn BrokerHealthSource>>,
 }
 
 impl ServiceHealthProbe {
     pub fn new(
         repository_core: PgRepositoryCore,
-        broker_core: Option<Arc<RabbitMQTaskEventCore>>,
+        broker: Option<Arc<dyn BrokerHealthSource>>,
     ) -> Self {
         Self {
             repository_core,
-            broker_core,
+            broker,
+        }
+    }
+
+    /// Checks the broker, giving up if it takes too long to answer
+    async fn check_broker(
+        broker: &dyn BrokerHealthSource,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let timeout = Duration::from_secs(BROKER_HEALTH_CHECK_TIMEOUT_SECS);
+        match tokio::time::timeout(timeout, broker.check_broker()).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("No answer after {} seconds", timeout.as_secs()).into()),
         }
     }
 
@@ -62,8 +151,8 @@ impl ServiceHealthProbe {
         reports.push(db_health);
 
         // Check broker health if configured
-        if let Some(broker) = &self.broker_core {
-            let broker_health = match broker.health_check().await {
+        if let Some(broker) = &self.broker {
+            let broker_health = match Self::check_broker(broker.as_ref()).await {
                 Ok(_) => ServiceHealth {
                     is_healthy: true,
                     component: "broker".to_string(),
@@ -84,3 +173,25 @@ impl ServiceHealthProbe {
         (is_system_healthy, reports)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readiness_waits_for_every_step() {
+        let readiness = Readiness::new(2);
+        let shared = readiness.clone();
+        assert!(!readiness.is_ready());
+
+        shared.complete_step("migrations");
+        assert!(!readiness.is_ready());
+
+        shared.complete_step("queue");
+        assert!(readiness.is_ready());
+
+        // Extra completions don't underflow
+        shared.complete_step("queue");
+        assert!(readiness.is_ready());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod scheduled_tasks;
+pub mod stale_tasks;
 pub mod task_cleanup;
-pub use task_cleanup::TaskCleanupJob;
+pub use scheduled_tasks::ScheduledTaskJob;
+pub use stale_tasks::{StaleTaskAction, StaleTaskJob};
+pub use task_cleanup::{CleanupMode, CleanupStats, TaskCleanupJob};
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+use tracing::{debug, error, info, info_span, Instrument};
+
+use crate::models::{Task, TaskAssignmentUpdate};
+use crate::repo::{TaskRepository, WorkerRepository};
+use crate::task_event_consumer::Event;
+use crate::task_event_publisher::TaskEventPublisher;
+
+/// Periodically publishes the assignments of scheduled tasks whose time has
+/// come. They are held back when the tasks are submitted, so workers don't
+/// receive them early.
+pub struct ScheduledTaskJob {
+    task_repository: TaskRepository,
+    worker_repository: WorkerRepository,
+    task_event_publisher: Arc<dyn TaskEventPublisher>,
+    interval: Duration,
+    batch_size: i64,
+}
+
+impl ScheduledTaskJob {
+    /// Creates a new scheduled task job
+    ///
+    /// # Arguments
+    /// * `task_repository` - The repository the scheduled tasks are read from
+    /// * `worker_repository` - The repository the routing keys of the worker kinds are read from
+    /// * `task_event_publisher` - The publisher of the due assignments
+    /// * `interval` - Time between two checks
+    /// * `batch_size` - Most tasks published per check
+    pub fn new(
+        task_repository: TaskRepository,
+        worker_repository: WorkerRepository,
+        task_event_publisher: Arc<dyn TaskEventPublisher>,
+        interval: Duration,
+        batch_size: i64,
+    ) -> Self {
+        info!(
+            interval_seconds = interval.as_secs(),
+            batch_size = batch_size,
+            "Creating scheduled task job"
+        );
+        Self {
+            task_repository,
+            worker_repository,
+            task_event_publisher,
+            interval,
+            batch_size,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        info!(
+            interval_seconds = self.interval.as_secs(),
+            "Starting scheduled task job"
+        );
+
+        let mut interval = time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            debug!("Scheduled task check tick triggered");
+
+            if let Err(e) = self.publish_due_tasks().await {
+                error!(error = %e, "Error publishing due scheduled tasks");
+            }
+        }
+    }
+
+    /// Publishes the assignment of every scheduled task that is due.
+    ///
+    /// # Returns
+    /// The number of tasks published
+    async fn publish_due_tasks(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let span = info_span!("publish_due_tasks");
+
+        async {
+            let now = chrono::Utc::now().naive_utc();
+            let tasks = self
+                .task_repository
+                .find_due_scheduled_tasks(now, self.batch_size)
+                .await?;
+            if tasks.is_empty() {
+                debug!("No due scheduled tasks");
+                return Ok(0);
+            }
+
+            let mut published = 0;
+            for task in tasks {
+                match self.publish_task(&task).await {
+                    Ok(true) => published += 1,
+                    Ok(false) => debug!(task_id = %task.id, "Scheduled task already released"),
+                    // One failure shouldn't hold back the other tasks
+                    Err(e) => {
+                        error!(task_id = %task.id, error = %e, "Failed to publish scheduled task")
+                    }
+                }
+            }
+
+            info!(published = published, "Published due scheduled tasks");
+            Ok(published)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Releases a scheduled task and publishes its assignment. The task is
+    /// held again if publishing fails, so the next check retries it.
+    ///
+    /// # Returns
+    /// Whether the task was published, `false` if another relay released it
+    async fn publish_task(&self, task: &Task) -> Result<bool, Box<dyn std::error::Error>> {
+        let assignment = TaskAssignmentUpdate::from_task(task).ok_or("Task was never assigned")?;
+        if !self
+            .task_repository
+            .release_scheduled_task(&task.id)
+            .await?
+        {
+            return Ok(false);
+        }
+
+        let routing_key = self
+            .worker_repository
+            .routing_key_of(&assignment.worker_kind)
+            .await?;
+        if let Err(e) = self
+            .task_event_publisher
+            .publish(&Event::Assignment(assignment), &routing_key)
+            .await
+        {
+            if let Some(scheduled_for) = task.scheduled_for {
+                self.task_repository
+                    .hold_scheduled_task(&task.id, scheduled_for)
+                    .await?;
+            }
+            return Err(e.to_string().into());
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TaskStatus;
+    use crate::repo::PgRepositoryCore;
+    use crate::testing::test::RecordingPublisher;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    /// Submits a task scheduled `delay` from now
+    async fn schedule_task(repo: &TaskRepository, delay: chrono::Duration) -> Uuid {
+        let now = chrono::Utc::now().naive_utc();
+        let assignment = TaskAssignmentUpdate {
+            id: Uuid::new_v4(),
+            task_kind: "TaskKindName".to_string(),
+            worker_kind: "WorkerKindName".to_string(),
+            created_at: now - chrono::Duration::hours(1),
+            scheduled_for: Some(now + delay),
+            ..Default::default()
+        };
+        repo.create_tasks_from_assignments(&[assignment.clone()])
+            .await
+            .unwrap();
+        assignment.id
+    }
+
+    fn job(pool: &PgPool, publisher: Arc<RecordingPublisher>) -> ScheduledTaskJob {
+        ScheduledTaskJob::new(
+            TaskRepository::new(PgRepositoryCore::new(pool.clone())),
+            WorkerRepository::new(PgRepositoryCore::new(pool.clone())),
+            publisher,
+            Duration::from_secs(1),
+            10,
+        )
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_publish_due_scheduled_tasks(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let due = schedule_task(&repo, chrono::Duration::seconds(-5)).await;
+        let future = schedule_task(&repo, chrono::Duration::hours(1)).await;
+        let publisher = Arc::new(RecordingPublisher::default());
+
+        let job = job(&pool, publisher.clone());
+        assert_eq!(job.publish_due_tasks().await.unwrap(), 1);
+        assert_eq!(
+            *publisher.published.lock().unwrap(),
+            vec![("tasks.WorkerKindName".to_string(), due)]
+        );
+
+        // Published tasks aren't published again, and future ones wait
+        assert_eq!(job.publish_due_tasks().await.unwrap(), 0);
+        assert_eq!(publisher.published.lock().unwrap().len(), 1);
+        assert_eq!(
+            repo.get_task_status(&future).await.unwrap(),
+            Some(TaskStatus::Pending)
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_failed_publish_is_retried(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let due = schedule_task(&repo, chrono::Duration::seconds(-5)).await;
+
+        let failing = Arc::new(RecordingPublisher {
+            fail_after: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(job(&pool, failing).publish_due_tasks().await.unwrap(), 0);
+
+        let publisher = Arc::new(RecordingPublisher::default());
+        assert_eq!(
+            job(&pool, publisher.clone())
+                .publish_due_tasks()
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            *publisher.published.lock().unwrap(),
+            vec![("tasks.WorkerKindName".to_string(), due)]
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_publish_with_stored_routing_key(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        WorkerRepository::new(PgRepositoryCore::new(pool.clone()))
+            .create_worker_kind("WorkerKindName", "gpu.tasks", "gpu_queue")
+            .await
+            .unwrap()
+            .unwrap();
+        let due = schedule_task(&repo, chrono::Duration::seconds(-5)).await;
+
+        let publisher = Arc::new(RecordingPublisher::default());
+        assert_eq!(
+            job(&pool, publisher.clone())
+                .publish_due_tasks()
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            *publisher.published.lock().unwrap(),
+            vec![("gpu.tasks".to_string(), due)]
+        );
+    }
+}
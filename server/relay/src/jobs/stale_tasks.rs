@@ -0,0 +1,344 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+use crate::models::{Task, TaskAssignmentUpdate, TaskCompletedUpdate};
+use crate::repo::{TaskRepository, WorkerRepository};
+use crate::task_event_consumer::Event;
+use crate::task_event_publisher::TaskEventPublisher;
+
+/// Output stored on the tasks failed because their worker went silent
+pub const WORKER_LOST_OUTPUT: &str = "worker lost";
+
+/// What happens to the tasks of a worker that stopped sending heartbeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleTaskAction {
+    /// Complete the task with a "worker lost" error
+    Fail,
+    /// Put the task back in `Pending` and publish its assignment again
+    Requeue,
+}
+
+impl FromStr for StaleTaskAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail" => Ok(StaleTaskAction::Fail),
+            "requeue" => Ok(StaleTaskAction::Requeue),
+            _ => Err(format!("Unknown stale task action: {}", s)),
+        }
+    }
+}
+
+/// Periodically reclaims the tasks started by workers whose latest heartbeat
+/// is older than a threshold, so the work of crashed workers isn't stuck in
+/// `Processing` forever.
+pub struct StaleTaskJob {
+    task_repository: TaskRepository,
+    worker_repository: WorkerRepository,
+    task_event_publisher: Option<Arc<dyn TaskEventPublisher>>,
+    interval: Duration,
+    threshold: Duration,
+    action: StaleTaskAction,
+}
+
+impl StaleTaskJob {
+    /// Creates a new stale task job
+    ///
+    /// # Arguments
+    /// * `task_repository` - The repository the tasks are read from and updated in
+    /// * `worker_repository` - The repository the routing keys of the worker kinds are read from
+    /// * `task_event_publisher` - The publisher of requeued assignments, required to requeue
+    /// * `interval` - Time between two checks
+    /// * `threshold` - Age of the latest heartbeat after which a worker is lost
+    /// * `action` - What happens to the tasks of lost workers
+    pub fn new(
+        task_repository: TaskRepository,
+        worker_repository: WorkerRepository,
+        task_event_publisher: Option<Arc<dyn TaskEventPublisher>>,
+        interval: Duration,
+        threshold: Duration,
+        action: StaleTaskAction,
+    ) -> Self {
+        info!(
+            interval_seconds = interval.as_secs(),
+            threshold_seconds = threshold.as_secs(),
+            action = ?action,
+            "Creating stale task job"
+        );
+        Self {
+            task_repository,
+            worker_repository,
+            task_event_publisher,
+            interval,
+            threshold,
+            action,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        info!(
+            interval_seconds = self.interval.as_secs(),
+            "Starting stale task job"
+        );
+
+        let mut interval = time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            debug!("Stale task check tick triggered");
+
+            if let Err(e) = self.reclaim_stale_tasks().await {
+                error!(error = %e, "Error reclaiming tasks of stale workers");
+            }
+        }
+    }
+
+    /// Fails or requeues every task of a stale worker.
+    ///
+    /// # Returns
+    /// The number of tasks reclaimed
+    async fn reclaim_stale_tasks(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let span = info_span!("reclaim_stale_tasks");
+
+        async {
+            let stale_before =
+                chrono::Utc::now().naive_utc() - chrono::Duration::from_std(self.threshold)?;
+            let tasks = self
+                .task_repository
+                .find_tasks_of_stale_workers(stale_before)
+                .await?;
+            if tasks.is_empty() {
+                debug!("No tasks of stale workers");
+                return Ok(0);
+            }
+
+            let mut reclaimed = 0;
+            for task in tasks {
+                warn!(
+                    task_id = %task.id,
+                    executed_by = ?task.executed_by,
+                    action = ?self.action,
+                    "Reclaiming task of stale worker"
+                );
+                let result = match self.action {
+                    StaleTaskAction::Fail => self.fail_task(&task.id).await,
+                    StaleTaskAction::Requeue => self.requeue_task(&task).await,
+                };
+                match result {
+                    Ok(()) => reclaimed += 1,
+                    // One failure shouldn't keep the other tasks stuck
+                    Err(e) => error!(task_id = %task.id, error = %e, "Failed to reclaim task"),
+                }
+            }
+
+            info!(reclaimed = reclaimed, "Reclaimed tasks of stale workers");
+            Ok(reclaimed)
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn fail_task(&self, id: &uuid::Uuid) -> Result<(), Box<dyn std::error::Error>> {
+        let update = TaskCompletedUpdate {
+            id: *id,
+            completed_at: chrono::Utc::now().naive_utc(),
+            output_data: WORKER_LOST_OUTPUT.as_bytes().to_vec(),
+            is_error: true,
+            output_content_type: Some("text/plain".to_string()),
+            ..Default::default()
+        };
+        // The worker may have reported a completion since the task was found
+        if !self
+            .task_repository
+            .complete_unfinished_task(&update)
+            .await?
+        {
+            debug!(task_id = %id, "Task completed before it could be failed");
+        }
+        Ok(())
+    }
+
+    async fn requeue_task(&self, task: &Task) -> Result<(), Box<dyn std::error::Error>> {
+        let publisher = self
+            .task_event_publisher
+            .as_ref()
+            .ok_or("Requeuing tasks requires the task event publisher")?;
+        let assignment = TaskAssignmentUpdate::from_task(task).ok_or("Task was never assigned")?;
+
+        // The task may have completed since it was found
+        if self.task_repository.requeue_task(&task.id).await?.is_none() {
+            return Ok(());
+        }
+        let routing_key = self
+            .worker_repository
+            .routing_key_of(&assignment.worker_kind)
+            .await?;
+        publisher
+            .publish(&Event::Assignment(assignment), &routing_key)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Task, TaskRunningUpdate, TaskStatus, WorkerHeartbeatUpdate};
+    use crate::repo::PgRepositoryCore;
+    use crate::testing::test::RecordingPublisher;
+    use sqlx::PgPool;
+
+    /// Starts a task on each worker, one of which sent its last heartbeat an
+    /// hour ago, and returns the tasks of the lost and the live worker.
+    async fn start_tasks(pool: &PgPool, repo: &TaskRepository) -> (Task, Task) {
+        let workers = WorkerRepository::new(PgRepositoryCore::new(pool.clone()));
+        let now = chrono::Utc::now().naive_utc();
+        let an_hour_ago = now - chrono::Duration::hours(1);
+
+        let mut tasks = Vec::new();
+        for (worker, heartbeat_at) in [("lost-worker", an_hour_ago), ("live-worker", now)] {
+            workers
+                .save_heartbeat(&WorkerHeartbeatUpdate::new(
+                    worker,
+                    "WorkerKindName",
+                    heartbeat_at,
+                ))
+                .await
+                .unwrap();
+
+            let task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+            repo.create_task(&task).await.unwrap();
+            repo.update_task_from_running_update(&TaskRunningUpdate::new(
+                task.id,
+                an_hour_ago,
+                worker.to_string(),
+            ))
+            .await
+            .unwrap();
+            tasks.push(task);
+        }
+        (tasks.remove(0), tasks.remove(0))
+    }
+
+    fn job(
+        pool: &PgPool,
+        publisher: Option<Arc<dyn TaskEventPublisher>>,
+        action: StaleTaskAction,
+    ) -> StaleTaskJob {
+        StaleTaskJob::new(
+            TaskRepository::new(PgRepositoryCore::new(pool.clone())),
+            WorkerRepository::new(PgRepositoryCore::new(pool.clone())),
+            publisher,
+            Duration::from_secs(60),
+            Duration::from_secs(300),
+            action,
+        )
+    }
+
+    #[test]
+    fn test_parse_stale_task_action() {
+        assert_eq!("fail".parse(), Ok(StaleTaskAction::Fail));
+        assert_eq!("requeue".parse(), Ok(StaleTaskAction::Requeue));
+        assert!("retry".parse::<StaleTaskAction>().is_err());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_fail_tasks_of_stale_workers(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let (lost, live) = start_tasks(&pool, &repo).await;
+
+        let job = job(&pool, None, StaleTaskAction::Fail);
+        assert_eq!(job.reclaim_stale_tasks().await.unwrap(), 1);
+
+        let task = repo.get_task_by_id(&lost.id).await.unwrap().unwrap();
+        assert!(task.completed_at.is_some());
+        assert_eq!(task.is_error, Some(true));
+        assert_eq!(
+            task.output_data.as_deref(),
+            Some(WORKER_LOST_OUTPUT.as_bytes())
+        );
+        assert_eq!(
+            repo.get_task_status(&live.id).await.unwrap(),
+            Some(TaskStatus::Processing)
+        );
+
+        // Failed tasks aren't picked up again
+        assert_eq!(job.reclaim_stale_tasks().await.unwrap(), 0);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_failing_keeps_completions_reported_meanwhile(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let (lost, _) = start_tasks(&pool, &repo).await;
+
+        // The worker completes the task after the job found it
+        let completed = TaskCompletedUpdate {
+            id: lost.id,
+            completed_at: chrono::Utc::now().naive_utc(),
+            output_data: b"result".to_vec(),
+            is_error: false,
+            ..Default::default()
+        };
+        repo.update_task_from_completed_update(&completed)
+            .await
+            .unwrap();
+
+        let job = job(&pool, None, StaleTaskAction::Fail);
+        job.fail_task(&lost.id).await.unwrap();
+
+        let task = repo.get_task_by_id(&lost.id).await.unwrap().unwrap();
+        assert_eq!(task.is_error, Some(false));
+        assert_eq!(task.output_data.as_deref(), Some(b"result".as_slice()));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_tasks_of_workers_without_heartbeats_are_kept(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let an_hour_ago = chrono::Utc::now().naive_utc() - chrono::Duration::hours(1);
+
+        // Workers that don't send heartbeats can run tasks for any time
+        let task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+        repo.create_task(&task).await.unwrap();
+        repo.update_task_from_running_update(&TaskRunningUpdate::new(
+            task.id,
+            an_hour_ago,
+            "silent-worker".to_string(),
+        ))
+        .await
+        .unwrap();
+
+        let job = job(&pool, None, StaleTaskAction::Fail);
+        assert_eq!(job.reclaim_stale_tasks().await.unwrap(), 0);
+        assert_eq!(
+            repo.get_task_status(&task.id).await.unwrap(),
+            Some(TaskStatus::Processing)
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_requeue_tasks_of_stale_workers(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let (lost, live) = start_tasks(&pool, &repo).await;
+        let publisher = Arc::new(RecordingPublisher::default());
+
+        let job = job(&pool, Some(publisher.clone()), StaleTaskAction::Requeue);
+        assert_eq!(job.reclaim_stale_tasks().await.unwrap(), 1);
+
+        assert_eq!(
+            repo.get_task_status(&lost.id).await.unwrap(),
+            Some(TaskStatus::Pending)
+        );
+        assert_eq!(
+            repo.get_task_status(&live.id).await.unwrap(),
+            Some(TaskStatus::Processing)
+        );
+        assert_eq!(
+            *publisher.published.lock().unwrap(),
+            vec![("tasks.WorkerKindName".to_string(), lost.id)]
+        );
+    }
+}
@@ -1,27 +1,114 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time;
 use tracing::{debug, error, info, info_span, warn, Instrument};
+use utoipa::ToSchema;
 
 use crate::repo::TaskRepository;
 
+/// Outcome of the task cleanup runs since the relay started.
+///
+/// # Fields
+/// * `interval_seconds` - Time between two cleanup runs
+/// * `last_run_at` - When the last successful run finished (UTC), if any
+/// * `last_run_deleted` - Number of tasks deleted by the last successful run
+/// * `total_deleted` - Number of tasks deleted since the relay started
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CleanupStats {
+    pub interval_seconds: u64,
+    pub last_run_at: Option<NaiveDateTime>,
+    pub last_run_deleted: u64,
+    pub total_deleted: u64,
+}
+
+impl CleanupStats {
+    /// Records a successful cleanup run.
+    ///
+    /// # Arguments
+    /// * `deleted` - Number of tasks deleted by the run
+    pub fn record_run(&mut self, deleted: u64) {
+        self.last_run_at = Some(chrono::Utc::now().naive_utc());
+        self.last_run_deleted = deleted;
+        self.total_deleted += deleted;
+    }
+}
+
+/// What happens to the tasks whose TTL expired.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CleanupMode {
+    /// Delete the tasks for good
+    #[default]
+    Delete,
+    /// Move the tasks to `archived_tasks`, where they stay for audit
+    Archive,
+}
+
+impl FromStr for CleanupMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delete" => Ok(CleanupMode::Delete),
+            "archive" => Ok(CleanupMode::Archive),
+            _ => Err(format!("Unknown cleanup mode: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TaskCleanupJob {
     task_repository: TaskRepository,
     interval: Duration,
+    chunk_size: i64,
+    chunk_delay: Duration,
+    mode: CleanupMode,
+    stats: Arc<Mutex<CleanupStats>>,
 }
 
 impl TaskCleanupJob {
-    pub fn new(task_repository: TaskRepository, interval_seconds: u64) -> Self {
+    /// Creates a new task cleanup job
+    ///
+    /// # Arguments
+    /// * `task_repository` - The repository the expired tasks are deleted from
+    /// * `interval_seconds` - Time between two cleanup runs
+    /// * `chunk_size` - Most tasks deleted per statement
+    /// * `chunk_delay` - Pause between two delete statements of a run
+    /// * `mode` - Whether expired tasks are deleted or archived
+    pub fn new(
+        task_repository: TaskRepository,
+        interval_seconds: u64,
+        chunk_size: i64,
+        chunk_delay: Duration,
+        mode: CleanupMode,
+    ) -> Self {
         info!(
             interval_seconds = interval_seconds,
+            chunk_size = chunk_size,
+            chunk_delay_ms = chunk_delay.as_millis() as u64,
+            mode = ?mode,
             "Creating task cleanup job"
         );
         Self {
             task_repository,
             interval: Duration::from_secs(interval_seconds),
+            chunk_size,
+            chunk_delay,
+            mode,
+            stats: Arc::new(Mutex::new(CleanupStats {
+                interval_seconds,
+                ..Default::default()
+            })),
         }
     }
 
+    /// Stats updated after every successful run, shared with the API.
+    pub fn stats(&self) -> Arc<Mutex<CleanupStats>> {
+        self.stats.clone()
+    }
+
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
         info!(
             interval_seconds = self.interval.as_secs(),
@@ -68,13 +155,32 @@ impl TaskCleanupJob {
         let span = info_span!("clean_expired_tasks");
 
         async {
-            info!("Running cleanup of expired tasks");
+            info!(mode = ?self.mode, "Running cleanup of expired tasks");
+
+            let result = match self.mode {
+                CleanupMode::Delete => {
+                    self.task_repository
+                        .delete_expired_tasks(self.chunk_size, self.chunk_delay)
+                        .await
+                }
+                CleanupMode::Archive => {
+                    self.task_repository
+                        .archive_expired_tasks(self.chunk_size, self.chunk_delay)
+                        .await
+                }
+            };
+            match result {
+                Ok(cleanup) => {
+                    let count = cleanup.deleted;
+                    self.stats
+                        .lock()
+                        .expect("Cleanup stats lock poisoned")
+                        .record_run(count);
 
-            match self.task_repository.delete_expired_tasks().await {
-                Ok(count) => {
                     if count > 0 {
                         info!(
                             deleted_count = count,
+                            chunks = cleanup.chunks,
                             "Successfully cleaned up expired tasks"
                         );
                     } else {
@@ -93,3 +199,57 @@ impl TaskCleanupJob {
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Task;
+    use crate::repo::PgRepositoryCore;
+    use sqlx::PgPool;
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_cleanup_updates_stats(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+        let job = TaskCleanupJob::new(repo.clone(), 300, 100, Duration::ZERO, CleanupMode::Delete);
+
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+        task.completed_at = Some(chrono::Utc::now().naive_utc() - chrono::Duration::days(1));
+        repo.create_task(&task).await.unwrap();
+
+        job.clean_expired_tasks().await.unwrap();
+        job.clean_expired_tasks().await.unwrap();
+
+        let stats = job.stats().lock().unwrap().clone();
+        assert_eq!(stats.interval_seconds, 300);
+        assert!(stats.last_run_at.is_some());
+        assert_eq!(stats.last_run_deleted, 0);
+        assert_eq!(stats.total_deleted, 1);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_cleanup_archive_mode(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+        let job = TaskCleanupJob::new(repo.clone(), 300, 100, Duration::ZERO, CleanupMode::Archive);
+
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+        task.completed_at = Some(chrono::Utc::now().naive_utc() - chrono::Duration::days(1));
+        repo.create_task(&task).await.unwrap();
+
+        job.clean_expired_tasks().await.unwrap();
+
+        assert!(repo.get_task_by_id(&task.id).await.unwrap().is_none());
+        assert!(repo
+            .get_archived_task_by_id(&task.id)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(job.stats().lock().unwrap().total_deleted, 1);
+    }
+
+    #[test]
+    fn test_cleanup_mode_from_str() {
+        assert_eq!("delete".parse(), Ok(CleanupMode::Delete));
+        assert_eq!("archive".parse(), Ok(CleanupMode::Archive));
+        assert!("purge".parse::<CleanupMode>().is_err());
+    }
+}
@@ -1,19 +1,27 @@
-use crate::constants::RELAY_QUEUE;
-use crate::health_probe::ServiceHealthProbe;
-use crate::jobs::TaskCleanupJob;
-use crate::repo::{PgRepositoryCore, TaskRepository};
-use crate::server::Server;
+use crate::constants::{
+    CONSUMER_DRAIN_TIMEOUT_SECS, SCHEDULED_TASK_BATCH_SIZE, SCHEDULED_TASK_CHECK_INTERVAL_SECS,
+    STALE_TASK_CHECK_INTERVAL_SECS,
+};
+use crate::health_probe::{BrokerHealthSource, Readiness, ServiceHealthProbe};
+use crate::jobs::{CleanupStats, ScheduledTaskJob, StaleTaskJob, TaskCleanupJob};
+use crate::models::TtlPolicy;
+use crate::repo::{DbPoolSettings, PgRepositoryCore, TaskRepository, WorkerRepository};
+use crate::server::{with_compression, with_request_limits, RequestLimits, Server};
 use crate::task_event_consumer::{
-    RabbitMQTaskEventConsumer, RabbitMQTaskEventCore, TaskEventConsumer,
+    BrokerTlsConfig, CircuitBreakerSettings, ConsumerSettings, RabbitMQTaskEventConsumer,
+    TaskEventConsumer, WireFormat,
 };
+use crate::task_event_publisher::{RabbitMQTaskEventPublisher, TaskEventPublisher};
 use crate::{api, Config};
 use axum::Router;
 use axum_tracing_opentelemetry::middleware::{OtelAxumLayer, OtelInResponseLayer};
 use backoff::ExponentialBackoffBuilder;
 use sqlx::PgPool;
-use std::sync::{atomic::AtomicBool, Arc};
+use std::error::Error;
+use std::sync::{atomic::AtomicBool, Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
 /// Represents the shared application state that can be accessed by all routes
@@ -22,7 +30,18 @@ use tracing::{debug, error, info, warn};
 #[derive(Clone)]
 pub struct AppState {
     pub task_repository: TaskRepository,
+    pub worker_repository: WorkerRepository,
     pub health_probe: ServiceHealthProbe,
+    pub broker: Option<Arc<dyn BrokerHealthSource>>,
+    pub task_event_publisher: Option<Arc<dyn TaskEventPublisher>>,
+    pub admin_token: Option<String>,
+    pub max_submit_batch_size: usize,
+    pub max_payload_bytes: usize,
+    pub allow_avro: bool,
+    pub strict_worker_kind: bool,
+    pub request_timeout: Duration,
+    pub cleanup_stats: Option<Arc<Mutex<CleanupStats>>>,
+    pub readiness: Readiness,
 }
 
 /// Application components that need to be started and shut down
@@ -30,6 +49,34 @@ pub struct AppComponents {
     pub rest_server: Option<Server>,
     pub update_consumer: Option<Arc<RabbitMQTaskEventConsumer>>,
     pub task_cleanup_job: Option<Arc<TaskCleanupJob>>,
+    pub stale_task_job: Option<Arc<StaleTaskJob>>,
+    pub scheduled_task_job: Option<Arc<ScheduledTaskJob>>,
+    pub task_event_publisher: Option<Arc<RabbitMQTaskEventPublisher>>,
+    /// Pool of the cleanup job, closed on shutdown along with the shared one
+    pub cleanup_pool: Option<PgPool>,
+}
+
+/// Stops the task event consumer on shutdown and tells when it has drained.
+pub struct ConsumerShutdown {
+    signal: Box<dyn FnOnce() -> Result<(), Box<dyn Error + Send + Sync>> + Send>,
+    stopped: oneshot::Receiver<()>,
+}
+
+impl ConsumerShutdown {
+    /// # Arguments
+    ///
+    /// * `signal` - Asks the consumer to stop taking deliveries
+    /// * `stopped` - Resolves once the consumer handled its last delivery,
+    ///   or is dropped if the consumer task died
+    pub fn new(
+        signal: impl FnOnce() -> Result<(), Box<dyn Error + Send + Sync>> + Send + 'static,
+        stopped: oneshot::Receiver<()>,
+    ) -> Self {
+        Self {
+            signal: Box::new(signal),
+            stopped,
+        }
+    }
 }
 
 /// Creates database connection pools
@@ -40,6 +87,9 @@ pub struct AppComponents {
 pub async fn setup_db_pools(config: &Config) -> Result<PgPool, sqlx::Error> {
     info!(
         db_url_length = config.db_url.len(),
+        max_connections = config.db_pool.max_connections,
+        min_connections = config.db_pool.min_connections,
+        acquire_timeout_secs = config.db_pool.acquire_timeout.as_secs(),
         "Connecting to database"
     );
 
@@ -51,7 +101,7 @@ pub async fn setup_db_pools(config: &Config) -> Result<PgPool, sqlx::Error> {
 
     // Try to connect with retries
     let pool = match backoff::future::retry(backoff, || async {
-        match PgPool::connect(&config.db_url).await {
+        match config.db_pool.pool_options().connect(&config.db_url).await {
             Ok(pool) => Ok(pool),
             Err(e) => {
                 warn!(error = %e, "Failed to connect to database, retrying...");
@@ -85,20 +135,41 @@ pub async fn setup_db_pools(config: &Config) -> Result<PgPool, sqlx::Error> {
     }
 }
 
+/// Creates the pool of the cleanup job, apart from the one shared by the API
+/// and the consumer so a sweep deleting many tasks can't take the
+/// connections they need. It connects to the same database, lazily.
+///
+/// # Arguments
+///
+/// * `db_pools` - The shared database connection pools
+/// * `settings` - Sizing of the cleanup pool
+fn setup_cleanup_pool(db_pools: &PgPool, settings: &DbPoolSettings) -> PgPool {
+    debug!(
+        max_connections = settings.max_connections,
+        "Creating cleanup database pool"
+    );
+    settings
+        .pool_options()
+        .connect_lazy_with((*db_pools.connect_options()).clone())
+}
+
 /// Creates all repositories needed for the application
 ///
 /// # Arguments
 ///
 /// * `pool` - The database connection pool
-fn create_repositories(pool: &PgPool) -> TaskRepository {
+fn create_repositories(pool: &PgPool) -> (TaskRepository, WorkerRepository) {
     debug!("Creating repository core");
     let core = PgRepositoryCore::new(pool.clone());
 
     debug!("Creating task repository");
     let task_repository = TaskRepository::new(core.clone());
 
+    debug!("Creating worker repository");
+    let worker_repository = WorkerRepository::new(core);
+
     debug!("All repositories created successfully");
-    task_repository
+    (task_repository, worker_repository)
 }
 
 /// Initializes the application state based on the given configuration
@@ -106,21 +177,49 @@ fn create_repositories(pool: &PgPool) -> TaskRepository {
 /// # Arguments
 ///
 /// * `db_pools` - The database connection pools
-/// * `broker_core` - The broker core for the message queue (used in health check)
+/// * `task_repository` - The task repository, with the TTL policy applied to submitted tasks
+/// * `broker` - The broker checked on every health check, if the consumer is enabled
+/// * `task_event_publisher` - The publisher for outbound task events, if enabled
+/// * `admin_token` - The token required by admin endpoints
+/// * `strict_worker_kind` - Whether submitted tasks of a worker kind no worker
+///   registered with are rejected
+/// * `request_limits` - The batch size and payload limits of task submissions, and the
+///   request timeout synchronous submissions must answer within
+/// * `cleanup_stats` - The stats of the task cleanup job, if enabled
+/// * `readiness` - The setup steps to complete before serving traffic
+#[allow(clippy::too_many_arguments)]
 async fn setup_app_state(
     db_pools: &PgPool,
-    broker_core: Option<Arc<RabbitMQTaskEventCore>>,
+    task_repository: TaskRepository,
+    broker: Option<Arc<dyn BrokerHealthSource>>,
+    task_event_publisher: Option<Arc<dyn TaskEventPublisher>>,
+    admin_token: Option<String>,
+    strict_worker_kind: bool,
+    request_limits: &RequestLimits,
+    cleanup_stats: Option<Arc<Mutex<CleanupStats>>>,
+    readiness: Readiness,
 ) -> AppState {
     debug!("Setting up application state");
-    let task_repository = create_repositories(db_pools);
+    let (_, worker_repository) = create_repositories(db_pools);
     let repository_core = PgRepositoryCore::new(db_pools.clone());
 
-    let health_probe = ServiceHealthProbe::new(repository_core, broker_core);
+    let health_probe = ServiceHealthProbe::new(repository_core, broker.clone());
 
     info!("Application state initialized successfully");
     AppState {
         task_repository,
+        worker_repository,
         health_probe,
+        broker,
+        task_event_publisher,
+        admin_token,
+        max_submit_batch_size: request_limits.max_submit_batch_size,
+        max_payload_bytes: request_limits.max_payload_bytes,
+        allow_avro: request_limits.allow_avro,
+        strict_worker_kind,
+        request_timeout: request_limits.timeout,
+        cleanup_stats,
+        readiness,
     }
 }
 
@@ -131,19 +230,46 @@ async fn setup_app_state(
 /// # Arguments
 ///
 /// * `db_pools` - The database connection pools
+/// * `task_repository` - The task repository, with the TTL policy applied to submitted tasks
+/// * `broker` - The broker checked on every health check, if the consumer is enabled
+/// * `task_event_publisher` - The publisher for outbound task events, if enabled
+/// * `request_limits` - The body size, timeout, batch size and payload limits applied to requests
+/// * `admin_token` - The token required by admin endpoints, which are disabled if `None`
+/// * `strict_worker_kind` - Whether submitted tasks of a worker kind no worker
+///   registered with are rejected with 400
+/// * `cleanup_stats` - The stats of the task cleanup job, if enabled
+/// * `readiness` - The setup steps to complete before serving traffic
+#[allow(clippy::too_many_arguments)]
 pub async fn setup_app(
     db_pools: &PgPool,
-    broker_core: Option<Arc<RabbitMQTaskEventCore>>,
+    task_repository: TaskRepository,
+    broker: Option<Arc<dyn BrokerHealthSource>>,
+    task_event_publisher: Option<Arc<dyn TaskEventPublisher>>,
+    request_limits: &RequestLimits,
+    admin_token: Option<String>,
+    strict_worker_kind: bool,
+    cleanup_stats: Option<Arc<Mutex<CleanupStats>>>,
+    readiness: Readiness,
 ) -> Router {
     debug!("Beginning app setup");
-    let app_state = setup_app_state(db_pools, broker_core).await;
+    let app_state = setup_app_state(
+        db_pools,
+        task_repository,
+        broker,
+        task_event_publisher,
+        admin_token,
+        strict_worker_kind,
+        request_limits,
+        cleanup_stats,
+        readiness,
+    )
+    .await;
     info!("App state created");
 
     // Create base router with routes and state
     debug!("Creating router with OpenTelemetry layers");
-    let router = Router::new()
-        .merge(api::routes())
-        .with_state(app_state)
+    let router = Router::new().merge(api::routes()).with_state(app_state);
+    let router = with_compression(with_request_limits(router, request_limits))
         .layer(OtelInResponseLayer)
         .layer(OtelAxumLayer::default());
 
@@ -161,61 +287,141 @@ pub async fn setup_shutdown_signal() -> broadcast::Sender<()> {
 
     tokio::spawn(async move {
         debug!("Waiting for shutdown signal");
-        if tokio::signal::ctrl_c().await.is_ok() {
-            info!("Shutdown signal (Ctrl+C) received");
-            let _ = tx_clone.send(());
-        }
+        let signal = wait_for_shutdown_signal().await;
+        info!(signal = signal, "Shutdown signal received");
+        let _ = tx_clone.send(());
     });
 
     info!("Shutdown signal handler initialized");
     shutdown_tx
 }
 
+/// Waits for Ctrl+C or, on Unix, SIGTERM (sent by Kubernetes when a pod is
+/// terminated)
+///
+/// Returns the name of the signal that was received
+#[cfg(unix)]
+async fn wait_for_shutdown_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            warn!(error = %e, "Failed to listen for SIGTERM, only handling Ctrl+C");
+            let _ = tokio::signal::ctrl_c().await;
+            return "SIGINT";
+        }
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+        _ = terminate.recv() => "SIGTERM",
+    }
+}
+
+/// Waits for Ctrl+C
+///
+/// Returns the name of the signal that was received
+#[cfg(not(unix))]
+async fn wait_for_shutdown_signal() -> &'static str {
+    let _ = tokio::signal::ctrl_c().await;
+    "Ctrl+C"
+}
+
 /// Initializes all application components based on configuration
 ///
 /// # Arguments
 ///
 /// * `config` - The application configuration
+/// * `db_pools` - The database connection pools, already migrated. They are
+///   owned by the caller, which closes them once every component stopped
 /// * `shutdown_signal` - Broadcast channel for shutdown coordination
 pub async fn initialize_system(
     config: &Config,
+    db_pools: &PgPool,
     shutdown_signal: broadcast::Sender<()>,
 ) -> Result<AppComponents, Box<dyn std::error::Error + Send + Sync>> {
     debug!("Initializing system components");
     let shutdown = Arc::new(AtomicBool::new(false));
 
-    // Setup database connection
-    let db_pools = match setup_db_pools(config).await {
-        Ok(pools) => pools,
-        Err(e) => {
-            error!(error = %e, "Database connection setup failed");
-            return Err(Box::new(e));
-        }
+    // The relay is ready once the migrations ran and every consumed queue,
+    // dedicated queues included, was declared
+    let consumed_queues = if config.enable_relay_task_consumer {
+        config
+            .event_routing
+            .consumed_queues(&config.relay_queues)
+            .len()
+    } else {
+        0
     };
-    info!("Database connection pools created");
+    let readiness = Readiness::new(1 + consumed_queues);
+
+    // The pools are created and migrated before the components
+    readiness.complete_step("migrations");
 
     // Create repositories
     debug!("Creating repositories for components");
-    let task_repo = create_repositories(&db_pools);
+    let (task_repo, worker_repo) = create_repositories(db_pools);
+    let task_repo = task_repo.with_ttl_policy(TtlPolicy {
+        default_secs: config.default_task_ttl_secs,
+        min_secs: config.min_task_ttl_secs,
+    });
 
     // Initialize optional components based on configuration
     let mut components = AppComponents {
         rest_server: None,
         update_consumer: None,
         task_cleanup_job: None,
+        stale_task_job: None,
+        scheduled_task_job: None,
+        task_event_publisher: None,
+        cleanup_pool: None,
+    };
+
+    let broker_tls = BrokerTlsConfig {
+        ca_cert_path: config.broker_ca_cert.clone(),
+        client_identity_path: config.broker_client_identity.clone(),
+        client_identity_password: config.broker_client_identity_password.clone(),
     };
 
     // Setup task event consumer if enabled
     if config.enable_relay_task_consumer {
         debug!(
             broker_url = %config.broker_url,
-            queue = %RELAY_QUEUE,
+            queues = ?config.relay_queues,
             "Setting up message broker consumer"
         );
         let update_consumer = match RabbitMQTaskEventConsumer::new(
             &config.broker_url,
+            &broker_tls,
+            ConsumerSettings {
+                queues: config.relay_queues.clone(),
+                event_routing: config.event_routing.clone(),
+                exchange: config.assignment_exchange.clone(),
+                exchange_kind: config.exchange_kind.clone(),
+                max_retries: config.max_event_retries,
+                consumer_tag_prefix: config.consumer_tag_prefix.clone(),
+                prefetch_count: config.prefetch_count,
+                batch_size: config.batch_size,
+                batch_timeout: Duration::from_millis(config.batch_timeout_ms),
+                concurrency: config.consumer_concurrency,
+                queue_arguments: config.queue_arguments.clone(),
+                codec: config.inbound_wire_format.codec(),
+                max_message_bytes: config.max_message_bytes,
+                ack_mode: config.ack_mode,
+                max_payload_bytes: config.max_payload_bytes,
+                dedup_window: config.dedup_window,
+                circuit_breaker: CircuitBreakerSettings {
+                    failure_threshold: config.circuit_breaker_threshold,
+                    cooldown: Duration::from_secs(config.circuit_breaker_cooldown_secs),
+                },
+                idle_timeout: (config.consumer_idle_timeout_secs > 0)
+                    .then_some(Duration::from_secs(config.consumer_idle_timeout_secs)),
+            },
             Arc::new(task_repo.clone()),
+            Arc::new(worker_repo.clone()),
             shutdown.clone(),
+            readiness.clone(),
         )
         .await
         {
@@ -227,7 +433,7 @@ pub async fn initialize_system(
                 error!(
                     error = %e,
                     broker_url = %config.broker_url,
-                    queue = %RELAY_QUEUE,
+                    queues = ?config.relay_queues,
                     "Failed to setup message broker consumer"
                 );
                 return Err(e);
@@ -238,34 +444,142 @@ pub async fn initialize_system(
         info!("Task event consumer is disabled by configuration");
     }
 
+    // Setup task event publisher if enabled
+    if config.enable_relay_publisher {
+        debug!(broker_url = %config.broker_url, "Setting up task event publisher");
+        let publisher = match RabbitMQTaskEventPublisher::new(
+            &config.broker_url,
+            &broker_tls,
+            &config.assignment_exchange,
+            config.exchange_kind.clone(),
+            config.outbound_wire_format.codec(),
+            config.publish_max_attempts,
+            config.publish_persistent,
+        )
+        .await
+        {
+            Ok(publisher) => {
+                info!("Task event publisher initialized successfully");
+                publisher
+            }
+            Err(e) => {
+                error!(
+                    error = %e,
+                    broker_url = %config.broker_url,
+                    "Failed to setup task event publisher"
+                );
+                return Err(e);
+            }
+        };
+        components.task_event_publisher = Some(Arc::new(publisher));
+    } else {
+        info!("Task event publisher is disabled by configuration");
+    }
+
     // Setup cleanup job if enabled
     if config.enable_relay_cleanup {
-        debug!("Creating task cleanup job with 5-minute interval");
+        debug!(
+            interval_secs = config.cleanup_interval_secs,
+            "Creating task cleanup job"
+        );
+        let cleanup_pool = setup_cleanup_pool(
+            db_pools,
+            &DbPoolSettings {
+                max_connections: config.cleanup_db_max_connections,
+                min_connections: 0,
+                acquire_timeout: config.db_pool.acquire_timeout,
+            },
+        );
+        let (cleanup_repo, _) = create_repositories(&cleanup_pool);
         components.task_cleanup_job = Some(Arc::new(TaskCleanupJob::new(
-            task_repo.clone(),
-            300, // Every 5 minutes
+            cleanup_repo,
+            config.cleanup_interval_secs,
+            config.cleanup_chunk_size,
+            Duration::from_millis(config.cleanup_chunk_delay_ms),
+            config.cleanup_mode,
         )));
-        info!("Task cleanup job created with 300-second interval");
+        components.cleanup_pool = Some(cleanup_pool);
+        info!(
+            interval_secs = config.cleanup_interval_secs,
+            "Task cleanup job created"
+        );
     } else {
         info!("Task cleanup job is disabled by configuration");
     }
 
+    // Setup stale task job if enabled
+    if config.enable_relay_stale_task_check {
+        let task_event_publisher = components
+            .task_event_publisher
+            .clone()
+            .map(|publisher| publisher as Arc<dyn TaskEventPublisher>);
+        components.stale_task_job = Some(Arc::new(StaleTaskJob::new(
+            task_repo.clone(),
+            worker_repo.clone(),
+            task_event_publisher,
+            Duration::from_secs(STALE_TASK_CHECK_INTERVAL_SECS),
+            Duration::from_secs(config.stale_worker_threshold_secs),
+            config.stale_task_action,
+        )));
+        info!("Stale task job created");
+    } else {
+        info!("Stale task job is disabled by configuration");
+    }
+
+    // Scheduled tasks can only be submitted when the publisher is enabled
+    if let Some(publisher) = components.task_event_publisher.clone() {
+        components.scheduled_task_job = Some(Arc::new(ScheduledTaskJob::new(
+            task_repo.clone(),
+            worker_repo.clone(),
+            publisher,
+            Duration::from_secs(SCHEDULED_TASK_CHECK_INTERVAL_SECS),
+            SCHEDULED_TASK_BATCH_SIZE,
+        )));
+        info!("Scheduled task job created");
+    }
+
     // Setup API server if enabled
     if config.enable_relay_api {
-        let broker_core = match components.update_consumer.as_ref() {
-            Some(consumer) => Some(consumer.core().await?),
-            None => None,
-        };
+        let broker = components
+            .update_consumer
+            .clone()
+            .map(|consumer| consumer as Arc<dyn BrokerHealthSource>);
+        let task_event_publisher = components
+            .task_event_publisher
+            .clone()
+            .map(|publisher| publisher as Arc<dyn TaskEventPublisher>);
 
         // Setup axum app and state
         debug!("Setting up web application");
-        let app = setup_app(&db_pools, broker_core).await;
+        let app = setup_app(
+            db_pools,
+            task_repo.clone(),
+            broker,
+            task_event_publisher,
+            &RequestLimits {
+                max_body_bytes: config.max_request_body_bytes,
+                timeout: Duration::from_secs(config.request_timeout_secs),
+                max_submit_batch_size: config.max_submit_batch_size,
+                max_payload_bytes: config.max_payload_bytes,
+                allow_avro: config.wire_format == WireFormat::Avro,
+                rate_limit: config.rate_limit,
+            },
+            config.admin_token.clone(),
+            config.strict_worker_kind,
+            components
+                .task_cleanup_job
+                .as_ref()
+                .map(|cleanup_job| cleanup_job.stats()),
+            readiness.clone(),
+        )
+        .await;
 
         // Create server
-        debug!("Creating HTTP server on port 3000");
+        let addr = config.http_addr();
+        debug!(address = %addr, "Creating HTTP server");
         let shutdown_rx = shutdown_signal.subscribe();
-        components.rest_server = Some(Server::new(app, 3000, shutdown_rx));
-        info!(port = 3000, "HTTP server created");
+        components.rest_server = Some(Server::new(app, addr, shutdown_rx));
+        info!(address = %addr, "HTTP server created");
     } else {
         info!("API server is disabled by configuration");
     }
@@ -276,21 +590,17 @@ pub async fn initialize_system(
 
 /// Starts all enabled application background tasks
 ///
-/// Returns handles and a channel to coordinate shutdown
+/// Returns handles and a way to drain the consumer on shutdown
 ///
 /// # Arguments
 ///
 /// * `components` - The application components
-/// * `shutdown_rx` - Oneshot receiver for shutdown coordination
 pub async fn start_background_tasks(
     components: AppComponents,
-) -> (
-    Vec<tokio::task::JoinHandle<()>>,
-    Option<Arc<RabbitMQTaskEventConsumer>>,
-) {
+) -> (Vec<JoinHandle<()>>, Option<ConsumerShutdown>) {
     debug!("Starting enabled background tasks");
     let mut handles = Vec::new();
-    let mut update_consumer_shutdown = None;
+    let mut consumer_shutdown = None;
 
     // Start task cleanup job if enabled
     if let Some(cleanup_job) = components.task_cleanup_job {
@@ -306,10 +616,43 @@ pub async fn start_background_tasks(
         handles.push(cleanup_handle);
     }
 
+    // Start stale task job if enabled
+    if let Some(stale_task_job) = components.stale_task_job {
+        info!("Starting stale task job");
+        let stale_task_handle = tokio::spawn(async move {
+            debug!("Stale task job started");
+            if let Err(e) = stale_task_job.run().await {
+                error!(error = %e, "Stale task job failed");
+            } else {
+                info!("Stale task job completed successfully");
+            }
+        });
+        handles.push(stale_task_handle);
+    }
+
+    // Start scheduled task job if enabled
+    if let Some(scheduled_task_job) = components.scheduled_task_job {
+        info!("Starting scheduled task job");
+        let scheduled_task_handle = tokio::spawn(async move {
+            debug!("Scheduled task job started");
+            if let Err(e) = scheduled_task_job.run().await {
+                error!(error = %e, "Scheduled task job failed");
+            } else {
+                info!("Scheduled task job completed successfully");
+            }
+        });
+        handles.push(scheduled_task_handle);
+    }
+
     // Start update consumer if enabled
     if let Some(consumer) = components.update_consumer {
         // Keep a reference for shutdown
-        update_consumer_shutdown = Some(consumer.clone());
+        let (stopped_tx, stopped_rx) = oneshot::channel();
+        let draining = consumer.clone();
+        consumer_shutdown = Some(ConsumerShutdown::new(
+            move || draining.shutdown(),
+            stopped_rx,
+        ));
 
         info!("Starting update consumer");
         let consumer_handle = tokio::spawn(async move {
@@ -319,6 +662,7 @@ pub async fn start_background_tasks(
             } else {
                 info!("Update consumer completed successfully");
             }
+            let _ = stopped_tx.send(());
         });
         handles.push(consumer_handle);
     }
@@ -343,22 +687,131 @@ pub async fn start_background_tasks(
         info!("All enabled background tasks started");
     }
 
-    (handles, update_consumer_shutdown)
+    (handles, consumer_shutdown)
 }
 
-/// Performs graceful shutdown of all components
+/// Performs graceful shutdown of all components, in dependency order: the
+/// consumer drains its in-flight deliveries, then the remaining tasks are
+/// stopped, and only then are the database pools they write to closed.
 ///
 /// # Arguments
 ///
-/// * `update_consumer` - The update consumer to shut down
-pub fn perform_shutdown(update_consumer: Arc<RabbitMQTaskEventConsumer>) {
+/// * `consumer` - The update consumer to drain, if enabled
+/// * `tasks` - The background tasks still running
+/// * `db_pools` - The database connection pools to close last
+/// * `cleanup_pool` - The pool of the cleanup job, if enabled, closed last too
+pub async fn perform_shutdown(
+    consumer: Option<ConsumerShutdown>,
+    tasks: Vec<JoinHandle<()>>,
+    db_pools: PgPool,
+    cleanup_pool: Option<PgPool>,
+) {
     info!("Starting graceful shutdown procedure");
 
-    debug!("Shutting down update consumer");
-    match update_consumer.shutdown() {
-        Ok(_) => info!("Update consumer shut down successfully"),
-        Err(e) => error!(error = %e, "Failed to shutdown update consumer"),
+    if let Some(consumer) = consumer {
+        debug!("Shutting down update consumer");
+        match (consumer.signal)() {
+            Ok(_) => info!("Update consumer signaled to drain"),
+            Err(e) => error!(error = %e, "Failed to shutdown update consumer"),
+        }
+
+        let drain_timeout = Duration::from_secs(CONSUMER_DRAIN_TIMEOUT_SECS);
+        match tokio::time::timeout(drain_timeout, consumer.stopped).await {
+            Ok(_) => info!("Update consumer drained"),
+            Err(_) => warn!(
+                timeout_secs = CONSUMER_DRAIN_TIMEOUT_SECS,
+                "Update consumer did not drain in time, closing the database pool anyway"
+            ),
+        }
+    }
+
+    debug!(count = tasks.len(), "Stopping remaining background tasks");
+    for task in &tasks {
+        task.abort();
     }
+    futures::future::join_all(tasks).await;
 
-    info!("All components shut down, cleanup complete");
+    if let Some(cleanup_pool) = cleanup_pool {
+        debug!("Closing cleanup database pool");
+        cleanup_pool.close().await;
+    }
+    debug!("Closing database pool");
+    db_pools.close().await;
+    info!("Database pool closed, all components shut down");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_shutdown_closes_pool_after_consumer_drains(db_pools: PgPool) {
+        let (drain_tx, drain_rx) = oneshot::channel::<()>();
+        let (stopped_tx, stopped_rx) = oneshot::channel();
+        let drained = Arc::new(AtomicBool::new(false));
+
+        // Stands in for a consumer finishing its last write once asked to drain
+        let pool = db_pools.clone();
+        let wrote = drained.clone();
+        let consumer = tokio::spawn(async move {
+            drain_rx.await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+            wrote.store(true, Ordering::SeqCst);
+            let _ = stopped_tx.send(());
+        });
+        let idle_job = tokio::spawn(std::future::pending::<()>());
+
+        let shutdown = ConsumerShutdown::new(
+            move || {
+                drain_tx.send(()).unwrap();
+                Ok(())
+            },
+            stopped_rx,
+        );
+        let cleanup_pool = setup_cleanup_pool(
+            &db_pools,
+            &DbPoolSettings {
+                max_connections: 1,
+                min_connections: 0,
+                acquire_timeout: Duration::from_millis(100),
+            },
+        );
+        perform_shutdown(
+            Some(shutdown),
+            vec![idle_job],
+            db_pools.clone(),
+            Some(cleanup_pool.clone()),
+        )
+        .await;
+
+        assert!(drained.load(Ordering::SeqCst));
+        assert!(db_pools.is_closed());
+        assert!(cleanup_pool.is_closed());
+        consumer.await.unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_cleanup_pool_is_bounded(db_pools: PgPool) {
+        let cleanup_pool = setup_cleanup_pool(
+            &db_pools,
+            &DbPoolSettings {
+                max_connections: 2,
+                min_connections: 0,
+                acquire_timeout: Duration::from_millis(100),
+            },
+        );
+        assert_eq!(cleanup_pool.options().get_max_connections(), 2);
+
+        // Once the cleanup pool is exhausted it waits for its own
+        // connections, while the shared pool keeps serving queries
+        let _first = cleanup_pool.acquire().await.unwrap();
+        let _second = cleanup_pool.acquire().await.unwrap();
+        assert!(matches!(
+            cleanup_pool.acquire().await,
+            Err(sqlx::Error::PoolTimedOut)
+        ));
+        sqlx::query("SELECT 1").execute(&db_pools).await.unwrap();
+    }
 }
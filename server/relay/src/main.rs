@@ -5,26 +5,80 @@ mod health_probe;
 mod jobs;
 mod lifecycle;
 mod models;
+mod rate_limit;
 mod repo;
 mod server;
 mod task_event_consumer;
+mod task_event_publisher;
 mod testing;
 
+use init_tracing_opentelemetry::resource::DetectResource;
 use init_tracing_opentelemetry::tracing_subscriber_ext::{
-    build_level_filter_layer, build_otel_layer,
+    build_level_filter_layer, build_otel_layer, TracingGuard,
 };
+use opentelemetry_otlp::{ExporterBuildError, MetricExporter};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
 
-use config::Config;
+use config::{Config, LogFormat};
 use tracing::{debug, error, info, info_span, warn};
 use tracing_subscriber::{layer::SubscriberExt, Layer};
 
-/// Initializes the tracing system
+/// Keeps the OpenTelemetry providers alive. On drop, the pending metrics and
+/// traces are flushed to the collector.
+struct TelemetryGuard {
+    _tracing: TracingGuard,
+    meter_provider: SdkMeterProvider,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.meter_provider.shutdown() {
+            eprintln!("Failed to shut down the meter provider: {:?}", e);
+        }
+    }
+}
+
+/// Initializes the OpenTelemetry meter provider and installs it globally.
+/// Metrics are pushed to the same OTLP collector as the traces, using the
+/// standard `OTEL_EXPORTER_OTLP_*` variables. Without any of them set, the
+/// measurements are recorded but never exported.
+fn init_meter_provider() -> Result<SdkMeterProvider, ExporterBuildError> {
+    let protocol = std::env::var("OTEL_EXPORTER_OTLP_METRICS_PROTOCOL")
+        .or_else(|_| std::env::var("OTEL_EXPORTER_OTLP_PROTOCOL"))
+        .ok();
+    let endpoint = std::env::var("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT")
+        .or_else(|_| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT"))
+        .ok();
+
+    let exporter = match (protocol.as_deref(), endpoint.as_deref()) {
+        (Some("grpc"), _) => Some(MetricExporter::builder().with_tonic().build()?),
+        (Some(_), _) => Some(MetricExporter::builder().with_http().build()?),
+        (None, Some(endpoint)) if endpoint.contains(":4317") => {
+            Some(MetricExporter::builder().with_tonic().build()?)
+        }
+        (None, Some(_)) => Some(MetricExporter::builder().with_http().build()?),
+        (None, None) => None,
+    };
+
+    let mut builder = SdkMeterProvider::builder().with_resource(DetectResource::default().build());
+    if let Some(exporter) = exporter {
+        builder = builder.with_periodic_exporter(exporter);
+    }
+
+    let meter_provider = builder.build();
+    opentelemetry::global::set_meter_provider(meter_provider.clone());
+    Ok(meter_provider)
+}
+
 /// Initializes the unified tracing system with both local console output and OpenTelemetry
-fn init_tracing() -> Result<impl Drop, Box<dyn std::error::Error>> {
-    let logger_text: Box<dyn Layer<_> + Send + Sync + 'static> = if cfg!(debug_assertions) {
+///
+/// # Arguments
+/// * `config` - Picks the log format and, over `RUST_LOG`, the log level
+fn init_tracing(config: &Config) -> Result<impl Drop, Box<dyn std::error::Error>> {
+    let logger_text: Box<dyn Layer<_> + Send + Sync + 'static> = match config.log_format {
         // TODO: check if we need more information in these logs
-        // Development environment - human-readable logs with detailed context
-        Box::new(
+        // Human-readable logs with detailed context
+        LogFormat::Pretty => Box::new(
             tracing_subscriber::fmt::layer()
                 .pretty()
                 .with_line_number(true)
@@ -34,28 +88,34 @@ fn init_tracing() -> Result<impl Drop, Box<dyn std::error::Error>> {
                 .with_target(true)
                 .with_timer(tracing_subscriber::fmt::time::uptime())
                 .with_ansi(true),
-        )
-    } else {
-        // Production environment - structured JSON logs for machine processing
-        Box::new(
+        ),
+        // Structured JSON logs for machine processing
+        LogFormat::Json => Box::new(
             tracing_subscriber::fmt::layer()
                 .json()
                 .with_current_span(true)
                 .with_span_list(true)
                 .flatten_event(true) // Better for log aggregation systems
                 .with_timer(tracing_subscriber::fmt::time::SystemTime),
-        )
+        ),
     };
 
     let (layer, guard) = build_otel_layer()?;
 
     let subscriber = tracing_subscriber::registry()
         .with(layer)
-        .with(build_level_filter_layer("")?)
+        .with(build_level_filter_layer(
+            config.log_level.as_deref().unwrap_or_default(),
+        )?)
         .with(logger_text);
     tracing::subscriber::set_global_default(subscriber)?;
 
-    Ok(guard)
+    let meter_provider = init_meter_provider()?;
+
+    Ok(TelemetryGuard {
+        _tracing: guard,
+        meter_provider,
+    })
 }
 
 #[tokio::main]
@@ -67,7 +127,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Setup tracing
     debug!("Initializing tracing system");
-    let _guard = match init_tracing() {
+    let _guard = match init_tracing(&config) {
         Ok(guard) => {
             info!("Tracing initialized successfully");
             guard
@@ -81,17 +141,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let span = info_span!("manager_startup", service = "relay").entered();
     info!("Starting Relay service");
 
+    // Fail loudly if a schema is broken or a message type drifted from it
+    debug!("Validating Avro schemas");
+    if let Err(e) = models::validate_message_schemas() {
+        panic!("Avro schema validation failed: {}", e);
+    }
+
     // Log which services are enabled
     info!(
         task_consumer = config.enable_relay_task_consumer,
         cleanup = config.enable_relay_cleanup,
+        stale_task_check = config.enable_relay_stale_task_check,
         api = config.enable_relay_api,
+        publisher = config.enable_relay_publisher,
         "Service configuration"
     );
 
     // If no services are enabled, exit gracefully
     if !config.enable_relay_task_consumer
         && !config.enable_relay_cleanup
+        && !config.enable_relay_stale_task_check
         && !config.enable_relay_api
     {
         warn!("No services are enabled, exiting");
@@ -103,20 +172,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Initialize system components
     debug!("Initializing system components");
-    let components = match lifecycle::initialize_system(&config, shutdown_signal.clone()).await {
-        Ok(components) => components,
+    // The pools are owned here so they are closed after every component stopped
+    let db_pools = match lifecycle::setup_db_pools(&config).await {
+        Ok(pools) => pools,
         Err(e) => {
-            error!(error = %e, "Failed to initialize system");
-            return Err(e);
+            error!(error = %e, "Database connection setup failed");
+            return Err(Box::new(e));
         }
     };
+    info!("Database connection pools created");
+
+    let components =
+        match lifecycle::initialize_system(&config, &db_pools, shutdown_signal.clone()).await {
+            Ok(components) => components,
+            Err(e) => {
+                error!(error = %e, "Failed to initialize system");
+                db_pools.close().await;
+                return Err(e);
+            }
+        };
 
     // Start all enabled background tasks
     info!("Starting enabled background tasks and services");
-    let (handles, task_controller_shutdown) = lifecycle::start_background_tasks(components).await;
+    let cleanup_pool = components.cleanup_pool.clone();
+    let (handles, consumer_shutdown) = lifecycle::start_background_tasks(components).await;
 
     if handles.is_empty() {
         warn!("No services were started, exiting");
+        if let Some(cleanup_pool) = cleanup_pool {
+            cleanup_pool.close().await;
+        }
+        db_pools.close().await;
         return Ok(());
     }
 
@@ -124,16 +210,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     span.exit();
 
     // Wait for any task to complete, which signals shutdown
-    let (result, _, _) = futures::future::select_all(handles).await;
+    let (result, _, remaining) = futures::future::select_all(handles).await;
     if let Err(e) = result {
         error!(error = %e, "Task encountered an error");
     }
 
     // Perform graceful shutdown
     info!("Beginning shutdown sequence");
-    if let Some(task_controller) = task_controller_shutdown {
-        lifecycle::perform_shutdown(task_controller)
-    }
+    lifecycle::perform_shutdown(consumer_shutdown, remaining, db_pools, cleanup_pool).await;
 
     info!("Relay service shut down successfully");
 
@@ -1,5 +1,8 @@
+use apache_avro::rabin::Rabin;
+use apache_avro::schema::RecordSchema;
 use apache_avro::{from_avro_datum, from_value, to_avro_datum, types::Value, Schema};
 use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
 use std::error::Error;
 
 /// Converts a serializable type into a vector of key-value pairs suitable for
@@ -49,8 +52,14 @@ fn convert_to_avro_value<T: Serialize>(
 /// }
 ///
 /// impl AvroSerializable for MyType {
-///     fn schema() -> &'static Schema {
-///         Schema::parse_str(r#"{"type": "record", "name": "MyType", "fields": [{"name": "field1", "type": "string"}, {"name": "field2", "type": "int"}]}"#).unwrap()
+///     fn try_schema() -> Result<&'static Schema, &'static str> {
+///         lazy_static::lazy_static! {
+///             static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+///                 "MyType",
+///                 r#"{"type": "record", "name": "MyType", "fields": [{"name": "field1", "type": "string"}, {"name": "field2", "type": "int"}]}"#
+///             );
+///         }
+///         AVRO_SCHEMA.as_ref().map_err(String::as_str)
 ///     }
 /// }
 ///
@@ -59,23 +68,42 @@ fn convert_to_avro_value<T: Serialize>(
 /// let deserialized_my_type = MyType::from_avro_bytes(&avro_bytes);
 /// ```
 pub trait AvroSerializable: Sized + Serialize + DeserializeOwned {
+    /// Returns the Avro schema for this type, or why it couldn't be parsed.
+    /// Implementations parse it once and keep it, see [`parse_schema`].
+    fn try_schema() -> Result<&'static Schema, &'static str>;
+
     /// Returns the Avro schema for this type
-    fn schema() -> &'static Schema;
+    ///
+    /// # Panics
+    /// If the schema is invalid, which [`validate_message_schemas`] reports
+    /// at startup instead
+    fn schema() -> &'static Schema {
+        Self::try_schema().unwrap_or_else(|e| panic!("{}", e))
+    }
 
     /// Serializes the implementing type into Avro binary format
     ///
     /// # Returns
     /// A vector of bytes containing the Avro-encoded data
     fn try_into_avro_bytes(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let datum = self.try_into_avro_value()?;
+        to_avro_datum(Self::schema(), datum)
+            .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)
+    }
+
+    /// Converts the implementing type into an Avro record value, which can be
+    /// appended to an Avro container file writer.
+    ///
+    /// # Returns
+    /// The Avro record holding the fields of the implementing type
+    fn try_into_avro_value(&self) -> Result<Value, Box<dyn Error + Send + Sync>> {
         let fields = convert_to_avro_value(self)?;
-        let datum = Value::Record(
+        Ok(Value::Record(
             fields
                 .into_iter()
                 .map(|(k, v)| (k.to_string(), v))
                 .collect(),
-        );
-        to_avro_datum(Self::schema(), datum)
-            .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)
+        ))
     }
 
     /// Deserializes Avro binary data into an instance of the implementing type
@@ -97,6 +125,131 @@ pub trait AvroSerializable: Sized + Serialize + DeserializeOwned {
             Err(e) => Err(e),
         }
     }
+
+    /// Returns the CRC-64-AVRO fingerprint of the Parsing Canonical Form of
+    /// the schema, as a hex string. Services agree on the wire format of a
+    /// type exactly when their fingerprints match.
+    fn schema_fingerprint() -> Result<String, &'static str> {
+        Ok(Self::try_schema()?.fingerprint::<Rabin>().to_string())
+    }
+
+    /// Checks that the Avro schema of the implementing type parses and that
+    /// the serde field order of the type matches the field order of the
+    /// schema.
+    ///
+    /// Serialization relies on both orders being identical, and a mismatch
+    /// isn't always caught by the encoder, so this should be called for every
+    /// type at startup.
+    ///
+    /// # Returns
+    /// An error describing why the schema doesn't parse, or the mismatch if
+    /// the field names or their order differ
+    fn validate_schema() -> Result<(), String>
+    where
+        Self: Default,
+    {
+        let schema = Self::try_schema()?;
+        let expected: Vec<&str> = match schema {
+            Schema::Record(RecordSchema { fields, .. }) => {
+                fields.iter().map(|field| field.name.as_str()).collect()
+            }
+            _ => return Err("Avro schema is not a record".to_string()),
+        };
+
+        let value = apache_avro::to_value(Self::default()).map_err(|e| e.to_string())?;
+        let produced: Vec<&str> = match &value {
+            Value::Record(fields) => fields.iter().map(|(name, _)| name.as_str()).collect(),
+            _ => return Err("Type did not serialize to an Avro record".to_string()),
+        };
+
+        if produced != expected {
+            return Err(format!(
+                "Field order mismatch for schema {:?}: schema has [{}], type serializes [{}]",
+                schema.name().map(|name| name.fullname(None)),
+                expected.join(", "),
+                produced.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the Avro schema of a type, naming the schema in the error so a
+/// broken one can be found.
+///
+/// # Arguments
+/// * `name` - The name of the schema, such as its file name
+/// * `source` - The JSON definition of the schema
+pub fn parse_schema(name: &str, source: &str) -> Result<Schema, String> {
+    Schema::parse_str(source).map_err(|e| format!("Invalid Avro schema {}: {}", name, e))
+}
+
+/// Validates the schema and field order of every message type exchanged over
+/// Avro. Called at startup so that a broken schema stops the relay from
+/// booting, instead of failing the first request that uses it.
+/// See [`AvroSerializable::validate_schema`].
+pub fn validate_message_schemas() -> Result<(), String> {
+    use crate::models::{
+        Task, TaskAcknowledgedUpdate, TaskAssignmentUpdate, TaskBatchCompletedUpdate,
+        TaskCompletedUpdate, TaskRunningUpdate, WorkerHeartbeatUpdate, WorkerRegistrationUpdate,
+    };
+
+    Task::validate_schema().map_err(|e| format!("Task: {}", e))?;
+    TaskAssignmentUpdate::validate_schema().map_err(|e| format!("TaskAssignmentUpdate: {}", e))?;
+    TaskCompletedUpdate::validate_schema().map_err(|e| format!("TaskCompletedUpdate: {}", e))?;
+    TaskBatchCompletedUpdate::validate_schema()
+        .map_err(|e| format!("TaskBatchCompletedUpdate: {}", e))?;
+    TaskRunningUpdate::validate_schema().map_err(|e| format!("TaskRunningUpdate: {}", e))?;
+    TaskAcknowledgedUpdate::validate_schema()
+        .map_err(|e| format!("TaskAcknowledgedUpdate: {}", e))?;
+    WorkerHeartbeatUpdate::validate_schema()
+        .map_err(|e| format!("WorkerHeartbeatUpdate: {}", e))?;
+    WorkerRegistrationUpdate::validate_schema()
+        .map_err(|e| format!("WorkerRegistrationUpdate: {}", e))?;
+    Ok(())
+}
+
+/// Fingerprints the schema of every message type exchanged over Avro, keyed
+/// by type name, so deployments can compare them across services.
+/// See [`AvroSerializable::schema_fingerprint`].
+pub fn message_schema_fingerprints() -> Result<BTreeMap<&'static str, String>, String> {
+    use crate::models::{
+        Task, TaskAcknowledgedUpdate, TaskAssignmentUpdate, TaskBatchCompletedUpdate,
+        TaskCompletedUpdate, TaskRunningUpdate, WorkerHeartbeatUpdate, WorkerRegistrationUpdate,
+    };
+
+    Ok(BTreeMap::from([
+        ("Task", Task::schema_fingerprint()?),
+        (
+            "TaskAcknowledgedUpdate",
+            TaskAcknowledgedUpdate::schema_fingerprint()?,
+        ),
+        (
+            "TaskAssignmentUpdate",
+            TaskAssignmentUpdate::schema_fingerprint()?,
+        ),
+        (
+            "TaskBatchCompletedUpdate",
+            TaskBatchCompletedUpdate::schema_fingerprint()?,
+        ),
+        (
+            "TaskCompletedUpdate",
+            TaskCompletedUpdate::schema_fingerprint()?,
+        ),
+        (
+            "TaskRunningUpdate",
+            TaskRunningUpdate::schema_fingerprint()?,
+        ),
+        (
+            "WorkerHeartbeatUpdate",
+            WorkerHeartbeatUpdate::schema_fingerprint()?,
+        ),
+        (
+            "WorkerRegistrationUpdate",
+            WorkerRegistrationUpdate::schema_fingerprint()?,
+        ),
+    ]))
 }
 
 /// Helper functions for serializing and deserializing datetime values in Avro
@@ -173,3 +326,105 @@ pub mod serde_avro_datetime_opt {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Default, Serialize, Deserialize)]
+    struct OutOfOrder {
+        field2: i32,
+        field1: String,
+    }
+
+    impl AvroSerializable for OutOfOrder {
+        fn try_schema() -> Result<&'static Schema, &'static str> {
+            lazy_static::lazy_static! {
+                static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                    "OutOfOrder",
+                    r#"{"type": "record", "name": "OutOfOrder", "fields": [{"name": "field1", "type": "string"}, {"name": "field2", "type": "int"}]}"#
+                );
+            }
+            AVRO_SCHEMA.as_ref().map_err(String::as_str)
+        }
+    }
+
+    #[derive(Default, Serialize, Deserialize)]
+    struct BrokenSchema {
+        field1: String,
+    }
+
+    impl AvroSerializable for BrokenSchema {
+        fn try_schema() -> Result<&'static Schema, &'static str> {
+            lazy_static::lazy_static! {
+                static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                    "broken_schema.json",
+                    r#"{"type": "record", "name": "BrokenSchema", "fields": [{"name": "field1", "type": "strin"}]}"#
+                );
+            }
+            AVRO_SCHEMA.as_ref().map_err(String::as_str)
+        }
+    }
+
+    #[test]
+    fn test_message_schemas_are_valid() {
+        validate_message_schemas().unwrap();
+    }
+
+    #[test]
+    fn test_validate_schema_detects_field_order_mismatch() {
+        let err = OutOfOrder::validate_schema().unwrap_err();
+        assert!(err.contains("field1, field2"));
+        assert!(err.contains("field2, field1"));
+    }
+
+    #[test]
+    fn test_validate_schema_reports_broken_schema() {
+        let err = BrokenSchema::validate_schema().unwrap_err();
+        assert!(
+            err.starts_with("Invalid Avro schema broken_schema.json"),
+            "{}",
+            err
+        );
+        assert_eq!(BrokenSchema::try_schema().unwrap_err(), err);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Avro schema broken_schema.json")]
+    fn test_schema_panics_on_broken_schema() {
+        BrokenSchema::schema();
+    }
+
+    #[test]
+    fn test_schema_fingerprint_is_stable() {
+        // Computed from the canonical form, so formatting doesn't matter
+        let fingerprint = OutOfOrder::schema_fingerprint().unwrap();
+        let reformatted = parse_schema(
+            "OutOfOrder",
+            r#"{
+                "name": "OutOfOrder",
+                "type": "record",
+                "doc": "Same fields, laid out differently",
+                "fields": [
+                    {"type": "string", "name": "field1"},
+                    {"type": "int", "name": "field2"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(fingerprint, reformatted.fingerprint::<Rabin>().to_string());
+        assert_eq!(fingerprint, "0a000bad6da3d6b9");
+
+        assert!(BrokenSchema::schema_fingerprint().is_err());
+    }
+
+    #[test]
+    fn test_message_schema_fingerprints() {
+        let fingerprints = message_schema_fingerprints().unwrap();
+        assert_eq!(fingerprints.len(), 8);
+        assert!(fingerprints
+            .values()
+            .all(|fingerprint| fingerprint.len() == 16));
+    }
+}
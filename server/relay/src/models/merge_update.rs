@@ -0,0 +1,33 @@
+use crate::models::Task;
+
+/// Merges a task update into a task. The task repository stores every update
+/// by merging it into the stored task, so these are the only precedence
+/// rules. Updates can be delivered more than once and in any order, so the
+/// outcome only depends on the task and the update, never on which of them
+/// came first.
+///
+/// Implementors define which fields they merge and, if needed, when they are
+/// ignored altogether. [`MergeUpdate::apply_to`] ties both together.
+pub trait MergeUpdate {
+    /// Whether the update may change `task` at all. Updates apply to any task
+    /// by default.
+    fn applies_to(&self, _task: &Task) -> bool {
+        true
+    }
+
+    /// Merges the fields of the update into `task`, without checking
+    /// [`MergeUpdate::applies_to`] first.
+    fn merge_into(&self, task: &mut Task);
+
+    /// Applies the update to `task`, unless it must be ignored.
+    ///
+    /// # Returns
+    /// Whether the update was applied
+    fn apply_to(&self, task: &mut Task) -> bool {
+        if !self.applies_to(task) {
+            return false;
+        }
+        self.merge_into(task);
+        true
+    }
+}
@@ -1,11 +1,43 @@
 mod avro_trait;
+mod merge_update;
+mod queue_depth;
 mod task;
+mod task_acknowledged;
 mod task_assignment;
+mod task_batch_completed;
 mod task_completed;
+mod task_event;
+mod task_input;
+mod task_kind;
+mod task_page;
+mod task_result;
 mod task_running;
+mod task_spec;
+mod task_stats;
+mod timestamp;
+mod worker;
+mod worker_heartbeat;
+mod worker_kind;
+mod worker_registration;
 
 pub use avro_trait::*;
+pub use merge_update::*;
+pub use queue_depth::*;
 pub use task::*;
+pub use task_acknowledged::*;
 pub use task_assignment::*;
+pub use task_batch_completed::*;
 pub use task_completed::*;
+pub use task_event::*;
+pub use task_input::*;
+pub use task_kind::*;
+pub use task_page::*;
+pub use task_result::*;
 pub use task_running::*;
+pub use task_spec::*;
+pub use task_stats::*;
+pub use timestamp::*;
+pub use worker::*;
+pub use worker_heartbeat::*;
+pub use worker_kind::*;
+pub use worker_registration::*;
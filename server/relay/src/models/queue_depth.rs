@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Messages waiting in a consumed queue, as reported by the broker.
+///
+/// # Fields
+/// * `queue` - The name of the queue
+/// * `messages` - The number of messages ready to be delivered
+/// * `consumers` - The number of consumers attached to the queue
+/// * `dead_letter_messages` - The number of messages in the dead letter queue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct QueueDepth {
+    pub queue: String,
+    pub messages: u32,
+    pub consumers: u32,
+    pub dead_letter_messages: u32,
+}
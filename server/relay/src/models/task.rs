@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use chrono::NaiveDateTime;
 use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::TraceContextExt;
 use opentelemetry::Context;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
 use serde::{Deserialize, Serialize};
@@ -12,7 +13,7 @@ use thiserror::Error;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::models::{serde_avro_datetime, serde_avro_datetime_opt, AvroSerializable};
+use crate::models::{parse_schema, serde_avro_datetime, serde_avro_datetime_opt, AvroSerializable};
 use apache_avro::{serde_avro_bytes_opt, Schema};
 
 // This is currently like this as it is only used for methods used in testing
@@ -23,7 +24,7 @@ use chrono::Local;
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Context extraction failed")]
-    _ContextExtractionError,
+    ContextExtractionError,
 }
 
 // Task status enum
@@ -31,7 +32,11 @@ pub enum Error {
 /// * `Pending`: Task is created but not yet assigned
 /// * `Processing`: Task has been assigned to a worker and sent to a queue
 /// * `Completed`: Task completed successfully or not
-#[derive(Display, EnumString, Debug, PartialEq, ToSchema, Clone)]
+///
+/// Maps to the `task_status` Postgres enum, so a value the relay doesn't know
+/// about fails to decode instead of being silently ignored.
+#[derive(Display, EnumString, Debug, PartialEq, ToSchema, Clone, sqlx::Type)]
+#[sqlx(type_name = "task_status")]
 pub enum TaskStatus {
     Pending,    // Task is created but not yet assigned
     Processing, // Task has been assigned to a worker and sent to a queue
@@ -41,47 +46,120 @@ pub enum TaskStatus {
 /// Tasks are sent to workers to be executed with a specific payload.
 /// Workers are eligble for receiving certain tasks depending on their
 /// list of capabilities.
-#[derive(Debug, ToSchema, Clone, Serialize, Deserialize, FromRow)]
+///
+/// Fields are declared in the same order as the Avro schema, see
+/// [`AvroSerializable::validate_schema`].
+#[derive(Debug, ToSchema, Clone, Default, Serialize, Deserialize, FromRow)]
 pub struct Task {
     pub id: Uuid,
     #[sqlx(rename = "task_kind_name")]
     pub task_kind: Option<String>,
 
-    // Task data
-    #[serde(with = "serde_avro_bytes_opt")]
-    pub input_data: Option<Vec<u8>>, // byte array
-    #[serde(with = "serde_avro_bytes_opt")]
-    pub output_data: Option<Vec<u8>>, // byte array
-    pub is_error: Option<i32>,
-
-    pub priority: Option<i32>,
-
     // Relations
     #[sqlx(rename = "worker_kind_name")]
     pub worker_kind: Option<String>,
-    pub executed_by: Option<String>, // worker that it is assigned to
 
     // Task status
+    #[serde(with = "serde_avro_datetime")]
+    pub created_at: NaiveDateTime,
     #[serde(with = "serde_avro_datetime_opt")]
     pub started_at: Option<NaiveDateTime>,
     #[serde(with = "serde_avro_datetime_opt")]
     pub completed_at: Option<NaiveDateTime>,
+    #[serde(with = "serde_avro_datetime")]
+    pub updated_at: NaiveDateTime,
+
+    // Task data
+    #[serde(with = "serde_avro_bytes_opt")]
+    pub input_data: Option<Vec<u8>>, // byte array
+    #[serde(with = "serde_avro_bytes_opt")]
+    pub output_data: Option<Vec<u8>>, // byte array
+    pub is_error: Option<bool>,
 
+    pub priority: Option<i32>,
     pub ttl_duration: Option<i64>, // in seconds
 
-    // Metadata
-    #[serde(with = "serde_avro_datetime")]
-    pub created_at: NaiveDateTime,
-    #[serde(with = "serde_avro_datetime")]
-    pub updated_at: NaiveDateTime,
+    pub executed_by: Option<String>, // worker that it is assigned to
 
     // OpenTelemetry context carrier
     pub otel_ctx_carrier: Option<JsonValue>,
+
+    // Appended to the schema after release, so older readers can skip them
+    #[serde(default, with = "serde_avro_datetime_opt")]
+    pub scheduled_for: Option<NaiveDateTime>,
+    #[serde(default, with = "serde_avro_datetime_opt")]
+    pub acknowledged_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub input_content_type: Option<String>,
+    #[serde(default)]
+    pub output_content_type: Option<String>,
+}
+
+/// Time a completed task spent in each stage of its lifecycle.
+///
+/// # Fields
+/// * `pending_ms` - Time between creation and start, if the start is known
+/// * `running_ms` - Time between start and completion, if the start is known
+/// * `total_ms` - Time between creation and completion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskLatency {
+    pub pending_ms: Option<i64>,
+    pub running_ms: Option<i64>,
+    pub total_ms: i64,
+}
+
+/// The lifecycle of a task as stored with its completion, enough to log its
+/// latency without reading the whole task back.
+///
+/// # Fields
+/// * `id` - The task
+/// * `created_at` - When the task was created
+/// * `started_at` - When the task was started, if known
+/// * `completed_at` - When the task was completed
+/// * `otel_ctx_carrier` - The context of the trace that originated the task
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskLifecycle {
+    pub id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub completed_at: NaiveDateTime,
+    pub otel_ctx_carrier: Option<JsonValue>,
+}
+
+impl TaskLifecycle {
+    /// The lifecycle of `task`, or `None` if it hasn't completed.
+    pub fn of(task: &Task) -> Option<Self> {
+        Some(Self {
+            id: task.id,
+            created_at: task.created_at,
+            started_at: task.started_at,
+            completed_at: task.completed_at?,
+            otel_ctx_carrier: task.otel_ctx_carrier.clone(),
+        })
+    }
+
+    /// Returns how long the task spent pending and running.
+    pub fn latency(&self) -> TaskLatency {
+        TaskLatency {
+            pending_ms: self
+                .started_at
+                .map(|started_at| (started_at - self.created_at).num_milliseconds()),
+            running_ms: self
+                .started_at
+                .map(|started_at| (self.completed_at - started_at).num_milliseconds()),
+            total_ms: (self.completed_at - self.created_at).num_milliseconds(),
+        }
+    }
+
+    /// Returns the context of the task.
+    pub fn context(&self) -> Context {
+        carrier_context(self.otel_ctx_carrier.as_ref())
+    }
 }
 
-#[cfg(test)]
 impl Task {
     /// Creates a new task with minimal required parameters
+    #[cfg(test)]
     pub fn new(
         task_kind_name: &str,
         worker_kind_name: &str,
@@ -93,12 +171,16 @@ impl Task {
             task_kind: Some(task_kind_name.to_string()),
             input_data: None,
             output_data: None,
-            is_error: Some(0),
+            is_error: Some(false),
             priority: Some(priority),
             worker_kind: Some(worker_kind_name.to_string()),
             executed_by: None,
             started_at: None,
             completed_at: None,
+            scheduled_for: None,
+            acknowledged_at: None,
+            input_content_type: None,
+            output_content_type: None,
             ttl_duration: Some(ttl_duration),
             otel_ctx_carrier: None,
             created_at: Local::now().naive_local(),
@@ -107,36 +189,42 @@ impl Task {
     }
 
     /// Sets the input data
+    #[cfg(test)]
     pub fn with_input_data(mut self, input_data: Vec<u8>) -> Self {
         self.input_data = Some(input_data);
         self
     }
 
     /// Sets the output data
+    #[cfg(test)]
     pub fn with_output_data(mut self, output_data: Vec<u8>) -> Self {
         self.output_data = Some(output_data);
         self
     }
 
     /// Sets the error status
+    #[cfg(test)]
     pub fn with_error(mut self, is_error: bool) -> Self {
-        self.is_error = if is_error { Some(1) } else { Some(0) };
+        self.is_error = Some(is_error);
         self
     }
 
     /// Sets the assigned worker
+    #[cfg(test)]
     pub fn _executed_by(mut self, worker_name: String) -> Self {
         self.executed_by = Some(worker_name);
         self
     }
 
     /// Sets the OpenTelemetry context
-    pub fn _with_otel_context(mut self, ctx: JsonValue) -> Self {
+    #[cfg(test)]
+    pub fn with_otel_context(mut self, ctx: JsonValue) -> Self {
         self.otel_ctx_carrier = Some(ctx);
         self
     }
 
     /// Returns the status of the task.
+    #[cfg(test)]
     pub fn _status(&self) -> TaskStatus {
         if self.completed_at.is_some() {
             TaskStatus::Completed
@@ -148,12 +236,19 @@ impl Task {
     }
 
     /// Returns the context of the task.
-    pub fn _context(&self) -> Context {
-        let carrier_value = self.otel_ctx_carrier.clone();
-        match carrier_value {
-            Some(carrier) => _extract_context(&carrier).unwrap_or_else(|_| Context::new()),
-            None => Context::new(),
+    pub fn context(&self) -> Context {
+        carrier_context(self.otel_ctx_carrier.as_ref())
+    }
+
+    /// Returns the W3C `traceparent` of the trace that originated this task,
+    /// or `None` if the task carries no valid OpenTelemetry context.
+    pub fn traceparent(&self) -> Option<String> {
+        let context = self.context();
+        if !context.span().span_context().is_valid() {
+            return None;
         }
+
+        inject_context(&context).remove("traceparent")
     }
 }
 
@@ -162,13 +257,14 @@ impl Task {
 // ----------------------------------------------------------------------------
 
 impl AvroSerializable for Task {
-    fn schema() -> &'static Schema {
+    fn try_schema() -> Result<&'static Schema, &'static str> {
         lazy_static::lazy_static! {
-            static ref AVRO_SCHEMA: Schema = Schema::parse_str(
+            static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                "task.json",
                 include_str!("schemas/avro/task.json")
-            ).expect("Failed to parse Avro schema");
+            );
         }
-        &AVRO_SCHEMA
+        AVRO_SCHEMA.as_ref().map_err(String::as_str)
     }
 }
 
@@ -176,9 +272,9 @@ impl AvroSerializable for Task {
 // Context Extraction (this was a motherfucker)
 // ----------------------------------------------------------------------------
 
-struct _HashMapExtractor<'a>(&'a std::collections::HashMap<String, String>);
+struct HashMapExtractor<'a>(&'a std::collections::HashMap<String, String>);
 
-impl Extractor for _HashMapExtractor<'_> {
+impl Extractor for HashMapExtractor<'_> {
     fn get(&self, key: &str) -> Option<&str> {
         self.0.get(key).map(|v| v.as_str())
     }
@@ -190,7 +286,7 @@ impl Extractor for _HashMapExtractor<'_> {
 
 /// Removes all non-string values from the map. Basically ensures that
 /// the map is a valid OpenTelemetry context carrier.
-fn _strip_map(map: &serde_json::Map<String, JsonValue>) -> HashMap<String, String> {
+fn strip_map(map: &serde_json::Map<String, JsonValue>) -> HashMap<String, String> {
     let hashmap: HashMap<String, String> = map
         .iter()
         .map(|(k, v)| (k.clone(), v.as_str().unwrap_or("").to_string()))
@@ -198,18 +294,35 @@ fn _strip_map(map: &serde_json::Map<String, JsonValue>) -> HashMap<String, Strin
     hashmap
 }
 
+/// Extracts the context from an optional carrier, falling back to an empty
+/// context when there is none or it is invalid.
+fn carrier_context(carrier: Option<&JsonValue>) -> Context {
+    carrier
+        .and_then(|carrier| extract_context(carrier).ok())
+        .unwrap_or_else(Context::new)
+}
+
 /// Extracts the context from the carrier.
-fn _extract_context(carrier: &JsonValue) -> Result<Context, Error> {
+fn extract_context(carrier: &JsonValue) -> Result<Context, Error> {
     match carrier {
         JsonValue::Object(map) => {
             let propagator = TraceContextPropagator::new();
-            let otel_cx = propagator.extract(&_HashMapExtractor(&_strip_map(map)));
+            let otel_cx = propagator.extract(&HashMapExtractor(&strip_map(map)));
             Ok(otel_cx)
         }
-        _ => Err(Error::_ContextExtractionError),
+        _ => Err(Error::ContextExtractionError),
     }
 }
 
+/// Injects the context into a new carrier, the inverse of `extract_context`.
+/// The relay uses it whenever it publishes, so the trace continues on the
+/// workers. A context without a valid span gives an empty carrier.
+pub fn inject_context(context: &Context) -> HashMap<String, String> {
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(context, &mut carrier);
+    carrier
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -248,4 +361,55 @@ mod tests {
         assert_eq!(task.task_kind, deserialized.task_kind);
         assert_eq!(task.input_data, deserialized.input_data);
     }
+
+    #[test]
+    fn test_inject_context_round_trip() {
+        let traceparent = "00-3f4a168998a20c019615e558ec12d985-47d2075b594ffe86-01";
+        let context = extract_context(&json!({ "traceparent": traceparent })).unwrap();
+
+        let carrier = inject_context(&context);
+        assert_eq!(
+            carrier.get("traceparent").map(String::as_str),
+            Some(traceparent)
+        );
+
+        let extracted = extract_context(&serde_json::to_value(&carrier).unwrap()).unwrap();
+        assert_eq!(
+            extracted.span().span_context().trace_id(),
+            context.span().span_context().trace_id()
+        );
+        assert!(extracted.span().span_context().is_valid());
+
+        assert!(inject_context(&Context::new()).is_empty());
+    }
+
+    #[test]
+    fn test_latency() {
+        let created_at = Local::now().naive_local();
+        let mut lifecycle = TaskLifecycle {
+            id: Uuid::new_v4(),
+            created_at,
+            started_at: None,
+            completed_at: created_at + chrono::Duration::milliseconds(300),
+            otel_ctx_carrier: None,
+        };
+        assert_eq!(
+            lifecycle.latency(),
+            TaskLatency {
+                pending_ms: None,
+                running_ms: None,
+                total_ms: 300,
+            }
+        );
+
+        lifecycle.started_at = Some(created_at + chrono::Duration::milliseconds(100));
+        assert_eq!(
+            lifecycle.latency(),
+            TaskLatency {
+                pending_ms: Some(100),
+                running_ms: Some(200),
+                total_ms: 300,
+            }
+        );
+    }
 }
@@ -0,0 +1,137 @@
+use crate::models::{parse_schema, serde_avro_datetime, AvroSerializable, MergeUpdate, Task};
+use apache_avro::Schema;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// TaskAcknowledgedUpdate represents an update to a task when a worker
+/// received it, before the worker starts running it.
+///
+/// # Fields
+/// * `id` - The id of the task
+/// * `acknowledged_at` - The timestamp when the worker received the task
+/// * `update_type` - The type of update
+#[derive(Debug, ToSchema, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaskAcknowledgedUpdate {
+    pub id: Uuid,
+    #[serde(with = "serde_avro_datetime")]
+    pub acknowledged_at: NaiveDateTime,
+    #[serde(default = "TaskAcknowledgedUpdate::update_type")]
+    pub update_type: String,
+}
+
+// ----------------------------------------------------------------------------
+// Constructors
+// ----------------------------------------------------------------------------
+
+impl TaskAcknowledgedUpdate {
+    fn update_type() -> String {
+        "Acknowledged".to_string()
+    }
+
+    pub fn validate_update_type(&self) -> Result<(), String> {
+        if self.update_type != "Acknowledged" {
+            return Err(format!(
+                "Invalid update type. Expected 'Acknowledged', got '{}'",
+                self.update_type
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for TaskAcknowledgedUpdate {
+    fn default() -> Self {
+        Self {
+            id: Uuid::nil(),
+            acknowledged_at: NaiveDateTime::default(),
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl TaskAcknowledgedUpdate {
+    /// Creates a new TaskAcknowledgedUpdate with the specified parameters.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the task
+    /// * `acknowledged_at` - The timestamp when the worker received the task
+    ///
+    /// # Returns
+    /// A new TaskAcknowledgedUpdate instance
+    pub fn new(id: Uuid, acknowledged_at: NaiveDateTime) -> Self {
+        Self {
+            id,
+            acknowledged_at,
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Merging
+// ----------------------------------------------------------------------------
+
+/// The first acknowledgement wins, so a task redelivered to another worker
+/// keeps the time it first reached one.
+impl MergeUpdate for TaskAcknowledgedUpdate {
+    fn merge_into(&self, task: &mut Task) {
+        task.acknowledged_at.get_or_insert(self.acknowledged_at);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Avro Serialization
+// ----------------------------------------------------------------------------
+
+impl AvroSerializable for TaskAcknowledgedUpdate {
+    fn try_schema() -> Result<&'static Schema, &'static str> {
+        lazy_static::lazy_static! {
+            static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                "task_acknowledged_update.json",
+                include_str!("schemas/avro/task_acknowledged_update.json")
+            );
+        }
+        AVRO_SCHEMA.as_ref().map_err(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn test_task_acknowledged_update_avro_serde() {
+        let update = TaskAcknowledgedUpdate::new(Uuid::new_v4(), Local::now().naive_local());
+
+        let avro_bytes = update.try_into_avro_bytes().unwrap();
+        let deserialized = TaskAcknowledgedUpdate::try_from_avro_bytes(&avro_bytes).unwrap();
+
+        assert_eq!(update.id, deserialized.id);
+        assert_eq!(
+            update.acknowledged_at.and_utc().timestamp_micros(),
+            deserialized.acknowledged_at.and_utc().timestamp_micros()
+        );
+        assert_eq!(update.update_type, deserialized.update_type);
+        assert!(deserialized.validate_update_type().is_ok());
+    }
+
+    #[test]
+    fn test_acknowledged_merge_keeps_first_acknowledgement() {
+        let acknowledged_at = Local::now().naive_local();
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+
+        let update = TaskAcknowledgedUpdate::new(task.id, acknowledged_at);
+        assert!(update.apply_to(&mut task));
+        assert_eq!(task.acknowledged_at, Some(acknowledged_at));
+
+        let redelivered =
+            TaskAcknowledgedUpdate::new(task.id, acknowledged_at + chrono::Duration::seconds(5));
+        assert!(redelivered.apply_to(&mut task));
+        assert_eq!(task.acknowledged_at, Some(acknowledged_at));
+    }
+}
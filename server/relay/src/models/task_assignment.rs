@@ -1,4 +1,8 @@
-use crate::models::{serde_avro_datetime, AvroSerializable};
+use crate::constants::{DEFAULT_MIN_TASK_TTL_SECS, DEFAULT_TASK_TTL_SECS, MAX_TTL_DURATION_SECS};
+use crate::models::{
+    inject_context, parse_schema, serde_avro_datetime, serde_avro_datetime_opt, AvroSerializable,
+    MergeUpdate, Task,
+};
 use apache_avro::{serde_avro_bytes, Schema};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
@@ -15,13 +19,18 @@ use uuid::Uuid;
 /// * `created_at` - The timestamp when the task was created
 /// * `input_data` - Optional input data for the task
 /// * `priority` - The priority of the task
-/// * `ttl_duration` - Time to live duration in microseconds
+/// * `ttl_duration` - Time to live duration in seconds, counted from completion.
+///   Negative if the publisher didn't set one, which JSON publishers do by
+///   leaving it out
 /// * `otel_ctx_carrier` - OpenTelemetry context carrier map
 /// * `update_type` - The type of update - IMPORTANT NOTE: This isn't useless.
 ///   if the task happens to be deserialized from a message with the same byte
 ///   count, an error won't be thrown but the data will be totally f-ed. We
 ///   ALWAYS need to validate that the update_type is correct and matches the
 ///   expected type.
+/// * `input_content_type` - The MIME type of the input data, if known
+/// * `scheduled_for` - When the task may start at the earliest. Only tasks
+///   submitted through the relay API are held back until then
 #[derive(Debug, ToSchema, Clone, Serialize, Deserialize, FromRow)]
 pub struct TaskAssignmentUpdate {
     pub id: Uuid,
@@ -32,10 +41,34 @@ pub struct TaskAssignmentUpdate {
     #[serde(with = "serde_avro_bytes")]
     pub input_data: Vec<u8>,
     pub priority: i32,
+    #[serde(default = "TaskAssignmentUpdate::unset_ttl_duration")]
     pub ttl_duration: i64,
     pub otel_ctx_carrier: std::collections::HashMap<String, String>,
     #[serde(default = "TaskAssignmentUpdate::update_type")]
     pub update_type: String,
+    #[serde(default)]
+    pub input_content_type: Option<String>,
+    #[serde(default, with = "serde_avro_datetime_opt")]
+    pub scheduled_for: Option<NaiveDateTime>,
+}
+
+/// Parses a task input as JSON if its content type is `application/json`,
+/// whatever its parameters.
+///
+/// # Returns
+/// `None` if the input isn't declared as JSON, or an error if it is but
+/// doesn't parse
+pub fn parse_json_input(
+    content_type: Option<&str>,
+    input_data: &[u8],
+) -> Result<Option<serde_json::Value>, serde_json::Error> {
+    let is_json = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"));
+    if !is_json {
+        return Ok(None);
+    }
+    serde_json::from_slice(input_data).map(Some)
 }
 
 impl TaskAssignmentUpdate {
@@ -43,6 +76,10 @@ impl TaskAssignmentUpdate {
         "Assignment".to_string()
     }
 
+    fn unset_ttl_duration() -> i64 {
+        -1
+    }
+
     pub fn validate_update_type(&self) -> Result<(), String> {
         if self.update_type != "Assignment" {
             return Err(format!(
@@ -52,6 +89,146 @@ impl TaskAssignmentUpdate {
         }
         Ok(())
     }
+
+    /// Parses the input as JSON if it is declared as `application/json`, so
+    /// it can be searched. Other inputs are left opaque.
+    ///
+    /// # Returns
+    /// `None` if the input isn't declared as JSON, or an error if it is but
+    /// doesn't parse
+    pub fn input_json(&self) -> Result<Option<serde_json::Value>, serde_json::Error> {
+        parse_json_input(self.input_content_type.as_deref(), &self.input_data)
+    }
+
+    /// The time the assignment has to be held back until, if the task is
+    /// scheduled to start later than it was created.
+    pub fn held_until(&self) -> Option<NaiveDateTime> {
+        self.scheduled_for
+            .filter(|scheduled_for| *scheduled_for > self.created_at)
+    }
+
+    /// Checks that `ttl_duration` is a plausible number of seconds. Values
+    /// beyond [`MAX_TTL_DURATION_SECS`] usually mean the publisher sent
+    /// milliseconds or microseconds instead. Negative values mean it is
+    /// unset and are left to the [`TtlPolicy`].
+    pub fn validate_ttl_duration(&self) -> Result<(), String> {
+        if self.ttl_duration > MAX_TTL_DURATION_SECS {
+            return Err(format!(
+                "Invalid ttl_duration {}. Expected seconds up to {}",
+                self.ttl_duration, MAX_TTL_DURATION_SECS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the assignment of a stored task, so it can be published
+    /// again. The TTL was already resolved when the task was stored, and the
+    /// trace context of the task is carried over so its trace continues.
+    ///
+    /// # Returns
+    /// `None` if the assignment of the task was never received
+    pub fn from_task(task: &Task) -> Option<Self> {
+        Some(Self {
+            id: task.id,
+            task_kind: task.task_kind.clone()?,
+            worker_kind: task.worker_kind.clone()?,
+            created_at: task.created_at,
+            input_data: task.input_data.clone().unwrap_or_default(),
+            priority: task.priority.unwrap_or_default(),
+            ttl_duration: task.ttl_duration.unwrap_or_else(Self::unset_ttl_duration),
+            otel_ctx_carrier: inject_context(&task.context()),
+            input_content_type: task.input_content_type.clone(),
+            scheduled_for: task.scheduled_for,
+            update_type: Self::update_type(),
+        })
+    }
+}
+
+/// How the TTL of an assigned task is decided.
+///
+/// # Fields
+/// * `default_secs` - The TTL of tasks published without one
+/// * `min_secs` - The smallest TTL kept, smaller ones are raised to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtlPolicy {
+    pub default_secs: i64,
+    pub min_secs: i64,
+}
+
+impl TtlPolicy {
+    /// Resolves the TTL to store for a published `ttl_duration`.
+    ///
+    /// # Arguments
+    /// * `ttl_duration` - The TTL sent by the publisher, negative if unset
+    pub fn resolve(&self, ttl_duration: i64) -> i64 {
+        let ttl_duration = if ttl_duration < 0 {
+            self.default_secs
+        } else {
+            ttl_duration
+        };
+        ttl_duration.max(self.min_secs)
+    }
+}
+
+impl Default for TtlPolicy {
+    fn default() -> Self {
+        Self {
+            default_secs: DEFAULT_TASK_TTL_SECS,
+            min_secs: DEFAULT_MIN_TASK_TTL_SECS,
+        }
+    }
+}
+
+impl Default for TaskAssignmentUpdate {
+    fn default() -> Self {
+        Self {
+            id: Uuid::nil(),
+            task_kind: String::new(),
+            worker_kind: String::new(),
+            created_at: NaiveDateTime::default(),
+            input_data: Vec::new(),
+            priority: 0,
+            ttl_duration: 0,
+            otel_ctx_carrier: std::collections::HashMap::new(),
+            input_content_type: None,
+            scheduled_for: None,
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Merging
+// ----------------------------------------------------------------------------
+
+/// Assignments only fill the fields the task doesn't have yet, so a replayed
+/// assignment never overwrites the first one. They are ignored once the task
+/// completed, unless the task was created by an event received before its
+/// assignment.
+///
+/// The TTL is merged as published. The repository resolves it with its
+/// [`TtlPolicy`] before storing it.
+impl MergeUpdate for TaskAssignmentUpdate {
+    fn applies_to(&self, task: &Task) -> bool {
+        task.completed_at.is_none() || task.task_kind.is_none()
+    }
+
+    fn merge_into(&self, task: &mut Task) {
+        task.task_kind.get_or_insert_with(|| self.task_kind.clone());
+        task.worker_kind
+            .get_or_insert_with(|| self.worker_kind.clone());
+        task.input_data
+            .get_or_insert_with(|| self.input_data.clone());
+        task.ttl_duration.get_or_insert(self.ttl_duration);
+        task.priority.get_or_insert(self.priority);
+        if task.otel_ctx_carrier.is_none() {
+            task.otel_ctx_carrier = serde_json::to_value(&self.otel_ctx_carrier).ok();
+        }
+        if task.input_content_type.is_none() {
+            task.input_content_type = self.input_content_type.clone();
+        }
+        task.scheduled_for = task.scheduled_for.or(self.scheduled_for);
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -59,13 +236,14 @@ impl TaskAssignmentUpdate {
 // ----------------------------------------------------------------------------
 
 impl AvroSerializable for TaskAssignmentUpdate {
-    fn schema() -> &'static Schema {
+    fn try_schema() -> Result<&'static Schema, &'static str> {
         lazy_static::lazy_static! {
-            static ref AVRO_SCHEMA: Schema = Schema::parse_str(
+            static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                "task_assignment_update.json",
                 include_str!("schemas/avro/task_assignment_update.json")
-            ).expect("Failed to parse Avro schema");
+            );
         }
-        &AVRO_SCHEMA
+        AVRO_SCHEMA.as_ref().map_err(String::as_str)
     }
 }
 
@@ -89,8 +267,10 @@ mod tests {
             created_at: Local::now().naive_local(),
             input_data: vec![1, 2, 3],
             priority: 1,
-            ttl_duration: 3600000000, // 1 hour in microseconds
+            ttl_duration: 3600, // 1 hour in seconds
             otel_ctx_carrier: otel_ctx.clone(),
+            input_content_type: None,
+            scheduled_for: None,
             update_type: "Assignment".to_string(),
         };
 
@@ -123,8 +303,10 @@ mod tests {
             created_at: Local::now().naive_local(),
             input_data: vec![1, 2, 3],
             priority: 1,
-            ttl_duration: 3600000000,
+            ttl_duration: 3600,
             otel_ctx_carrier: HashMap::new(),
+            input_content_type: None,
+            scheduled_for: None,
             update_type: "Assignment".to_string(),
         };
 
@@ -133,4 +315,128 @@ mod tests {
         assignment.update_type = "Wrong".to_string();
         assert!(assignment.validate_update_type().is_err());
     }
+
+    #[test]
+    fn test_task_assignment_input_json() {
+        let mut assignment = TaskAssignmentUpdate {
+            input_data: br#"{"user": "alice"}"#.to_vec(),
+            ..Default::default()
+        };
+        assert!(assignment.input_json().unwrap().is_none());
+
+        assignment.input_content_type = Some("application/json; charset=utf-8".to_string());
+        assert_eq!(
+            assignment.input_json().unwrap(),
+            Some(serde_json::json!({ "user": "alice" }))
+        );
+
+        assignment.input_data = vec![0xff, 0x00];
+        assert!(assignment.input_json().is_err());
+    }
+
+    #[test]
+    fn test_task_assignment_validate_ttl_duration() {
+        let mut assignment = TaskAssignmentUpdate {
+            ttl_duration: 3600,
+            ..Default::default()
+        };
+        assert!(assignment.validate_ttl_duration().is_ok());
+
+        // 1 hour in microseconds
+        assignment.ttl_duration = 3_600_000_000;
+        assert!(assignment.validate_ttl_duration().is_err());
+
+        // Left to the TTL policy
+        assignment.ttl_duration = -1;
+        assert!(assignment.validate_ttl_duration().is_ok());
+    }
+
+    #[test]
+    fn test_assignment_merge_fills_missing_fields() {
+        let now = Local::now().naive_local();
+        let assignment = TaskAssignmentUpdate {
+            id: Uuid::new_v4(),
+            task_kind: "TaskKindName".to_string(),
+            worker_kind: "WorkerKindName".to_string(),
+            input_data: vec![1, 2, 3],
+            input_content_type: Some("image/png".to_string()),
+            priority: 5,
+            ttl_duration: 60,
+            scheduled_for: Some(now),
+            ..Default::default()
+        };
+
+        // A task created by a running event before its assignment
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+        task.task_kind = None;
+        task.worker_kind = None;
+        task.priority = None;
+        task.ttl_duration = None;
+        task.executed_by = Some("worker".to_string());
+        assert!(assignment.apply_to(&mut task));
+        assert_eq!(task.task_kind.as_deref(), Some("TaskKindName"));
+        assert_eq!(task.worker_kind.as_deref(), Some("WorkerKindName"));
+        assert_eq!(task.input_data, Some(vec![1, 2, 3]));
+        assert_eq!(task.input_content_type.as_deref(), Some("image/png"));
+        assert_eq!(task.priority, Some(5));
+        assert_eq!(task.ttl_duration, Some(60));
+        assert_eq!(task.scheduled_for, Some(now));
+        assert_eq!(task.executed_by.as_deref(), Some("worker"));
+
+        // A replayed assignment keeps the first one
+        let replayed = TaskAssignmentUpdate {
+            priority: 9,
+            input_data: vec![4],
+            ..assignment.clone()
+        };
+        assert!(replayed.apply_to(&mut task));
+        assert_eq!(task.priority, Some(5));
+        assert_eq!(task.input_data, Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_assignment_merge_skips_completed_tasks() {
+        let assignment = TaskAssignmentUpdate {
+            task_kind: "TaskKindName".to_string(),
+            input_data: vec![1],
+            ..Default::default()
+        };
+
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+        task.completed_at = Some(Local::now().naive_local());
+        assert!(!assignment.apply_to(&mut task));
+        assert_eq!(task.input_data, None);
+
+        // Unless the completion arrived before the assignment
+        task.task_kind = None;
+        assert!(assignment.apply_to(&mut task));
+        assert_eq!(task.input_data, Some(vec![1]));
+    }
+
+    #[test]
+    fn test_ttl_policy_resolve() {
+        let policy = TtlPolicy {
+            default_secs: 3600,
+            min_secs: 60,
+        };
+        assert_eq!(policy.resolve(-1), 3600);
+        assert_eq!(policy.resolve(0), 60);
+        assert_eq!(policy.resolve(30), 60);
+        assert_eq!(policy.resolve(120), 120);
+    }
+
+    #[test]
+    fn test_task_assignment_json_without_ttl_duration() {
+        let assignment: TaskAssignmentUpdate = serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "task_kind": "TaskKindName",
+            "worker_kind": "WorkerKindName",
+            "created_at": 0,
+            "input_data": [],
+            "priority": 0,
+            "otel_ctx_carrier": {},
+        }))
+        .unwrap();
+        assert!(assignment.ttl_duration < 0);
+    }
 }
@@ -0,0 +1,139 @@
+use crate::models::{parse_schema, AvroSerializable, TaskCompletedUpdate};
+use apache_avro::Schema;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// TaskBatchCompletedUpdate represents the completion of several tasks,
+/// published at once by a worker finishing a batch of them instead of one
+/// completed update per task.
+///
+/// # Fields
+/// * `completions` - The completed update of every task of the batch
+/// * `update_type` - The type of update
+#[derive(Debug, ToSchema, Clone, Serialize, Deserialize)]
+pub struct TaskBatchCompletedUpdate {
+    pub completions: Vec<TaskCompletedUpdate>,
+    #[serde(default = "TaskBatchCompletedUpdate::update_type")]
+    pub update_type: String,
+}
+
+// ----------------------------------------------------------------------------
+// Constructors
+// ----------------------------------------------------------------------------
+
+impl TaskBatchCompletedUpdate {
+    fn update_type() -> String {
+        "BatchCompleted".to_string()
+    }
+
+    /// Checks the update type of the batch and of every completion in it. An
+    /// empty batch is refused, as it doesn't complete anything.
+    pub fn validate_update_type(&self) -> Result<(), String> {
+        if self.update_type != "BatchCompleted" {
+            return Err(format!(
+                "Invalid update type. Expected 'BatchCompleted', got '{}'",
+                self.update_type
+            ));
+        }
+        if self.completions.is_empty() {
+            return Err("A batch of completions must complete at least one task".to_string());
+        }
+        self.completions
+            .iter()
+            .try_for_each(TaskCompletedUpdate::validate_update_type)
+    }
+}
+
+impl Default for TaskBatchCompletedUpdate {
+    fn default() -> Self {
+        Self {
+            completions: Vec::new(),
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl TaskBatchCompletedUpdate {
+    /// Creates a new TaskBatchCompletedUpdate with the specified completions.
+    ///
+    /// # Arguments
+    /// * `completions` - The completed update of every task of the batch
+    ///
+    /// # Returns
+    /// A new TaskBatchCompletedUpdate instance
+    pub fn new(completions: Vec<TaskCompletedUpdate>) -> Self {
+        Self {
+            completions,
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Avro Serialization
+// ----------------------------------------------------------------------------
+
+impl AvroSerializable for TaskBatchCompletedUpdate {
+    fn try_schema() -> Result<&'static Schema, &'static str> {
+        lazy_static::lazy_static! {
+            static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                "task_batch_completed_update.json",
+                include_str!("schemas/avro/task_batch_completed_update.json")
+            );
+        }
+        AVRO_SCHEMA.as_ref().map_err(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use uuid::Uuid;
+
+    fn test_batch() -> TaskBatchCompletedUpdate {
+        let now = Local::now().naive_local();
+        TaskBatchCompletedUpdate::new(vec![
+            TaskCompletedUpdate::new(Uuid::new_v4(), now, vec![1, 2], false),
+            TaskCompletedUpdate {
+                output_content_type: Some("text/plain".to_string()),
+                ..TaskCompletedUpdate::new(Uuid::new_v4(), now, b"failed".to_vec(), true)
+            },
+        ])
+    }
+
+    #[test]
+    fn test_task_batch_completed_update_avro_serde() {
+        let batch = test_batch();
+
+        let avro_bytes = batch.try_into_avro_bytes().unwrap();
+        let deserialized = TaskBatchCompletedUpdate::try_from_avro_bytes(&avro_bytes).unwrap();
+
+        assert_eq!(deserialized.update_type, batch.update_type);
+        assert_eq!(deserialized.completions.len(), 2);
+        for (completed, parsed) in batch.completions.iter().zip(&deserialized.completions) {
+            assert_eq!(completed.id, parsed.id);
+            assert_eq!(
+                completed.completed_at.and_utc().timestamp_micros(),
+                parsed.completed_at.and_utc().timestamp_micros()
+            );
+            assert_eq!(completed.output_data, parsed.output_data);
+            assert_eq!(completed.is_error, parsed.is_error);
+            assert_eq!(completed.output_content_type, parsed.output_content_type);
+        }
+    }
+
+    #[test]
+    fn test_task_batch_completed_validate_update_type() {
+        let mut batch = test_batch();
+        assert!(batch.validate_update_type().is_ok());
+
+        batch.completions[1].update_type = "Running".to_string();
+        assert!(batch.validate_update_type().is_err());
+
+        assert!(TaskBatchCompletedUpdate::new(Vec::new())
+            .validate_update_type()
+            .is_err());
+    }
+}
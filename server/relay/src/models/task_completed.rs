@@ -1,4 +1,4 @@
-use crate::models::{serde_avro_datetime, AvroSerializable};
+use crate::models::{parse_schema, serde_avro_datetime, AvroSerializable, MergeUpdate, Task};
 use apache_avro::{serde_avro_bytes, Schema};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
@@ -14,6 +14,7 @@ use uuid::Uuid;
 /// * `output_data` - Optional output data from the task execution
 /// * `is_error` - Whether the task completed with an error
 /// * `update_type` - The type of update
+/// * `output_content_type` - The MIME type of the output data, if the worker declared one
 #[derive(Debug, ToSchema, Clone, Serialize, Deserialize, FromRow)]
 pub struct TaskCompletedUpdate {
     pub id: Uuid,
@@ -21,9 +22,11 @@ pub struct TaskCompletedUpdate {
     pub completed_at: NaiveDateTime,
     #[serde(with = "serde_avro_bytes")]
     pub output_data: Vec<u8>,
-    pub is_error: i32,
+    pub is_error: bool,
     #[serde(default = "TaskCompletedUpdate::update_type")]
     pub update_type: String,
+    #[serde(default)]
+    pub output_content_type: Option<String>,
 }
 
 // ----------------------------------------------------------------------------
@@ -45,6 +48,19 @@ impl TaskCompletedUpdate {
     }
 }
 
+impl Default for TaskCompletedUpdate {
+    fn default() -> Self {
+        Self {
+            id: Uuid::nil(),
+            completed_at: NaiveDateTime::default(),
+            output_data: Vec::new(),
+            is_error: false,
+            output_content_type: None,
+            update_type: Self::update_type(),
+        }
+    }
+}
+
 #[cfg(test)]
 impl TaskCompletedUpdate {
     /// Creates a new TaskCompletedUpdate with the specified parameters.
@@ -57,12 +73,18 @@ impl TaskCompletedUpdate {
     ///
     /// # Returns
     /// A new TaskCompletedUpdate instance
-    pub fn new(id: Uuid, completed_at: NaiveDateTime, output_data: Vec<u8>, is_error: i32) -> Self {
+    pub fn new(
+        id: Uuid,
+        completed_at: NaiveDateTime,
+        output_data: Vec<u8>,
+        is_error: bool,
+    ) -> Self {
         Self {
             id,
             completed_at,
             output_data,
             is_error,
+            output_content_type: None,
             update_type: Self::update_type(),
         }
     }
@@ -79,7 +101,8 @@ impl TaskCompletedUpdate {
             id,
             completed_at: NaiveDateTime::MIN,
             output_data: vec![],
-            is_error: 0,
+            is_error: false,
+            output_content_type: None,
             update_type: Self::update_type(),
         }
     }
@@ -115,10 +138,47 @@ impl TaskCompletedUpdate {
     ///
     /// # Returns
     /// A new TaskCompletedUpdate instance
-    pub fn _with_is_error(mut self, is_error: i32) -> Self {
+    pub fn _with_is_error(mut self, is_error: bool) -> Self {
         self.is_error = is_error;
         self
     }
+
+    /// Sets the output_content_type field.
+    ///
+    /// # Arguments
+    /// * `output_content_type` - The MIME type of the output data
+    ///
+    /// # Returns
+    /// A new TaskCompletedUpdate instance
+    pub fn with_output_content_type(mut self, output_content_type: &str) -> Self {
+        self.output_content_type = Some(output_content_type.to_string());
+        self
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Merging
+// ----------------------------------------------------------------------------
+
+/// The most recent completion wins, and an error beats a success reported at
+/// the same time. The winning completion replaces the whole result.
+impl MergeUpdate for TaskCompletedUpdate {
+    fn applies_to(&self, task: &Task) -> bool {
+        match task.completed_at {
+            None => true,
+            Some(completed_at) if self.completed_at != completed_at => {
+                self.completed_at > completed_at
+            }
+            Some(_) => self.is_error && !task.is_error.unwrap_or(false),
+        }
+    }
+
+    fn merge_into(&self, task: &mut Task) {
+        task.completed_at = Some(self.completed_at);
+        task.output_data = Some(self.output_data.clone());
+        task.is_error = Some(self.is_error);
+        task.output_content_type = self.output_content_type.clone();
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -126,13 +186,14 @@ impl TaskCompletedUpdate {
 // ----------------------------------------------------------------------------
 
 impl AvroSerializable for TaskCompletedUpdate {
-    fn schema() -> &'static Schema {
+    fn try_schema() -> Result<&'static Schema, &'static str> {
         lazy_static::lazy_static! {
-            static ref AVRO_SCHEMA: Schema = Schema::parse_str(
+            static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                "task_completed_update.json",
                 include_str!("schemas/avro/task_completed_update.json")
-            ).expect("Failed to parse Avro schema");
+            );
         }
-        &AVRO_SCHEMA
+        AVRO_SCHEMA.as_ref().map_err(String::as_str)
     }
 }
 
@@ -143,8 +204,13 @@ mod tests {
 
     #[test]
     fn test_task_completed_update_avro_serde() {
-        let mut update =
-            TaskCompletedUpdate::new(Uuid::new_v4(), Local::now().naive_local(), vec![1, 2, 3], 0);
+        let mut update = TaskCompletedUpdate::new(
+            Uuid::new_v4(),
+            Local::now().naive_local(),
+            vec![1, 2, 3],
+            false,
+        )
+        .with_output_content_type("image/png");
         update.update_type = "Completed".to_string();
 
         // Serialize to Avro bytes
@@ -162,13 +228,68 @@ mod tests {
         );
         assert_eq!(update.output_data, deserialized.output_data);
         assert_eq!(update.is_error, deserialized.is_error);
+        assert_eq!(update.output_content_type, deserialized.output_content_type);
         assert_eq!(update.update_type, deserialized.update_type);
     }
 
+    #[test]
+    fn test_completed_merge_keeps_latest_completion() {
+        let completed_at = Local::now().naive_local();
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+
+        let update = TaskCompletedUpdate::new(task.id, completed_at, vec![1], false)
+            .with_output_content_type("image/png");
+        assert!(update.apply_to(&mut task));
+        assert_eq!(task.completed_at, Some(completed_at));
+        assert_eq!(task.output_data, Some(vec![1]));
+        assert_eq!(task.is_error, Some(false));
+        assert_eq!(task.output_content_type.as_deref(), Some("image/png"));
+
+        // An older completion arriving late is ignored
+        let older = TaskCompletedUpdate::new(
+            task.id,
+            completed_at - chrono::Duration::seconds(1),
+            vec![2],
+            true,
+        );
+        assert!(!older.apply_to(&mut task));
+        assert_eq!(task.output_data, Some(vec![1]));
+
+        // A newer one replaces the result, even with a success
+        let newer = TaskCompletedUpdate::new(
+            task.id,
+            completed_at + chrono::Duration::seconds(1),
+            vec![3],
+            false,
+        );
+        assert!(newer.apply_to(&mut task));
+        assert_eq!(task.completed_at, Some(newer.completed_at));
+        assert_eq!(task.output_data, Some(vec![3]));
+        assert_eq!(task.output_content_type, None);
+    }
+
+    #[test]
+    fn test_completed_merge_prefers_errors_at_the_same_time() {
+        let completed_at = Local::now().naive_local();
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+
+        let success = TaskCompletedUpdate::new(task.id, completed_at, vec![1], false);
+        let error = TaskCompletedUpdate::new(task.id, completed_at, vec![2], true);
+        assert!(success.apply_to(&mut task));
+        assert!(error.apply_to(&mut task));
+        assert_eq!(task.output_data, Some(vec![2]));
+        assert_eq!(task.is_error, Some(true));
+
+        // Neither a replayed error nor the success take over again
+        assert!(!error.apply_to(&mut task));
+        assert!(!success.apply_to(&mut task));
+        assert_eq!(task.is_error, Some(true));
+    }
+
     #[test]
     fn test_task_completed_validate_update_type() {
         let mut update =
-            TaskCompletedUpdate::new(Uuid::new_v4(), Local::now().naive_local(), vec![], 0);
+            TaskCompletedUpdate::new(Uuid::new_v4(), Local::now().naive_local(), vec![], false);
         update.update_type = "Completed".to_string();
         assert!(update.validate_update_type().is_ok());
 
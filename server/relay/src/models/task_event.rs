@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// An event applied to a task, as recorded in its history.
+///
+/// # Fields
+/// * `task_id` - The task the event was applied to
+/// * `event_type` - The type of the event, `TaskAssignment`, `TaskRunning`,
+///   `TaskCompleted` or `TaskRequeued`
+/// * `payload` - The event as received, without its input or output data
+/// * `occurred_at` - When the event happened according to its publisher
+/// * `recorded_at` - When the relay recorded the event
+#[derive(Debug, ToSchema, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaskEvent {
+    pub task_id: Uuid,
+    pub event_type: String,
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    pub occurred_at: NaiveDateTime,
+    pub recorded_at: NaiveDateTime,
+}
@@ -0,0 +1,12 @@
+use sqlx::FromRow;
+
+/// The input of a task, as served by the input endpoint.
+///
+/// # Fields
+/// * `input_data` - The input data of the task, if its assignment was received
+/// * `input_content_type` - The MIME type of the input data, if the publisher declared one
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct TaskInput {
+    pub input_data: Option<Vec<u8>>,
+    pub input_content_type: Option<String>,
+}
@@ -0,0 +1,42 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A kind of task that has been submitted to the relay. Task kinds aren't
+/// registered up front, so they are derived from the recorded tasks.
+///
+/// # Fields
+/// * `name` - The name of the task kind
+/// * `worker_kind` - The kind of worker the task kind was most recently submitted to
+/// * `created_at` - The creation timestamp of the first task of this kind
+/// * `default_priority` - The priority of the tasks submitted without one
+/// * `default_ttl_duration` - The TTL in seconds of the tasks submitted without one
+/// * `input_schema` - JSON Schema the inputs declared as JSON must conform to
+#[derive(Debug, ToSchema, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct TaskKind {
+    pub name: String,
+    pub worker_kind: String,
+    pub created_at: NaiveDateTime,
+    #[serde(default)]
+    pub default_priority: Option<i32>,
+    #[serde(default)]
+    pub default_ttl_duration: Option<i64>,
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub input_schema: Option<serde_json::Value>,
+}
+
+/// Defaults applied to the tasks of a kind submitted without them. Unset
+/// defaults fall back to the relay-wide ones.
+///
+/// # Fields
+/// * `default_priority` - The priority of the tasks submitted without one
+/// * `default_ttl_duration` - The TTL in seconds of the tasks submitted without one
+#[derive(Debug, ToSchema, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct TaskKindDefaults {
+    #[serde(default)]
+    pub default_priority: Option<i32>,
+    #[serde(default)]
+    pub default_ttl_duration: Option<i64>,
+}
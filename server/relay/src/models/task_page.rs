@@ -0,0 +1,113 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::Task;
+
+/// Position in the task listing, pointing right after a task. Tasks are
+/// listed newest first, so the next page holds the tasks created before it.
+///
+/// Cursors are exchanged as opaque strings, see [`TaskCursor::encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskCursor {
+    pub created_at: NaiveDateTime,
+    pub id: Uuid,
+}
+
+impl TaskCursor {
+    /// Points right after a task.
+    pub fn after(task: &Task) -> Self {
+        Self {
+            created_at: task.created_at,
+            id: task.id,
+        }
+    }
+
+    /// Encodes the cursor as URL-safe base64.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{},{}",
+            self.created_at.and_utc().timestamp_micros(),
+            self.id
+        ))
+    }
+}
+
+impl FromStr for TaskCursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid cursor: {}", s);
+
+        let decoded = URL_SAFE_NO_PAD.decode(s).map_err(|_| invalid())?;
+        let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+        let (micros, id) = decoded.split_once(',').ok_or_else(invalid)?;
+
+        let created_at = micros
+            .parse::<i64>()
+            .ok()
+            .and_then(DateTime::from_timestamp_micros)
+            .ok_or_else(invalid)?
+            .naive_utc();
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+
+        Ok(Self { created_at, id })
+    }
+}
+
+/// A page of tasks, newest first.
+///
+/// # Fields
+/// * `tasks` - The tasks of the page
+/// * `next_cursor` - Cursor of the next page, `None` on the last page
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    pub next_cursor: Option<String>,
+}
+
+/// Tasks changed since a date, oldest update first. Tasks deleted or
+/// archived since then aren't part of it.
+///
+/// # Fields
+/// * `tasks` - The changed tasks
+/// * `cursor` - Latest update date of the tasks, or the requested date if
+///   none changed. Pass it as `since` to get the next changes.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskChanges {
+    pub tasks: Vec<Task>,
+    pub cursor: NaiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = TaskCursor {
+            created_at: DateTime::from_timestamp_micros(
+                Local::now().naive_local().and_utc().timestamp_micros(),
+            )
+            .unwrap()
+            .naive_utc(),
+            id: Uuid::new_v4(),
+        };
+
+        assert_eq!(cursor.encode().parse::<TaskCursor>(), Ok(cursor));
+    }
+
+    #[test]
+    fn test_invalid_cursor_is_rejected() {
+        assert!("not a cursor!".parse::<TaskCursor>().is_err());
+        assert!(URL_SAFE_NO_PAD
+            .encode("123,not-a-uuid")
+            .parse::<TaskCursor>()
+            .is_err());
+    }
+}
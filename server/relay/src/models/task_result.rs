@@ -0,0 +1,29 @@
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+/// The result of a task, as served by the result endpoint.
+///
+/// # Fields
+/// * `completed_at` - When the task completed, if it did
+/// * `output_data` - The output data of the task
+/// * `output_content_type` - The MIME type of the output data, if the worker declared one
+/// * `is_error` - Whether the output is the error the task failed with
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct TaskResult {
+    pub completed_at: Option<NaiveDateTime>,
+    pub output_data: Option<Vec<u8>>,
+    pub output_content_type: Option<String>,
+    pub is_error: Option<bool>,
+}
+
+impl TaskResult {
+    /// Whether the task completed and its output is available.
+    pub fn is_completed(&self) -> bool {
+        self.completed_at.is_some()
+    }
+
+    /// Whether the task failed, its output being the error it failed with.
+    pub fn is_failed(&self) -> bool {
+        self.is_error == Some(true)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::models::{serde_avro_datetime, AvroSerializable};
+use crate::models::{parse_schema, serde_avro_datetime, AvroSerializable, MergeUpdate, Task};
 use apache_avro::Schema;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
@@ -43,6 +43,17 @@ impl TaskRunningUpdate {
     }
 }
 
+impl Default for TaskRunningUpdate {
+    fn default() -> Self {
+        Self {
+            id: Uuid::nil(),
+            started_at: NaiveDateTime::default(),
+            executed_by: String::new(),
+            update_type: Self::update_type(),
+        }
+    }
+}
+
 #[cfg(test)]
 impl TaskRunningUpdate {
     /// Creates a new TaskRunningUpdate with the specified parameters.
@@ -104,18 +115,33 @@ impl TaskRunningUpdate {
     }
 }
 
+// ----------------------------------------------------------------------------
+// Merging
+// ----------------------------------------------------------------------------
+
+/// The first running update wins, so a redelivered or late one doesn't move
+/// the start of the task or change the worker executing it.
+impl MergeUpdate for TaskRunningUpdate {
+    fn merge_into(&self, task: &mut Task) {
+        task.started_at.get_or_insert(self.started_at);
+        task.executed_by
+            .get_or_insert_with(|| self.executed_by.clone());
+    }
+}
+
 // ----------------------------------------------------------------------------
 // Avro Serialization
 // ----------------------------------------------------------------------------
 
 impl AvroSerializable for TaskRunningUpdate {
-    fn schema() -> &'static Schema {
+    fn try_schema() -> Result<&'static Schema, &'static str> {
         lazy_static::lazy_static! {
-            static ref AVRO_SCHEMA: Schema = Schema::parse_str(
+            static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                "task_running_update.json",
                 include_str!("schemas/avro/task_running_update.json")
-            ).expect("Failed to parse Avro schema");
+            );
         }
-        &AVRO_SCHEMA
+        AVRO_SCHEMA.as_ref().map_err(String::as_str)
     }
 }
 
@@ -147,6 +173,26 @@ mod tests {
         assert_eq!(update.update_type, deserialized.update_type);
     }
 
+    #[test]
+    fn test_running_merge_keeps_first_start() {
+        let started_at = Local::now().naive_local();
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+
+        let update = TaskRunningUpdate::new(task.id, started_at, "worker_1".to_string());
+        assert!(update.apply_to(&mut task));
+        assert_eq!(task.started_at, Some(started_at));
+        assert_eq!(task.executed_by.as_deref(), Some("worker_1"));
+
+        let redelivered = TaskRunningUpdate::new(
+            task.id,
+            started_at + chrono::Duration::seconds(5),
+            "worker_2".to_string(),
+        );
+        assert!(redelivered.apply_to(&mut task));
+        assert_eq!(task.started_at, Some(started_at));
+        assert_eq!(task.executed_by.as_deref(), Some("worker_1"));
+    }
+
     #[test]
     fn test_task_running_validate_update_type() {
         let mut update =
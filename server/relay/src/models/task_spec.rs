@@ -0,0 +1,188 @@
+use crate::constants::MAX_TTL_DURATION_SECS;
+use crate::models::{
+    deserialize_timestamp_opt, parse_json_input, TaskAssignmentUpdate, TaskKindDefaults,
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A task submitted through the API, from which its assignment is built.
+///
+/// # Fields
+/// * `id` - The id of the task, generated if unset
+/// * `task_kind` - The kind of the task
+/// * `worker_kind` - The kind of worker executing the task
+/// * `input_data` - The input data of the task
+/// * `priority` - The priority of the task, from 0 to 255. The default of
+///   the task kind applies if unset, else 0
+/// * `ttl_duration` - How long the task is kept after completing, in
+///   seconds. The default of the task kind applies if unset, else the relay
+///   default
+/// * `otel_ctx_carrier` - OpenTelemetry context to propagate to the workers
+/// * `input_content_type` - The MIME type of the input data, if known
+/// * `scheduled_for` - When the task may start at the earliest. Its
+///   assignment is held until then, and published right away if unset or
+///   in the past
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TaskSpec {
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    pub task_kind: String,
+    pub worker_kind: String,
+    #[serde(default)]
+    pub input_data: Vec<u8>,
+    #[serde(default)]
+    pub priority: Option<i32>,
+    #[serde(default)]
+    pub ttl_duration: Option<i64>,
+    #[serde(default)]
+    pub otel_ctx_carrier: HashMap<String, String>,
+    #[serde(default)]
+    pub input_content_type: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_timestamp_opt")]
+    pub scheduled_for: Option<NaiveDateTime>,
+}
+
+impl TaskSpec {
+    /// Checks the task can be submitted as is. The defaults of task kinds
+    /// are validated when they are set, so they don't need checking here.
+    ///
+    /// # Arguments
+    /// * `max_payload_bytes` - Largest input accepted, like the consumer does
+    ///
+    /// # Returns
+    /// Every problem found, empty if the task is valid
+    pub fn validate(&self, max_payload_bytes: usize) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.task_kind.trim().is_empty() {
+            problems.push("task_kind must not be blank".to_string());
+        }
+        if self.worker_kind.trim().is_empty() {
+            problems.push("worker_kind must not be blank".to_string());
+        }
+        if self.input_data.len() > max_payload_bytes {
+            problems.push(format!(
+                "input_data of {} bytes exceeds the maximum payload size of {} bytes",
+                self.input_data.len(),
+                max_payload_bytes
+            ));
+        }
+        if self
+            .priority
+            .is_some_and(|priority| !(0..=255).contains(&priority))
+        {
+            problems.push("priority must be between 0 and 255".to_string());
+        }
+        if let Some(ttl) = self.ttl_duration.filter(|ttl| *ttl > MAX_TTL_DURATION_SECS) {
+            problems.push(format!(
+                "Invalid ttl_duration {}. Expected seconds up to {}",
+                ttl, MAX_TTL_DURATION_SECS
+            ));
+        }
+        problems
+    }
+
+    /// Parses the input as JSON if it is declared as `application/json`.
+    ///
+    /// # Returns
+    /// `None` if the input isn't declared as JSON, or an error if it is but
+    /// doesn't parse
+    pub fn input_json(&self) -> Result<Option<serde_json::Value>, serde_json::Error> {
+        parse_json_input(self.input_content_type.as_deref(), &self.input_data)
+    }
+
+    /// Builds the assignment of the task, generating its id and filling in
+    /// the unset settings from the defaults of its kind.
+    ///
+    /// # Arguments
+    /// * `created_at` - The creation date of the task
+    /// * `defaults` - The defaults of the task kind
+    pub fn into_assignment(
+        self,
+        created_at: NaiveDateTime,
+        defaults: &TaskKindDefaults,
+    ) -> TaskAssignmentUpdate {
+        TaskAssignmentUpdate {
+            id: self.id.unwrap_or_else(Uuid::new_v4),
+            task_kind: self.task_kind,
+            worker_kind: self.worker_kind,
+            created_at,
+            input_data: self.input_data,
+            priority: self
+                .priority
+                .or(defaults.default_priority)
+                .unwrap_or_default(),
+            ttl_duration: self
+                .ttl_duration
+                .or(defaults.default_ttl_duration)
+                .unwrap_or(-1),
+            otel_ctx_carrier: self.otel_ctx_carrier,
+            input_content_type: self.input_content_type,
+            scheduled_for: self.scheduled_for,
+            update_type: "Assignment".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn spec() -> TaskSpec {
+        serde_json::from_value(serde_json::json!({
+            "task_kind": "resize",
+            "worker_kind": "image_worker"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(spec().validate(8).is_empty());
+
+        let invalid = TaskSpec {
+            task_kind: " ".to_string(),
+            input_data: vec![0; 9],
+            priority: Some(256),
+            ttl_duration: Some(i64::MAX),
+            ..spec()
+        };
+        let problems = invalid.validate(8);
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert_eq!(problems[0], "task_kind must not be blank");
+        assert!(problems[1].starts_with("input_data of 9 bytes"));
+        assert_eq!(problems[2], "priority must be between 0 and 255");
+        assert!(problems[3].starts_with("Invalid ttl_duration"));
+    }
+
+    #[test]
+    fn test_into_assignment_applies_kind_defaults() {
+        let now = Utc::now().naive_utc();
+        let defaults = TaskKindDefaults {
+            default_priority: Some(7),
+            default_ttl_duration: Some(60),
+        };
+
+        let assignment = spec().into_assignment(now, &defaults);
+        assert_eq!(assignment.priority, 7);
+        assert_eq!(assignment.ttl_duration, 60);
+
+        // Settings of the task win over the defaults of its kind
+        let assignment = TaskSpec {
+            priority: Some(1),
+            ttl_duration: Some(10),
+            ..spec()
+        }
+        .into_assignment(now, &defaults);
+        assert_eq!(assignment.priority, 1);
+        assert_eq!(assignment.ttl_duration, 10);
+
+        // Without defaults, the relay-wide ones apply
+        let assignment = spec().into_assignment(now, &TaskKindDefaults::default());
+        assert_eq!(assignment.priority, 0);
+        assert_eq!(assignment.ttl_duration, -1);
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use crate::models::TaskStatus;
+
+/// Number of tasks sharing a status, as computed by the repository.
+#[derive(Debug, Clone, FromRow)]
+pub struct TaskStatusCount {
+    pub status: TaskStatus,
+    pub count: i64,
+}
+
+/// Number of tasks assigned to a worker kind. Tasks whose assignment event
+/// hasn't arrived yet have no worker kind.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
+pub struct WorkerKindCount {
+    pub worker_kind: Option<String>,
+    pub count: i64,
+}
+
+/// Task counts per status. Every status is always present so the shape of
+/// the response stays stable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TaskStatusCounts {
+    pub pending: i64,
+    pub processing: i64,
+    pub completed: i64,
+}
+
+/// Aggregated task counts used by dashboards.
+///
+/// # Fields
+/// * `total` - The total number of tasks in the window
+/// * `by_status` - Task counts per status
+/// * `by_worker_kind` - Task counts per worker kind
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TaskStats {
+    pub total: i64,
+    pub by_status: TaskStatusCounts,
+    pub by_worker_kind: Vec<WorkerKindCount>,
+}
+
+impl TaskStats {
+    /// Builds the stats from the raw counts returned by the repository.
+    pub fn from_counts(
+        status_counts: Vec<TaskStatusCount>,
+        by_worker_kind: Vec<WorkerKindCount>,
+    ) -> Self {
+        let mut by_status = TaskStatusCounts::default();
+        for status_count in status_counts {
+            match status_count.status {
+                TaskStatus::Pending => by_status.pending += status_count.count,
+                TaskStatus::Processing => by_status.processing += status_count.count,
+                TaskStatus::Completed => by_status.completed += status_count.count,
+            }
+        }
+
+        Self {
+            total: by_status.pending + by_status.processing + by_status.completed,
+            by_status,
+            by_worker_kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_stats_from_counts() {
+        let stats = TaskStats::from_counts(
+            vec![
+                TaskStatusCount {
+                    status: TaskStatus::Pending,
+                    count: 2,
+                },
+                TaskStatusCount {
+                    status: TaskStatus::Completed,
+                    count: 3,
+                },
+            ],
+            vec![],
+        );
+
+        assert_eq!(stats.total, 5);
+        assert_eq!(
+            stats.by_status,
+            TaskStatusCounts {
+                pending: 2,
+                processing: 0,
+                completed: 3,
+            }
+        );
+    }
+}
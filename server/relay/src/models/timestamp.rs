@@ -0,0 +1,115 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{de::Deserializer, Deserialize};
+
+/// Parses a timestamp given by a client into a UTC date and time.
+///
+/// RFC 3339 timestamps keep their offset and are converted to UTC, so
+/// `2024-01-01T12:00:00+02:00` is `2024-01-01T10:00:00`. Timestamps without
+/// an offset, like `2024-01-01T12:00:00`, are assumed to already be UTC.
+///
+/// A date without a time is rejected instead of being read as midnight, as
+/// which midnight the client meant is ambiguous.
+pub fn parse_timestamp(s: &str) -> Result<NaiveDateTime, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.naive_utc());
+    }
+
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(dt);
+        }
+    }
+
+    if NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok() {
+        return Err(format!(
+            "Ambiguous timestamp '{}': a time is required, e.g. {}T00:00:00Z",
+            s, s
+        ));
+    }
+
+    Err(format!(
+        "Invalid timestamp '{}': expected an RFC 3339 timestamp like 2024-01-01T12:00:00Z, \
+         or a date and time without an offset, which is assumed to be UTC",
+        s
+    ))
+}
+
+/// Deserializes an optional timestamp with [`parse_timestamp`].
+///
+/// # Example
+/// ```rust
+/// #[derive(Deserialize)]
+/// struct MyQuery {
+///     #[serde(default, deserialize_with = "deserialize_timestamp_opt")]
+///     since: Option<NaiveDateTime>,
+/// }
+/// ```
+pub fn deserialize_timestamp_opt<'de, D>(deserializer: D) -> Result<Option<NaiveDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(s) => parse_timestamp(&s)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").unwrap()
+    }
+
+    #[test]
+    fn test_zulu_timestamp_is_utc() {
+        assert_eq!(
+            parse_timestamp("2024-01-01T12:00:00Z"),
+            Ok(utc("2024-01-01T12:00:00"))
+        );
+        assert_eq!(
+            parse_timestamp("2024-01-01T12:00:00.250Z"),
+            Ok(utc("2024-01-01T12:00:00.250"))
+        );
+    }
+
+    #[test]
+    fn test_offset_timestamp_is_converted_to_utc() {
+        assert_eq!(
+            parse_timestamp("2024-01-01T12:00:00+02:00"),
+            Ok(utc("2024-01-01T10:00:00"))
+        );
+        assert_eq!(
+            parse_timestamp("2024-01-01T01:30:00-05:00"),
+            Ok(utc("2024-01-01T06:30:00"))
+        );
+    }
+
+    #[test]
+    fn test_naive_timestamp_is_assumed_utc() {
+        assert_eq!(
+            parse_timestamp("2024-01-01T12:00:00"),
+            Ok(utc("2024-01-01T12:00:00"))
+        );
+        assert_eq!(
+            parse_timestamp("2024-01-01 12:00:00.5"),
+            Ok(utc("2024-01-01T12:00:00.5"))
+        );
+    }
+
+    #[test]
+    fn test_date_without_time_is_ambiguous() {
+        let err = parse_timestamp("2024-01-01").unwrap_err();
+        assert!(err.starts_with("Ambiguous timestamp"), "{}", err);
+    }
+
+    #[test]
+    fn test_invalid_timestamp_is_rejected() {
+        let err = parse_timestamp("yesterday").unwrap_err();
+        assert!(err.starts_with("Invalid timestamp"), "{}", err);
+        assert!(parse_timestamp("2024-01-01T12:00:00+25:00").is_err());
+    }
+}
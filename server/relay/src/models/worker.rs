@@ -0,0 +1,27 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A worker known to the relay through the registration and heartbeats it
+/// publishes.
+///
+/// # Fields
+/// * `name` - The unique name of the worker
+/// * `worker_kind` - The kind of the worker
+/// * `task_kinds` - The task kinds the worker announced when registering
+/// * `version` - The SDK version the worker announced when registering
+/// * `last_heartbeat_at` - The timestamp of the latest heartbeat received
+/// * `registered_at` - When the worker last registered, if it ever did
+#[derive(Debug, ToSchema, Clone, Serialize, Deserialize, FromRow)]
+pub struct Worker {
+    pub name: String,
+    #[sqlx(rename = "worker_kind_name")]
+    pub worker_kind: String,
+    pub task_kinds: Vec<String>,
+    pub version: Option<String>,
+    pub last_heartbeat_at: NaiveDateTime,
+    pub registered_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
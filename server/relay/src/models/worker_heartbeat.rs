@@ -0,0 +1,124 @@
+use crate::models::{parse_schema, serde_avro_datetime, AvroSerializable};
+use apache_avro::Schema;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// WorkerHeartbeatUpdate is published periodically by workers to signal that
+/// they are alive, regardless of whether they are processing tasks.
+///
+/// # Fields
+/// * `worker_name` - The name of the worker
+/// * `worker_kind` - The kind of the worker
+/// * `heartbeat_at` - The timestamp when the heartbeat was sent
+/// * `update_type` - The type of update
+#[derive(Debug, ToSchema, Clone, Serialize, Deserialize)]
+pub struct WorkerHeartbeatUpdate {
+    pub worker_name: String,
+    pub worker_kind: String,
+    #[serde(with = "serde_avro_datetime")]
+    pub heartbeat_at: NaiveDateTime,
+    #[serde(default = "WorkerHeartbeatUpdate::update_type")]
+    pub update_type: String,
+}
+
+// ----------------------------------------------------------------------------
+// Constructors
+// ----------------------------------------------------------------------------
+
+impl WorkerHeartbeatUpdate {
+    fn update_type() -> String {
+        "Heartbeat".to_string()
+    }
+
+    pub fn validate_update_type(&self) -> Result<(), String> {
+        if self.update_type != "Heartbeat" {
+            return Err(format!(
+                "Invalid update type. Expected 'Heartbeat', got '{}'",
+                self.update_type
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for WorkerHeartbeatUpdate {
+    fn default() -> Self {
+        Self {
+            worker_name: String::new(),
+            worker_kind: String::new(),
+            heartbeat_at: NaiveDateTime::default(),
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl WorkerHeartbeatUpdate {
+    /// Creates a new WorkerHeartbeatUpdate with the specified parameters.
+    ///
+    /// # Arguments
+    /// * `worker_name` - The name of the worker
+    /// * `worker_kind` - The kind of the worker
+    /// * `heartbeat_at` - The timestamp when the heartbeat was sent
+    ///
+    /// # Returns
+    /// A new WorkerHeartbeatUpdate instance
+    pub fn new(worker_name: &str, worker_kind: &str, heartbeat_at: NaiveDateTime) -> Self {
+        Self {
+            worker_name: worker_name.to_string(),
+            worker_kind: worker_kind.to_string(),
+            heartbeat_at,
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Avro Serialization
+// ----------------------------------------------------------------------------
+
+impl AvroSerializable for WorkerHeartbeatUpdate {
+    fn try_schema() -> Result<&'static Schema, &'static str> {
+        lazy_static::lazy_static! {
+            static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                "worker_heartbeat_update.json",
+                include_str!("schemas/avro/worker_heartbeat_update.json")
+            );
+        }
+        AVRO_SCHEMA.as_ref().map_err(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn test_worker_heartbeat_update_avro_serde() {
+        let update =
+            WorkerHeartbeatUpdate::new("worker-1", "test_worker", Local::now().naive_local());
+
+        let avro_bytes = update.try_into_avro_bytes().unwrap();
+        let deserialized = WorkerHeartbeatUpdate::try_from_avro_bytes(&avro_bytes).unwrap();
+
+        assert_eq!(update.worker_name, deserialized.worker_name);
+        assert_eq!(update.worker_kind, deserialized.worker_kind);
+        assert_eq!(
+            update.heartbeat_at.and_utc().timestamp_micros(),
+            deserialized.heartbeat_at.and_utc().timestamp_micros()
+        );
+        assert_eq!(update.update_type, deserialized.update_type);
+    }
+
+    #[test]
+    fn test_worker_heartbeat_validate_update_type() {
+        let mut update =
+            WorkerHeartbeatUpdate::new("worker-1", "test_worker", Local::now().naive_local());
+        assert!(update.validate_update_type().is_ok());
+
+        update.update_type = "Wrong".to_string();
+        assert!(update.validate_update_type().is_err());
+    }
+}
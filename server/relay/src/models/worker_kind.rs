@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A worker kind known to the relay, either created through the API or by
+/// the registration of one of its workers.
+///
+/// # Fields
+/// * `name` - The unique name of the worker kind
+/// * `routing_key` - The routing key the task assignments of the kind are published with
+/// * `queue_name` - The queue the workers of the kind consume their assignments from
+#[derive(Debug, ToSchema, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct WorkerKind {
+    pub name: String,
+    pub routing_key: String,
+    pub queue_name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A worker kind to create. Unset routing settings follow the SDK
+/// conventions, like for the kinds created by a worker registration.
+///
+/// # Fields
+/// * `name` - The unique name of the worker kind
+/// * `routing_key` - The routing key the task assignments of the kind are published with
+/// * `queue_name` - The queue the workers of the kind consume their assignments from
+#[derive(Debug, ToSchema, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewWorkerKind {
+    pub name: String,
+    #[serde(default)]
+    pub routing_key: Option<String>,
+    #[serde(default)]
+    pub queue_name: Option<String>,
+}
@@ -0,0 +1,148 @@
+use crate::models::{parse_schema, serde_avro_datetime, AvroSerializable};
+use apache_avro::Schema;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// WorkerRegistrationUpdate is published by workers on startup, announcing
+/// their kind and the task kinds they can execute before any task flows.
+///
+/// # Fields
+/// * `worker_name` - The name of the worker
+/// * `worker_kind` - The kind of the worker
+/// * `task_kinds` - The task kinds the worker has handlers for
+/// * `version` - The version of the SDK the worker runs
+/// * `registered_at` - The timestamp when the worker started
+/// * `update_type` - The type of update
+#[derive(Debug, ToSchema, Clone, Serialize, Deserialize)]
+pub struct WorkerRegistrationUpdate {
+    pub worker_name: String,
+    pub worker_kind: String,
+    pub task_kinds: Vec<String>,
+    pub version: String,
+    #[serde(with = "serde_avro_datetime")]
+    pub registered_at: NaiveDateTime,
+    #[serde(default = "WorkerRegistrationUpdate::update_type")]
+    pub update_type: String,
+}
+
+// ----------------------------------------------------------------------------
+// Constructors
+// ----------------------------------------------------------------------------
+
+impl WorkerRegistrationUpdate {
+    fn update_type() -> String {
+        "Registration".to_string()
+    }
+
+    pub fn validate_update_type(&self) -> Result<(), String> {
+        if self.update_type != "Registration" {
+            return Err(format!(
+                "Invalid update type. Expected 'Registration', got '{}'",
+                self.update_type
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for WorkerRegistrationUpdate {
+    fn default() -> Self {
+        Self {
+            worker_name: String::new(),
+            worker_kind: String::new(),
+            task_kinds: Vec::new(),
+            version: String::new(),
+            registered_at: NaiveDateTime::default(),
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl WorkerRegistrationUpdate {
+    /// Creates a new WorkerRegistrationUpdate with the specified parameters.
+    ///
+    /// # Arguments
+    /// * `worker_name` - The name of the worker
+    /// * `worker_kind` - The kind of the worker
+    /// * `task_kinds` - The task kinds the worker has handlers for
+    /// * `registered_at` - The timestamp when the worker started
+    ///
+    /// # Returns
+    /// A new WorkerRegistrationUpdate instance
+    pub fn new(
+        worker_name: &str,
+        worker_kind: &str,
+        task_kinds: &[&str],
+        registered_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            worker_name: worker_name.to_string(),
+            worker_kind: worker_kind.to_string(),
+            task_kinds: task_kinds.iter().map(|kind| kind.to_string()).collect(),
+            version: "0.0.0".to_string(),
+            registered_at,
+            update_type: Self::update_type(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Avro Serialization
+// ----------------------------------------------------------------------------
+
+impl AvroSerializable for WorkerRegistrationUpdate {
+    fn try_schema() -> Result<&'static Schema, &'static str> {
+        lazy_static::lazy_static! {
+            static ref AVRO_SCHEMA: Result<Schema, String> = parse_schema(
+                "worker_registration_update.json",
+                include_str!("schemas/avro/worker_registration_update.json")
+            );
+        }
+        AVRO_SCHEMA.as_ref().map_err(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn test_worker_registration_update_avro_serde() {
+        let update = WorkerRegistrationUpdate::new(
+            "worker-1",
+            "test_worker",
+            &["task_a", "task_b"],
+            Local::now().naive_local(),
+        );
+
+        let avro_bytes = update.try_into_avro_bytes().unwrap();
+        let deserialized = WorkerRegistrationUpdate::try_from_avro_bytes(&avro_bytes).unwrap();
+
+        assert_eq!(update.worker_name, deserialized.worker_name);
+        assert_eq!(update.worker_kind, deserialized.worker_kind);
+        assert_eq!(update.task_kinds, deserialized.task_kinds);
+        assert_eq!(update.version, deserialized.version);
+        assert_eq!(
+            update.registered_at.and_utc().timestamp_micros(),
+            deserialized.registered_at.and_utc().timestamp_micros()
+        );
+        assert_eq!(update.update_type, deserialized.update_type);
+    }
+
+    #[test]
+    fn test_worker_registration_validate_update_type() {
+        let mut update = WorkerRegistrationUpdate::new(
+            "worker-1",
+            "test_worker",
+            &[],
+            Local::now().naive_local(),
+        );
+        assert!(update.validate_update_type().is_ok());
+
+        update.update_type = "Heartbeat".to_string();
+        assert!(update.validate_update_type().is_err());
+    }
+}
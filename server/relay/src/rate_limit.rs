@@ -0,0 +1,229 @@
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Endpoints never rate limited, so probes and metric scrapers keep working
+/// while clients are throttled
+const EXEMPT_PATHS: [&str; 2] = ["/health", "/metrics"];
+
+/// How many requests a client may send per window.
+///
+/// # Fields
+/// * `max_requests` - Requests accepted from a client per window
+/// * `window` - Length of a window, starting with the first request of a client
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub window: Duration,
+}
+
+/// Requests counted for a client in its current window
+struct ClientWindow {
+    started_at: Instant,
+    requests: u32,
+}
+
+struct Windows {
+    clients: HashMap<Option<IpAddr>, ClientWindow>,
+    pruned_at: Instant,
+}
+
+/// Counts the requests of every client IP in fixed windows. Clones share the
+/// same counters.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    windows: Arc<Mutex<Windows>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            windows: Arc::new(Mutex::new(Windows {
+                clients: HashMap::new(),
+                pruned_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// Counts a request of a client.
+    ///
+    /// # Arguments
+    /// * `client` - IP of the client, `None` if unknown
+    /// * `now` - When the request was received
+    ///
+    /// # Returns
+    /// The time until the client may send requests again if it is over the
+    /// limit, in which case the request isn't counted
+    fn check(&self, client: Option<IpAddr>, now: Instant) -> Result<(), Duration> {
+        let window_length = self.limit.window;
+        let mut windows = self.windows.lock().expect("Rate limiter lock poisoned");
+
+        // Forget the clients whose window ended, at most once per window
+        if now.duration_since(windows.pruned_at) >= window_length {
+            windows
+                .clients
+                .retain(|_, window| now.duration_since(window.started_at) < window_length);
+            windows.pruned_at = now;
+        }
+
+        let window = windows.clients.entry(client).or_insert(ClientWindow {
+            started_at: now,
+            requests: 0,
+        });
+        let elapsed = now.duration_since(window.started_at);
+        if elapsed >= window_length {
+            window.started_at = now;
+            window.requests = 0;
+        } else if window.requests >= self.limit.max_requests {
+            return Err(window_length - elapsed);
+        }
+
+        window.requests += 1;
+        Ok(())
+    }
+}
+
+fn is_exempt(path: &str) -> bool {
+    EXEMPT_PATHS.iter().any(|exempt| {
+        path.strip_prefix(exempt)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+async fn rate_limit(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    if is_exempt(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    // Only missing when the router isn't served over a connection, as in
+    // tests, where every request then shares the same window
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    match limiter.check(client, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            // Rounded up, so clients retrying on time are accepted
+            let retry_after_secs =
+                retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            warn!(
+                client = ?client,
+                path = %request.uri().path(),
+                retry_after_secs = retry_after_secs,
+                "Rate limit exceeded"
+            );
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                "Too many requests, retry later",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Rejects the requests of clients over the limit with 429 and a
+/// `Retry-After` header. Clients are told apart by IP, so the server must
+/// be served with connect info. Health and metrics endpoints are exempt.
+///
+/// # Arguments
+///
+/// * `router` - The router to wrap
+/// * `limit` - The limit to enforce per client
+pub fn with_rate_limit(router: Router, limit: RateLimit) -> Router {
+    router.layer(middleware::from_fn_with_state(
+        RateLimiter::new(limit),
+        rate_limit,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use axum_test::TestServer;
+
+    fn limiter(max_requests: u32) -> RateLimiter {
+        RateLimiter::new(RateLimit {
+            max_requests,
+            window: Duration::from_secs(10),
+        })
+    }
+
+    #[test]
+    fn test_check_counts_per_client_and_window() {
+        let limiter = limiter(2);
+        let start = Instant::now();
+        let client = Some(IpAddr::from([10, 0, 0, 1]));
+
+        assert_eq!(limiter.check(client, start), Ok(()));
+        assert_eq!(limiter.check(client, start), Ok(()));
+        assert_eq!(
+            limiter.check(client, start + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+
+        // Other clients have their own window
+        assert_eq!(
+            limiter.check(Some(IpAddr::from([10, 0, 0, 2])), start),
+            Ok(())
+        );
+
+        // The limit resets once the window ended
+        assert_eq!(
+            limiter.check(client, start + Duration::from_secs(10)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_is_exempt() {
+        assert!(is_exempt("/health"));
+        assert!(is_exempt("/health/ready"));
+        assert!(is_exempt("/metrics"));
+        assert!(!is_exempt("/healthz"));
+        assert!(!is_exempt("/tasks"));
+    }
+
+    #[tokio::test]
+    async fn test_exceeding_the_limit_yields_429() {
+        let router = Router::new()
+            .route("/tasks", get(|| async { "tasks" }))
+            .route("/health", get(|| async { "ok" }));
+        let server = TestServer::new(with_rate_limit(
+            router,
+            RateLimit {
+                max_requests: 2,
+                window: Duration::from_secs(60),
+            },
+        ))
+        .unwrap();
+
+        server.get("/tasks").await.assert_status_ok();
+        server.get("/tasks").await.assert_status_ok();
+
+        let response = server.get("/tasks").await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response
+            .header(header::RETRY_AFTER)
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+
+        // Health checks aren't throttled
+        server.get("/health").await.assert_status_ok();
+    }
+}
@@ -1,4 +1,45 @@
+use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::constants::{
+    DEFAULT_DB_ACQUIRE_TIMEOUT_SECS, DEFAULT_DB_MAX_CONNECTIONS, DEFAULT_DB_MIN_CONNECTIONS,
+};
+
+/// Sizing of the database connection pool, shared by the consumer, the API
+/// and the cleanup job.
+///
+/// # Fields
+/// * `max_connections` - Maximum number of open connections
+/// * `min_connections` - Number of connections kept open when idle
+/// * `acquire_timeout` - Time to wait for a free connection before failing
+///   with a pool timeout
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbPoolSettings {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+}
+
+impl Default for DbPoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: DEFAULT_DB_MAX_CONNECTIONS,
+            min_connections: DEFAULT_DB_MIN_CONNECTIONS,
+            acquire_timeout: Duration::from_secs(DEFAULT_DB_ACQUIRE_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl DbPoolSettings {
+    /// Builds the pool options to connect with.
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct PgRepositoryCore {
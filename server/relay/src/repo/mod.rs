@@ -1,5 +1,9 @@
 pub mod core;
+mod retry;
 pub mod task_repo;
+pub mod worker_repo;
 
-pub use core::PgRepositoryCore;
+pub use core::{DbPoolSettings, PgRepositoryCore};
+pub use retry::is_transient;
 pub use task_repo::*;
+pub use worker_repo::*;
@@ -0,0 +1,112 @@
+use backoff::ExponentialBackoffBuilder;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// Delay before the first retry of a failed write
+const INITIAL_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+/// Longest delay between two retries of a failed write
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+/// Time after which a failing write is given up on
+const MAX_RETRY_ELAPSED: Duration = Duration::from_secs(5);
+
+/// Whether an error is likely to go away by itself, such as a dropped
+/// connection or an exhausted pool during a database failover. Errors caused
+/// by the query or its data (constraint violations, bad encodings, ...)
+/// would fail again and are not transient.
+pub fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => true,
+        sqlx::Error::Database(e) => e.code().is_some_and(|code| {
+            // Connection exceptions (08xxx), serialization failures and
+            // deadlocks, and the server shutting down or starting up
+            code.starts_with("08")
+                || matches!(
+                    code.as_ref(),
+                    "40001" | "40P01" | "57P01" | "57P02" | "57P03"
+                )
+        }),
+        _ => false,
+    }
+}
+
+/// Runs a database write, retrying it with exponential backoff for as long
+/// as it fails with a transient error. Permanent errors are returned
+/// immediately.
+///
+/// # Arguments
+///
+/// * `operation` - Name of the write, used in logs
+/// * `write` - Creates the future performing the write
+pub async fn with_retry<T, F, Fut>(operation: &str, mut write: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let backoff = ExponentialBackoffBuilder::new()
+        .with_initial_interval(INITIAL_RETRY_INTERVAL)
+        .with_max_interval(MAX_RETRY_INTERVAL)
+        .with_max_elapsed_time(Some(MAX_RETRY_ELAPSED))
+        .build();
+
+    backoff::future::retry(backoff, || {
+        let write = write();
+        async move {
+            write.await.map_err(|e| {
+                if is_transient(&e) {
+                    warn!(operation = %operation, error = %e, "Transient database error, retrying");
+                    backoff::Error::transient(e)
+                } else {
+                    backoff::Error::permanent(e)
+                }
+            })
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&sqlx::Error::PoolTimedOut));
+        assert!(is_transient(&sqlx::Error::Io(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset
+        ))));
+        assert!(!is_transient(&sqlx::Error::RowNotFound));
+        assert!(!is_transient(&sqlx::Error::PoolClosed));
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_retries_transient_errors() {
+        let attempts = AtomicU32::new(0);
+
+        let result = with_retry("test", || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(sqlx::Error::PoolTimedOut),
+                _ => Ok(()),
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_fails_fast_on_permanent_errors() {
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), _> = with_retry("test", || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(sqlx::Error::RowNotFound)
+        })
+        .await;
+
+        assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
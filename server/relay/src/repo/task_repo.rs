@@ -1,17 +1,299 @@
-use crate::models::{Task, TaskAssignmentUpdate, TaskCompletedUpdate, TaskRunningUpdate};
-use tracing::{debug, error, info, instrument};
+use crate::models::{
+    parse_json_input, MergeUpdate, Task, TaskAcknowledgedUpdate, TaskAssignmentUpdate,
+    TaskCompletedUpdate, TaskCursor, TaskEvent, TaskInput, TaskKind, TaskKindDefaults,
+    TaskLifecycle, TaskResult, TaskRunningUpdate, TaskStatus, TaskStatusCount, TtlPolicy,
+    WorkerKindCount,
+};
+use chrono::NaiveDateTime;
+use futures::Stream;
+use serde::Serialize;
+use sqlx::PgConnection;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn};
 use uuid::Uuid;
 
+use crate::repo::retry::with_retry;
 use crate::repo::PgRepositoryCore;
 
+/// Serializes an event for the task history. Input and output data are
+/// already stored on the task, so they are left out of the payload.
+///
+/// # Arguments
+/// * `update` - The event to serialize
+/// * `data_fields` - The fields holding input or output data
+fn event_payload<T: Serialize>(
+    update: &T,
+    data_fields: &[&str],
+) -> Result<serde_json::Value, sqlx::Error> {
+    let mut payload = serde_json::to_value(update).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    if let Some(fields) = payload.as_object_mut() {
+        for field in data_fields {
+            fields.remove(*field);
+        }
+    }
+    Ok(payload)
+}
+
+/// Appends an event to the history of a task, in the transaction applying it.
+async fn record_task_event(
+    tx: &mut PgConnection,
+    task_id: &Uuid,
+    event_type: &str,
+    payload: &serde_json::Value,
+    occurred_at: NaiveDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO task_events (task_id, event_type, payload, occurred_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        task_id,
+        event_type,
+        payload,
+        occurred_at
+    )
+    .execute(tx)
+    .await?;
+    Ok(())
+}
+
+/// Merges an update into the task `id` and saves the merged task, in the
+/// transaction `tx`. The task is created if the update is the first one
+/// received, and its row stays locked until `tx` ends, so concurrent updates
+/// of a task are merged one after the other. Which update wins is decided by
+/// [`MergeUpdate`] alone.
+///
+/// # Arguments
+/// * `created_at` - When the task was created, if the update knows it
+///
+/// # Returns
+/// The merged task, or `None` if the update doesn't apply to the task
+async fn merge_update(
+    tx: &mut PgConnection,
+    id: &Uuid,
+    created_at: Option<NaiveDateTime>,
+    update: &impl MergeUpdate,
+) -> Result<Option<Task>, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO tasks (id, created_at)
+        VALUES ($1, COALESCE($2, LOCALTIMESTAMP))
+        ON CONFLICT (id) DO NOTHING
+        "#,
+        id,
+        created_at
+    )
+    .execute(&mut *tx)
+    .await?;
+    let mut task = sqlx::query_as!(
+        Task,
+        r#"SELECT
+            id,
+            task_kind_name AS task_kind,
+            input_data,
+            output_data,
+            is_error,
+            started_at,
+            completed_at,
+            scheduled_for,
+            acknowledged_at,
+            input_content_type,
+            output_content_type,
+            ttl_duration,
+            worker_kind_name AS worker_kind,
+            executed_by,
+            created_at,
+            updated_at,
+            priority,
+            otel_ctx_carrier
+        FROM tasks WHERE id = $1
+        FOR UPDATE"#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let (input_data, output_data) = (task.input_data.clone(), task.output_data.clone());
+    if !update.apply_to(&mut task) {
+        return Ok(None);
+    }
+    // Inputs and outputs can be large, so they are only written when the
+    // merge changed them
+    let changed_input = (task.input_data != input_data)
+        .then_some(task.input_data.as_ref())
+        .flatten();
+    let changed_output = (task.output_data != output_data)
+        .then_some(task.output_data.as_ref())
+        .flatten();
+    sqlx::query!(
+        r#"
+        UPDATE tasks SET
+            task_kind_name = $2,
+            worker_kind_name = $3,
+            input_data = COALESCE($4, input_data),
+            output_data = COALESCE($5, output_data),
+            is_error = $6,
+            priority = $7,
+            ttl_duration = $8,
+            executed_by = $9,
+            otel_ctx_carrier = $10,
+            started_at = $11,
+            completed_at = $12,
+            scheduled_for = $13,
+            acknowledged_at = $14,
+            input_content_type = $15,
+            output_content_type = $16
+        WHERE id = $1
+        "#,
+        task.id,
+        task.task_kind,
+        task.worker_kind,
+        changed_input,
+        changed_output,
+        task.is_error,
+        task.priority,
+        task.ttl_duration,
+        task.executed_by,
+        task.otel_ctx_carrier,
+        task.started_at,
+        task.completed_at,
+        task.scheduled_for,
+        task.acknowledged_at,
+        task.input_content_type,
+        task.output_content_type
+    )
+    .execute(&mut *tx)
+    .await?;
+    Ok(Some(task))
+}
+
+/// An assignment ready to be stored
+struct PreparedAssignment {
+    /// The assignment with its TTL resolved by the [`TtlPolicy`]
+    update: TaskAssignmentUpdate,
+    payload: serde_json::Value,
+}
+
+/// Merges an assignment into its task and appends the assignment to the
+/// task history, in the transaction `tx`.
+async fn apply_assignment(
+    tx: &mut PgConnection,
+    prepared: &PreparedAssignment,
+) -> Result<(), sqlx::Error> {
+    let update = &prepared.update;
+    match merge_update(tx, &update.id, Some(update.created_at), update).await? {
+        Some(task) => {
+            // A malformed JSON input is still stored, it just can't be searched
+            let input_json = parse_json_input(
+                task.input_content_type.as_deref(),
+                task.input_data.as_deref().unwrap_or_default(),
+            )
+            .unwrap_or_else(|e| {
+                warn!(task_id = %update.id, error = %e, "Task input is declared as JSON but doesn't parse");
+                None
+            });
+            sqlx::query!(
+                "UPDATE tasks SET input_json = $2 WHERE id = $1",
+                update.id,
+                input_json
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        None => debug!(
+            task_id = %update.id,
+            "Task already completed, skipping replayed assignment"
+        ),
+    }
+    record_task_event(
+        tx,
+        &update.id,
+        "TaskAssignment",
+        &prepared.payload,
+        update.created_at,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Merges the completion of a task and appends it to the task history, in
+/// the transaction `tx`. Returns the lifecycle of the task, or `None` if it
+/// keeps a more recent completion.
+async fn apply_completion(
+    tx: &mut PgConnection,
+    update: &TaskCompletedUpdate,
+    payload: &serde_json::Value,
+) -> Result<Option<TaskLifecycle>, sqlx::Error> {
+    let task = merge_update(tx, &update.id, None, update).await?;
+    // Recorded even when the task keeps a more recent completion
+    record_task_event(
+        tx,
+        &update.id,
+        "TaskCompleted",
+        payload,
+        update.completed_at,
+    )
+    .await?;
+    Ok(task.as_ref().and_then(TaskLifecycle::of))
+}
+
+/// Holds back the assignment of a task until `scheduled_for`, in the
+/// connection `conn`.
+async fn hold_assignment(
+    conn: &mut PgConnection,
+    id: &Uuid,
+    scheduled_for: NaiveDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO scheduled_tasks (task_id, scheduled_for)
+        VALUES ($1, $2)
+        ON CONFLICT (task_id) DO UPDATE SET scheduled_for = EXCLUDED.scheduled_for
+        "#,
+        id,
+        scheduled_for
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// The outcome of deleting or archiving the expired tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpiredTaskCleanup {
+    /// Number of tasks removed from `tasks`, whether deleted or archived
+    pub deleted: u64,
+    /// Number of chunks processed
+    pub chunks: u32,
+}
+
 #[derive(Clone, Debug)]
 pub struct TaskRepository {
     core: PgRepositoryCore,
+    ttl_policy: TtlPolicy,
 }
 
 impl TaskRepository {
     pub fn new(core: PgRepositoryCore) -> Self {
-        Self { core }
+        Self {
+            core,
+            ttl_policy: TtlPolicy::default(),
+        }
+    }
+
+    /// Sets how the TTL of assigned tasks is decided.
+    ///
+    /// # Arguments
+    /// * `ttl_policy` - The default and minimum TTL
+    pub fn with_ttl_policy(mut self, ttl_policy: TtlPolicy) -> Self {
+        self.ttl_policy = ttl_policy;
+        self
+    }
+
+    /// Checks that the database is reachable.
+    pub async fn health_check(&self) -> Result<(), sqlx::Error> {
+        self.core.health_check().await
     }
 
     // Basic CRUD
@@ -29,6 +311,10 @@ impl TaskRepository {
                 is_error, 
                 started_at, 
                 completed_at, 
+                scheduled_for,
+                acknowledged_at,
+                input_content_type,
+                output_content_type,
                 ttl_duration,
                 worker_kind_name AS worker_kind, 
                 executed_by, 
@@ -43,6 +329,248 @@ impl TaskRepository {
         .await
     }
 
+    /// Gets the tasks with any of the given IDs. IDs without a task are
+    /// skipped, and the tasks come in no particular order.
+    #[instrument(skip(self, ids), fields(count = ids.len()))]
+    pub async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Task>, sqlx::Error> {
+        debug!(count = ids.len(), "Getting tasks by ID");
+        sqlx::query_as!(
+            Task,
+            r#"SELECT
+                id,
+                task_kind_name AS task_kind,
+                input_data,
+                output_data,
+                is_error,
+                started_at,
+                completed_at,
+                scheduled_for,
+                acknowledged_at,
+                input_content_type,
+                output_content_type,
+                ttl_duration,
+                worker_kind_name AS worker_kind,
+                executed_by,
+                created_at,
+                updated_at,
+                priority,
+                otel_ctx_carrier
+            FROM tasks WHERE id = ANY($1)"#,
+            ids
+        )
+        .fetch_all(&self.core.pool)
+        .await
+    }
+
+    /// Streams tasks ordered by creation date, reading them from a database
+    /// cursor instead of loading them all in memory.
+    ///
+    /// # Arguments
+    /// * `worker_kind` - Only stream tasks of this worker kind
+    /// * `input_contains` - Only stream tasks whose JSON input contains this value
+    #[instrument(skip(self))]
+    pub fn stream_tasks<'a>(
+        &'a self,
+        worker_kind: Option<&'a str>,
+        input_contains: Option<&'a serde_json::Value>,
+    ) -> impl Stream<Item = Result<Task, sqlx::Error>> + Send + 'a {
+        debug!("Streaming tasks");
+        sqlx::query_as!(
+            Task,
+            r#"SELECT
+                id,
+                task_kind_name AS task_kind,
+                input_data,
+                output_data,
+                is_error,
+                started_at,
+                completed_at,
+                scheduled_for,
+                acknowledged_at,
+                input_content_type,
+                output_content_type,
+                ttl_duration,
+                worker_kind_name AS worker_kind,
+                executed_by,
+                created_at,
+                updated_at,
+                priority,
+                otel_ctx_carrier
+            FROM tasks
+            WHERE ($1::text IS NULL OR worker_kind_name = $1)
+                AND ($2::jsonb IS NULL OR input_json @> $2)
+            ORDER BY created_at, id"#,
+            worker_kind,
+            input_contains
+        )
+        .fetch(&self.core.pool)
+    }
+
+    /// Lists a page of tasks, newest first, using keyset pagination so deep
+    /// pages are as fast as the first one.
+    ///
+    /// # Arguments
+    /// * `after` - Only list tasks after this cursor, from the start if `None`
+    /// * `worker_kind` - Only list tasks of this worker kind
+    /// * `input_contains` - Only list tasks whose JSON input contains this value
+    /// * `limit` - The maximum number of tasks to list
+    #[instrument(skip(self))]
+    pub async fn list_tasks_after(
+        &self,
+        after: Option<TaskCursor>,
+        worker_kind: Option<&str>,
+        input_contains: Option<&serde_json::Value>,
+        limit: i64,
+    ) -> Result<Vec<Task>, sqlx::Error> {
+        debug!("Listing a page of tasks");
+        sqlx::query_as!(
+            Task,
+            r#"SELECT
+                id,
+                task_kind_name AS task_kind,
+                input_data,
+                output_data,
+                is_error,
+                started_at,
+                completed_at,
+                scheduled_for,
+                acknowledged_at,
+                input_content_type,
+                output_content_type,
+                ttl_duration,
+                worker_kind_name AS worker_kind,
+                executed_by,
+                created_at,
+                updated_at,
+                priority,
+                otel_ctx_carrier
+            FROM tasks
+            WHERE ($1::timestamp IS NULL OR (created_at, id) < ($1, $2))
+                AND ($3::text IS NULL OR worker_kind_name = $3)
+                AND ($4::jsonb IS NULL OR input_json @> $4)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $5"#,
+            after.map(|cursor| cursor.created_at),
+            after.map(|cursor| cursor.id),
+            worker_kind,
+            input_contains,
+            limit
+        )
+        .fetch_all(&self.core.pool)
+        .await
+    }
+
+    /// Lists the tasks updated after `since`, oldest update first.
+    ///
+    /// Tasks sharing the update date of the last listed task are all listed,
+    /// even past `limit`, so the date of the last task can be used as `since`
+    /// of the next call without skipping any task.
+    ///
+    /// Updates are dated when written but only visible once committed, so
+    /// tasks updated less than `lag_secs` ago aren't listed yet. Otherwise a
+    /// transaction committing after a later update was listed would never
+    /// be. Transactions taking longer than the lag can still be missed.
+    ///
+    /// Only rows still in `tasks` are listed. Tasks deleted through the API,
+    /// or deleted or archived by the cleanup job, leave no tombstone, so they
+    /// never show up as changes.
+    ///
+    /// # Arguments
+    /// * `since` - Only list tasks updated strictly after this date
+    /// * `limit` - The number of tasks to list, besides the ties of the last one
+    /// * `lag_secs` - How long ago a task must have been updated to be listed
+    #[instrument(skip(self))]
+    pub async fn list_changed_since(
+        &self,
+        since: NaiveDateTime,
+        limit: i64,
+        lag_secs: f64,
+    ) -> Result<Vec<Task>, sqlx::Error> {
+        debug!("Listing changed tasks");
+        sqlx::query_as!(
+            Task,
+            r#"SELECT
+                id,
+                task_kind_name AS task_kind,
+                input_data,
+                output_data,
+                is_error,
+                started_at,
+                completed_at,
+                scheduled_for,
+                acknowledged_at,
+                input_content_type,
+                output_content_type,
+                ttl_duration,
+                worker_kind_name AS worker_kind,
+                executed_by,
+                created_at,
+                updated_at,
+                priority,
+                otel_ctx_carrier
+            FROM tasks
+            WHERE updated_at > $1
+                AND updated_at <= LOCALTIMESTAMP - make_interval(secs => $3)
+                AND updated_at <= COALESCE(
+                    (
+                        SELECT updated_at FROM tasks
+                        WHERE updated_at > $1
+                            AND updated_at <= LOCALTIMESTAMP - make_interval(secs => $3)
+                        ORDER BY updated_at
+                        OFFSET $2::bigint - 1
+                        LIMIT 1
+                    ),
+                    'infinity'::timestamp
+                )
+            ORDER BY updated_at, id"#,
+            since,
+            limit,
+            lag_secs
+        )
+        .fetch_all(&self.core.pool)
+        .await
+    }
+
+    /// Gets the result of a task: its output and the content type the worker
+    /// declared for it.
+    #[instrument(skip(self, id), fields(id = %id))]
+    pub async fn get_task_result(&self, id: &Uuid) -> Result<Option<TaskResult>, sqlx::Error> {
+        debug!(task_id = %id, "Getting task result");
+        sqlx::query_as!(
+            TaskResult,
+            r#"SELECT completed_at, output_data, output_content_type, is_error FROM tasks WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.core.pool)
+        .await
+    }
+
+    /// Gets the input of a task and the content type its publisher declared
+    /// for it.
+    #[instrument(skip(self, id), fields(id = %id))]
+    pub async fn get_task_input(&self, id: &Uuid) -> Result<Option<TaskInput>, sqlx::Error> {
+        debug!(task_id = %id, "Getting task input");
+        sqlx::query_as!(
+            TaskInput,
+            r#"SELECT input_data, input_content_type FROM tasks WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.core.pool)
+        .await
+    }
+
+    /// Gets the status of a task, as stored in the generated `status` column.
+    #[instrument(skip(self, id), fields(id = %id))]
+    pub async fn get_task_status(&self, id: &Uuid) -> Result<Option<TaskStatus>, sqlx::Error> {
+        debug!(task_id = %id, "Getting task status");
+        sqlx::query_scalar!(
+            r#"SELECT status AS "status: TaskStatus" FROM tasks WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.core.pool)
+        .await
+    }
+
     #[instrument(skip(self))]
     pub async fn create_task(&self, task: &Task) -> Result<(), sqlx::Error> {
         sqlx::query!(
@@ -50,9 +578,10 @@ impl TaskRepository {
             INSERT INTO tasks (
                 id, task_kind_name, worker_kind_name, input_data, output_data,
                 executed_by, is_error, priority, otel_ctx_carrier, ttl_duration,
-                started_at, completed_at, created_at, updated_at
+                started_at, completed_at, scheduled_for, acknowledged_at, created_at, updated_at,
+                input_content_type, output_content_type
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
             "#,
             task.id,
             task.task_kind,
@@ -66,8 +595,12 @@ impl TaskRepository {
             task.ttl_duration,
             task.started_at,
             task.completed_at,
+            task.scheduled_for,
+            task.acknowledged_at,
             task.created_at,
-            task.updated_at
+            task.updated_at,
+            task.input_content_type,
+            task.output_content_type
         )
         .execute(&self.core.pool)
         .await?;
@@ -76,164 +609,948 @@ impl TaskRepository {
 
     // Update Consumer
 
+    /// Records the assignment of a task. Assignments can arrive after the
+    /// task completed, in which case they only fill in what the completion
+    /// couldn't. Replays for a completed task that already has its assignment
+    /// are skipped, though still recorded in its history.
     #[instrument(skip(self))]
     pub async fn update_task_from_assignment_update(
         &self,
         update: &TaskAssignmentUpdate,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            r#"
-            INSERT INTO tasks (
-                id, task_kind_name, worker_kind_name, input_data, 
-                ttl_duration, priority, created_at, otel_ctx_carrier
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            ON CONFLICT (id) DO UPDATE SET
-                task_kind_name = COALESCE(tasks.task_kind_name, EXCLUDED.task_kind_name),
-                worker_kind_name = COALESCE(tasks.worker_kind_name, EXCLUDED.worker_kind_name), 
-                input_data = COALESCE(tasks.input_data, EXCLUDED.input_data),
-                ttl_duration = COALESCE(tasks.ttl_duration, EXCLUDED.ttl_duration),
-                priority = COALESCE(tasks.priority, EXCLUDED.priority),
-                created_at = COALESCE(tasks.created_at, EXCLUDED.created_at),
-                otel_ctx_carrier = COALESCE(tasks.otel_ctx_carrier, EXCLUDED.otel_ctx_carrier)
-            "#,
-            update.id,
-            update.task_kind,
-            update.worker_kind,
-            update.input_data,
-            update.ttl_duration,
-            update.priority,
-            update.created_at,
-            serde_json::to_value(&update.otel_ctx_carrier).unwrap()
-        )
-        .execute(&self.core.pool)
+        let prepared = self.prepare_assignment(update)?;
+
+        with_retry("update_task_from_assignment_update", || async {
+            let mut tx = self.core.pool.begin().await?;
+            apply_assignment(&mut tx, &prepared).await?;
+            tx.commit().await
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Records the assignments of several tasks in a single transaction, so
+    /// either all of them are recorded or none is. Each is recorded like
+    /// [`Self::update_task_from_assignment_update`] does, and the assignments
+    /// scheduled for later are held until the scheduled task job publishes
+    /// them.
+    #[instrument(skip(self, updates), fields(count = updates.len()))]
+    pub async fn create_tasks_from_assignments(
+        &self,
+        updates: &[TaskAssignmentUpdate],
+    ) -> Result<(), sqlx::Error> {
+        let prepared = updates
+            .iter()
+            .map(|update| self.prepare_assignment(update))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        with_retry("create_tasks_from_assignments", || async {
+            let mut tx = self.core.pool.begin().await?;
+            for prepared in &prepared {
+                apply_assignment(&mut tx, prepared).await?;
+                if let Some(scheduled_for) = prepared.update.held_until() {
+                    hold_assignment(&mut tx, &prepared.update.id, scheduled_for).await?;
+                }
+            }
+            tx.commit().await
+        })
         .await?;
         Ok(())
     }
 
+    /// Resolves the TTL of an assignment and serializes it for the task
+    /// history.
+    fn prepare_assignment(
+        &self,
+        update: &TaskAssignmentUpdate,
+    ) -> Result<PreparedAssignment, sqlx::Error> {
+        let ttl_duration = self.ttl_policy.resolve(update.ttl_duration);
+        if ttl_duration != update.ttl_duration {
+            debug!(
+                task_id = %update.id,
+                ttl_duration = update.ttl_duration,
+                resolved_ttl_duration = ttl_duration,
+                "Applied TTL policy to task assignment"
+            );
+        }
+
+        Ok(PreparedAssignment {
+            payload: event_payload(update, &["input_data"])?,
+            update: TaskAssignmentUpdate {
+                ttl_duration,
+                ..update.clone()
+            },
+        })
+    }
+
+    /// Records the completion of a task. Completed events can be delivered
+    /// more than once and out of order, so the most recent completion wins and
+    /// an error beats a success reported at the same time.
+    ///
+    /// # Returns
+    /// The lifecycle of the task, or `None` if it keeps a more recent
+    /// completion
     #[instrument(skip(self))]
     pub async fn update_task_from_completed_update(
         &self,
         update: &TaskCompletedUpdate,
+    ) -> Result<Option<TaskLifecycle>, sqlx::Error> {
+        let payload = event_payload(update, &["output_data"])?;
+
+        with_retry("update_task_from_completed_update", || async {
+            let mut tx = self.core.pool.begin().await?;
+            let lifecycle = apply_completion(&mut tx, update, &payload).await?;
+            tx.commit().await?;
+            Ok(lifecycle)
+        })
+        .await
+    }
+
+    /// Records the completions of several tasks in a single transaction, so
+    /// either all of them are recorded or none is. Each is recorded like
+    /// [`Self::update_task_from_completed_update`] does.
+    ///
+    /// # Returns
+    /// The lifecycles of the tasks, leaving out the ones that keep a more
+    /// recent completion
+    #[instrument(skip(self, updates), fields(count = updates.len()))]
+    pub async fn update_tasks_from_completed_updates(
+        &self,
+        updates: &[TaskCompletedUpdate],
+    ) -> Result<Vec<TaskLifecycle>, sqlx::Error> {
+        if updates.is_empty() {
+            return Ok(Vec::new());
+        }
+        let payloads = updates
+            .iter()
+            .map(|update| event_payload(update, &["output_data"]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        with_retry("update_tasks_from_completed_updates", || async {
+            let mut tx = self.core.pool.begin().await?;
+            let mut lifecycles = Vec::with_capacity(updates.len());
+            for (update, payload) in updates.iter().zip(&payloads) {
+                lifecycles.extend(apply_completion(&mut tx, update, payload).await?);
+            }
+            tx.commit().await?;
+            Ok(lifecycles)
+        })
+        .await
+    }
+
+    /// Records a completion decided by the relay rather than reported by a
+    /// worker, such as the failure of a task whose worker went silent. It is
+    /// only applied to tasks that haven't completed yet, so a completion the
+    /// worker reported in the meantime is never overwritten.
+    ///
+    /// # Returns
+    /// Whether the completion was applied, `false` if the task already
+    /// completed or doesn't exist
+    #[instrument(skip(self))]
+    pub async fn complete_unfinished_task(
+        &self,
+        update: &TaskCompletedUpdate,
+    ) -> Result<bool, sqlx::Error> {
+        let payload = event_payload(update, &["output_data"])?;
+
+        with_retry("complete_unfinished_task", || async {
+            let mut tx = self.core.pool.begin().await?;
+            let result = sqlx::query!(
+                r#"
+                UPDATE tasks SET
+                    completed_at = $2,
+                    output_data = $3,
+                    is_error = $4,
+                    output_content_type = $5
+                WHERE id = $1 AND completed_at IS NULL
+                "#,
+                update.id,
+                update.completed_at,
+                update.output_data,
+                update.is_error,
+                update.output_content_type
+            )
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Ok(false);
+            }
+            record_task_event(
+                &mut tx,
+                &update.id,
+                "TaskCompleted",
+                &payload,
+                update.completed_at,
+            )
+            .await?;
+            tx.commit().await?;
+            Ok(true)
+        })
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn update_task_from_running_update(
+        &self,
+        update: &TaskRunningUpdate,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            r#"
-            INSERT INTO tasks (
-                id, completed_at, output_data, is_error
+        let payload = event_payload(update, &[])?;
+
+        with_retry("update_task_from_running_update", || async {
+            let mut tx = self.core.pool.begin().await?;
+            merge_update(&mut tx, &update.id, None, update).await?;
+            record_task_event(
+                &mut tx,
+                &update.id,
+                "TaskRunning",
+                &payload,
+                update.started_at,
             )
-            VALUES ($1, $2, $3, $4)
-            ON CONFLICT (id) DO UPDATE SET
-                completed_at = COALESCE(tasks.completed_at, EXCLUDED.completed_at),
-                output_data = COALESCE(tasks.output_data, EXCLUDED.output_data),
-                is_error = COALESCE(tasks.is_error, EXCLUDED.is_error)
-            "#,
-            update.id,
-            update.completed_at,
-            update.output_data,
-            update.is_error
-        )
-        .execute(&self.core.pool)
+            .await?;
+            tx.commit().await
+        })
         .await?;
         Ok(())
     }
 
+    /// Records that a worker received a task, before starting it. The first
+    /// acknowledgement is kept.
     #[instrument(skip(self))]
-    pub async fn update_task_from_running_update(
+    pub async fn update_task_from_acknowledged_update(
         &self,
-        update: &TaskRunningUpdate,
+        update: &TaskAcknowledgedUpdate,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            r#"
-            INSERT INTO tasks (
-                id, started_at, executed_by
+        let payload = event_payload(update, &[])?;
+
+        with_retry("update_task_from_acknowledged_update", || async {
+            let mut tx = self.core.pool.begin().await?;
+            merge_update(&mut tx, &update.id, None, update).await?;
+            record_task_event(
+                &mut tx,
+                &update.id,
+                "TaskAcknowledged",
+                &payload,
+                update.acknowledged_at,
             )
-            VALUES ($1, $2, $3)
-            ON CONFLICT (id) DO UPDATE SET
-                started_at = COALESCE(tasks.started_at, EXCLUDED.started_at),
-                executed_by = COALESCE(tasks.executed_by, EXCLUDED.executed_by)
-            "#,
-            update.id,
-            update.started_at,
-            update.executed_by
-        )
-        .execute(&self.core.pool)
+            .await?;
+            tx.commit().await
+        })
         .await?;
         Ok(())
     }
 
-    // Cleanup
+    /// Puts a task that was started back in `Pending`, forgetting the worker
+    /// that acknowledged and executed it, so it can be assigned again.
+    /// Completed tasks are left untouched.
+    ///
+    /// # Returns
+    /// The requeued task, or `None` if it doesn't exist or already completed
+    #[instrument(skip(self, id), fields(id = %id))]
+    pub async fn requeue_task(&self, id: &Uuid) -> Result<Option<Task>, sqlx::Error> {
+        debug!(task_id = %id, "Requeuing task");
+        with_retry("requeue_task", || async {
+            let mut tx = self.core.pool.begin().await?;
+            let Some(executed_by) = sqlx::query_scalar!(
+                r#"SELECT executed_by FROM tasks
+                WHERE id = $1 AND completed_at IS NULL
+                FOR UPDATE"#,
+                id
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            else {
+                return Ok(None);
+            };
 
+            let task = sqlx::query_as!(
+                Task,
+                r#"UPDATE tasks SET
+                    started_at = NULL,
+                    acknowledged_at = NULL,
+                    executed_by = NULL,
+                    updated_at = NOW()
+                WHERE id = $1
+                RETURNING
+                    id,
+                    task_kind_name AS task_kind,
+                    input_data,
+                    output_data,
+                    is_error,
+                    started_at,
+                    completed_at,
+                    scheduled_for,
+                    acknowledged_at,
+                    input_content_type,
+                    output_content_type,
+                    ttl_duration,
+                    worker_kind_name AS worker_kind,
+                    executed_by,
+                    created_at,
+                    updated_at,
+                    priority,
+                    otel_ctx_carrier"#,
+                id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            let payload = serde_json::json!({ "id": id, "executed_by": executed_by });
+            record_task_event(&mut tx, id, "TaskRequeued", &payload, task.updated_at).await?;
+            tx.commit().await?;
+            Ok(Some(task))
+        })
+        .await
+    }
+
+    /// Gets the started tasks whose worker's latest heartbeat is older than
+    /// `stale_before`. Tasks of workers that never sent a heartbeat are left
+    /// alone, since there is no telling whether those workers are alive.
     #[instrument(skip(self))]
-    pub async fn delete_expired_tasks(&self) -> Result<u64, sqlx::Error> {
-        info!("Cleaning up expired tasks");
-        let now = chrono::Utc::now().naive_utc();
+    pub async fn find_tasks_of_stale_workers(
+        &self,
+        stale_before: NaiveDateTime,
+    ) -> Result<Vec<Task>, sqlx::Error> {
+        debug!(stale_before = %stale_before, "Finding tasks of stale workers");
+        sqlx::query_as!(
+            Task,
+            r#"SELECT
+                tasks.id,
+                tasks.task_kind_name AS task_kind,
+                tasks.input_data,
+                tasks.output_data,
+                tasks.is_error,
+                tasks.started_at,
+                tasks.completed_at,
+                tasks.scheduled_for,
+                tasks.acknowledged_at,
+                tasks.input_content_type,
+                tasks.output_content_type,
+                tasks.ttl_duration,
+                tasks.worker_kind_name AS worker_kind,
+                tasks.executed_by,
+                tasks.created_at,
+                tasks.updated_at,
+                tasks.priority,
+                tasks.otel_ctx_carrier
+            FROM tasks
+            JOIN workers ON workers.name = tasks.executed_by
+            WHERE tasks.status = 'Processing'
+                AND workers.last_heartbeat_at < $1
+            ORDER BY tasks.started_at"#,
+            stale_before
+        )
+        .fetch_all(&self.core.pool)
+        .await
+    }
 
-        let result = match sqlx::query!(
-            r#"DELETE FROM tasks
-                WHERE completed_at IS NOT NULL AND completed_at + interval '1 second' * ttl_duration < $1
-            "#,
+    /// Gets the tasks whose assignment is held until a time that has come,
+    /// the longest overdue first.
+    ///
+    /// # Arguments
+    /// * `now` - The current time
+    /// * `limit` - The maximum number of tasks to get
+    #[instrument(skip(self))]
+    pub async fn find_due_scheduled_tasks(
+        &self,
+        now: NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<Task>, sqlx::Error> {
+        debug!(now = %now, "Finding due scheduled tasks");
+        sqlx::query_as!(
+            Task,
+            r#"SELECT
+                tasks.id,
+                tasks.task_kind_name AS task_kind,
+                tasks.input_data,
+                tasks.output_data,
+                tasks.is_error,
+                tasks.started_at,
+                tasks.completed_at,
+                tasks.scheduled_for,
+                tasks.acknowledged_at,
+                tasks.input_content_type,
+                tasks.output_content_type,
+                tasks.ttl_duration,
+                tasks.worker_kind_name AS worker_kind,
+                tasks.executed_by,
+                tasks.created_at,
+                tasks.updated_at,
+                tasks.priority,
+                tasks.otel_ctx_carrier
+            FROM scheduled_tasks
+            JOIN tasks ON tasks.id = scheduled_tasks.task_id
+            WHERE scheduled_tasks.scheduled_for <= $1
+            ORDER BY scheduled_tasks.scheduled_for
+            LIMIT $2"#,
             now,
+            limit
         )
-        .execute(&self.core.pool)
+        .fetch_all(&self.core.pool)
         .await
-        {
-            Ok(result) => result,
-            Err(e) => {
-                error!(error = %e, "Failed to delete expired tasks");
-                return Err(e);
-            }
-        };
+    }
 
-        let count = result.rows_affected();
-        info!(deleted_count = count, "Deleted expired tasks");
-        Ok(count)
+    /// Stops holding the assignment of a scheduled task, before publishing it.
+    ///
+    /// # Returns
+    /// Whether the assignment was still held, `false` if another relay
+    /// already released it
+    #[instrument(skip(self, id), fields(id = %id))]
+    pub async fn release_scheduled_task(&self, id: &Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM scheduled_tasks WHERE task_id = $1", id)
+            .execute(&self.core.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+    /// Holds the assignment of a task until `scheduled_for`, such as when
+    /// publishing it failed after it was released.
+    #[instrument(skip(self, id), fields(id = %id))]
+    pub async fn hold_scheduled_task(
+        &self,
+        id: &Uuid,
+        scheduled_for: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        let mut conn = self.core.pool.acquire().await?;
+        hold_assignment(&mut conn, id, scheduled_for).await
+    }
 
-    use chrono::Local;
-    use sqlx::PgPool;
-    use uuid::Uuid;
+    /// Gets the events applied to a task, in the order they happened.
+    #[instrument(skip(self, id), fields(id = %id))]
+    pub async fn get_task_history(&self, id: &Uuid) -> Result<Vec<TaskEvent>, sqlx::Error> {
+        debug!(task_id = %id, "Getting task history");
+        sqlx::query_as!(
+            TaskEvent,
+            r#"SELECT task_id, event_type, payload, occurred_at, recorded_at
+            FROM task_events
+            WHERE task_id = $1
+            ORDER BY occurred_at, id"#,
+            id
+        )
+        .fetch_all(&self.core.pool)
+        .await
+    }
 
-    use super::*;
-    use crate::repo::PgRepositoryCore;
-    use crate::testing::test::init_test_logger;
+    // Stats
 
-    // This runs before any test in this module
-    #[ctor::ctor]
-    fn init() {
-        init_test_logger();
+    /// Counts tasks per status, optionally restricted to tasks created within
+    /// `[since, until)`.
+    #[instrument(skip(self))]
+    pub async fn count_tasks_by_status(
+        &self,
+        since: Option<NaiveDateTime>,
+        until: Option<NaiveDateTime>,
+    ) -> Result<Vec<TaskStatusCount>, sqlx::Error> {
+        debug!("Counting tasks by status");
+        sqlx::query_as!(
+            TaskStatusCount,
+            r#"SELECT
+                status AS "status: TaskStatus",
+                COUNT(*) AS "count!"
+            FROM tasks
+            WHERE ($1::timestamp IS NULL OR created_at >= $1)
+                AND ($2::timestamp IS NULL OR created_at < $2)
+            GROUP BY status"#,
+            since,
+            until
+        )
+        .fetch_all(&self.core.pool)
+        .await
     }
 
-    /// Creates a test task
-    fn get_test_task() -> Task {
-        Task::new("TaskKindName", "WorkerKindName", 0, 0)
-            .with_input_data(vec![1, 2, 3])
-            .with_output_data(vec![4, 5, 6])
-            .with_error(false)
+    /// Counts tasks per worker kind, optionally restricted to tasks created
+    /// within `[since, until)`.
+    #[instrument(skip(self))]
+    pub async fn count_tasks_by_worker_kind(
+        &self,
+        since: Option<NaiveDateTime>,
+        until: Option<NaiveDateTime>,
+    ) -> Result<Vec<WorkerKindCount>, sqlx::Error> {
+        debug!("Counting tasks by worker kind");
+        sqlx::query_as!(
+            WorkerKindCount,
+            r#"SELECT
+                worker_kind_name AS worker_kind,
+                COUNT(*) AS "count!"
+            FROM tasks
+            WHERE ($1::timestamp IS NULL OR created_at >= $1)
+                AND ($2::timestamp IS NULL OR created_at < $2)
+            GROUP BY worker_kind_name
+            ORDER BY worker_kind_name"#,
+            since,
+            until
+        )
+        .fetch_all(&self.core.pool)
+        .await
     }
 
-    /// Creates a task and then retrieves it by id
-    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn create_and_get_task(pool: PgPool) {
-        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+    // Task kinds
 
-        let task = get_test_task();
-
-        repo.create_task(&task).await.unwrap();
-        let new_task = repo.get_task_by_id(&task.id).await.unwrap().unwrap();
+    #[instrument(skip(self))]
+    pub async fn list_task_kinds(&self) -> Result<Vec<TaskKind>, sqlx::Error> {
+        debug!("Listing task kinds");
+        sqlx::query_as!(
+            TaskKind,
+            r#"SELECT
+                tasks.task_kind_name AS "name!",
+                (ARRAY_AGG(tasks.worker_kind_name ORDER BY tasks.created_at DESC))[1] AS "worker_kind!",
+                MIN(tasks.created_at) AS "created_at!",
+                task_kinds.default_priority,
+                task_kinds.default_ttl_duration,
+                task_kinds.input_schema
+            FROM tasks
+            LEFT JOIN task_kinds ON task_kinds.name = tasks.task_kind_name
+            WHERE tasks.task_kind_name IS NOT NULL AND tasks.worker_kind_name IS NOT NULL
+            GROUP BY tasks.task_kind_name, task_kinds.name
+            ORDER BY tasks.task_kind_name"#
+        )
+        .fetch_all(&self.core.pool)
+        .await
+    }
 
-        assert_eq!(new_task.id, task.id, "Created Task ID should match");
-        let retrieved = repo.get_task_by_id(&task.id).await.unwrap().unwrap();
-        assert_eq!(retrieved.id, task.id, "Retrieved Task ID should match");
+    #[instrument(skip(self))]
+    pub async fn get_task_kind(&self, name: &str) -> Result<Option<TaskKind>, sqlx::Error> {
+        debug!(task_kind = %name, "Getting task kind");
+        sqlx::query_as!(
+            TaskKind,
+            r#"SELECT
+                tasks.task_kind_name AS "name!",
+                (ARRAY_AGG(tasks.worker_kind_name ORDER BY tasks.created_at DESC))[1] AS "worker_kind!",
+                MIN(tasks.created_at) AS "created_at!",
+                task_kinds.default_priority,
+                task_kinds.default_ttl_duration,
+                task_kinds.input_schema
+            FROM tasks
+            LEFT JOIN task_kinds ON task_kinds.name = tasks.task_kind_name
+            WHERE tasks.task_kind_name = $1 AND tasks.worker_kind_name IS NOT NULL
+            GROUP BY tasks.task_kind_name, task_kinds.name"#,
+            name
+        )
+        .fetch_optional(&self.core.pool)
+        .await
     }
 
-    /// Tests task updating logic
-    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn test_task_assignment_update(pool: PgPool) {
+    /// Sets the defaults of a task kind, which doesn't need to have any task
+    /// yet. Unset defaults are cleared.
+    #[instrument(skip(self))]
+    pub async fn set_task_kind_defaults(
+        &self,
+        name: &str,
+        defaults: &TaskKindDefaults,
+    ) -> Result<(), sqlx::Error> {
+        debug!(task_kind = %name, "Setting task kind defaults");
+        sqlx::query!(
+            r#"
+            INSERT INTO task_kinds (name, default_priority, default_ttl_duration)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name) DO UPDATE SET
+                default_priority = EXCLUDED.default_priority,
+                default_ttl_duration = EXCLUDED.default_ttl_duration,
+                updated_at = NOW()
+            "#,
+            name,
+            defaults.default_priority,
+            defaults.default_ttl_duration
+        )
+        .execute(&self.core.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the defaults of the given task kinds. Kinds without defaults are
+    /// left out.
+    #[instrument(skip(self, names), fields(count = names.len()))]
+    pub async fn get_task_kind_defaults(
+        &self,
+        names: &[String],
+    ) -> Result<HashMap<String, TaskKindDefaults>, sqlx::Error> {
+        debug!(count = names.len(), "Getting task kind defaults");
+        let rows = sqlx::query!(
+            r#"SELECT name, default_priority, default_ttl_duration
+            FROM task_kinds
+            WHERE name = ANY($1)"#,
+            names
+        )
+        .fetch_all(&self.core.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.name,
+                    TaskKindDefaults {
+                        default_priority: row.default_priority,
+                        default_ttl_duration: row.default_ttl_duration,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Sets the JSON Schema of the inputs of a task kind, which doesn't need
+    /// to have any task yet. `None` clears it.
+    #[instrument(skip(self, schema))]
+    pub async fn set_task_kind_input_schema(
+        &self,
+        name: &str,
+        schema: Option<&serde_json::Value>,
+    ) -> Result<(), sqlx::Error> {
+        debug!(task_kind = %name, "Setting task kind input schema");
+        sqlx::query!(
+            r#"
+            INSERT INTO task_kinds (name, input_schema)
+            VALUES ($1, $2)
+            ON CONFLICT (name) DO UPDATE SET
+                input_schema = EXCLUDED.input_schema,
+                updated_at = NOW()
+            "#,
+            name,
+            schema
+        )
+        .execute(&self.core.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the input schemas of the given task kinds. Kinds without a
+    /// schema are left out.
+    #[instrument(skip(self, names), fields(count = names.len()))]
+    pub async fn get_task_kind_input_schemas(
+        &self,
+        names: &[String],
+    ) -> Result<HashMap<String, serde_json::Value>, sqlx::Error> {
+        debug!(count = names.len(), "Getting task kind input schemas");
+        let rows = sqlx::query!(
+            r#"SELECT name, input_schema AS "input_schema!"
+            FROM task_kinds
+            WHERE name = ANY($1) AND input_schema IS NOT NULL"#,
+            names
+        )
+        .fetch_all(&self.core.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.name, row.input_schema))
+            .collect())
+    }
+
+    // Cleanup
+
+    /// Deletes the tasks whose TTL expired, in chunks of at most
+    /// `chunk_size` tasks. Every chunk is its own statement, committed before
+    /// the next one starts, so ingest isn't blocked behind one long delete.
+    /// Tasks locked by another transaction are skipped until the next run.
+    ///
+    /// # Arguments
+    /// * `chunk_size` - Most tasks deleted per statement
+    /// * `chunk_delay` - Pause between two chunks
+    #[instrument(skip(self))]
+    pub async fn delete_expired_tasks(
+        &self,
+        chunk_size: i64,
+        chunk_delay: Duration,
+    ) -> Result<ExpiredTaskCleanup, sqlx::Error> {
+        info!("Cleaning up expired tasks");
+        self.remove_expired_tasks(chunk_size, chunk_delay, false)
+            .await
+    }
+
+    /// Moves the tasks whose TTL expired to `archived_tasks`, chunk by chunk
+    /// like [`Self::delete_expired_tasks`]. Each chunk is copied and deleted
+    /// in one transaction, so a task is never lost nor left in both tables.
+    /// The event history of archived tasks is deleted with them.
+    ///
+    /// # Arguments
+    /// * `chunk_size` - Most tasks archived per transaction
+    /// * `chunk_delay` - Pause between two chunks
+    #[instrument(skip(self))]
+    pub async fn archive_expired_tasks(
+        &self,
+        chunk_size: i64,
+        chunk_delay: Duration,
+    ) -> Result<ExpiredTaskCleanup, sqlx::Error> {
+        info!("Archiving expired tasks");
+        self.remove_expired_tasks(chunk_size, chunk_delay, true)
+            .await
+    }
+
+    /// Removes the expired tasks from `tasks` in chunks, stopping at the
+    /// first partial chunk.
+    async fn remove_expired_tasks(
+        &self,
+        chunk_size: i64,
+        chunk_delay: Duration,
+        archive: bool,
+    ) -> Result<ExpiredTaskCleanup, sqlx::Error> {
+        let now = chrono::Utc::now().naive_utc();
+
+        let mut count = 0;
+        let mut chunks = 0;
+        loop {
+            if chunks > 0 {
+                tokio::time::sleep(chunk_delay).await;
+            }
+
+            let result = if archive {
+                self.archive_expired_chunk(now, chunk_size).await
+            } else {
+                self.delete_expired_chunk(now, chunk_size).await
+            };
+            let removed = match result {
+                Ok(removed) => removed,
+                Err(e) => {
+                    error!(error = %e, deleted_count = count, archive, "Failed to remove expired tasks");
+                    return Err(e);
+                }
+            };
+
+            count += removed;
+            chunks += 1;
+            debug!(
+                deleted_count = removed,
+                chunk = chunks,
+                archive,
+                "Removed chunk of expired tasks"
+            );
+
+            // A partial chunk means nothing unlocked is left to remove
+            if removed < chunk_size as u64 {
+                break;
+            }
+        }
+
+        info!(
+            deleted_count = count,
+            chunks = chunks,
+            archive,
+            "Removed expired tasks"
+        );
+        Ok(ExpiredTaskCleanup {
+            deleted: count,
+            chunks,
+        })
+    }
+
+    /// Deletes a chunk of expired tasks.
+    ///
+    /// # Returns
+    /// The number of tasks deleted
+    async fn delete_expired_chunk(
+        &self,
+        now: NaiveDateTime,
+        chunk_size: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"DELETE FROM tasks
+                WHERE id IN (
+                    SELECT id FROM tasks
+                    WHERE completed_at IS NOT NULL AND completed_at + interval '1 second' * ttl_duration < $1
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+            "#,
+            now,
+            chunk_size,
+        )
+        .execute(&self.core.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Copies a chunk of expired tasks to `archived_tasks`, then deletes
+    /// them, in one transaction.
+    ///
+    /// # Returns
+    /// The number of tasks archived
+    async fn archive_expired_chunk(
+        &self,
+        now: NaiveDateTime,
+        chunk_size: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.core.pool.begin().await?;
+
+        let ids = sqlx::query_scalar!(
+            r#"SELECT id FROM tasks
+                WHERE completed_at IS NOT NULL AND completed_at + interval '1 second' * ttl_duration < $1
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            "#,
+            now,
+            chunk_size,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        sqlx::query!(
+            r#"INSERT INTO archived_tasks (
+                id, task_kind_name, worker_kind_name, input_data, output_data,
+                executed_by, is_error, priority, otel_ctx_carrier, ttl_duration,
+                started_at, completed_at, created_at, updated_at, status,
+                output_content_type, input_json, input_content_type, scheduled_for,
+                acknowledged_at
+            )
+            SELECT
+                id, task_kind_name, worker_kind_name, input_data, output_data,
+                executed_by, is_error, priority, otel_ctx_carrier, ttl_duration,
+                started_at, completed_at, created_at, updated_at, status,
+                output_content_type, input_json, input_content_type, scheduled_for,
+                acknowledged_at
+            FROM tasks WHERE id = ANY($1)"#,
+            &ids,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query!("DELETE FROM tasks WHERE id = ANY($1)", &ids)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Gets an archived task by its ID, the latest archived if the ID was
+    /// archived more than once.
+    #[instrument(skip(self, id), fields(id = %id))]
+    pub async fn get_archived_task_by_id(&self, id: &Uuid) -> Result<Option<Task>, sqlx::Error> {
+        debug!(task_id = %id, "Getting archived task by ID");
+        sqlx::query_as!(
+            Task,
+            r#"SELECT
+                id,
+                task_kind_name AS task_kind,
+                input_data,
+                output_data,
+                is_error,
+                started_at,
+                completed_at,
+                scheduled_for,
+                acknowledged_at,
+                input_content_type,
+                output_content_type,
+                ttl_duration,
+                worker_kind_name AS worker_kind,
+                executed_by,
+                created_at,
+                updated_at,
+                priority,
+                otel_ctx_carrier
+            FROM archived_tasks WHERE id = $1
+            ORDER BY archived_at DESC
+            LIMIT 1"#,
+            id
+        )
+        .fetch_optional(&self.core.pool)
+        .await
+    }
+
+    /// Deletes the tasks matching every given filter. Filters left as `None`
+    /// match all tasks, so callers are expected to set at least one.
+    ///
+    /// # Arguments
+    /// * `worker_kind` - Only delete tasks of this worker kind
+    /// * `before` - Only delete tasks created before this time
+    #[instrument(skip(self))]
+    pub async fn delete_tasks_by_filter(
+        &self,
+        worker_kind: Option<&str>,
+        before: Option<NaiveDateTime>,
+    ) -> Result<u64, sqlx::Error> {
+        let result = match sqlx::query!(
+            r#"DELETE FROM tasks
+                WHERE ($1::text IS NULL OR worker_kind_name = $1)
+                AND ($2::timestamp IS NULL OR created_at < $2)
+            "#,
+            worker_kind,
+            before,
+        )
+        .execute(&self.core.pool)
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                error!(error = %e, "Failed to delete tasks by filter");
+                return Err(e);
+            }
+        };
+
+        let count = result.rows_affected();
+        info!(deleted_count = count, "Deleted tasks by filter");
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Local;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::repo::PgRepositoryCore;
+    use crate::testing::test::init_test_logger;
+
+    // This runs before any test in this module
+    #[ctor::ctor]
+    fn init() {
+        init_test_logger();
+    }
+
+    /// Creates a test task
+    fn get_test_task() -> Task {
+        Task::new("TaskKindName", "WorkerKindName", 0, 0)
+            .with_input_data(vec![1, 2, 3])
+            .with_output_data(vec![4, 5, 6])
+            .with_error(false)
+    }
+
+    /// Creates a task and then retrieves it by id
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn create_and_get_task(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+
+        let task = get_test_task();
+
+        repo.create_task(&task).await.unwrap();
+        let new_task = repo.get_task_by_id(&task.id).await.unwrap().unwrap();
+
+        assert_eq!(new_task.id, task.id, "Created Task ID should match");
+        let retrieved = repo.get_task_by_id(&task.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.id, task.id, "Retrieved Task ID should match");
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_find_by_ids(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let first = get_test_task();
+        let second = get_test_task();
+        repo.create_task(&first).await.unwrap();
+        repo.create_task(&second).await.unwrap();
+
+        let mut found: Vec<Uuid> = repo
+            .find_by_ids(&[first.id, second.id, Uuid::new_v4()])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|task| task.id)
+            .collect();
+        found.sort();
+        let mut expected = vec![first.id, second.id];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        assert!(repo.find_by_ids(&[]).await.unwrap().is_empty());
+    }
+
+    /// Tests task updating logic
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_task_assignment_update(pool: PgPool) {
         let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
         let id = Uuid::new_v4();
         let now = Local::now().naive_local();
@@ -247,6 +1564,8 @@ mod tests {
             priority: 1,
             ttl_duration: 60,
             otel_ctx_carrier: HashMap::new(),
+            input_content_type: None,
+            scheduled_for: None,
             update_type: "Assignment".to_string(),
         };
 
@@ -282,16 +1601,54 @@ mod tests {
         assert_eq!(task.executed_by, Some("worker-1".to_string()));
     }
 
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_changed_since_waits_for_updates_to_settle(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let mut task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+        task.updated_at = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc();
+        repo.create_task(&task).await.unwrap();
+
+        let changed = repo
+            .list_changed_since(chrono::DateTime::UNIX_EPOCH.naive_utc(), 10, 60.0)
+            .await
+            .unwrap();
+        assert_eq!(changed.len(), 1);
+        let since = changed[0].updated_at;
+
+        repo.update_task_from_running_update(&TaskRunningUpdate::new(
+            task.id,
+            Local::now().naive_local(),
+            "worker-1".to_string(),
+        ))
+        .await
+        .unwrap();
+
+        // The update is only listed once it is older than the lag
+        assert!(repo
+            .list_changed_since(since, 10, 60.0)
+            .await
+            .unwrap()
+            .is_empty());
+        let changed = repo.list_changed_since(since, 10, 0.0).await.unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].executed_by.as_deref(), Some("worker-1"));
+        assert!(changed[0].updated_at > since);
+    }
+
     #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
     async fn test_task_completed_update(pool: PgPool) {
         let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
         let id = Uuid::new_v4();
         let now = Local::now().naive_local();
 
-        let update = TaskCompletedUpdate::new(id, now, vec![4, 5, 6], 0);
+        let update = TaskCompletedUpdate::new(id, now, vec![4, 5, 6], false);
 
-        repo.update_task_from_completed_update(&update)
+        let lifecycle = repo
+            .update_task_from_completed_update(&update)
             .await
+            .unwrap()
             .unwrap();
 
         let task = repo.get_task_by_id(&id).await.unwrap().unwrap();
@@ -300,86 +1657,891 @@ mod tests {
             now.and_utc().timestamp_micros()
         );
         assert_eq!(task.output_data, Some(vec![4, 5, 6]));
-        assert_eq!(task.is_error, Some(0));
+        assert_eq!(task.is_error, Some(false));
+
+        // The stored lifecycle is returned, so the task isn't read back
+        assert_eq!(lifecycle.id, id);
+        assert_eq!(lifecycle.created_at, task.created_at);
+        assert_eq!(Some(lifecycle.completed_at), task.completed_at);
+
+        // A stale completion is recorded but leaves the task as it is
+        let stale =
+            TaskCompletedUpdate::new(id, now - chrono::Duration::seconds(1), vec![7], false);
+        assert_eq!(
+            repo.update_task_from_completed_update(&stale)
+                .await
+                .unwrap(),
+            None
+        );
     }
 
     #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn test_task_full_lifecycle(pool: PgPool) {
+    async fn test_get_task_result(pool: PgPool) {
         let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
         let id = Uuid::new_v4();
-        let now = Local::now().naive_local();
 
-        // 1. Assignment
-        let assignment = TaskAssignmentUpdate {
-            id,
-            task_kind: "test_task".to_string(),
-            worker_kind: "test_worker".to_string(),
-            created_at: now,
-            input_data: vec![1, 2, 3],
-            priority: 1,
-            ttl_duration: 3600000000, // 1 hour in microseconds
-            otel_ctx_carrier: HashMap::new(),
-            update_type: "Assignment".to_string(),
-        };
-        repo.update_task_from_assignment_update(&assignment)
+        assert!(repo.get_task_result(&id).await.unwrap().is_none());
+
+        let update = TaskCompletedUpdate::new(id, Local::now().naive_local(), vec![4, 5, 6], false)
+            .with_output_content_type("image/png");
+        repo.update_task_from_completed_update(&update)
             .await
             .unwrap();
 
-        // 2. Running
-        let running = TaskRunningUpdate::new(id, now, "worker-1".to_string());
-        repo.update_task_from_running_update(&running)
+        let result = repo.get_task_result(&id).await.unwrap().unwrap();
+        assert!(result.is_completed());
+        assert_eq!(result.output_data, Some(vec![4, 5, 6]));
+        assert_eq!(result.output_content_type, Some("image/png".to_string()));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_stale_completed_update_does_not_overwrite_error(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+        let id = Uuid::new_v4();
+        let now = Local::now().naive_local();
+
+        let error = TaskCompletedUpdate::new(id, now, b"boom".to_vec(), true);
+        let stale_success =
+            TaskCompletedUpdate::new(id, now - chrono::Duration::seconds(5), vec![1], false);
+        repo.update_task_from_completed_update(&error)
             .await
             .unwrap();
-
-        // 3. Completed
-        let completed = TaskCompletedUpdate::new(id, now, vec![4, 5, 6], 0);
-        repo.update_task_from_completed_update(&completed)
+        repo.update_task_from_completed_update(&stale_success)
             .await
             .unwrap();
 
-        // Verify final state
         let task = repo.get_task_by_id(&id).await.unwrap().unwrap();
-        assert_eq!(task.task_kind, Some("test_task".to_string()));
-        assert_eq!(task.worker_kind, Some("test_worker".to_string()));
-        assert_eq!(task.input_data, Some(vec![1, 2, 3]));
-        assert_eq!(task.ttl_duration, Some(3600000000));
-        assert_eq!(task.priority, Some(1));
-        assert_eq!(
-            task.started_at.unwrap().and_utc().timestamp_micros(),
-            now.and_utc().timestamp_micros()
-        );
-        assert_eq!(task.executed_by, Some("worker-1".to_string()));
+        assert_eq!(task.is_error, Some(true));
+        assert_eq!(task.output_data, Some(b"boom".to_vec()));
         assert_eq!(
             task.completed_at.unwrap().and_utc().timestamp_micros(),
             now.and_utc().timestamp_micros()
         );
-        assert_eq!(task.output_data, Some(vec![4, 5, 6]));
-        assert_eq!(task.is_error, Some(0));
     }
 
-    /// Attempts to retrieve a non-existent task (should fail)
     #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn get_nonexistent_task(pool: PgPool) {
+    async fn test_newer_completed_update_wins(pool: PgPool) {
         let repo = TaskRepository::new(PgRepositoryCore::new(pool));
-        let task = repo.get_task_by_id(&Uuid::new_v4()).await.unwrap();
-        assert!(task.is_none());
+        let id = Uuid::new_v4();
+        let now = Local::now().naive_local();
+
+        // The stale success arrives first, the newer error must replace it
+        let stale_success =
+            TaskCompletedUpdate::new(id, now - chrono::Duration::seconds(5), vec![1], false);
+        let error = TaskCompletedUpdate::new(id, now, b"boom".to_vec(), true);
+        repo.update_task_from_completed_update(&stale_success)
+            .await
+            .unwrap();
+        repo.update_task_from_completed_update(&error)
+            .await
+            .unwrap();
+
+        let task = repo.get_task_by_id(&id).await.unwrap().unwrap();
+        assert_eq!(task.is_error, Some(true));
+        assert_eq!(task.output_data, Some(b"boom".to_vec()));
     }
 
-    // Tests task cleanup on expired ttl
     #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
-    async fn cleanup_expired_tasks(pool: PgPool) {
-        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
-
-        // Set completed at one day ago
-        let mut task = get_test_task();
-        task.completed_at = Some(Local::now().naive_local() - chrono::Duration::days(1));
+    async fn test_error_wins_over_simultaneous_success(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+        let id = Uuid::new_v4();
+        let now = Local::now().naive_local();
 
-        repo.create_task(&task).await.unwrap();
+        let error = TaskCompletedUpdate::new(id, now, b"boom".to_vec(), true);
+        let success = TaskCompletedUpdate::new(id, now, vec![1], false);
+        repo.update_task_from_completed_update(&error)
+            .await
+            .unwrap();
+        repo.update_task_from_completed_update(&success)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_task_by_id(&id).await.unwrap().unwrap().is_error,
+            Some(true)
+        );
+
+        // A redelivery of the same event changes nothing
+        repo.update_task_from_completed_update(&error)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_task_by_id(&id).await.unwrap().unwrap().output_data,
+            Some(b"boom".to_vec())
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_update_retries_while_pool_is_exhausted(pool: PgPool) {
+        // A single connection with a tiny acquire timeout, held by someone
+        // else, makes every acquire time out until it is released
+        let tiny_pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_millis(20))
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap();
+        let held = tiny_pool.acquire().await.unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            drop(held);
+        });
+
+        let repo = TaskRepository::new(PgRepositoryCore::new(tiny_pool));
+        let id = Uuid::new_v4();
+        let running = TaskRunningUpdate::new(id, Local::now().naive_local(), "worker-1".into());
+        repo.update_task_from_running_update(&running)
+            .await
+            .unwrap();
+
+        let task = repo.get_task_by_id(&id).await.unwrap().unwrap();
+        assert_eq!(task.executed_by, Some("worker-1".to_string()));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_task_full_lifecycle(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let id = Uuid::new_v4();
+        let now = Local::now().naive_local();
+
+        // 1. Assignment
+        let assignment = TaskAssignmentUpdate {
+            id,
+            task_kind: "test_task".to_string(),
+            worker_kind: "test_worker".to_string(),
+            created_at: now,
+            input_data: vec![1, 2, 3],
+            priority: 1,
+            ttl_duration: 3600, // 1 hour in seconds
+            otel_ctx_carrier: HashMap::new(),
+            input_content_type: None,
+            scheduled_for: None,
+            update_type: "Assignment".to_string(),
+        };
+        repo.update_task_from_assignment_update(&assignment)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_task_status(&id).await.unwrap(),
+            Some(TaskStatus::Pending)
+        );
+
+        // 2. Running
+        let running = TaskRunningUpdate::new(id, now, "worker-1".to_string());
+        repo.update_task_from_running_update(&running)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_task_status(&id).await.unwrap(),
+            Some(TaskStatus::Processing)
+        );
+
+        // 3. Completed
+        let completed = TaskCompletedUpdate::new(id, now, vec![4, 5, 6], false);
+        repo.update_task_from_completed_update(&completed)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_task_status(&id).await.unwrap(),
+            Some(TaskStatus::Completed)
+        );
+
+        // Verify final state
+        let task = repo.get_task_by_id(&id).await.unwrap().unwrap();
+        assert_eq!(task.task_kind, Some("test_task".to_string()));
+        assert_eq!(task.worker_kind, Some("test_worker".to_string()));
+        assert_eq!(task.input_data, Some(vec![1, 2, 3]));
+        assert_eq!(task.ttl_duration, Some(3600));
+        assert_eq!(task.priority, Some(1));
+        assert_eq!(
+            task.started_at.unwrap().and_utc().timestamp_micros(),
+            now.and_utc().timestamp_micros()
+        );
+        assert_eq!(task.executed_by, Some("worker-1".to_string()));
+        assert_eq!(
+            task.completed_at.unwrap().and_utc().timestamp_micros(),
+            now.and_utc().timestamp_micros()
+        );
+        assert_eq!(task.output_data, Some(vec![4, 5, 6]));
+        assert_eq!(task.is_error, Some(false));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_task_status_decoding_is_strict(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+        repo.create_task(&task).await.unwrap();
+
+        // A newer migration adding a status must not make this relay guess
+        sqlx::query("ALTER TYPE task_status ADD VALUE 'Cancelled'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let result = sqlx::query_scalar::<_, TaskStatus>("SELECT 'Cancelled'::task_status")
+            .fetch_one(&pool)
+            .await;
+        assert!(matches!(result, Err(sqlx::Error::ColumnDecode { .. })));
+
+        // Statuses it does know keep decoding from the stored column
+        assert_eq!(
+            repo.get_task_status(&task.id).await.unwrap(),
+            Some(TaskStatus::Pending)
+        );
+
+        // A plain text value isn't decoded as a status either
+        let result = sqlx::query_scalar::<_, TaskStatus>("SELECT 'Pending'::text")
+            .fetch_one(&pool)
+            .await;
+        assert!(matches!(result, Err(sqlx::Error::ColumnDecode { .. })));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_assignment_after_completion(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let id = Uuid::new_v4();
+        let now = Local::now().naive_local();
+        let assignment = TaskAssignmentUpdate {
+            id,
+            task_kind: "test_task".to_string(),
+            worker_kind: "test_worker".to_string(),
+            created_at: now,
+            input_data: vec![1, 2, 3],
+            ttl_duration: 3600,
+            ..Default::default()
+        };
+        let row_version = || async {
+            sqlx::query_scalar!(
+                r#"SELECT xmin::text AS "xmin!" FROM tasks WHERE id = $1"#,
+                id
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        };
+
+        // A completion arriving first still gets the assignment filled in
+        repo.update_task_from_completed_update(&TaskCompletedUpdate::new(id, now, vec![4], false))
+            .await
+            .unwrap();
+        repo.update_task_from_assignment_update(&assignment)
+            .await
+            .unwrap();
+        let task = repo.get_task_by_id(&id).await.unwrap().unwrap();
+        assert_eq!(task.worker_kind, Some("test_worker".to_string()));
+        assert_eq!(task.input_data, Some(vec![1, 2, 3]));
+
+        // A replayed assignment leaves the completed task untouched
+        let version = row_version().await;
+        repo.update_task_from_assignment_update(&assignment)
+            .await
+            .unwrap();
+        assert_eq!(row_version().await, version);
+        assert_eq!(
+            repo.get_task_status(&id).await.unwrap(),
+            Some(TaskStatus::Completed)
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_task_history_records_every_event(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let id = Uuid::new_v4();
+        let now = Local::now().naive_local();
+
+        // Events arrive out of order, and the stale completion doesn't change the task
+        let completed =
+            TaskCompletedUpdate::new(id, now + chrono::Duration::seconds(2), vec![4], false);
+        repo.update_task_from_completed_update(&completed)
+            .await
+            .unwrap();
+        let stale =
+            TaskCompletedUpdate::new(id, now + chrono::Duration::seconds(1), vec![5], false);
+        repo.update_task_from_completed_update(&stale)
+            .await
+            .unwrap();
+        let running = TaskRunningUpdate::new(id, now, "worker-1".to_string());
+        repo.update_task_from_running_update(&running)
+            .await
+            .unwrap();
+        let assignment = TaskAssignmentUpdate {
+            id,
+            task_kind: "test_task".to_string(),
+            worker_kind: "test_worker".to_string(),
+            created_at: now - chrono::Duration::seconds(1),
+            input_data: vec![1, 2, 3],
+            ..TaskAssignmentUpdate::default()
+        };
+        repo.update_task_from_assignment_update(&assignment)
+            .await
+            .unwrap();
+
+        let history = repo.get_task_history(&id).await.unwrap();
+        let event_types: Vec<_> = history
+            .iter()
+            .map(|event| event.event_type.as_str())
+            .collect();
+        assert_eq!(
+            event_types,
+            vec![
+                "TaskAssignment",
+                "TaskRunning",
+                "TaskCompleted",
+                "TaskCompleted"
+            ]
+        );
+        assert_eq!(history[0].payload["task_kind"], "test_task");
+        assert!(history[0].payload.get("input_data").is_none());
+        assert_eq!(history[1].payload["executed_by"], "worker-1");
+        assert!(history[2].payload.get("output_data").is_none());
+
+        // The history goes away with the task
+        repo.delete_tasks_by_filter(Some("test_worker"), None)
+            .await
+            .unwrap();
+        assert!(repo.get_task_history(&id).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_list_task_kinds(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+
+        let mut first = Task::new("resize_image", "WorkerA", 0, 0);
+        first.created_at -= chrono::Duration::hours(1);
+        let latest = Task::new("resize_image", "WorkerB", 0, 0);
+        let other = Task::new("send_email", "WorkerC", 0, 0);
+        for task in [&first, &latest, &other] {
+            repo.create_task(task).await.unwrap();
+        }
+
+        let task_kinds = repo.list_task_kinds().await.unwrap();
+        let summary: Vec<(&str, &str)> = task_kinds
+            .iter()
+            .map(|kind| (kind.name.as_str(), kind.worker_kind.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("resize_image", "WorkerB"), ("send_email", "WorkerC")]
+        );
+        assert_eq!(
+            task_kinds[0].created_at.and_utc().timestamp_micros(),
+            first.created_at.and_utc().timestamp_micros()
+        );
+
+        let task_kind = repo.get_task_kind("send_email").await.unwrap().unwrap();
+        assert_eq!(task_kind, task_kinds[1]);
+        assert!(repo.get_task_kind("missing").await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_count_tasks_by_status_and_worker_kind(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+        let now = Local::now().naive_local();
+
+        let pending = Task::new("TaskKindName", "WorkerA", 0, 0);
+        let mut processing = Task::new("TaskKindName", "WorkerA", 0, 0);
+        processing.started_at = Some(now);
+        let mut completed = Task::new("TaskKindName", "WorkerB", 0, 0);
+        completed.started_at = Some(now);
+        completed.completed_at = Some(now);
+        let mut old = Task::new("TaskKindName", "WorkerB", 0, 0);
+        old.created_at = now - chrono::Duration::days(7);
+
+        for task in [&pending, &processing, &completed, &old] {
+            repo.create_task(task).await.unwrap();
+        }
 
-        let count = repo.delete_expired_tasks().await.unwrap();
+        let by_status = repo.count_tasks_by_status(None, None).await.unwrap();
+        let mut by_status: Vec<(String, i64)> = by_status
+            .into_iter()
+            .map(|c| (c.status.to_string(), c.count))
+            .collect();
+        by_status.sort();
+        assert_eq!(
+            by_status,
+            vec![
+                ("Completed".to_string(), 1),
+                ("Pending".to_string(), 2),
+                ("Processing".to_string(), 1)
+            ]
+        );
+
+        let since = Some(now - chrono::Duration::days(1));
+        let by_worker_kind = repo.count_tasks_by_worker_kind(since, None).await.unwrap();
+        let by_worker_kind: Vec<(Option<String>, i64)> = by_worker_kind
+            .into_iter()
+            .map(|c| (c.worker_kind, c.count))
+            .collect();
+        assert_eq!(
+            by_worker_kind,
+            vec![
+                (Some("WorkerA".to_string()), 2),
+                (Some("WorkerB".to_string()), 1)
+            ]
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_stream_tasks(pool: PgPool) {
+        use futures::TryStreamExt;
+
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+
+        let mut first = Task::new("TaskKindName", "WorkerA", 0, 0);
+        first.created_at = Local::now().naive_local() - chrono::Duration::hours(1);
+        let second = Task::new("TaskKindName", "WorkerA", 0, 0);
+        let other = Task::new("TaskKindName", "WorkerB", 0, 0);
+        for task in [&second, &first, &other] {
+            repo.create_task(task).await.unwrap();
+        }
+
+        let tasks: Vec<Task> = repo
+            .stream_tasks(Some("WorkerA"), None)
+            .try_collect()
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![first.id, second.id]);
+
+        let tasks: Vec<Task> = repo.stream_tasks(None, None).try_collect().await.unwrap();
+        assert_eq!(tasks.len(), 3);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_search_tasks_by_input(pool: PgPool) {
+        use futures::TryStreamExt;
+
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+        let assignment = |input: &[u8], content_type: Option<&str>| TaskAssignmentUpdate {
+            id: Uuid::new_v4(),
+            worker_kind: "WorkerA".to_string(),
+            created_at: Local::now().naive_local(),
+            input_data: input.to_vec(),
+            input_content_type: content_type.map(str::to_string),
+            ..Default::default()
+        };
+        let alice = assignment(
+            br#"{"user": "alice", "tags": ["a", "b"]}"#,
+            Some("application/json"),
+        );
+        let bob = assignment(br#"{"user": "bob"}"#, Some("application/json"));
+        // Same bytes, but not declared as JSON
+        let opaque = assignment(br#"{"user": "alice"}"#, None);
+        for update in [&alice, &bob, &opaque] {
+            repo.update_task_from_assignment_update(update)
+                .await
+                .unwrap();
+        }
+
+        let filter = serde_json::json!({ "user": "alice" });
+        let tasks: Vec<Task> = repo
+            .stream_tasks(None, Some(&filter))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            tasks.iter().map(|task| task.id).collect::<Vec<_>>(),
+            vec![alice.id]
+        );
+
+        let filter = serde_json::json!({ "tags": ["b"] });
+        let tasks = repo
+            .list_tasks_after(None, Some("WorkerA"), Some(&filter), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            tasks.iter().map(|task| task.id).collect::<Vec<_>>(),
+            vec![alice.id]
+        );
+    }
+
+    /// Attempts to retrieve a non-existent task (should fail)
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn get_nonexistent_task(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+        let task = repo.get_task_by_id(&Uuid::new_v4()).await.unwrap();
+        assert!(task.is_none());
+    }
+
+    // Tests task cleanup on expired ttl
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn cleanup_expired_tasks(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+
+        // Set completed at one day ago
+        let mut task = get_test_task();
+        task.completed_at = Some(Local::now().naive_local() - chrono::Duration::days(1));
+
+        repo.create_task(&task).await.unwrap();
+
+        let count = repo
+            .delete_expired_tasks(100, Duration::ZERO)
+            .await
+            .unwrap()
+            .deleted;
         assert_eq!(count, 1, "One task should be deleted");
 
-        let count = repo.delete_expired_tasks().await.unwrap();
+        let count = repo
+            .delete_expired_tasks(100, Duration::ZERO)
+            .await
+            .unwrap()
+            .deleted;
         assert_eq!(count, 0, "No more tasks should be deleted");
     }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn cleanup_expired_tasks_in_chunks(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+
+        for _ in 0..5 {
+            let mut task = get_test_task();
+            task.completed_at = Some(Local::now().naive_local() - chrono::Duration::days(1));
+            repo.create_task(&task).await.unwrap();
+        }
+        let live = get_test_task();
+        repo.create_task(&live).await.unwrap();
+
+        // Two full chunks, then a partial one ending the cleanup
+        let cleanup = repo
+            .delete_expired_tasks(2, Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            cleanup,
+            ExpiredTaskCleanup {
+                deleted: 5,
+                chunks: 3
+            }
+        );
+        assert!(repo.get_task_by_id(&live.id).await.unwrap().is_some());
+
+        // Under the chunk size, a single statement is enough
+        let cleanup = repo.delete_expired_tasks(2, Duration::ZERO).await.unwrap();
+        assert_eq!(cleanup.chunks, 1);
+        assert_eq!(cleanup.deleted, 0);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn archive_expired_tasks(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+
+        let mut expired = Vec::new();
+        for _ in 0..3 {
+            let mut task = get_test_task();
+            task.completed_at = Some(Local::now().naive_local() - chrono::Duration::days(1));
+            repo.create_task(&task).await.unwrap();
+            expired.push(task);
+        }
+        let live = get_test_task();
+        repo.create_task(&live).await.unwrap();
+
+        let cleanup = repo.archive_expired_tasks(2, Duration::ZERO).await.unwrap();
+        assert_eq!(
+            cleanup,
+            ExpiredTaskCleanup {
+                deleted: 3,
+                chunks: 2
+            }
+        );
+
+        // Archived tasks leave the tasks table with their data intact
+        for task in &expired {
+            assert!(repo.get_task_by_id(&task.id).await.unwrap().is_none());
+            let archived = repo
+                .get_archived_task_by_id(&task.id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(archived.input_data, task.input_data);
+            assert_eq!(archived.task_kind, task.task_kind);
+            assert!(archived.completed_at.is_some());
+        }
+        assert!(repo.get_task_by_id(&live.id).await.unwrap().is_some());
+        assert!(repo
+            .get_archived_task_by_id(&live.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let cleanup = repo.archive_expired_tasks(2, Duration::ZERO).await.unwrap();
+        assert_eq!(cleanup.deleted, 0);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_assignment_applies_ttl_policy(pool: PgPool) {
+        let repo =
+            TaskRepository::new(PgRepositoryCore::new(pool.clone())).with_ttl_policy(TtlPolicy {
+                default_secs: 3600,
+                min_secs: 60,
+            });
+        let now = chrono::Utc::now().naive_utc();
+
+        let zero_ttl = TaskAssignmentUpdate {
+            id: Uuid::new_v4(),
+            task_kind: "test_task".to_string(),
+            worker_kind: "test_worker".to_string(),
+            ttl_duration: 0,
+            ..Default::default()
+        };
+        let unset_ttl = TaskAssignmentUpdate {
+            id: Uuid::new_v4(),
+            ttl_duration: -1,
+            ..zero_ttl.clone()
+        };
+        for assignment in [&zero_ttl, &unset_ttl] {
+            repo.update_task_from_assignment_update(assignment)
+                .await
+                .unwrap();
+        }
+
+        let task = repo.get_task_by_id(&zero_ttl.id).await.unwrap().unwrap();
+        assert_eq!(task.ttl_duration, Some(60));
+        let task = repo.get_task_by_id(&unset_ttl.id).await.unwrap().unwrap();
+        assert_eq!(task.ttl_duration, Some(3600));
+
+        // Completed 30 seconds ago, the clamped task outlives its zero TTL
+        let completed = TaskCompletedUpdate::new(
+            zero_ttl.id,
+            now - chrono::Duration::seconds(30),
+            vec![],
+            false,
+        );
+        repo.update_task_from_completed_update(&completed)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.delete_expired_tasks(100, Duration::ZERO)
+                .await
+                .unwrap()
+                .deleted,
+            0
+        );
+
+        // Completed updates never move the completion back, so it is aged directly
+        sqlx::query!(
+            "UPDATE tasks SET completed_at = $1 WHERE id = $2",
+            now - chrono::Duration::seconds(90),
+            zero_ttl.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            repo.delete_expired_tasks(100, Duration::ZERO)
+                .await
+                .unwrap()
+                .deleted,
+            1
+        );
+        assert!(repo.get_task_by_id(&zero_ttl.id).await.unwrap().is_none());
+    }
+
+    /// Stored tasks must match the tasks [`MergeUpdate`] merges the same
+    /// updates into, whatever order the updates arrive in.
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_updates_follow_merge_rules(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+        // Whole seconds, so nothing is lost to the precision of the database
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc();
+        let id = Uuid::new_v4();
+
+        let assignment = TaskAssignmentUpdate {
+            id,
+            task_kind: "TaskKindName".to_string(),
+            worker_kind: "WorkerKindName".to_string(),
+            created_at: t0,
+            input_data: vec![1],
+            priority: 3,
+            ttl_duration: 3600,
+            ..Default::default()
+        };
+        let running = TaskRunningUpdate::new(id, t0 + chrono::Duration::seconds(1), "w".into());
+        let late_running =
+            TaskRunningUpdate::new(id, t0 + chrono::Duration::seconds(2), "other".into());
+        let completed =
+            TaskCompletedUpdate::new(id, t0 + chrono::Duration::seconds(3), vec![2], false)
+                .with_output_content_type("image/png");
+        let older = TaskCompletedUpdate::new(id, t0 + chrono::Duration::seconds(2), vec![3], true);
+        let error = TaskCompletedUpdate::new(id, t0 + chrono::Duration::seconds(3), vec![4], true);
+        let acknowledged =
+            TaskAcknowledgedUpdate::new(id, t0 + chrono::Duration::milliseconds(500));
+        let late_acknowledged = TaskAcknowledgedUpdate::new(id, t0 + chrono::Duration::seconds(2));
+
+        // The first event creates the task, the others merge into it
+        repo.update_task_from_running_update(&running)
+            .await
+            .unwrap();
+        check_merge(
+            &repo,
+            &id,
+            &completed,
+            repo.update_task_from_completed_update(&completed),
+        )
+        .await;
+        check_merge(
+            &repo,
+            &id,
+            &assignment,
+            repo.update_task_from_assignment_update(&assignment),
+        )
+        .await;
+        check_merge(
+            &repo,
+            &id,
+            &late_running,
+            repo.update_task_from_running_update(&late_running),
+        )
+        .await;
+        check_merge(
+            &repo,
+            &id,
+            &acknowledged,
+            repo.update_task_from_acknowledged_update(&acknowledged),
+        )
+        .await;
+        check_merge(
+            &repo,
+            &id,
+            &late_acknowledged,
+            repo.update_task_from_acknowledged_update(&late_acknowledged),
+        )
+        .await;
+        check_merge(
+            &repo,
+            &id,
+            &older,
+            repo.update_task_from_completed_update(&older),
+        )
+        .await;
+        check_merge(
+            &repo,
+            &id,
+            &error,
+            repo.update_task_from_completed_update(&error),
+        )
+        .await;
+        check_merge(
+            &repo,
+            &id,
+            &assignment,
+            repo.update_task_from_assignment_update(&assignment),
+        )
+        .await;
+    }
+
+    /// Stores an update of the task `id`, checking the stored task matches
+    /// the task it was merged into.
+    async fn check_merge<T>(
+        repo: &TaskRepository,
+        id: &Uuid,
+        update: &impl MergeUpdate,
+        store: impl std::future::Future<Output = Result<T, sqlx::Error>>,
+    ) {
+        let mut expected = repo.get_task_by_id(id).await.unwrap().unwrap();
+        update.apply_to(&mut expected);
+
+        store.await.unwrap();
+        let stored = repo.get_task_by_id(id).await.unwrap().unwrap();
+        expected.updated_at = stored.updated_at;
+        assert_eq!(
+            serde_json::to_value(&stored).unwrap(),
+            serde_json::to_value(&expected).unwrap()
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_delete_tasks_by_filter(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+
+        let mut old_task = Task::new("TaskKindName", "OldWorkerKind", 0, 0);
+        old_task.created_at = Local::now().naive_local() - chrono::Duration::days(2);
+        let new_task = Task::new("TaskKindName", "OldWorkerKind", 0, 0);
+        let other_task = Task::new("TaskKindName", "OtherWorkerKind", 0, 0);
+        for task in [&old_task, &new_task, &other_task] {
+            repo.create_task(task).await.unwrap();
+        }
+
+        // Both filters must match
+        let cutoff = Local::now().naive_local() - chrono::Duration::days(1);
+        let count = repo
+            .delete_tasks_by_filter(Some("OldWorkerKind"), Some(cutoff))
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(repo.get_task_by_id(&old_task.id).await.unwrap().is_none());
+
+        let count = repo
+            .delete_tasks_by_filter(Some("OldWorkerKind"), None)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(repo.get_task_by_id(&new_task.id).await.unwrap().is_none());
+        assert!(repo.get_task_by_id(&other_task.id).await.unwrap().is_some());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_requeue_task(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool.clone()));
+
+        let task = Task::new("TaskKindName", "WorkerKindName", 0, 0);
+        repo.create_task(&task).await.unwrap();
+        let running =
+            TaskRunningUpdate::new(task.id, Local::now().naive_local(), "worker-1".to_string());
+        repo.update_task_from_running_update(&running)
+            .await
+            .unwrap();
+        let acknowledged = TaskAcknowledgedUpdate::new(task.id, Local::now().naive_local());
+        repo.update_task_from_acknowledged_update(&acknowledged)
+            .await
+            .unwrap();
+
+        let requeued = repo.requeue_task(&task.id).await.unwrap().unwrap();
+        assert_eq!(requeued.started_at, None);
+        assert_eq!(requeued.acknowledged_at, None);
+        assert_eq!(requeued.executed_by, None);
+        assert_eq!(
+            repo.get_task_status(&task.id).await.unwrap(),
+            Some(TaskStatus::Pending)
+        );
+
+        let history = repo.get_task_history(&task.id).await.unwrap();
+        let requeue = history.last().unwrap();
+        assert_eq!(requeue.event_type, "TaskRequeued");
+        assert_eq!(requeue.payload["executed_by"], "worker-1");
+
+        // Completed tasks and unknown tasks are left alone
+        let completed =
+            TaskCompletedUpdate::new(task.id, Local::now().naive_local(), vec![], false);
+        repo.update_task_from_completed_update(&completed)
+            .await
+            .unwrap();
+        assert!(repo.requeue_task(&task.id).await.unwrap().is_none());
+        assert!(repo.requeue_task(&Uuid::new_v4()).await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_republished_assignment_keeps_input_content_type(pool: PgPool) {
+        let repo = TaskRepository::new(PgRepositoryCore::new(pool));
+
+        let assignment = TaskAssignmentUpdate {
+            id: Uuid::new_v4(),
+            task_kind: "TaskKindName".to_string(),
+            worker_kind: "WorkerKindName".to_string(),
+            created_at: Local::now().naive_local(),
+            input_data: b"{}".to_vec(),
+            input_content_type: Some("application/json".to_string()),
+            ..Default::default()
+        };
+        repo.update_task_from_assignment_update(&assignment)
+            .await
+            .unwrap();
+
+        let task = repo.get_task_by_id(&assignment.id).await.unwrap().unwrap();
+        assert_eq!(task.input_content_type.as_deref(), Some("application/json"));
+        let republished = TaskAssignmentUpdate::from_task(&task).unwrap();
+        assert_eq!(
+            republished.input_content_type,
+            assignment.input_content_type
+        );
+    }
 }
@@ -0,0 +1,503 @@
+use crate::models::{Worker, WorkerHeartbeatUpdate, WorkerKind, WorkerRegistrationUpdate};
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+use crate::repo::PgRepositoryCore;
+use crate::task_event_publisher::worker_routing_key;
+
+/// The outcome of deleting a worker kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerKindDeletion {
+    Deleted,
+    NotFound,
+    /// Workers or tasks still reference the kind, so it was kept
+    InUse,
+}
+
+#[derive(Clone, Debug)]
+pub struct WorkerRepository {
+    core: PgRepositoryCore,
+}
+
+impl WorkerRepository {
+    pub fn new(core: PgRepositoryCore) -> Self {
+        Self { core }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_worker_by_name(&self, name: &str) -> Result<Option<Worker>, sqlx::Error> {
+        debug!(worker_name = %name, "Getting worker by name");
+        sqlx::query_as!(
+            Worker,
+            r#"SELECT
+                name,
+                worker_kind_name AS worker_kind,
+                task_kinds,
+                version,
+                last_heartbeat_at,
+                registered_at,
+                created_at,
+                updated_at
+            FROM workers WHERE name = $1"#,
+            name
+        )
+        .fetch_optional(&self.core.pool)
+        .await
+    }
+
+    /// Gets the worker that executed a task.
+    ///
+    /// # Returns
+    /// `None` if the task doesn't exist, hasn't been picked up by a worker
+    /// yet, or its worker never sent a heartbeat nor registered
+    #[instrument(skip(self))]
+    pub async fn get_worker_of_task(&self, task_id: &Uuid) -> Result<Option<Worker>, sqlx::Error> {
+        debug!(task_id = %task_id, "Getting worker of task");
+        sqlx::query_as!(
+            Worker,
+            r#"SELECT
+                w.name,
+                w.worker_kind_name AS worker_kind,
+                w.task_kinds,
+                w.version,
+                w.last_heartbeat_at,
+                w.registered_at,
+                w.created_at,
+                w.updated_at
+            FROM tasks t
+            JOIN workers w ON w.name = t.executed_by
+            WHERE t.id = $1"#,
+            task_id
+        )
+        .fetch_optional(&self.core.pool)
+        .await
+    }
+
+    /// Records a heartbeat, registering the worker if it isn't known yet.
+    /// Heartbeats can arrive out of order, so an older heartbeat never moves
+    /// `last_heartbeat_at` back.
+    #[instrument(skip(self, update), fields(worker_name = %update.worker_name))]
+    pub async fn save_heartbeat(&self, update: &WorkerHeartbeatUpdate) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO workers (
+                name, worker_kind_name, last_heartbeat_at
+            )
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name) DO UPDATE SET
+                worker_kind_name = EXCLUDED.worker_kind_name,
+                last_heartbeat_at = GREATEST(workers.last_heartbeat_at, EXCLUDED.last_heartbeat_at),
+                updated_at = NOW()
+            "#,
+            update.worker_name,
+            update.worker_kind,
+            update.heartbeat_at
+        )
+        .execute(&self.core.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records a worker registration, creating its worker kind if needed.
+    /// Registering counts as a sign of life, so it also moves
+    /// `last_heartbeat_at` forward.
+    #[instrument(skip(self, update), fields(worker_name = %update.worker_name))]
+    pub async fn save_registration(
+        &self,
+        update: &WorkerRegistrationUpdate,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.core.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO worker_kinds (name)
+            VALUES ($1)
+            ON CONFLICT (name) DO UPDATE SET updated_at = NOW()
+            "#,
+            update.worker_kind
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO workers (
+                name, worker_kind_name, task_kinds, version, last_heartbeat_at, registered_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $5)
+            ON CONFLICT (name) DO UPDATE SET
+                worker_kind_name = EXCLUDED.worker_kind_name,
+                task_kinds = EXCLUDED.task_kinds,
+                version = EXCLUDED.version,
+                last_heartbeat_at = GREATEST(workers.last_heartbeat_at, EXCLUDED.last_heartbeat_at),
+                registered_at = EXCLUDED.registered_at,
+                updated_at = NOW()
+            "#,
+            update.worker_name,
+            update.worker_kind,
+            &update.task_kinds,
+            update.version,
+            update.registered_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    /// Finds every worker of a kind, ordered by name.
+    #[instrument(skip(self))]
+    pub async fn find_workers_by_kind(
+        &self,
+        worker_kind: &str,
+    ) -> Result<Vec<Worker>, sqlx::Error> {
+        debug!(worker_kind = %worker_kind, "Finding workers by kind");
+        sqlx::query_as!(
+            Worker,
+            r#"SELECT
+                name,
+                worker_kind_name AS worker_kind,
+                task_kinds,
+                version,
+                last_heartbeat_at,
+                registered_at,
+                created_at,
+                updated_at
+            FROM workers WHERE worker_kind_name = $1
+            ORDER BY name"#,
+            worker_kind
+        )
+        .fetch_all(&self.core.pool)
+        .await
+    }
+
+    /// Whether a worker kind is known, either because a worker registered
+    /// under it or because one of its workers sent a heartbeat.
+    #[instrument(skip(self))]
+    pub async fn worker_kind_exists(&self, worker_kind: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT (
+                EXISTS (SELECT 1 FROM worker_kinds WHERE name = $1)
+                OR EXISTS (SELECT 1 FROM workers WHERE worker_kind_name = $1)
+            ) AS "exists!""#,
+            worker_kind
+        )
+        .fetch_one(&self.core.pool)
+        .await
+    }
+
+    /// Finds every worker kind, ordered by name. Kinds created by a worker
+    /// registration have no routing settings stored, so they get the ones
+    /// of the SDKs, see `worker_routing_key`.
+    #[instrument(skip(self))]
+    pub async fn find_all_worker_kinds(&self) -> Result<Vec<WorkerKind>, sqlx::Error> {
+        debug!("Finding all worker kinds");
+        sqlx::query_as!(
+            WorkerKind,
+            r#"SELECT
+                name,
+                COALESCE(routing_key, 'tasks.' || name) AS "routing_key!",
+                COALESCE(queue_name, name) AS "queue_name!",
+                created_at,
+                updated_at
+            FROM worker_kinds
+            ORDER BY name"#
+        )
+        .fetch_all(&self.core.pool)
+        .await
+    }
+
+    /// Gets a worker kind by its name, see [`Self::find_all_worker_kinds`].
+    #[instrument(skip(self))]
+    pub async fn get_worker_kind(&self, name: &str) -> Result<Option<WorkerKind>, sqlx::Error> {
+        sqlx::query_as!(
+            WorkerKind,
+            r#"SELECT
+                name,
+                COALESCE(routing_key, 'tasks.' || name) AS "routing_key!",
+                COALESCE(queue_name, name) AS "queue_name!",
+                created_at,
+                updated_at
+            FROM worker_kinds
+            WHERE name = $1"#,
+            name
+        )
+        .fetch_optional(&self.core.pool)
+        .await
+    }
+
+    /// The routing key the assignments of a worker kind are published with:
+    /// the one stored for the kind, or the one of the SDKs for kinds the
+    /// relay doesn't know yet.
+    #[instrument(skip(self))]
+    pub async fn routing_key_of(&self, worker_kind: &str) -> Result<String, sqlx::Error> {
+        Ok(self
+            .get_worker_kind(worker_kind)
+            .await?
+            .map(|kind| kind.routing_key)
+            .unwrap_or_else(|| worker_routing_key(worker_kind)))
+    }
+
+    /// Creates a worker kind.
+    ///
+    /// # Returns
+    /// The created worker kind, or `None` if a kind with that name exists
+    #[instrument(skip(self))]
+    pub async fn create_worker_kind(
+        &self,
+        name: &str,
+        routing_key: &str,
+        queue_name: &str,
+    ) -> Result<Option<WorkerKind>, sqlx::Error> {
+        sqlx::query_as!(
+            WorkerKind,
+            r#"
+            INSERT INTO worker_kinds (name, routing_key, queue_name)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name) DO NOTHING
+            RETURNING
+                name,
+                routing_key AS "routing_key!",
+                queue_name AS "queue_name!",
+                created_at,
+                updated_at
+            "#,
+            name,
+            routing_key,
+            queue_name
+        )
+        .fetch_optional(&self.core.pool)
+        .await
+    }
+
+    /// Deletes a worker kind, unless workers or tasks still reference it.
+    #[instrument(skip(self))]
+    pub async fn delete_worker_kind(&self, name: &str) -> Result<WorkerKindDeletion, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"DELETE FROM worker_kinds
+            WHERE name = $1
+                AND NOT EXISTS (SELECT 1 FROM workers WHERE worker_kind_name = $1)
+                AND NOT EXISTS (SELECT 1 FROM tasks WHERE worker_kind_name = $1)"#,
+            name
+        )
+        .execute(&self.core.pool)
+        .await?
+        .rows_affected();
+
+        if deleted > 0 {
+            return Ok(WorkerKindDeletion::Deleted);
+        }
+        match self.get_worker_kind(name).await? {
+            Some(_) => Ok(WorkerKindDeletion::InUse),
+            None => Ok(WorkerKindDeletion::NotFound),
+        }
+    }
+
+    /// Counts the workers of a kind that have registered with the relay.
+    #[instrument(skip(self))]
+    pub async fn count_registered_workers(&self, worker_kind: &str) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM workers
+            WHERE worker_kind_name = $1 AND registered_at IS NOT NULL"#,
+            worker_kind
+        )
+        .fetch_one(&self.core.pool)
+        .await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local};
+    use sqlx::PgPool;
+
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_save_heartbeat(pool: PgPool) {
+        let repo = WorkerRepository::new(PgRepositoryCore::new(pool));
+        let now = Local::now().naive_local();
+
+        repo.save_heartbeat(&WorkerHeartbeatUpdate::new("worker-1", "TestWorker", now))
+            .await
+            .unwrap();
+        let worker = repo.get_worker_by_name("worker-1").await.unwrap().unwrap();
+        assert_eq!(worker.worker_kind, "TestWorker");
+        assert_eq!(
+            worker.last_heartbeat_at.and_utc().timestamp_micros(),
+            now.and_utc().timestamp_micros()
+        );
+
+        // A newer heartbeat moves the timestamp forward
+        let later = now + Duration::seconds(10);
+        repo.save_heartbeat(&WorkerHeartbeatUpdate::new("worker-1", "TestWorker", later))
+            .await
+            .unwrap();
+        let worker = repo.get_worker_by_name("worker-1").await.unwrap().unwrap();
+        assert_eq!(
+            worker.last_heartbeat_at.and_utc().timestamp_micros(),
+            later.and_utc().timestamp_micros()
+        );
+
+        // A late, older heartbeat doesn't move it back
+        repo.save_heartbeat(&WorkerHeartbeatUpdate::new("worker-1", "TestWorker", now))
+            .await
+            .unwrap();
+        let worker = repo.get_worker_by_name("worker-1").await.unwrap().unwrap();
+        assert_eq!(
+            worker.last_heartbeat_at.and_utc().timestamp_micros(),
+            later.and_utc().timestamp_micros()
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_save_registration(pool: PgPool) {
+        let repo = WorkerRepository::new(PgRepositoryCore::new(pool));
+        let now = Local::now().naive_local();
+
+        // Heartbeats alone don't register a worker
+        repo.save_heartbeat(&WorkerHeartbeatUpdate::new("worker-1", "TestWorker", now))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.count_registered_workers("TestWorker").await.unwrap(),
+            0
+        );
+
+        let registration =
+            WorkerRegistrationUpdate::new("worker-1", "TestWorker", &["task_a", "task_b"], now);
+        repo.save_registration(&registration).await.unwrap();
+        repo.save_registration(&WorkerRegistrationUpdate::new(
+            "worker-2",
+            "TestWorker",
+            &["task_a"],
+            now,
+        ))
+        .await
+        .unwrap();
+
+        let worker = repo.get_worker_by_name("worker-1").await.unwrap().unwrap();
+        assert_eq!(worker.task_kinds, vec!["task_a", "task_b"]);
+        assert_eq!(worker.version.as_deref(), Some("0.0.0"));
+        assert!(worker.registered_at.is_some());
+        assert_eq!(
+            repo.count_registered_workers("TestWorker").await.unwrap(),
+            2
+        );
+        assert_eq!(
+            repo.count_registered_workers("OtherWorker").await.unwrap(),
+            0
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_find_workers_by_kind(pool: PgPool) {
+        let repo = WorkerRepository::new(PgRepositoryCore::new(pool));
+        let now = Local::now().naive_local();
+
+        assert!(!repo.worker_kind_exists("TestWorker").await.unwrap());
+
+        repo.save_heartbeat(&WorkerHeartbeatUpdate::new("worker-2", "TestWorker", now))
+            .await
+            .unwrap();
+        repo.save_registration(&WorkerRegistrationUpdate::new(
+            "worker-1",
+            "TestWorker",
+            &["task_a"],
+            now,
+        ))
+        .await
+        .unwrap();
+        repo.save_heartbeat(&WorkerHeartbeatUpdate::new("worker-3", "OtherWorker", now))
+            .await
+            .unwrap();
+
+        assert!(repo.worker_kind_exists("TestWorker").await.unwrap());
+        let workers = repo.find_workers_by_kind("TestWorker").await.unwrap();
+        let names: Vec<_> = workers.iter().map(|worker| worker.name.as_str()).collect();
+        assert_eq!(names, vec!["worker-1", "worker-2"]);
+        assert!(repo
+            .find_workers_by_kind("Unknown")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_worker_kind_crud(pool: PgPool) {
+        let repo = WorkerRepository::new(PgRepositoryCore::new(pool));
+        let now = Local::now().naive_local();
+
+        let created = repo
+            .create_worker_kind("GpuWorker", "gpu.tasks", "gpu_queue")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(created.routing_key, "gpu.tasks");
+        assert_eq!(created.queue_name, "gpu_queue");
+        assert!(repo
+            .create_worker_kind("GpuWorker", "other", "other")
+            .await
+            .unwrap()
+            .is_none());
+
+        // Kinds created by a registration follow the SDK conventions
+        repo.save_registration(&WorkerRegistrationUpdate::new(
+            "worker-1",
+            "TestWorker",
+            &["task_a"],
+            now,
+        ))
+        .await
+        .unwrap();
+        let kinds = repo.find_all_worker_kinds().await.unwrap();
+        let summary: Vec<_> = kinds
+            .iter()
+            .map(|kind| {
+                (
+                    kind.name.as_str(),
+                    kind.routing_key.as_str(),
+                    kind.queue_name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("GpuWorker", "gpu.tasks", "gpu_queue"),
+                ("TestWorker", "tasks.TestWorker", "TestWorker")
+            ]
+        );
+        assert_eq!(
+            repo.get_worker_kind("GpuWorker").await.unwrap(),
+            Some(created)
+        );
+        assert_eq!(repo.routing_key_of("GpuWorker").await.unwrap(), "gpu.tasks");
+        assert_eq!(
+            repo.routing_key_of("UnknownWorker").await.unwrap(),
+            "tasks.UnknownWorker"
+        );
+
+        // Kinds still referenced by workers are kept
+        assert_eq!(
+            repo.delete_worker_kind("TestWorker").await.unwrap(),
+            WorkerKindDeletion::InUse
+        );
+        assert_eq!(
+            repo.delete_worker_kind("GpuWorker").await.unwrap(),
+            WorkerKindDeletion::Deleted
+        );
+        assert_eq!(
+            repo.delete_worker_kind("GpuWorker").await.unwrap(),
+            WorkerKindDeletion::NotFound
+        );
+        assert!(repo.get_worker_kind("GpuWorker").await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_get_unknown_worker(pool: PgPool) {
+        let repo = WorkerRepository::new(PgRepositoryCore::new(pool));
+        assert!(repo.get_worker_by_name("unknown").await.unwrap().is_none());
+    }
+}
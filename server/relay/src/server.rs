@@ -1,26 +1,107 @@
+use axum::extract::DefaultBodyLimit;
+use axum::http::StatusCode;
 use axum::Router;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tokio::sync::broadcast;
+use tower_http::compression::predicate::{DefaultPredicate, NotForContentType, Predicate};
+use tower_http::compression::CompressionLayer;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
 use tracing::{error, info};
 
+use crate::constants::{
+    DEFAULT_MAX_PAYLOAD_BYTES, DEFAULT_MAX_REQUEST_BODY_BYTES, DEFAULT_MAX_SUBMIT_BATCH_SIZE,
+    DEFAULT_REQUEST_TIMEOUT_SECS,
+};
+use crate::rate_limit::{with_rate_limit, RateLimit};
+
+/// Limits applied to every request handled by the server.
+///
+/// # Fields
+/// * `max_body_bytes` - Requests with a larger body are rejected with 413
+/// * `timeout` - Requests taking longer are aborted with 408
+/// * `max_submit_batch_size` - Batch submissions with more tasks are rejected with 400
+/// * `max_payload_bytes` - Submitted tasks with a larger input are rejected with 400
+/// * `allow_avro` - When false, requests only accepting Avro are rejected with 406
+/// * `rate_limit` - Clients over it are rejected with 429, unlimited when `None`
+#[derive(Clone, Debug)]
+pub struct RequestLimits {
+    pub max_body_bytes: usize,
+    pub timeout: Duration,
+    pub max_submit_batch_size: usize,
+    pub max_payload_bytes: usize,
+    pub allow_avro: bool,
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: DEFAULT_MAX_REQUEST_BODY_BYTES,
+            timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+            max_submit_batch_size: DEFAULT_MAX_SUBMIT_BATCH_SIZE,
+            max_payload_bytes: DEFAULT_MAX_PAYLOAD_BYTES,
+            allow_avro: true,
+            rate_limit: None,
+        }
+    }
+}
+
+/// Wraps a router with the body size, timeout and rate limits.
+///
+/// # Arguments
+///
+/// * `router` - The router to wrap
+/// * `limits` - The limits to enforce
+pub fn with_request_limits(router: Router, limits: &RequestLimits) -> Router {
+    let router = router
+        // Extractors have their own, smaller, default limit
+        .layer(DefaultBodyLimit::max(limits.max_body_bytes))
+        .layer(RequestBodyLimitLayer::new(limits.max_body_bytes))
+        .layer(TimeoutLayer::with_status_code(
+            StatusCode::REQUEST_TIMEOUT,
+            limits.timeout,
+        ));
+    // Outermost, so throttled requests are rejected before anything else runs
+    match limits.rate_limit {
+        Some(limit) => with_rate_limit(router, limit),
+        None => router,
+    }
+}
+
+/// Compresses responses with gzip, deflate or brotli for clients sending
+/// `Accept-Encoding`. Avro and raw binary bodies are sent as is, as they
+/// gain little from it.
+///
+/// # Arguments
+///
+/// * `router` - The router to wrap
+pub fn with_compression(router: Router) -> Router {
+    let predicate = DefaultPredicate::new()
+        .and(NotForContentType::const_new("application/avro"))
+        .and(NotForContentType::const_new("application/octet-stream"));
+    router.layer(CompressionLayer::new().compress_when(predicate))
+}
+
 pub struct Server {
     app: Router,
-    port: u16,
+    addr: SocketAddr,
     shutdown_rx: broadcast::Receiver<()>,
 }
 
 impl Server {
-    /// Creates a new server that will listen on the given port
+    /// Creates a new server that will listen on the given address
     ///
     /// # Arguments
     ///
     /// * `app` - The axum Router to serve
-    /// * `port` - The port to listen on
+    /// * `addr` - The interface and port to listen on
     /// * `shutdown_rx` - Receiver for shutdown signals
-    pub fn new(app: Router, port: u16, shutdown_rx: broadcast::Receiver<()>) -> Self {
+    pub fn new(app: Router, addr: SocketAddr, shutdown_rx: broadcast::Receiver<()>) -> Self {
         Self {
             app,
-            port,
+            addr,
             shutdown_rx,
         }
     }
@@ -31,24 +112,121 @@ impl Server {
     ///
     /// A result indicating success or failure
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let addr = SocketAddr::from(([0, 0, 0, 0], self.port));
-        info!(address = %addr, "Starting server");
+        info!(address = %self.addr, "Starting server");
 
-        let listener = tokio::net::TcpListener::bind(&addr).await?;
-        info!(address = %addr, "Server listening");
+        let listener = tokio::net::TcpListener::bind(&self.addr).await?;
+        info!(address = %listener.local_addr()?, "Server listening");
 
         let app = self.app.clone();
         let mut shutdown_rx = self.shutdown_rx.resubscribe();
 
-        axum::serve(listener, app)
-            .with_graceful_shutdown(async move {
-                if let Err(e) = shutdown_rx.recv().await {
-                    error!(error = %e, "Error receiving shutdown signal");
-                }
-                info!("Server shutdown signal received");
-            })
-            .await?;
+        // The rate limit tells clients apart by their address
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move {
+            if let Err(e) = shutdown_rx.recv().await {
+                error!(error = %e, "Error receiving shutdown signal");
+            }
+            info!("Server shutdown signal received");
+        })
+        .await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::header;
+    use axum::routing::{get, post};
+    use axum_test::TestServer;
+
+    fn test_server(limits: &RequestLimits) -> TestServer {
+        let router = Router::new()
+            .route("/echo", post(|body: String| async move { body }))
+            .route(
+                "/slow",
+                post(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }),
+            );
+        TestServer::new(with_request_limits(router, limits)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limit() {
+        let server = test_server(&RequestLimits {
+            max_body_bytes: 16,
+            ..RequestLimits::default()
+        });
+
+        let response = server.post("/echo").text("small body").await;
+        response.assert_status_ok();
+        response.assert_text("small body");
+    }
+
+    #[tokio::test]
+    async fn test_body_too_large() {
+        let server = test_server(&RequestLimits {
+            max_body_bytes: 16,
+            ..RequestLimits::default()
+        });
+
+        let response = server
+            .post("/echo")
+            .text("a body that is way over the limit")
+            .await;
+        response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let server = test_server(&RequestLimits {
+            timeout: Duration::from_millis(50),
+            ..RequestLimits::default()
+        });
+
+        let response = server.post("/slow").await;
+        response.assert_status(StatusCode::REQUEST_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_compression() {
+        let body = "[".to_string() + &vec!["{\"name\": \"task\"}"; 500].join(",") + "]";
+        let json_body = body.clone();
+        let avro_body = body.clone();
+        let router = Router::new()
+            .route(
+                "/json",
+                get(|| async move { ([(header::CONTENT_TYPE, "application/json")], json_body) }),
+            )
+            .route(
+                "/avro",
+                get(|| async move { ([(header::CONTENT_TYPE, "application/avro")], avro_body) }),
+            );
+        let server = TestServer::new(with_compression(router)).unwrap();
+
+        let response = server
+            .get("/json")
+            .add_header(header::ACCEPT_ENCODING, "gzip")
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header(header::CONTENT_ENCODING), "gzip");
+        assert!(response.as_bytes().len() < body.len());
+
+        // Clients that don't ask for compression get the plain body
+        let response = server.get("/json").await;
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        response.assert_text(&body);
+
+        let response = server
+            .get("/avro")
+            .add_header(header::ACCEPT_ENCODING, "gzip")
+            .await;
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(response.as_bytes().len(), body.len());
+    }
+}
@@ -0,0 +1,180 @@
+use std::error::Error;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::repo::is_transient;
+
+/// Whether a handler error means the database is unreachable, rather than
+/// something wrong with the events themselves. The repositories already
+/// retried these for a while before giving up.
+pub fn is_database_outage(error: &(dyn Error + Send + Sync + 'static)) -> bool {
+    error
+        .downcast_ref::<sqlx::Error>()
+        .is_some_and(is_transient)
+}
+
+/// When the circuit breaker stops consumption.
+///
+/// # Fields
+/// * `failure_threshold` - How many batches in a row must fail because the
+///   database is unreachable before consumption pauses, 0 to never pause
+/// * `cooldown` - How long consumption pauses before the database is probed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerSettings {
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open: bool,
+}
+
+/// Pauses consumption while the database is down, so deliveries stay queued
+/// on the broker instead of burning their retries in a failure storm.
+///
+/// The breaker opens after `failure_threshold` consecutive failures, then
+/// stays open until a probe succeeds. Probes run once per cooldown.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    settings: CircuitBreakerSettings,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(settings: CircuitBreakerSettings) -> Self {
+        Self {
+            settings,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether consumption is paused.
+    pub fn is_open(&self) -> bool {
+        self.state
+            .lock()
+            .expect("Circuit breaker lock poisoned")
+            .open
+    }
+
+    /// Records a batch that reached the database.
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().expect("Circuit breaker lock poisoned");
+        state.consecutive_failures = 0;
+        state.open = false;
+    }
+
+    /// Records a batch that failed because the database is unreachable.
+    ///
+    /// # Returns
+    /// Whether the breaker is open
+    pub fn record_failure(&self) -> bool {
+        let mut state = self.state.lock().expect("Circuit breaker lock poisoned");
+        state.consecutive_failures += 1;
+
+        let threshold = self.settings.failure_threshold;
+        if !state.open && threshold > 0 && state.consecutive_failures >= threshold {
+            warn!(
+                consecutive_failures = state.consecutive_failures,
+                cooldown_secs = self.settings.cooldown.as_secs_f64(),
+                "Database unreachable, pausing consumption"
+            );
+            state.open = true;
+        }
+        state.open
+    }
+
+    /// Waits until the breaker is closed. While it is open, `probe` is run
+    /// after every cooldown and closes the breaker once it succeeds.
+    ///
+    /// # Arguments
+    /// * `probe` - Checks whether the database is reachable again
+    pub async fn wait_until_closed<F, Fut>(&self, probe: F)
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        while self.is_open() {
+            tokio::time::sleep(self.settings.cooldown).await;
+            // Another waiter may have closed it meanwhile
+            if !self.is_open() {
+                break;
+            }
+            if probe().await {
+                info!("Database reachable again, resuming consumption");
+                self.record_success();
+            } else {
+                warn!("Database still unreachable, keeping consumption paused");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn breaker(failure_threshold: u32) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerSettings {
+            failure_threshold,
+            cooldown: Duration::from_millis(10),
+        })
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = breaker(3);
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+
+        // A success in between starts the count over
+        breaker.record_success();
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        assert!(breaker.record_failure());
+        assert!(breaker.is_open());
+    }
+
+    #[test]
+    fn test_is_database_outage() {
+        let outage: Box<dyn Error + Send + Sync> = Box::new(sqlx::Error::PoolTimedOut);
+        assert!(is_database_outage(outage.as_ref()));
+
+        let permanent: Box<dyn Error + Send + Sync> = Box::new(sqlx::Error::RowNotFound);
+        assert!(!is_database_outage(permanent.as_ref()));
+
+        let other: Box<dyn Error + Send + Sync> = "Invalid event".into();
+        assert!(!is_database_outage(other.as_ref()));
+    }
+
+    #[test]
+    fn test_zero_threshold_never_opens() {
+        let breaker = breaker(0);
+        for _ in 0..100 {
+            assert!(!breaker.record_failure());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_closed_probes_after_cooldown() {
+        let breaker = breaker(1);
+        let probes = AtomicU32::new(0);
+
+        // Nothing to wait for while closed
+        breaker
+            .wait_until_closed(|| async { panic!("Closed breakers aren't probed") })
+            .await;
+
+        breaker.record_failure();
+        breaker
+            .wait_until_closed(|| async { probes.fetch_add(1, Ordering::SeqCst) >= 2 })
+            .await;
+
+        assert_eq!(probes.load(Ordering::SeqCst), 3);
+        assert!(!breaker.is_open());
+    }
+}
@@ -0,0 +1,287 @@
+use crate::models::{
+    TaskAcknowledgedUpdate, TaskAssignmentUpdate, TaskBatchCompletedUpdate, TaskCompletedUpdate,
+    TaskRunningUpdate, WorkerHeartbeatUpdate, WorkerRegistrationUpdate,
+};
+use crate::task_event_consumer::event_parsing::{
+    try_parse_event_from_avro_bytes, Event, EventType, MessageProcessingError,
+};
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Encodes and decodes the payload of task event messages. The event type
+/// travels separately (in the `message_type` header), so codecs only deal
+/// with the body.
+pub trait MessageCodec: Debug + Send + Sync {
+    /// The MIME type of the encoded payloads, set as the message content type
+    fn content_type(&self) -> &'static str;
+
+    /// Encodes the data inside an event
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to encode
+    fn encode(&self, event: &Event) -> Result<Vec<u8>, MessageProcessingError>;
+
+    /// Decodes a payload into an event of the given type and validates it
+    ///
+    /// # Arguments
+    ///
+    /// * `event_type` - The type of the event the payload holds
+    /// * `bytes` - The payload to decode
+    fn decode(&self, event_type: EventType, bytes: &[u8]) -> Result<Event, MessageProcessingError>;
+}
+
+/// Avro binary encoding, using the schemas shared with the client SDKs. This
+/// is the format TacoQ services exchange by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AvroCodec;
+
+impl MessageCodec for AvroCodec {
+    fn content_type(&self) -> &'static str {
+        "application/avro"
+    }
+
+    fn encode(&self, event: &Event) -> Result<Vec<u8>, MessageProcessingError> {
+        event.try_into_avro_bytes()
+    }
+
+    fn decode(&self, event_type: EventType, bytes: &[u8]) -> Result<Event, MessageProcessingError> {
+        try_parse_event_from_avro_bytes(event_type, bytes)
+    }
+}
+
+/// JSON encoding of the same models. Timestamps are encoded as microseconds
+/// since the epoch and binary data as arrays of bytes, like their Avro
+/// counterparts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+fn from_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, MessageProcessingError> {
+    serde_json::from_slice(bytes)
+        .map_err(|e| MessageProcessingError::JsonDeserializationError(e.to_string()))
+}
+
+impl MessageCodec for JsonCodec {
+    fn content_type(&self) -> &'static str {
+        "application/json"
+    }
+
+    fn encode(&self, event: &Event) -> Result<Vec<u8>, MessageProcessingError> {
+        let bytes = match event {
+            Event::Assignment(assignment) => serde_json::to_vec(assignment),
+            Event::Acknowledged(acknowledged) => serde_json::to_vec(acknowledged),
+            Event::Completed(completed) => serde_json::to_vec(completed),
+            Event::BatchCompleted(batch) => serde_json::to_vec(batch),
+            Event::Running(running) => serde_json::to_vec(running),
+            Event::Heartbeat(heartbeat) => serde_json::to_vec(heartbeat),
+            Event::Registration(registration) => serde_json::to_vec(registration),
+        };
+        bytes.map_err(|e| MessageProcessingError::JsonSerializationError(e.to_string()))
+    }
+
+    fn decode(&self, event_type: EventType, bytes: &[u8]) -> Result<Event, MessageProcessingError> {
+        // Validate message integrity like the Avro path does
+        let invalid = MessageProcessingError::JsonDeserializationError;
+        match event_type {
+            EventType::Assignment => {
+                let assignment: TaskAssignmentUpdate = from_json(bytes)?;
+                assignment.validate_update_type().map_err(invalid)?;
+                Ok(Event::Assignment(assignment))
+            }
+            EventType::Acknowledged => {
+                let acknowledged: TaskAcknowledgedUpdate = from_json(bytes)?;
+                acknowledged.validate_update_type().map_err(invalid)?;
+                Ok(Event::Acknowledged(acknowledged))
+            }
+            EventType::Completed => {
+                let completed: TaskCompletedUpdate = from_json(bytes)?;
+                completed.validate_update_type().map_err(invalid)?;
+                Ok(Event::Completed(completed))
+            }
+            EventType::BatchCompleted => {
+                let batch: TaskBatchCompletedUpdate = from_json(bytes)?;
+                batch.validate_update_type().map_err(invalid)?;
+                Ok(Event::BatchCompleted(batch))
+            }
+            EventType::Running => {
+                let running: TaskRunningUpdate = from_json(bytes)?;
+                running.validate_update_type().map_err(invalid)?;
+                Ok(Event::Running(running))
+            }
+            EventType::Heartbeat => {
+                let heartbeat: WorkerHeartbeatUpdate = from_json(bytes)?;
+                heartbeat.validate_update_type().map_err(invalid)?;
+                Ok(Event::Heartbeat(heartbeat))
+            }
+            EventType::Registration => {
+                let registration: WorkerRegistrationUpdate = from_json(bytes)?;
+                registration.validate_update_type().map_err(invalid)?;
+                Ok(Event::Registration(registration))
+            }
+        }
+    }
+}
+
+/// The format the relay exchanges messages in, set with `TACOQ_WIRE_FORMAT`.
+/// It picks the codec of the published events and of the consumed ones
+/// without a content type, unless `TACOQ_OUTBOUND_WIRE_FORMAT` or
+/// `TACOQ_INBOUND_WIRE_FORMAT` override one direction. In JSON mode the API
+/// doesn't serve Avro either, so deployments can skip Avro entirely.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    #[default]
+    Avro,
+    Json,
+}
+
+impl WireFormat {
+    /// The codec of the format.
+    pub fn codec(&self) -> Arc<dyn MessageCodec> {
+        match self {
+            WireFormat::Avro => Arc::new(AvroCodec),
+            WireFormat::Json => Arc::new(JsonCodec),
+        }
+    }
+}
+
+impl FromStr for WireFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "avro" => Ok(WireFormat::Avro),
+            "json" => Ok(WireFormat::Json),
+            _ => Err("expected avro or json".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn test_events() -> Vec<Event> {
+        let now = Local::now().naive_local();
+        let id = Uuid::new_v4();
+        vec![
+            Event::Assignment(TaskAssignmentUpdate {
+                id,
+                task_kind: "test_task".to_string(),
+                worker_kind: "test_worker".to_string(),
+                created_at: now,
+                input_data: vec![1, 2, 3],
+                priority: 1,
+                ttl_duration: 3600,
+                otel_ctx_carrier: HashMap::from([("traceparent".to_string(), "00".to_string())]),
+                ..Default::default()
+            }),
+            Event::Acknowledged(TaskAcknowledgedUpdate::new(id, now)),
+            Event::Running(TaskRunningUpdate::new(id, now, "worker-1".to_string())),
+            Event::Completed(TaskCompletedUpdate::new(id, now, vec![4, 5, 6], true)),
+            Event::BatchCompleted(TaskBatchCompletedUpdate::new(vec![
+                TaskCompletedUpdate::new(id, now, vec![7], false),
+                TaskCompletedUpdate::new(Uuid::new_v4(), now, vec![8], true),
+            ])),
+            Event::Heartbeat(WorkerHeartbeatUpdate::new("worker-1", "test_worker", now)),
+            Event::Registration(WorkerRegistrationUpdate::new(
+                "worker-1",
+                "test_worker",
+                &["test_task"],
+                now,
+            )),
+        ]
+    }
+
+    fn assert_round_trip(codec: &dyn MessageCodec) {
+        for event in test_events() {
+            let bytes = codec.encode(&event).unwrap();
+            let decoded = codec.decode(event.event_type(), &bytes).unwrap();
+
+            // Timestamps only survive with microsecond precision
+            match (&event, &decoded) {
+                (Event::Assignment(a), Event::Assignment(b)) => {
+                    assert_eq!(a.id, b.id);
+                    assert_eq!(a.input_data, b.input_data);
+                    assert_eq!(a.ttl_duration, b.ttl_duration);
+                    assert_eq!(a.otel_ctx_carrier, b.otel_ctx_carrier);
+                }
+                (Event::Acknowledged(a), Event::Acknowledged(b)) => {
+                    assert_eq!(a.id, b.id);
+                    assert_eq!(
+                        a.acknowledged_at.and_utc().timestamp_micros(),
+                        b.acknowledged_at.and_utc().timestamp_micros()
+                    );
+                }
+                (Event::Running(a), Event::Running(b)) => {
+                    assert_eq!(a.id, b.id);
+                    assert_eq!(a.executed_by, b.executed_by);
+                }
+                (Event::Completed(a), Event::Completed(b)) => {
+                    assert_eq!(a.id, b.id);
+                    assert_eq!(a.output_data, b.output_data);
+                    assert_eq!(a.is_error, b.is_error);
+                }
+                (Event::BatchCompleted(a), Event::BatchCompleted(b)) => {
+                    assert_eq!(a.completions.len(), b.completions.len());
+                    for (a, b) in a.completions.iter().zip(&b.completions) {
+                        assert_eq!(a.id, b.id);
+                        assert_eq!(a.output_data, b.output_data);
+                        assert_eq!(a.is_error, b.is_error);
+                    }
+                }
+                (Event::Heartbeat(a), Event::Heartbeat(b)) => {
+                    assert_eq!(a.worker_name, b.worker_name);
+                    assert_eq!(
+                        a.heartbeat_at.and_utc().timestamp_micros(),
+                        b.heartbeat_at.and_utc().timestamp_micros()
+                    );
+                }
+                (Event::Registration(a), Event::Registration(b)) => {
+                    assert_eq!(a.worker_name, b.worker_name);
+                    assert_eq!(a.task_kinds, b.task_kinds);
+                    assert_eq!(
+                        a.registered_at.and_utc().timestamp_micros(),
+                        b.registered_at.and_utc().timestamp_micros()
+                    );
+                }
+                _ => panic!("Decoded event has the wrong type: {:?}", decoded),
+            }
+        }
+    }
+
+    #[test]
+    fn test_avro_codec_round_trip() {
+        assert_round_trip(&AvroCodec);
+    }
+
+    #[test]
+    fn test_json_codec_round_trip() {
+        assert_round_trip(&JsonCodec);
+    }
+
+    #[test]
+    fn test_wire_format() {
+        assert_eq!(WireFormat::default(), WireFormat::Avro);
+        assert_eq!("json".parse::<WireFormat>(), Ok(WireFormat::Json));
+        assert_eq!(WireFormat::Avro.codec().content_type(), "application/avro");
+        assert_eq!(WireFormat::Json.codec().content_type(), "application/json");
+        assert!("msgpack".parse::<WireFormat>().is_err());
+    }
+
+    #[test]
+    fn test_json_codec_rejects_mismatched_update_type() {
+        let running = Event::Running(TaskRunningUpdate::new(
+            Uuid::new_v4(),
+            Local::now().naive_local(),
+            "worker-1".to_string(),
+        ));
+        let bytes = JsonCodec.encode(&running).unwrap();
+
+        assert!(JsonCodec.decode(EventType::Completed, &bytes).is_err());
+    }
+}
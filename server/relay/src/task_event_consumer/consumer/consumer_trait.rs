@@ -6,7 +6,18 @@ use std::sync::Arc;
 // The TaskEventCore trait represents the lowest form of the task event consumer.
 // It is used to check the health of the consumer.
 pub trait TaskEventCore: Send + Sync {
-    async fn health_check(&self) -> Result<(), Box<dyn Error + Send + Sync>>;
+    /// Whether the broker connection is usable. Must not have side effects on
+    /// the broker, as it is polled by the health endpoint.
+    async fn is_healthy(&self) -> bool;
+
+    /// Checks the broker connection, describing the problem if it is unhealthy
+    async fn health_check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.is_healthy().await {
+            Ok(())
+        } else {
+            Err("Broker connection is not healthy".into())
+        }
+    }
 }
 
 /// A Task Event Consumer consumes task events from the broker and continuously
@@ -34,3 +45,22 @@ pub trait TaskEventConsumer: Send + Sync {
     // Method that returns a dyn TaskEventCore trait object that allows the API to check the health of the consumer
     async fn core(&self) -> Result<Arc<Self::Core>, Box<dyn Error + Send + Sync>>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StubCore(bool);
+
+    impl TaskEventCore for StubCore {
+        async fn is_healthy(&self) -> bool {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_health_check_follows_is_healthy() {
+        assert!(StubCore(true).health_check().await.is_ok());
+        assert!(StubCore(false).health_check().await.is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use lapin::options::BasicConsumeOptions;
+use std::str::FromStr;
+
+/// When the broker considers a delivery handled.
+///
+/// With manual acknowledgements, a delivery is acknowledged once its event
+/// was stored, so events survive a crash of the relay and are handled at
+/// least once. With automatic acknowledgements, the broker forgets a delivery
+/// as soon as it sent it. Events in flight when the relay stops are lost, so
+/// they are handled at most once, but the broker doesn't wait on the relay
+/// and ignores the prefetch count, which makes consumption faster.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AckMode {
+    /// Acknowledge deliveries once handled, at least once delivery
+    #[default]
+    Manual,
+    /// Let the broker acknowledge deliveries when sending them, at most once
+    /// delivery
+    Auto,
+}
+
+impl AckMode {
+    /// The options to consume a queue with in this mode.
+    pub fn consume_options(&self) -> BasicConsumeOptions {
+        BasicConsumeOptions {
+            no_ack: *self == AckMode::Auto,
+            ..BasicConsumeOptions::default()
+        }
+    }
+
+    /// Whether the relay acknowledges deliveries itself.
+    pub fn is_manual(&self) -> bool {
+        *self == AckMode::Manual
+    }
+}
+
+impl FromStr for AckMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manual" => Ok(AckMode::Manual),
+            "auto" => Ok(AckMode::Auto),
+            _ => Err(format!("Unknown acknowledgement mode: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consume_options() {
+        assert!(!AckMode::Manual.consume_options().no_ack);
+        assert!(AckMode::Auto.consume_options().no_ack);
+        assert!(!AckMode::default().consume_options().no_ack);
+    }
+
+    #[test]
+    fn test_ack_mode_from_str() {
+        assert_eq!("manual".parse(), Ok(AckMode::Manual));
+        assert_eq!("auto".parse(), Ok(AckMode::Auto));
+        assert!("none".parse::<AckMode>().is_err());
+    }
+}
@@ -1,119 +1,575 @@
-use crate::repo::task_repo::TaskRepository;
+use crate::constants::RELAY_EVENT_EXCHANGE;
+use crate::health_probe::Readiness;
+use crate::models::QueueDepth;
+use crate::repo::{TaskRepository, WorkerRepository};
 use crate::task_event_consumer::consumer::TaskEventCore;
 use crate::task_event_consumer::{
-    event_parsing::Event, handler::TaskEventHandler, TaskEventConsumer,
+    circuit_breaker::{is_database_outage, CircuitBreaker, CircuitBreakerSettings},
+    codec::MessageCodec,
+    event_parsing::{Event, EventType},
+    handler::TaskEventHandler,
+    lag::ConsumerLag,
+    metrics::{ConsumeErrorKind, ConsumerMetrics},
+    TaskEventConsumer,
 };
-use futures::StreamExt;
+use futures::channel::mpsc;
+use futures::future::join_all;
+use futures::{SinkExt, Stream, StreamExt};
 use lapin::message::Delivery;
-use lapin::options::QueueDeclareOptions;
-use lapin::options::{BasicAckOptions, BasicConsumeOptions};
+use lapin::options::{
+    BasicAckOptions, BasicNackOptions, BasicPublishOptions, BasicQosOptions, ExchangeBindOptions,
+    ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions,
+};
 use lapin::types::FieldTable;
-use lapin::{Channel, Consumer};
+use lapin::{Channel, Consumer, ExchangeKind};
 use std::error::Error;
+use std::future::Future;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::atomic::Ordering;
 use std::sync::{atomic::AtomicBool, Arc};
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify};
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
-use super::connection::RabbitMQConnection;
+use super::ack_mode::AckMode;
+use super::connection::{BrokerTlsConfig, RabbitMQConnection};
+use super::decoding::decode_delivery;
+use super::queue_arguments::QueueArguments;
+use super::retry::{retry_count, retry_decision, with_retry_count, RetryDecision};
+use super::routing::{binding_arguments, EventRouting};
 
-static QUEUE_NAME: &str = "tacoq_relay_queue";
+/// Name of the queue holding the deliveries of `queue` that could not be
+/// handled, either because they can't be parsed or because they ran out of
+/// retries.
+fn dead_letter_queue_name(queue: &str) -> String {
+    format!("{}_dlq", queue)
+}
 
-pub struct RabbitMQTaskEventCore {
+/// Builds a consumer tag that is unique across relay replicas, so replicas
+/// attached to the same queue can be told apart on the broker.
+///
+/// # Arguments
+///
+/// * `prefix` - The configured tag prefix
+fn unique_consumer_tag(prefix: &str) -> String {
+    let hostname = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .filter(|hostname| !hostname.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    let suffix = Uuid::new_v4().simple().to_string();
+
+    format!("{}-{}-{}", prefix, hostname, &suffix[..8])
+}
+
+/// Settings controlling how the consumer reads from the broker.
+///
+/// # Fields
+/// * `queues` - The queues to consume from
+/// * `event_routing` - Dedicated queues of some event types, consumed along
+///   with `queues`
+/// * `exchange` - The exchange task events are published to, which the
+///   dedicated queues receive their events from
+/// * `exchange_kind` - The kind of `exchange`
+/// * `max_retries` - How many times a delivery that fails to be handled is
+///   re-published before being moved to the dead letter queue
+/// * `consumer_tag_prefix` - Prefix of the consumer tag, completed with the
+///   hostname and a random suffix
+/// * `prefetch_count` - How many unacknowledged deliveries the broker sends
+///   to each queue consumer at once
+/// * `batch_size` - How many deliveries are handled and acknowledged together
+/// * `batch_timeout` - How long to wait for a batch to fill once its first
+///   delivery arrived
+/// * `concurrency` - How many batches of each queue are handled at once.
+///   Events of the same task or worker are always handled in order
+/// * `queue_arguments` - Optional arguments the consumed queues are declared with
+/// * `codec` - The codec the consumed payloads are encoded with
+/// * `max_message_bytes` - Largest delivery decoded, bigger ones are dead
+///   lettered without being parsed
+/// * `ack_mode` - Whether deliveries are acknowledged once handled or by the
+///   broker when sent
+/// * `max_payload_bytes` - Largest task input or output stored
+/// * `dedup_window` - How many recently handled task events are remembered to
+///   skip their redeliveries, 0 to disable
+/// * `circuit_breaker` - When consumption pauses while the database is down
+/// * `idle_timeout` - How long a queue may go without deliveries before its
+///   channel is checked, `None` to wait indefinitely
+#[derive(Clone, Debug)]
+pub struct ConsumerSettings {
+    pub queues: Vec<String>,
+    pub event_routing: EventRouting,
+    pub exchange: String,
+    pub exchange_kind: ExchangeKind,
+    pub max_retries: u32,
+    pub consumer_tag_prefix: String,
+    pub prefetch_count: u16,
+    pub batch_size: usize,
+    pub batch_timeout: Duration,
+    pub concurrency: usize,
+    pub queue_arguments: QueueArguments,
+    pub codec: Arc<dyn MessageCodec>,
+    pub max_message_bytes: usize,
+    pub ack_mode: AckMode,
+    pub max_payload_bytes: usize,
+    pub dedup_window: usize,
+    pub circuit_breaker: CircuitBreakerSettings,
+    pub idle_timeout: Option<Duration>,
+}
+
+/// Waits for the next item of a stream, then collects more until `max` items
+/// were gathered or `timeout` elapsed.
+///
+/// # Arguments
+///
+/// * `stream` - The stream to read from
+/// * `max` - The maximum number of items in the batch
+/// * `timeout` - How long to wait for the batch to fill after the first item
+///
+/// # Returns
+/// The batch, or `None` if the stream ended before any item arrived
+async fn next_batch<S>(stream: &mut S, max: usize, timeout: Duration) -> Option<Vec<S::Item>>
+where
+    S: Stream + Unpin,
+{
+    let first = stream.next().await?;
+    let mut batch = vec![first];
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    while batch.len() < max {
+        match tokio::time::timeout_at(deadline, stream.next()).await {
+            Ok(Some(item)) => batch.push(item),
+            // The stream ended or the batch timed out, hand over what we have
+            Ok(None) | Err(_) => break,
+        }
+    }
+
+    Some(batch)
+}
+
+/// The outcome of waiting for the next item of a stream with [`next_or_idle`].
+#[derive(Debug, PartialEq)]
+enum NextItem<T> {
+    Item(T),
+    /// The stream ended
+    Ended,
+    /// Nothing arrived within the idle timeout
+    Idle,
+}
+
+/// Waits for the next item of a stream, giving up once `idle_timeout`
+/// elapsed so a connection that died silently can't hang the consumer.
+///
+/// # Arguments
+///
+/// * `stream` - The stream to read from
+/// * `idle_timeout` - How long to wait, `None` to wait indefinitely
+async fn next_or_idle<S>(stream: &mut S, idle_timeout: Option<Duration>) -> NextItem<S::Item>
+where
+    S: Stream + Unpin,
+{
+    let next = match idle_timeout {
+        Some(idle_timeout) => match tokio::time::timeout(idle_timeout, stream.next()).await {
+            Ok(next) => next,
+            Err(_) => return NextItem::Idle,
+        },
+        None => stream.next().await,
+    };
+
+    match next {
+        Some(item) => NextItem::Item(item),
+        None => NextItem::Ended,
+    }
+}
+
+/// Checks a queue that went idle. A quiet queue whose channel is still
+/// connected is left alone, while a dead channel is replaced.
+///
+/// # Arguments
+///
+/// * `connected` - Whether the channel of the queue is still connected
+/// * `reconnect` - Opens a new channel and consumer
+///
+/// # Returns
+/// The new channel and consumer, if the queue was reconnected
+async fn recover_idle<T, F, Fut>(connected: bool, reconnect: F) -> Option<T>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn Error + Send + Sync>>>,
+{
+    if connected {
+        return None;
+    }
+
+    match reconnect().await {
+        Ok(reconnected) => Some(reconnected),
+        Err(e) => {
+            error!(error = %e, "Failed to reconnect idle queue");
+            None
+        }
+    }
+}
+
+/// A delivery waiting on a lane, with the channel it must be acknowledged on.
+struct PendingDelivery {
     channel: Channel,
+    delivery: Delivery,
+    event: Event,
 }
 
-impl TaskEventCore for RabbitMQTaskEventCore {
-    async fn health_check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        if !self.channel.status().connected() {
-            return Err("RabbitMQ channel is not connected".into());
+/// Picks the lane of an ordering key. The same key always maps to the same
+/// lane, so its items are handled in the order they were sent.
+fn lane_index(key: &str, lanes: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % lanes as u64) as usize
+}
+
+/// Sends an item to the lane of its ordering key, waiting while the lane is full.
+async fn send_to_lane<T>(
+    lanes: &mut [mpsc::Sender<T>],
+    key: &str,
+    item: T,
+) -> Result<(), mpsc::SendError> {
+    let lane = lane_index(key, lanes.len());
+    lanes[lane].send(item).await
+}
+
+/// Runs a dispatcher feeding `count` lanes, each handled concurrently. The
+/// lanes stop once the dispatcher returns and they are drained.
+///
+/// # Arguments
+///
+/// * `count` - The number of lanes
+/// * `capacity` - How many items each lane buffers before the dispatcher waits
+/// * `dispatch` - Sends items to the lanes it is given
+/// * `handle` - Handles the items of a single lane
+///
+/// # Returns
+/// The result of the dispatcher
+async fn run_lanes<T, D, DFut, H, HFut>(
+    count: usize,
+    capacity: usize,
+    dispatch: D,
+    handle: H,
+) -> DFut::Output
+where
+    D: FnOnce(Vec<mpsc::Sender<T>>) -> DFut,
+    DFut: Future,
+    H: Fn(mpsc::Receiver<T>) -> HFut,
+    HFut: Future<Output = ()>,
+{
+    let (senders, receivers): (Vec<_>, Vec<_>) =
+        (0..count).map(|_| mpsc::channel(capacity)).unzip();
+    let (dispatched, _) = futures::join!(
+        dispatch(senders),
+        join_all(receivers.into_iter().map(handle))
+    );
+    dispatched
+}
+
+/// Runs one consumer per queue concurrently and waits for all of them to stop.
+/// A queue failing doesn't stop the others.
+///
+/// # Arguments
+///
+/// * `queues` - The queues to consume from
+/// * `consume` - Consumes a single queue until shutdown
+///
+/// # Returns
+/// The first error returned by a consumer, if any
+async fn consume_queues<'a, F, Fut>(
+    queues: &'a [String],
+    consume: F,
+) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    F: Fn(&'a str) -> Fut,
+    Fut: Future<Output = Result<(), Box<dyn Error + Send + Sync>>>,
+{
+    let results = join_all(queues.iter().map(|queue| consume(queue))).await;
+
+    let mut first_error = None;
+    for (queue, result) in queues.iter().zip(results) {
+        if let Err(e) = result {
+            error!(error = %e, queue = %queue, "Queue consumer stopped with an error");
+            first_error.get_or_insert(e);
         }
+    }
 
-        // Check if we can successfully perform a channel operation
-        match self
-            .channel
-            .basic_publish(
-                "",                    // default exchange
-                "health_check_target", // routing key
-                lapin::options::BasicPublishOptions::default(),
-                &[], // empty payload
-                lapin::BasicProperties::default(),
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+pub struct RabbitMQTaskEventCore {
+    connection: RabbitMQConnection,
+}
+
+impl TaskEventCore for RabbitMQTaskEventCore {
+    /// The connection is closed as soon as it fails, so its status is enough
+    /// to tell whether the broker is reachable.
+    async fn is_healthy(&self) -> bool {
+        self.connection.is_connected()
+    }
+
+    async fn health_check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !self.is_healthy().await {
+            return Err(format!(
+                "RabbitMQ connection is not connected (state: {:?})",
+                self.connection.state()
             )
-            .await
-        {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("Failed to publish health check message: {}", e).into()),
+            .into());
         }
+        Ok(())
     }
 }
 
 /// A consumer that listens for task events from RabbitMQ and uploads
-/// them to the task repository.
+/// them to the task repository. Every queue is consumed concurrently on its
+/// own channel, sharing the connection and the event handler.
 pub struct RabbitMQTaskEventConsumer {
     event_handler: TaskEventHandler,
     connection: Arc<Mutex<RabbitMQConnection>>,
+    queues: Vec<String>,
+    relay_queues: Vec<String>,
+    event_routing: EventRouting,
+    exchange: String,
+    exchange_kind: ExchangeKind,
     shutdown: Arc<AtomicBool>,
+    shutdown_notify: Notify,
+    readiness: Readiness,
+    max_retries: u32,
+    consumer_tag: String,
+    prefetch_count: u16,
+    batch_size: usize,
+    batch_timeout: Duration,
+    concurrency: usize,
+    queue_arguments: QueueArguments,
+    codec: Arc<dyn MessageCodec>,
+    max_message_bytes: usize,
+    ack_mode: AckMode,
+    circuit_breaker: CircuitBreaker,
+    idle_timeout: Option<Duration>,
+    metrics: ConsumerMetrics,
 }
 
 impl RabbitMQTaskEventConsumer {
     /// Creates a new RabbitMQ task event consumer. Does not connect directly
     /// as that is instead done in the `lifecycle` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `url_string` - The broker URL
+    /// * `tls` - The TLS settings used for `amqps` URLs
+    /// * `settings` - The queues to consume from and how to consume them
+    /// * `task_repository` - The repository the task events are uploaded to
+    /// * `worker_repository` - The repository the worker heartbeats are uploaded to
+    /// * `shutdown` - Flag signaling the consumer to stop
+    /// * `readiness` - Gate with one step completed per queue once it is declared
     pub async fn new(
         url_string: &str,
+        tls: &BrokerTlsConfig,
+        settings: ConsumerSettings,
         task_repository: Arc<TaskRepository>,
+        worker_repository: Arc<WorkerRepository>,
         shutdown: Arc<AtomicBool>,
+        readiness: Readiness,
     ) -> Result<Self, Box<dyn Error + Send + Sync>> {
-        let connection = RabbitMQConnection::new(url_string).await?;
+        if settings.queues.is_empty() {
+            return Err("At least one queue must be consumed".into());
+        }
+        if settings.batch_size == 0 {
+            return Err("The batch size must be at least 1".into());
+        }
+        if settings.batch_size > usize::from(settings.prefetch_count) {
+            warn!(
+                batch_size = settings.batch_size,
+                prefetch_count = settings.prefetch_count,
+                "Batch size is larger than the prefetch count, batches will never fill up"
+            );
+        }
+        if settings.concurrency == 0 {
+            return Err("The consumer concurrency must be at least 1".into());
+        }
+        if settings.concurrency > usize::from(settings.prefetch_count) {
+            warn!(
+                concurrency = settings.concurrency,
+                prefetch_count = settings.prefetch_count,
+                "Consumer concurrency is larger than the prefetch count, some lanes will stay idle"
+            );
+        }
+
+        let consumer_tag = unique_consumer_tag(&settings.consumer_tag_prefix);
+        info!(
+            consumer_tag = %consumer_tag,
+            prefetch_count = settings.prefetch_count,
+            "Using consumer tag"
+        );
+
+        let event_handler = TaskEventHandler::new(task_repository, worker_repository)
+            .with_max_payload_bytes(settings.max_payload_bytes)
+            .with_dedup_window(settings.dedup_window);
+        let metrics = ConsumerMetrics::new(event_handler.lag());
+
+        let connection = RabbitMQConnection::new(url_string, tls).await?;
         Ok(Self {
             connection: Arc::new(Mutex::new(connection)),
-            event_handler: TaskEventHandler::new(task_repository),
+            queues: settings.event_routing.consumed_queues(&settings.queues),
+            relay_queues: settings.queues,
+            event_routing: settings.event_routing,
+            exchange: settings.exchange,
+            exchange_kind: settings.exchange_kind,
+            event_handler,
             shutdown,
+            shutdown_notify: Notify::new(),
+            readiness,
+            max_retries: settings.max_retries,
+            consumer_tag,
+            prefetch_count: settings.prefetch_count,
+            batch_size: settings.batch_size,
+            batch_timeout: settings.batch_timeout,
+            concurrency: settings.concurrency,
+            queue_arguments: settings.queue_arguments,
+            codec: settings.codec,
+            max_message_bytes: settings.max_message_bytes,
+            ack_mode: settings.ack_mode,
+            circuit_breaker: CircuitBreaker::new(settings.circuit_breaker),
+            idle_timeout: settings.idle_timeout,
+            metrics,
         })
     }
 
-    /// Creates a new RabbitMQ consumer based on a channel.
-    async fn consumer(&self, channel: &Channel) -> Result<Consumer, Box<dyn Error + Send + Sync>> {
-        info!(queue = %QUEUE_NAME, "Connecting to RabbitMQ for consumer");
+    /// How far behind the consumer is, shared with its metrics.
+    pub fn lag(&self) -> ConsumerLag {
+        self.event_handler.lag()
+    }
+
+    /// Reads the depth of every consumed queue and of its dead letter queue.
+    /// The queues are declared passively, so they are only inspected and
+    /// never created. The lookups use their own channel, as the broker
+    /// closes it when a queue is missing.
+    pub async fn queue_depths(&self) -> Result<Vec<QueueDepth>, Box<dyn Error + Send + Sync>> {
+        let channel = self.connection.lock().await.create_channel().await?;
+        let passive = QueueDeclareOptions {
+            passive: true,
+            ..QueueDeclareOptions::default()
+        };
+
+        let mut depths = Vec::with_capacity(self.queues.len());
+        for queue in &self.queues {
+            let declared = channel
+                .queue_declare(queue, passive, FieldTable::default())
+                .await?;
+            let dead_letter = channel
+                .queue_declare(
+                    &dead_letter_queue_name(queue),
+                    passive,
+                    FieldTable::default(),
+                )
+                .await?;
+            depths.push(QueueDepth {
+                queue: queue.clone(),
+                messages: declared.message_count(),
+                consumers: declared.consumer_count(),
+                dead_letter_messages: dead_letter.message_count(),
+            });
+        }
+
+        if let Err(e) = channel.close(200, "Queue depths read").await {
+            debug!(error = %e, "Failed to close the queue depth channel");
+        }
+        Ok(depths)
+    }
+
+    /// Creates a new RabbitMQ consumer for a queue based on a channel.
+    async fn consumer(
+        &self,
+        channel: &Channel,
+        queue: &str,
+    ) -> Result<Consumer, Box<dyn Error + Send + Sync>> {
+        let dead_letter_queue = dead_letter_queue_name(queue);
+        info!(queue = %queue, "Connecting to RabbitMQ for consumer");
 
-        let mut arguments = FieldTable::default();
-        arguments.insert("x-max-priority".into(), 255.into());
+        debug!(
+            queue = %queue,
+            arguments = ?self.queue_arguments,
+            "Declaring queue"
+        );
+        match channel
+            .queue_declare(
+                queue,
+                QueueDeclareOptions {
+                    durable: true,
+                    ..QueueDeclareOptions::default()
+                },
+                self.queue_arguments.field_table(),
+            )
+            .await
+        {
+            Ok(_) => debug!(queue = %queue, "Queue declared successfully"),
+            Err(e) => {
+                error!(error = %e, queue = %queue, "Failed to declare queue");
+                return Err(Box::new(e));
+            }
+        };
 
-        debug!(queue = %QUEUE_NAME, "Declaring queue with priority support");
+        debug!(queue = %dead_letter_queue, "Declaring dead letter queue");
         match channel
             .queue_declare(
-                QUEUE_NAME,
+                &dead_letter_queue,
                 QueueDeclareOptions {
                     durable: true,
                     ..QueueDeclareOptions::default()
                 },
-                arguments,
+                self.queue_arguments.dead_letter_field_table(),
             )
             .await
         {
-            Ok(_) => debug!(queue = %QUEUE_NAME, "Queue declared successfully"),
+            Ok(_) => {
+                debug!(queue = %dead_letter_queue, "Dead letter queue declared successfully")
+            }
             Err(e) => {
-                error!(error = %e, queue = %QUEUE_NAME, "Failed to declare queue");
+                error!(error = %e, queue = %dead_letter_queue, "Failed to declare dead letter queue");
                 return Err(Box::new(e));
             }
         };
 
-        info!(queue = %QUEUE_NAME, "RabbitMQ consumer setup complete");
+        self.bind_events(channel, queue).await?;
+
+        // Limit unacknowledged deliveries so the broker dispatches fairly
+        // across replicas instead of flooding the first one to connect
+        if let Err(e) = channel
+            .basic_qos(self.prefetch_count, BasicQosOptions::default())
+            .await
+        {
+            error!(error = %e, queue = %queue, "Failed to set prefetch count");
+            return Err(Box::new(e));
+        }
+
+        info!(queue = %queue, "RabbitMQ consumer setup complete");
 
         let consumer = match channel
             .basic_consume(
-                QUEUE_NAME,
-                "relay",
-                BasicConsumeOptions::default(),
+                queue,
+                &self.consumer_tag,
+                self.ack_mode.consume_options(),
                 FieldTable::default(),
             )
             .await
         {
             Ok(consumer) => {
-                info!(queue = %QUEUE_NAME, "Consumer registered successfully, waiting for messages");
+                info!(
+                    queue = %queue,
+                    consumer_tag = %self.consumer_tag,
+                    ack_mode = ?self.ack_mode,
+                    "Consumer registered successfully, waiting for messages"
+                );
                 consumer
             }
             Err(e) => {
-                error!(error = %e, queue = %QUEUE_NAME, "Failed to register consumer");
+                error!(error = %e, queue = %queue, "Failed to register consumer");
                 return Err(Box::new(e));
             }
         };
@@ -121,17 +577,244 @@ impl RabbitMQTaskEventConsumer {
         Ok(consumer)
     }
 
-    /// Reconnects to RabbitMQ and returns a new consumer.
-    async fn reconnect(&self) -> Result<Consumer, Box<dyn Error + Send + Sync>> {
-        let mut connection = self.connection.lock().await;
-        *connection = match connection.reconnect().await {
-            Ok(conn) => conn,
-            Err(e) => {
-                error!(error = %e, "Failed to reconnect to RabbitMQ");
-                return Err(e);
+    /// Binds a queue to the events it receives. Without any dedicated queue,
+    /// relay queues are bound to the task exchange and receive every event.
+    /// Otherwise every queue is bound to the routing exchange, which receives
+    /// every event of the task exchange, on the `message_type` header of the
+    /// event types it receives. Bindings left from another routing are
+    /// removed, so no event type reaches two queues.
+    async fn bind_events(
+        &self,
+        channel: &Channel,
+        queue: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let is_relay_queue = self.relay_queues.iter().any(|relay| relay == queue);
+        let is_routed = self.event_routing.is_enabled();
+        let bound_event_types = if is_routed {
+            self.event_routing.bound_event_types(queue, is_relay_queue)
+        } else {
+            Vec::new()
+        };
+
+        let durable = ExchangeDeclareOptions {
+            durable: true,
+            ..ExchangeDeclareOptions::default()
+        };
+        channel
+            .exchange_declare(
+                &self.exchange,
+                self.exchange_kind.clone(),
+                durable,
+                FieldTable::default(),
+            )
+            .await?;
+        channel
+            .exchange_declare(
+                RELAY_EVENT_EXCHANGE,
+                ExchangeKind::Headers,
+                durable,
+                FieldTable::default(),
+            )
+            .await?;
+        if is_routed {
+            channel
+                .exchange_bind(
+                    RELAY_EVENT_EXCHANGE,
+                    &self.exchange,
+                    "#",
+                    ExchangeBindOptions::default(),
+                    FieldTable::default(),
+                )
+                .await?;
+        }
+
+        if is_relay_queue {
+            let result = if is_routed {
+                debug!(queue = %queue, "Unbinding relay queue from every event");
+                channel
+                    .queue_unbind(queue, &self.exchange, "#", FieldTable::default())
+                    .await
+            } else {
+                debug!(queue = %queue, "Binding relay queue to every event");
+                channel
+                    .queue_bind(
+                        queue,
+                        &self.exchange,
+                        "#",
+                        QueueBindOptions::default(),
+                        FieldTable::default(),
+                    )
+                    .await
+            };
+            if let Err(e) = result {
+                error!(error = %e, queue = %queue, "Failed to bind relay queue to the task exchange");
+                return Err(Box::new(e));
             }
+        }
+
+        for event_type in EventType::ALL {
+            let result = if bound_event_types.contains(&event_type) {
+                debug!(queue = %queue, event_type = ?event_type, "Binding queue to event type");
+                channel
+                    .queue_bind(
+                        queue,
+                        RELAY_EVENT_EXCHANGE,
+                        "",
+                        QueueBindOptions::default(),
+                        binding_arguments(event_type),
+                    )
+                    .await
+            } else {
+                channel
+                    .queue_unbind(
+                        queue,
+                        RELAY_EVENT_EXCHANGE,
+                        "",
+                        binding_arguments(event_type),
+                    )
+                    .await
+            };
+            if let Err(e) = result {
+                error!(error = %e, queue = %queue, "Failed to bind queue to its event types");
+                return Err(Box::new(e));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publishes a copy of a delivery to a queue and acknowledges the
+    /// original. If the copy can't be published, the original is requeued so
+    /// it isn't lost. In automatic acknowledgement mode the broker already
+    /// forgot the original, so it is lost instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel the delivery was received on
+    /// * `delivery` - The delivery to move
+    /// * `queue` - The queue to publish the copy to
+    /// * `retry_count` - The retry count to set on the copy
+    async fn republish(
+        &self,
+        channel: &Channel,
+        delivery: &Delivery,
+        queue: &str,
+        retry_count: u32,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let properties = with_retry_count(&delivery.properties, retry_count);
+
+        let published = match channel
+            .basic_publish(
+                "",
+                queue,
+                BasicPublishOptions::default(),
+                &delivery.data,
+                properties,
+            )
+            .await
+        {
+            Ok(confirm) => confirm.await.map(|_| ()),
+            Err(e) => Err(e),
         };
 
+        if let Err(e) = published {
+            if !self.ack_mode.is_manual() {
+                error!(
+                    error = %e,
+                    queue = %queue,
+                    delivery_tag = %delivery.delivery_tag,
+                    "Failed to re-publish message, dropping it"
+                );
+                return Err(Box::new(e));
+            }
+            error!(
+                error = %e,
+                queue = %queue,
+                delivery_tag = %delivery.delivery_tag,
+                "Failed to re-publish message, requeueing it"
+            );
+            channel
+                .basic_nack(
+                    delivery.delivery_tag,
+                    BasicNackOptions {
+                        requeue: true,
+                        ..BasicNackOptions::default()
+                    },
+                )
+                .await?;
+            return Err(Box::new(e));
+        }
+
+        if self.ack_mode.is_manual() {
+            channel
+                .basic_ack(delivery.delivery_tag, BasicAckOptions::default())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Moves a delivery to the dead letter queue of the queue it came from.
+    async fn dead_letter(
+        &self,
+        channel: &Channel,
+        delivery: &Delivery,
+        queue: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dead_letter_queue = dead_letter_queue_name(queue);
+        let retry_count = retry_count(delivery);
+        warn!(
+            queue = %dead_letter_queue,
+            delivery_tag = %delivery.delivery_tag,
+            retry_count = retry_count,
+            "Moving message to the dead letter queue"
+        );
+        self.republish(channel, delivery, &dead_letter_queue, retry_count)
+            .await
+    }
+
+    /// Re-publishes a delivery that failed to be handled with its retry count
+    /// incremented, or moves it to the dead letter queue once it ran out of
+    /// retries.
+    async fn retry_or_dead_letter(
+        &self,
+        channel: &Channel,
+        delivery: &Delivery,
+        queue: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match retry_decision(retry_count(delivery), self.max_retries) {
+            RetryDecision::Retry(retry_count) => {
+                warn!(
+                    queue = %queue,
+                    delivery_tag = %delivery.delivery_tag,
+                    retry_count = retry_count,
+                    max_retries = self.max_retries,
+                    "Re-publishing message for retry"
+                );
+                self.republish(channel, delivery, queue, retry_count).await
+            }
+            RetryDecision::DeadLetter => self.dead_letter(channel, delivery, queue).await,
+        }
+    }
+
+    /// Reconnects to RabbitMQ and returns a new consumer for a queue. The
+    /// connection is shared by all queues, so it is only re-established if
+    /// another queue hasn't done so already.
+    async fn reconnect(
+        &self,
+        queue: &str,
+    ) -> Result<(Channel, Consumer), Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection.lock().await;
+        if !connection.is_connected() {
+            *connection = match connection.reconnect().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!(error = %e, "Failed to reconnect to RabbitMQ");
+                    return Err(e);
+                }
+            };
+            self.metrics.record_reconnect(queue);
+        }
+
         let new_channel = match connection.create_channel().await {
             Ok(ch) => ch,
             Err(e) => {
@@ -139,7 +822,7 @@ impl RabbitMQTaskEventConsumer {
                 return Err(e);
             }
         };
-        let consumer = match self.consumer(&new_channel).await {
+        let consumer = match self.consumer(&new_channel, queue).await {
             Ok(consumer) => consumer,
             Err(e) => {
                 error!(error = %e, "Failed to create consumer");
@@ -147,46 +830,83 @@ impl RabbitMQTaskEventConsumer {
             }
         };
 
-        Ok(consumer)
+        Ok((new_channel, consumer))
     }
-}
 
-impl TaskEventConsumer for RabbitMQTaskEventConsumer {
-    type Core = RabbitMQTaskEventCore;
-
-    fn event_handler(&self) -> &TaskEventHandler {
-        &self.event_handler
-    }
-
-    /// Creates a new RabbitMQ channel.
-    async fn core(&self) -> Result<Arc<Self::Core>, Box<dyn Error + Send + Sync>> {
-        let channel = self.connection.lock().await.create_channel().await?;
-        Ok(Arc::new(RabbitMQTaskEventCore { channel }))
-    }
-
-    async fn lifecycle(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        info!(queue = %QUEUE_NAME, "Starting message consumption");
+    /// Consumes a single queue until the shutdown flag is set. Deliveries
+    /// are spread over `concurrency` lanes handled concurrently, keeping the
+    /// events of a task or worker on the same lane so they stay in order.
+    async fn consume_queue(&self, queue: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        info!(queue = %queue, concurrency = self.concurrency, "Starting message consumption");
 
         let channel = match self.connection.lock().await.create_channel().await {
             Ok(ch) => ch,
             Err(e) => {
-                error!(error = %e, queue = %QUEUE_NAME, "Failed to create channel");
+                error!(error = %e, queue = %queue, "Failed to create channel");
                 return Err(e);
             }
         };
 
-        let mut consumer = match self.consumer(&channel).await {
+        let consumer = match self.consumer(&channel, queue).await {
             Ok(consumer) => consumer,
             Err(e) => {
-                error!(error = %e, queue = %QUEUE_NAME, "Failed to create consumer");
+                error!(error = %e, queue = %queue, "Failed to create consumer");
                 return Err(e);
             }
         };
+        self.readiness
+            .complete_step(&format!("declare queue {}", queue));
+
+        run_lanes(
+            self.concurrency,
+            self.batch_size,
+            |lanes| self.dispatch(queue, channel, consumer, lanes),
+            |lane| self.handle_lane(queue, lane),
+        )
+        .await
+    }
 
-        while let Some(delivery) = consumer.next().await {
-            // Check for shutdown signal every time a message is received
+    /// Reads deliveries from a queue until the shutdown flag is set, sending
+    /// each one to the lane of its event. Deliveries that can't be parsed are
+    /// dead lettered right away.
+    async fn dispatch(
+        &self,
+        queue: &str,
+        mut channel: Channel,
+        mut consumer: Consumer,
+        mut lanes: Vec<mpsc::Sender<PendingDelivery>>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        loop {
+            // Deliveries stay on the broker while the database is down
+            self.wait_for_database().await;
+            // An idle queue must not hold up the shutdown
+            let next = tokio::select! {
+                next = next_or_idle(&mut consumer, self.idle_timeout) => next,
+                _ = self.shutdown_requested() => NextItem::Ended,
+            };
+            let delivery = match next {
+                NextItem::Item(delivery) => delivery,
+                NextItem::Ended => break,
+                // The broker heartbeat can miss a half-open connection, so a
+                // quiet queue makes sure its channel is still alive
+                NextItem::Idle => {
+                    let connected = channel.status().connected();
+                    debug!(queue = %queue, connected, "No delivery within the idle timeout");
+                    if !connected {
+                        warn!(queue = %queue, "Channel died while idle, reconnecting");
+                    }
+                    if let Some(reconnected) =
+                        recover_idle(connected, || self.reconnect(queue)).await
+                    {
+                        (channel, consumer) = reconnected;
+                    }
+                    continue;
+                }
+            };
+
+            // Check for shutdown signal every time a delivery is received
             if self.shutdown.load(Ordering::SeqCst) {
-                warn!("Shutting down task event consumer due to shutdown signal");
+                warn!(queue = %queue, "Shutting down task event consumer due to shutdown signal");
                 break;
             }
 
@@ -195,59 +915,252 @@ impl TaskEventConsumer for RabbitMQTaskEventConsumer {
                 Ok(msg) => msg,
                 Err(e) => {
                     error!(error = %e, "Error receiving message");
+                    self.metrics
+                        .record_consume_error(queue, ConsumeErrorKind::Receive, 1);
 
+                    // Deliveries already sent to the lanes can't be
+                    // acknowledged anymore, the broker redelivers them on the
+                    // new channel
                     if let lapin::Error::IOError(e) = e {
                         error!(error = %e, "Connection aborted, attempting to reconnect");
-                        consumer = match self.reconnect().await {
-                            Ok(consumer) => consumer,
-                            Err(e) => {
-                                error!(error = %e, "Failed to reconnect to RabbitMQ");
-                                continue;
-                            }
-                        };
+                        match self.reconnect(queue).await {
+                            Ok(reconnected) => (channel, consumer) = reconnected,
+                            Err(e) => error!(error = %e, "Failed to reconnect to RabbitMQ"),
+                        }
                     }
-
                     continue;
                 }
             };
-            let delivery_tag = message.delivery_tag;
+            self.metrics.record_consumed(queue, 1);
 
-            // Parse the Event from the message
-            let event = match Event::try_from(message) {
-                Ok(msg) => msg,
+            // Parse the Event from the message. Retrying won't fix a message
+            // that can't be parsed, or is too large to be, so it goes straight
+            // to the dead letter queue.
+            let event = match decode_delivery(&message, self.codec.as_ref(), self.max_message_bytes)
+            {
+                Ok(event) => event,
                 Err(e) => {
                     error!(error = %e, "Error parsing message");
+                    self.metrics
+                        .record_consume_error(queue, ConsumeErrorKind::Parse, 1);
+                    if let Err(e) = self.dead_letter(&channel, &message, queue).await {
+                        error!(error = %e, "Failed to dead letter unparseable message");
+                    }
                     continue;
                 }
             };
 
-            // Handle the event. If it fails, we log it, nack it, and continue.
-            if let Err(e) = self.handle_events(vec![event]).await {
-                error!(error = %e, "Error handling events");
+            // Another queue receives the events of this type, so this copy
+            // only needs to be taken off the queue
+            let event_type = event.event_type();
+            if self.event_routing.is_routed_elsewhere(queue, event_type) {
+                debug!(queue = %queue, event_type = ?event_type, "Skipping event routed to another queue");
+                if !self.ack_mode.is_manual() {
+                    continue;
+                }
+                if let Err(e) = channel
+                    .basic_ack(message.delivery_tag, BasicAckOptions::default())
+                    .await
+                {
+                    error!(error = %e, "Failed to acknowledge skipped message");
+                    self.metrics.record_ack_failure(queue);
+                }
                 continue;
             }
 
-            // Ackowledge the message so we don't re-process it.
-            debug!(queue = %QUEUE_NAME, delivery_tag = %delivery_tag, "Acknowledging message");
+            let key = event.ordering_key();
+            let pending = PendingDelivery {
+                channel: channel.clone(),
+                delivery: message,
+                event,
+            };
+            if send_to_lane(&mut lanes, &key, pending).await.is_err() {
+                error!(queue = %queue, "Lane stopped unexpectedly, stopping consumption");
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolves once the shutdown flag is set.
+    async fn shutdown_requested(&self) {
+        loop {
+            let notified = self.shutdown_notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.shutdown.load(Ordering::SeqCst) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Handles the deliveries of a lane in batches until the dispatcher stops
+    /// and the lane is drained.
+    async fn handle_lane(&self, queue: &str, mut lane: mpsc::Receiver<PendingDelivery>) {
+        while let Some(batch) = next_batch(&mut lane, self.batch_size, self.batch_timeout).await {
+            let (events, messages): (Vec<_>, Vec<_>) = batch
+                .into_iter()
+                .map(|pending| (pending.event, (pending.channel, pending.delivery)))
+                .unzip();
+
+            // Handle the events. If it fails, we log it and retry them later.
+            // Updates are idempotent, so retrying the whole batch is safe.
+            self.wait_for_database().await;
+            let started_at = Instant::now();
+            let handled = self.handle_events(events).await;
+            self.metrics
+                .record_handler_latency(queue, started_at.elapsed());
+            if let Err(e) = handled {
+                error!(error = %e, batch_size = messages.len(), "Error handling events");
+                self.metrics.record_consume_error(
+                    queue,
+                    ConsumeErrorKind::Handler,
+                    messages.len() as u64,
+                );
+
+                // Retrying can't help until the database is back, so the
+                // deliveries go back to the queue without using a retry
+                if is_database_outage(e.as_ref()) && self.circuit_breaker.record_failure() {
+                    self.requeue(queue, &messages).await;
+                    continue;
+                }
+                for (channel, message) in &messages {
+                    if let Err(e) = self.retry_or_dead_letter(channel, message, queue).await {
+                        error!(error = %e, "Failed to schedule message for retry");
+                    }
+                }
+                continue;
+            }
+
+            self.circuit_breaker.record_success();
+            self.acknowledge(queue, &messages).await;
+        }
+    }
+
+    /// Waits while the circuit breaker is open, probing the database once
+    /// per cooldown.
+    async fn wait_for_database(&self) {
+        self.circuit_breaker
+            .wait_until_closed(|| async {
+                match self.event_handler.check_database().await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        debug!(error = %e, "Database probe failed");
+                        false
+                    }
+                }
+            })
+            .await;
+    }
+
+    /// Returns deliveries to their queue without counting a retry. In
+    /// automatic acknowledgement mode they already left the queue, so copies
+    /// are published to it instead.
+    async fn requeue(&self, queue: &str, messages: &[(Channel, Delivery)]) {
+        warn!(
+            queue = %queue,
+            batch_size = messages.len(),
+            "Requeueing messages until the database is reachable"
+        );
+        for (channel, message) in messages {
+            if !self.ack_mode.is_manual() {
+                if let Err(e) = self
+                    .republish(channel, message, queue, retry_count(message))
+                    .await
+                {
+                    error!(error = %e, "Failed to requeue message");
+                }
+                continue;
+            }
             if let Err(e) = channel
-                .basic_ack(delivery_tag, BasicAckOptions::default())
+                .basic_nack(
+                    message.delivery_tag,
+                    BasicNackOptions {
+                        requeue: true,
+                        ..BasicNackOptions::default()
+                    },
+                )
+                .await
+            {
+                error!(
+                    error = %e,
+                    delivery_tag = %message.delivery_tag,
+                    "Failed to requeue message"
+                );
+            }
+        }
+    }
+
+    /// Acknowledges handled deliveries on the channel they were received on
+    /// so we don't re-process them. A single lane sees every delivery, so the
+    /// whole batch is acknowledged at once. With several lanes, earlier
+    /// deliveries may still be in flight on another lane, so each delivery is
+    /// acknowledged on its own.
+    async fn acknowledge(&self, queue: &str, messages: &[(Channel, Delivery)]) {
+        if !self.ack_mode.is_manual() {
+            return;
+        }
+
+        let acks: Vec<_> = if self.concurrency == 1 {
+            messages
+                .last()
+                .map(|(channel, message)| (channel, message.delivery_tag, true))
+                .into_iter()
+                .collect()
+        } else {
+            messages
+                .iter()
+                .map(|(channel, message)| (channel, message.delivery_tag, false))
+                .collect()
+        };
+
+        debug!(
+            queue = %queue,
+            batch_size = messages.len(),
+            "Acknowledging messages"
+        );
+        for (channel, delivery_tag, multiple) in acks {
+            if let Err(e) = channel
+                .basic_ack(delivery_tag, BasicAckOptions { multiple })
                 .await
             {
                 error!(
                     error = %e,
                     delivery_tag = %delivery_tag,
-                    "Failed to acknowledge message"
+                    "Failed to acknowledge messages"
                 );
+                self.metrics.record_ack_failure(queue);
             }
         }
+    }
+}
 
-        Ok(())
+impl TaskEventConsumer for RabbitMQTaskEventConsumer {
+    type Core = RabbitMQTaskEventCore;
+
+    fn event_handler(&self) -> &TaskEventHandler {
+        &self.event_handler
+    }
+
+    /// Takes the connection currently shared by the queues, which is
+    /// replaced whenever they reconnect. Waits while a reconnect is underway.
+    async fn core(&self) -> Result<Arc<Self::Core>, Box<dyn Error + Send + Sync>> {
+        let connection = self.connection.lock().await.clone();
+        Ok(Arc::new(RabbitMQTaskEventCore { connection }))
+    }
+
+    async fn lifecycle(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        info!(queues = ?self.queues, "Starting consumers");
+        consume_queues(&self.queues, |queue| self.consume_queue(queue)).await
     }
 
     fn shutdown(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        info!(queue = %QUEUE_NAME, "Initiating consumer shutdown");
+        info!(queues = ?self.queues, "Initiating consumer shutdown");
         self.shutdown.store(true, Ordering::SeqCst);
-        debug!(queue = %QUEUE_NAME, "Shutdown flag set");
+        self.shutdown_notify.notify_waiters();
+        debug!(queues = ?self.queues, "Shutdown flag set");
         Ok(())
     }
 
@@ -255,3 +1168,318 @@ impl TaskEventConsumer for RabbitMQTaskEventConsumer {
         self.event_handler().handle_batch_events(events).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{TaskAssignmentUpdate, TaskCompletedUpdate, TaskRunningUpdate};
+    use crate::repo::PgRepositoryCore;
+    use crate::task_event_consumer::WireFormat;
+    use sqlx::PgPool;
+    use std::collections::HashMap;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_dead_letter_queue_name() {
+        assert_eq!(
+            dead_letter_queue_name("tacoq_relay_queue"),
+            "tacoq_relay_queue_dlq"
+        );
+    }
+
+    #[test]
+    fn test_consumer_tags_are_distinct() {
+        let first = unique_consumer_tag("relay");
+        let second = unique_consumer_tag("relay");
+
+        assert!(first.starts_with("relay-"));
+        assert!(second.starts_with("relay-"));
+        assert_ne!(first, second);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_consume_queues_share_handler(pool: PgPool) {
+        let repo = Arc::new(TaskRepository::new(PgRepositoryCore::new(pool.clone())));
+        let worker_repo = Arc::new(WorkerRepository::new(PgRepositoryCore::new(pool)));
+        let handler = TaskEventHandler::new(repo.clone(), worker_repo);
+
+        // One in-memory queue per name, each holding a single assignment
+        let queues = vec!["queue_a".to_string(), "queue_b".to_string()];
+        let mut receivers = HashMap::new();
+        let mut ids = Vec::new();
+        for queue in &queues {
+            let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+            let id = Uuid::new_v4();
+            sender
+                .send(Event::Assignment(TaskAssignmentUpdate {
+                    id,
+                    task_kind: "TestKind".to_string(),
+                    worker_kind: queue.clone(),
+                    ..TaskAssignmentUpdate::default()
+                }))
+                .unwrap();
+            receivers.insert(queue.as_str(), Mutex::new(receiver));
+            ids.push(id);
+        }
+
+        consume_queues(&queues, |queue| {
+            let receiver = &receivers[queue];
+            let handler = &handler;
+            async move {
+                // The senders were dropped, so this ends once the queue is drained
+                while let Some(event) = receiver.lock().await.recv().await {
+                    handler.handle_batch_events(vec![event]).await?;
+                }
+                Ok(())
+            }
+        })
+        .await
+        .unwrap();
+
+        for (id, queue) in ids.iter().zip(&queues) {
+            let task = repo.get_task_by_id(id).await.unwrap().unwrap();
+            assert_eq!(task.worker_kind.as_deref(), Some(queue.as_str()));
+        }
+    }
+
+    /// Delivery of an event encoded for the wire, without a content type
+    fn wire_delivery(event: &Event, wire_format: WireFormat) -> Delivery {
+        let message_type: &str = event.event_type().into();
+        let mut headers = FieldTable::default();
+        headers.insert(
+            "message_type".into(),
+            lapin::types::AMQPValue::LongString(message_type.into()),
+        );
+        Delivery {
+            delivery_tag: 0,
+            exchange: "".into(),
+            routing_key: "".into(),
+            redelivered: false,
+            properties: lapin::BasicProperties::default().with_headers(headers),
+            data: wire_format.codec().encode(event).unwrap(),
+            acker: lapin::acker::Acker::default(),
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_consume_json_wire_format(pool: PgPool) {
+        let repo = Arc::new(TaskRepository::new(PgRepositoryCore::new(pool.clone())));
+        let worker_repo = Arc::new(WorkerRepository::new(PgRepositoryCore::new(pool)));
+        let handler = TaskEventHandler::new(repo.clone(), worker_repo);
+        let codec = WireFormat::Json.codec();
+
+        let id = Uuid::new_v4();
+        let now = chrono::Local::now().naive_local();
+        let events = [
+            Event::Assignment(TaskAssignmentUpdate {
+                id,
+                task_kind: "TestKind".to_string(),
+                worker_kind: "json_worker".to_string(),
+                created_at: now,
+                ..TaskAssignmentUpdate::default()
+            }),
+            Event::Running(TaskRunningUpdate {
+                id,
+                started_at: now,
+                executed_by: "json_worker_1".to_string(),
+                update_type: "Running".to_string(),
+            }),
+            Event::Completed(TaskCompletedUpdate {
+                id,
+                completed_at: now,
+                output_data: vec![7, 8, 9],
+                is_error: false,
+                output_content_type: None,
+                update_type: "Completed".to_string(),
+            }),
+        ];
+
+        for event in &events {
+            let delivery = wire_delivery(event, WireFormat::Json);
+            assert!(serde_json::from_slice::<serde_json::Value>(&delivery.data).is_ok());
+
+            // Deliveries without a content type are decoded with the wire format
+            let decoded = decode_delivery(&delivery, codec.as_ref(), usize::MAX).unwrap();
+            handler.handle_batch_events(vec![decoded]).await.unwrap();
+        }
+
+        let task = repo.get_task_by_id(&id).await.unwrap().unwrap();
+        assert!(task.completed_at.is_some());
+        assert_eq!(task.executed_by.as_deref(), Some("json_worker_1"));
+        assert_eq!(task.output_data, Some(vec![7, 8, 9]));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_consume_across_wire_formats(pool: PgPool) {
+        let repo = Arc::new(TaskRepository::new(PgRepositoryCore::new(pool.clone())));
+        let worker_repo = Arc::new(WorkerRepository::new(PgRepositoryCore::new(pool)));
+        let handler = TaskEventHandler::new(repo.clone(), worker_repo);
+
+        // Publishers label what they send, so the inbound format of the
+        // consumer only matters for deliveries without a content type
+        for (outbound, inbound) in [
+            (WireFormat::Json, WireFormat::Avro),
+            (WireFormat::Avro, WireFormat::Json),
+        ] {
+            let id = Uuid::new_v4();
+            let event = Event::Assignment(TaskAssignmentUpdate {
+                id,
+                task_kind: "TestKind".to_string(),
+                worker_kind: "mixed_worker".to_string(),
+                created_at: chrono::Local::now().naive_local(),
+                ..TaskAssignmentUpdate::default()
+            });
+
+            let mut delivery = wire_delivery(&event, outbound);
+            delivery.properties = delivery
+                .properties
+                .with_content_type(outbound.codec().content_type().into());
+            let decoded = decode_delivery(&delivery, inbound.codec().as_ref(), usize::MAX).unwrap();
+            handler.handle_batch_events(vec![decoded]).await.unwrap();
+
+            let task = repo.get_task_by_id(&id).await.unwrap().unwrap();
+            assert_eq!(task.worker_kind.as_deref(), Some("mixed_worker"));
+
+            // Unlabeled deliveries must be in the inbound format
+            let unlabeled = wire_delivery(&event, outbound);
+            assert!(decode_delivery(&unlabeled, inbound.codec().as_ref(), usize::MAX).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_next_or_idle() {
+        let mut stream = futures::stream::iter(0..1);
+        assert_eq!(next_or_idle(&mut stream, None).await, NextItem::Item(0));
+        assert_eq!(next_or_idle(&mut stream, None).await, NextItem::Ended);
+
+        // A stream that never yields is reported idle instead of hanging
+        let mut stream = futures::stream::pending::<u32>();
+        let next = next_or_idle(&mut stream, Some(Duration::from_millis(20))).await;
+        assert_eq!(next, NextItem::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_idle_queue_reconnects_dead_channel() {
+        let reconnects = AtomicUsize::new(0);
+        let reconnect = || async {
+            reconnects.fetch_add(1, Ordering::SeqCst);
+            Ok("new channel")
+        };
+
+        // A quiet but connected channel is kept
+        let mut stream = futures::stream::pending::<u32>();
+        assert_eq!(
+            next_or_idle(&mut stream, Some(Duration::from_millis(20))).await,
+            NextItem::Idle
+        );
+        assert_eq!(recover_idle(true, reconnect).await, None);
+        assert_eq!(reconnects.load(Ordering::SeqCst), 0);
+
+        // A dead channel is replaced
+        assert_eq!(recover_idle(false, reconnect).await, Some("new channel"));
+        assert_eq!(reconnects.load(Ordering::SeqCst), 1);
+
+        // A failed reconnect is retried on the next idle timeout
+        let failed = recover_idle(false, || async {
+            Err::<(), _>(Box::<dyn Error + Send + Sync>::from("Connection refused"))
+        })
+        .await;
+        assert_eq!(failed, None);
+    }
+
+    #[tokio::test]
+    async fn test_next_batch_fills_up() {
+        let mut stream = futures::stream::iter(0..5);
+
+        let batch = next_batch(&mut stream, 3, Duration::from_secs(1)).await;
+        assert_eq!(batch, Some(vec![0, 1, 2]));
+        let batch = next_batch(&mut stream, 3, Duration::from_secs(1)).await;
+        assert_eq!(batch, Some(vec![3, 4]));
+        let batch = next_batch(&mut stream, 3, Duration::from_secs(1)).await;
+        assert_eq!(batch, None);
+    }
+
+    #[tokio::test]
+    async fn test_next_batch_times_out() {
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+        sender.unbounded_send(1).unwrap();
+
+        // The sender is kept alive, so only the timeout ends the batch
+        let batch = next_batch(&mut receiver, 10, Duration::from_millis(20)).await;
+        assert_eq!(batch, Some(vec![1]));
+        drop(sender);
+    }
+
+    #[tokio::test]
+    async fn test_consume_queues_returns_error() {
+        let queues = vec!["queue_a".to_string(), "queue_b".to_string()];
+        let result = consume_queues(&queues, |queue| async move {
+            if queue == "queue_b" {
+                Err("queue_b failed".into())
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "queue_b failed");
+    }
+
+    #[test]
+    fn test_lane_index_is_stable() {
+        let lane = lane_index("task-1", 4);
+        assert!(lane < 4);
+        assert_eq!(lane_index("task-1", 4), lane);
+        assert_eq!(lane_index("task-1", 1), 0);
+    }
+
+    #[tokio::test]
+    async fn test_lanes_run_concurrently_in_key_order() {
+        let keys: Vec<String> = (0..8).map(|key| format!("task-{}", key)).collect();
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let handled = std::sync::Mutex::new(Vec::new());
+
+        run_lanes(
+            4,
+            1,
+            |mut lanes| {
+                let keys = &keys;
+                async move {
+                    for sequence in 0..5 {
+                        for key in keys {
+                            send_to_lane(&mut lanes, key, (key.clone(), sequence))
+                                .await
+                                .unwrap();
+                        }
+                    }
+                }
+            },
+            |mut lane| {
+                let (in_flight, max_in_flight, handled) = (&in_flight, &max_in_flight, &handled);
+                async move {
+                    while let Some(item) = lane.next().await {
+                        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(current, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                        handled.lock().unwrap().push(item);
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                    }
+                }
+            },
+        )
+        .await;
+
+        let handled = handled.into_inner().unwrap();
+        assert_eq!(handled.len(), keys.len() * 5);
+        assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+        for key in &keys {
+            let sequences: Vec<_> = handled
+                .iter()
+                .filter(|(handled_key, _)| handled_key == key)
+                .map(|(_, sequence)| *sequence)
+                .collect();
+            assert_eq!(sequences, vec![0, 1, 2, 3, 4]);
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use crate::task_event_consumer::event_parsing::{
-    try_parse_event_from_avro_bytes, Event, EventType, MessageProcessingError,
-};
+use crate::constants::DEFAULT_MAX_MESSAGE_BYTES;
+use crate::task_event_consumer::codec::{AvroCodec, JsonCodec, MessageCodec};
+use crate::task_event_consumer::event_parsing::{Event, EventType, MessageProcessingError};
 use lapin::message::Delivery;
 use thiserror::Error;
 
@@ -14,13 +14,87 @@ pub enum DecodingError {
     InvalidMessageTypeFormat,
     #[error("Invalid message type: {0}")]
     InvalidMessageType(String),
+    #[error("Unsupported content type: {0}")]
+    UnsupportedContentType(String),
+    #[error("Message of {size} bytes exceeds the maximum of {max} bytes")]
+    MessageTooLarge { size: usize, max: usize },
     #[error("Failed to parse event: {0}")]
     EventParsingError(#[from] MessageProcessingError),
 }
 
+/// Reads the content type of a delivery, from its AMQP property or, for
+/// publishers that can't set it, from a `content-type` header.
+fn content_type(delivery: &Delivery) -> Option<String> {
+    if let Some(content_type) = delivery.properties.content_type() {
+        return Some(content_type.to_string());
+    }
+
+    delivery
+        .properties
+        .headers()
+        .as_ref()?
+        .inner()
+        .get("content-type")?
+        .as_long_string()
+        .map(|content_type| content_type.to_string())
+}
+
+/// Picks the codec matching a content type, ignoring its parameters (such as
+/// `charset`). Deliveries without a content type use the default codec.
+///
+/// # Arguments
+///
+/// * `content_type` - The content type of the delivery, if any
+/// * `default_codec` - The codec used when there is no content type
+fn codec_for<'a>(
+    content_type: Option<&str>,
+    default_codec: &'a dyn MessageCodec,
+) -> Result<&'a dyn MessageCodec, DecodingError> {
+    let Some(content_type) = content_type else {
+        return Ok(default_codec);
+    };
+
+    let mime_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match mime_type.as_str() {
+        "application/avro" | "avro/binary" => Ok(&AvroCodec),
+        "application/json" => Ok(&JsonCodec),
+        _ => Err(DecodingError::UnsupportedContentType(
+            content_type.to_string(),
+        )),
+    }
+}
+
 /// Decodes a RabbitMQ delivery into an Event based on the message type in
-/// headers.
-fn decode_delivery(delivery: &Delivery) -> Result<Event, DecodingError> {
+/// headers. The payload is decoded according to the delivery content type.
+///
+/// # Arguments
+///
+/// * `delivery` - The delivery to decode
+/// * `default_codec` - The codec used for deliveries without a content type
+/// * `max_message_bytes` - Largest payload decoded. Bigger ones are refused
+///   before parsing, as a malformed length in them could make the decoder
+///   allocate far more than the message holds. It also bounds what the Avro
+///   decoder allocates for a single value, a limit that is set once for the
+///   whole process by the first delivery decoded
+pub fn decode_delivery(
+    delivery: &Delivery,
+    default_codec: &dyn MessageCodec,
+    max_message_bytes: usize,
+) -> Result<Event, DecodingError> {
+    if delivery.data.len() > max_message_bytes {
+        return Err(DecodingError::MessageTooLarge {
+            size: delivery.data.len(),
+            max: max_message_bytes,
+        });
+    }
+    // A length read from a smaller message can still claim more than it holds
+    apache_avro::max_allocation_bytes(max_message_bytes);
+
     let headers = delivery
         .properties
         .headers()
@@ -38,15 +112,28 @@ fn decode_delivery(delivery: &Delivery) -> Result<Event, DecodingError> {
     let event_type: EventType = EventType::try_from(message_type)
         .map_err(|e| DecodingError::InvalidMessageType(e.to_string()))?;
 
-    try_parse_event_from_avro_bytes(event_type, &delivery.data)
+    let codec = codec_for(content_type(delivery).as_deref(), default_codec)?;
+    codec
+        .decode(event_type, &delivery.data)
         .map_err(DecodingError::EventParsingError)
 }
 
+/// Decodes a delivery with the default size limit. Deliveries without a
+/// content type are Avro encoded, like before content types were introduced.
+impl TryFrom<&Delivery> for Event {
+    type Error = DecodingError;
+
+    fn try_from(delivery: &Delivery) -> Result<Self, Self::Error> {
+        decode_delivery(delivery, &AvroCodec, DEFAULT_MAX_MESSAGE_BYTES)
+    }
+}
+
+/// See the conversion from `&Delivery`.
 impl TryFrom<Delivery> for Event {
     type Error = DecodingError;
 
     fn try_from(delivery: Delivery) -> Result<Self, Self::Error> {
-        decode_delivery(&delivery)
+        Event::try_from(&delivery)
     }
 }
 
@@ -56,6 +143,7 @@ mod tests {
     use crate::models::{
         AvroSerializable, TaskAssignmentUpdate, TaskCompletedUpdate, TaskRunningUpdate,
     };
+    use crate::task_event_consumer::codec::JsonCodec;
     use chrono::Local;
     use lapin::acker::Acker;
     use lapin::message::Delivery;
@@ -75,8 +163,10 @@ mod tests {
             created_at: Local::now().naive_local(),
             input_data: vec![1, 2, 3],
             priority: 1,
-            ttl_duration: 3600000000,
+            ttl_duration: 3600,
             otel_ctx_carrier: otel_ctx,
+            input_content_type: None,
+            scheduled_for: None,
             update_type: "Assignment".to_string(),
         }
     }
@@ -86,7 +176,8 @@ mod tests {
             id: Uuid::new_v4(),
             completed_at: Local::now().naive_local(),
             output_data: vec![4, 5, 6],
-            is_error: 0,
+            is_error: false,
+            output_content_type: None,
             update_type: "Completed".to_string(),
         }
     }
@@ -166,6 +257,104 @@ mod tests {
         }
     }
 
+    fn create_json_delivery<T: serde::Serialize>(update: &T, event_type: EventType) -> Delivery {
+        let mut delivery = create_delivery(
+            serde_json::to_vec(update).unwrap(),
+            create_headers(event_type.into()),
+        );
+        delivery.properties = delivery
+            .properties
+            .with_content_type("application/json".into());
+        delivery
+    }
+
+    #[test]
+    fn test_decode_json_assignment_event() {
+        let assignment = create_test_assignment();
+        let delivery = create_json_delivery(&assignment, EventType::Assignment);
+
+        match Event::try_from(delivery).unwrap() {
+            Event::Assignment(parsed) => {
+                assert_eq!(assignment.id, parsed.id);
+                assert_eq!(assignment.input_data, parsed.input_data);
+                assert_eq!(assignment.otel_ctx_carrier, parsed.otel_ctx_carrier);
+            }
+            _ => panic!("Expected Assignment event"),
+        }
+    }
+
+    #[test]
+    fn test_decode_json_completed_event() {
+        let completed = create_test_completed();
+        let delivery = create_json_delivery(&completed, EventType::Completed);
+
+        match Event::try_from(delivery).unwrap() {
+            Event::Completed(parsed) => {
+                assert_eq!(completed.id, parsed.id);
+                assert_eq!(completed.output_data, parsed.output_data);
+                assert_eq!(
+                    completed.completed_at.and_utc().timestamp_micros(),
+                    parsed.completed_at.and_utc().timestamp_micros()
+                );
+            }
+            _ => panic!("Expected Completed event"),
+        }
+    }
+
+    #[test]
+    fn test_decode_json_running_event() {
+        let running = create_test_running();
+        let delivery = create_json_delivery(&running, EventType::Running);
+
+        match Event::try_from(delivery).unwrap() {
+            Event::Running(parsed) => {
+                assert_eq!(running.id, parsed.id);
+                assert_eq!(running.executed_by, parsed.executed_by);
+            }
+            _ => panic!("Expected Running event"),
+        }
+    }
+
+    #[test]
+    fn test_decode_content_type_header() {
+        let running = create_test_running();
+        let mut headers = create_headers(EventType::Running.into());
+        headers.insert(
+            "content-type".into(),
+            AMQPValue::LongString("application/json; charset=utf-8".into()),
+        );
+        let delivery = create_delivery(serde_json::to_vec(&running).unwrap(), headers);
+
+        assert!(matches!(Event::try_from(delivery), Ok(Event::Running(_))));
+    }
+
+    #[test]
+    fn test_decode_without_content_type_uses_default_codec() {
+        let completed = create_test_completed();
+        let delivery = create_delivery(
+            serde_json::to_vec(&completed).unwrap(),
+            create_headers(EventType::Completed.into()),
+        );
+
+        assert!(decode_delivery(&delivery, &JsonCodec, usize::MAX).is_ok());
+        assert!(Event::try_from(&delivery).is_err());
+    }
+
+    #[test]
+    fn test_decode_unsupported_content_type() {
+        let running = create_test_running();
+        let mut delivery = create_delivery(
+            running.try_into_avro_bytes().unwrap(),
+            create_headers(EventType::Running.into()),
+        );
+        delivery.properties = delivery.properties.with_content_type("text/plain".into());
+
+        assert!(matches!(
+            Event::try_from(delivery),
+            Err(DecodingError::UnsupportedContentType(_))
+        ));
+    }
+
     #[test]
     fn test_decode_running_event() {
         let running = create_test_running();
@@ -269,4 +458,24 @@ mod tests {
             DecodingError::EventParsingError(_)
         ));
     }
+
+    #[test]
+    fn test_decode_oversized_message() {
+        // The first limit decoded with also bounds the Avro allocations of
+        // every other test, so the message is large enough not to get in
+        // their way
+        let assignment = TaskAssignmentUpdate {
+            input_data: vec![0; 1024 * 1024],
+            ..create_test_assignment()
+        };
+        let avro_bytes = assignment.try_into_avro_bytes().unwrap();
+        let size = avro_bytes.len();
+        let delivery = create_delivery(avro_bytes, create_headers(EventType::Assignment.into()));
+
+        assert!(decode_delivery(&delivery, &AvroCodec, size).is_ok());
+        assert!(matches!(
+            decode_delivery(&delivery, &AvroCodec, size - 1),
+            Err(DecodingError::MessageTooLarge { size: s, max }) if s == size && max == size - 1
+        ));
+    }
 }
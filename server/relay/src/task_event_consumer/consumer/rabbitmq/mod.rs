@@ -1,5 +1,13 @@
+mod ack_mode;
 mod connection;
 mod consumer;
 mod decoding;
+mod queue_arguments;
+mod retry;
+mod routing;
 
-pub use consumer::{RabbitMQTaskEventConsumer, RabbitMQTaskEventCore};
+pub use ack_mode::AckMode;
+pub use connection::{BrokerTlsConfig, RabbitMQConnection};
+pub use consumer::{ConsumerSettings, RabbitMQTaskEventConsumer};
+pub use queue_arguments::{QueueArguments, QueueOverflow, QueueType};
+pub use routing::EventRouting;
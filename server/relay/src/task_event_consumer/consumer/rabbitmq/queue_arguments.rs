@@ -0,0 +1,281 @@
+use lapin::types::{AMQPValue, FieldTable, LongString};
+use std::fmt;
+use std::str::FromStr;
+
+/// What the broker does with new messages once a queue reached its maximum
+/// length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueOverflow {
+    /// Drop the oldest messages to make room for new ones
+    DropHead,
+    /// Refuse new messages
+    RejectPublish,
+    /// Refuse new messages and dead letter them
+    RejectPublishDlx,
+}
+
+impl QueueOverflow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueueOverflow::DropHead => "drop-head",
+            QueueOverflow::RejectPublish => "reject-publish",
+            QueueOverflow::RejectPublishDlx => "reject-publish-dlx",
+        }
+    }
+}
+
+impl fmt::Display for QueueOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for QueueOverflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop-head" => Ok(QueueOverflow::DropHead),
+            "reject-publish" => Ok(QueueOverflow::RejectPublish),
+            "reject-publish-dlx" => Ok(QueueOverflow::RejectPublishDlx),
+            _ => Err(format!("Unknown queue overflow behavior: {}", s)),
+        }
+    }
+}
+
+/// Kind of queue the consumed queues and their dead letter queues are
+/// declared as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueueType {
+    /// Queue stored on a single node, supporting priorities
+    #[default]
+    Classic,
+    /// Queue replicated across the cluster, without priorities
+    Quorum,
+}
+
+impl QueueType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueueType::Classic => "classic",
+            QueueType::Quorum => "quorum",
+        }
+    }
+}
+
+impl fmt::Display for QueueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for QueueType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" => Ok(QueueType::Classic),
+            "quorum" => Ok(QueueType::Quorum),
+            _ => Err(format!("Unknown queue type: {}", s)),
+        }
+    }
+}
+
+/// Arguments the consumed queues are declared with. Unset values are left
+/// out so the broker applies its defaults.
+///
+/// Note that RabbitMQ refuses to re-declare an existing queue with different
+/// arguments, so changing these requires deleting the queue first.
+///
+/// # Fields
+/// * `queue_type` - Kind of queue to declare (`x-queue-type`)
+/// * `priority` - Whether the queue delivers urgent tasks first (`x-max-priority`)
+/// * `max_length` - Maximum number of messages kept in the queue (`x-max-length`)
+/// * `message_ttl_ms` - How long a message may wait in the queue before
+///   expiring, in milliseconds (`x-message-ttl`)
+/// * `overflow` - What happens to new messages once the queue is full (`x-overflow`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueueArguments {
+    pub queue_type: QueueType,
+    pub priority: bool,
+    pub max_length: Option<u32>,
+    pub message_ttl_ms: Option<u32>,
+    pub overflow: Option<QueueOverflow>,
+}
+
+impl Default for QueueArguments {
+    fn default() -> Self {
+        Self {
+            queue_type: QueueType::default(),
+            priority: true,
+            max_length: None,
+            message_ttl_ms: None,
+            overflow: None,
+        }
+    }
+}
+
+impl QueueArguments {
+    /// Checks the arguments are supported by the queue type, so a bad
+    /// combination is reported at startup instead of by the broker.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.queue_type == QueueType::Quorum {
+            if self.priority {
+                return Err("quorum queues don't support priorities".to_string());
+            }
+            if self.overflow == Some(QueueOverflow::RejectPublishDlx) {
+                return Err(format!(
+                    "quorum queues don't support the {} overflow behavior",
+                    QueueOverflow::RejectPublishDlx
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the arguments of a `queue_declare` call. The queue type is
+    /// only set for quorum queues, since classic is the broker default.
+    pub fn field_table(&self) -> FieldTable {
+        let mut arguments = self.dead_letter_field_table();
+
+        if self.priority {
+            arguments.insert("x-max-priority".into(), 255.into());
+        }
+
+        if let Some(max_length) = self.max_length {
+            arguments.insert("x-max-length".into(), AMQPValue::LongUInt(max_length));
+        }
+        if let Some(message_ttl_ms) = self.message_ttl_ms {
+            arguments.insert("x-message-ttl".into(), AMQPValue::LongUInt(message_ttl_ms));
+        }
+        if let Some(overflow) = self.overflow {
+            arguments.insert(
+                "x-overflow".into(),
+                AMQPValue::LongString(LongString::from(overflow.as_str())),
+            );
+        }
+
+        arguments
+    }
+
+    /// Builds the arguments of the `queue_declare` call of a dead letter
+    /// queue, which shares the type of the queue it belongs to.
+    pub fn dead_letter_field_table(&self) -> FieldTable {
+        let mut arguments = FieldTable::default();
+        if self.queue_type == QueueType::Quorum {
+            arguments.insert(
+                "x-queue-type".into(),
+                AMQPValue::LongString(LongString::from(self.queue_type.as_str())),
+            );
+        }
+        arguments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_queue_arguments_only_set_priority() {
+        let arguments = QueueArguments::default().field_table();
+
+        assert_eq!(arguments.inner().len(), 1);
+        assert!(arguments.inner().contains_key("x-max-priority"));
+    }
+
+    #[test]
+    fn test_queue_arguments_field_table() {
+        let arguments = QueueArguments {
+            max_length: Some(1000),
+            message_ttl_ms: Some(60_000),
+            overflow: Some(QueueOverflow::RejectPublishDlx),
+            ..QueueArguments::default()
+        }
+        .field_table();
+        let inner = arguments.inner();
+
+        assert_eq!(inner.len(), 4);
+        assert_eq!(inner.get("x-max-length"), Some(&AMQPValue::LongUInt(1000)));
+        assert_eq!(
+            inner.get("x-message-ttl"),
+            Some(&AMQPValue::LongUInt(60_000))
+        );
+        assert_eq!(
+            inner.get("x-overflow"),
+            Some(&AMQPValue::LongString("reject-publish-dlx".into()))
+        );
+    }
+
+    #[test]
+    fn test_quorum_queue_arguments_field_table() {
+        let arguments = QueueArguments {
+            queue_type: QueueType::Quorum,
+            priority: false,
+            max_length: Some(1000),
+            overflow: Some(QueueOverflow::RejectPublish),
+            ..QueueArguments::default()
+        };
+        assert_eq!(arguments.validate(), Ok(()));
+
+        let table = arguments.field_table();
+        let inner = table.inner();
+        assert_eq!(inner.len(), 3);
+        assert!(!inner.contains_key("x-max-priority"));
+        assert_eq!(
+            inner.get("x-queue-type"),
+            Some(&AMQPValue::LongString("quorum".into()))
+        );
+        assert_eq!(inner.get("x-max-length"), Some(&AMQPValue::LongUInt(1000)));
+
+        let dead_letter = arguments.dead_letter_field_table();
+        assert_eq!(dead_letter.inner().len(), 1);
+        assert_eq!(
+            dead_letter.inner().get("x-queue-type"),
+            Some(&AMQPValue::LongString("quorum".into()))
+        );
+        assert!(QueueArguments::default()
+            .dead_letter_field_table()
+            .inner()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_quorum_queue_arguments_validation() {
+        assert_eq!(QueueArguments::default().validate(), Ok(()));
+
+        let with_priority = QueueArguments {
+            queue_type: QueueType::Quorum,
+            ..QueueArguments::default()
+        };
+        assert!(with_priority.validate().is_err());
+
+        let with_dead_lettering_overflow = QueueArguments {
+            queue_type: QueueType::Quorum,
+            priority: false,
+            overflow: Some(QueueOverflow::RejectPublishDlx),
+            ..QueueArguments::default()
+        };
+        assert!(with_dead_lettering_overflow.validate().is_err());
+    }
+
+    #[test]
+    fn test_queue_type_from_str() {
+        for queue_type in [QueueType::Classic, QueueType::Quorum] {
+            assert_eq!(queue_type.as_str().parse::<QueueType>(), Ok(queue_type));
+        }
+        assert!("stream".parse::<QueueType>().is_err());
+    }
+
+    #[test]
+    fn test_queue_overflow_from_str() {
+        for overflow in [
+            QueueOverflow::DropHead,
+            QueueOverflow::RejectPublish,
+            QueueOverflow::RejectPublishDlx,
+        ] {
+            assert_eq!(overflow.as_str().parse::<QueueOverflow>(), Ok(overflow));
+        }
+        assert!("drop-tail".parse::<QueueOverflow>().is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use lapin::message::Delivery;
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::BasicProperties;
+
+/// Header holding how many times a delivery has already been re-published
+/// after failing to be handled.
+pub const RETRY_COUNT_HEADER: &str = "x-retry-count";
+
+/// What to do with a delivery that failed to be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Re-publish the delivery with the given retry count
+    Retry(u32),
+    /// Give up on the delivery and move it to the dead letter queue
+    DeadLetter,
+}
+
+/// Reads the retry count of a delivery. Deliveries without the header (or
+/// with a value that isn't a non-negative integer) have never been retried.
+pub fn retry_count(delivery: &Delivery) -> u32 {
+    let value = delivery
+        .properties
+        .headers()
+        .as_ref()
+        .and_then(|headers| headers.inner().get(RETRY_COUNT_HEADER).cloned());
+
+    let count = match value {
+        Some(AMQPValue::ShortShortInt(v)) => i64::from(v),
+        Some(AMQPValue::ShortShortUInt(v)) => i64::from(v),
+        Some(AMQPValue::ShortInt(v)) => i64::from(v),
+        Some(AMQPValue::ShortUInt(v)) => i64::from(v),
+        Some(AMQPValue::LongInt(v)) => i64::from(v),
+        Some(AMQPValue::LongUInt(v)) => i64::from(v),
+        Some(AMQPValue::LongLongInt(v)) => v,
+        _ => 0,
+    };
+
+    u32::try_from(count).unwrap_or(0)
+}
+
+/// Decides whether a failed delivery should be retried or dead-lettered.
+///
+/// # Arguments
+///
+/// * `retry_count` - The number of times the delivery was already retried
+/// * `max_retries` - The maximum number of retries before giving up
+pub fn retry_decision(retry_count: u32, max_retries: u32) -> RetryDecision {
+    if retry_count < max_retries {
+        RetryDecision::Retry(retry_count + 1)
+    } else {
+        RetryDecision::DeadLetter
+    }
+}
+
+/// Copies the properties of a delivery, setting the retry count header.
+pub fn with_retry_count(properties: &BasicProperties, retry_count: u32) -> BasicProperties {
+    let mut headers: FieldTable = properties.headers().clone().unwrap_or_default();
+    headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongUInt(retry_count));
+    properties.clone().with_headers(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lapin::acker::Acker;
+
+    fn create_delivery(properties: BasicProperties) -> Delivery {
+        Delivery {
+            delivery_tag: 0,
+            exchange: "".to_string().into(),
+            routing_key: "".to_string().into(),
+            data: vec![],
+            redelivered: false,
+            properties,
+            acker: Acker::default(),
+        }
+    }
+
+    #[test]
+    fn test_retry_count_missing_header() {
+        let delivery = create_delivery(BasicProperties::default());
+        assert_eq!(retry_count(&delivery), 0);
+    }
+
+    #[test]
+    fn test_retry_count_round_trip() {
+        let mut headers = FieldTable::default();
+        headers.insert(
+            "message_type".into(),
+            AMQPValue::LongString("TaskCompleted".to_string().into()),
+        );
+        let properties = with_retry_count(&BasicProperties::default().with_headers(headers), 3);
+        let delivery = create_delivery(properties);
+
+        assert_eq!(retry_count(&delivery), 3);
+        // Other headers are preserved
+        assert!(delivery
+            .properties
+            .headers()
+            .as_ref()
+            .unwrap()
+            .inner()
+            .contains_key("message_type"));
+    }
+
+    #[test]
+    fn test_retry_count_accepts_other_integer_types() {
+        let mut headers = FieldTable::default();
+        headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongLongInt(2));
+        let delivery = create_delivery(BasicProperties::default().with_headers(headers));
+        assert_eq!(retry_count(&delivery), 2);
+    }
+
+    #[test]
+    fn test_retry_decision() {
+        assert_eq!(retry_decision(0, 3), RetryDecision::Retry(1));
+        assert_eq!(retry_decision(2, 3), RetryDecision::Retry(3));
+        assert_eq!(retry_decision(3, 3), RetryDecision::DeadLetter);
+        assert_eq!(retry_decision(0, 0), RetryDecision::DeadLetter);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::task_event_consumer::event_parsing::EventType;
+use lapin::types::{AMQPValue, FieldTable, LongString};
+
+/// Dedicated queues for some task event types, so they can be consumed at
+/// their own rate, possibly by other relay instances. Event types without a
+/// dedicated queue stay on the relay queues.
+///
+/// Queues are bound to a headers exchange on the `message_type` header of
+/// the events, itself bound to the task exchange. Dedicated queues receive
+/// the event types routed to them, and relay queues the ones without a
+/// dedicated queue. Without any dedicated queue, the relay queues are bound
+/// to the task exchange directly and receive every event. The SDKs bind the
+/// relay queue the same way, so it receives events published before the
+/// relay first started, and have to be told not to when events are routed.
+/// Events of a type routed elsewhere that still arrive on a queue, such as
+/// ones queued before the routing changed, are acknowledged and skipped.
+///
+/// # Fields
+/// * `assignment_queue` - Queue of the task assignment events
+/// * `running_queue` - Queue of the task running events
+/// * `completed_queue` - Queue of the task completed events, single or batched
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventRouting {
+    pub assignment_queue: Option<String>,
+    pub running_queue: Option<String>,
+    pub completed_queue: Option<String>,
+}
+
+impl EventRouting {
+    /// Whether any event type has a dedicated queue.
+    pub fn is_enabled(&self) -> bool {
+        !self.routes().is_empty()
+    }
+
+    /// The dedicated queue of an event type, if it has one.
+    pub fn queue_for(&self, event_type: EventType) -> Option<&str> {
+        match event_type {
+            EventType::Assignment => self.assignment_queue.as_deref(),
+            EventType::Running => self.running_queue.as_deref(),
+            EventType::Completed | EventType::BatchCompleted => self.completed_queue.as_deref(),
+            EventType::Acknowledged | EventType::Heartbeat | EventType::Registration => None,
+        }
+    }
+
+    /// Every event type with a dedicated queue, along with that queue.
+    pub fn routes(&self) -> Vec<(EventType, &str)> {
+        [
+            EventType::Assignment,
+            EventType::Running,
+            EventType::Completed,
+            EventType::BatchCompleted,
+        ]
+        .into_iter()
+        .filter_map(|event_type| Some((event_type, self.queue_for(event_type)?)))
+        .collect()
+    }
+
+    /// The event types `queue` is bound to when some event types are routed:
+    /// the ones routed to it and, if it is a relay queue, the ones without a
+    /// dedicated queue.
+    pub fn bound_event_types(&self, queue: &str, is_relay_queue: bool) -> Vec<EventType> {
+        EventType::ALL
+            .into_iter()
+            .filter(|event_type| match self.queue_for(*event_type) {
+                Some(routed) => routed == queue,
+                None => is_relay_queue,
+            })
+            .collect()
+    }
+
+    /// Whether an event of `event_type` received on `queue` belongs to
+    /// another queue, and is left to that queue's consumer.
+    pub fn is_routed_elsewhere(&self, queue: &str, event_type: EventType) -> bool {
+        self.queue_for(event_type)
+            .is_some_and(|routed| routed != queue)
+    }
+
+    /// The relay queues followed by the dedicated queues, without duplicates.
+    pub fn consumed_queues(&self, relay_queues: &[String]) -> Vec<String> {
+        let mut queues = relay_queues.to_vec();
+        for (_, queue) in self.routes() {
+            if !queues.iter().any(|consumed| consumed == queue) {
+                queues.push(queue.to_string());
+            }
+        }
+        queues
+    }
+}
+
+/// Builds the arguments binding a queue to the routing exchange, matching
+/// the events of a single type on their `message_type` header.
+pub fn binding_arguments(event_type: EventType) -> FieldTable {
+    let message_type: &str = event_type.into();
+
+    let mut arguments = FieldTable::default();
+    arguments.insert(
+        "x-match".into(),
+        AMQPValue::LongString(LongString::from("all")),
+    );
+    arguments.insert(
+        "message_type".into(),
+        AMQPValue::LongString(LongString::from(message_type)),
+    );
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routing() -> EventRouting {
+        EventRouting {
+            assignment_queue: None,
+            running_queue: Some("tacoq_relay_updates".to_string()),
+            completed_queue: Some("tacoq_relay_completed".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_default_routing_keeps_every_event_on_the_relay_queue() {
+        let routing = EventRouting::default();
+
+        assert!(!routing.is_enabled());
+        assert!(!routing.is_routed_elsewhere("tacoq_relay_queue", EventType::Completed));
+        assert_eq!(
+            routing.consumed_queues(&["tacoq_relay_queue".to_string()]),
+            vec!["tacoq_relay_queue"]
+        );
+    }
+
+    #[test]
+    fn test_routing_decision() {
+        let routing = routing();
+
+        assert_eq!(routing.queue_for(EventType::Assignment), None);
+        assert_eq!(
+            routing.queue_for(EventType::Completed),
+            Some("tacoq_relay_completed")
+        );
+        assert_eq!(routing.queue_for(EventType::Heartbeat), None);
+
+        // Events are handled on their dedicated queue only
+        assert!(routing.is_routed_elsewhere("tacoq_relay_queue", EventType::Completed));
+        assert!(!routing.is_routed_elsewhere("tacoq_relay_completed", EventType::Completed));
+        assert!(!routing.is_routed_elsewhere("tacoq_relay_completed", EventType::BatchCompleted));
+        assert!(routing.is_routed_elsewhere("tacoq_relay_queue", EventType::BatchCompleted));
+        assert!(routing.is_routed_elsewhere("tacoq_relay_completed", EventType::Running));
+
+        // Events without a dedicated queue are handled wherever they arrive
+        assert!(!routing.is_routed_elsewhere("tacoq_relay_queue", EventType::Assignment));
+        assert!(!routing.is_routed_elsewhere("tacoq_relay_completed", EventType::Heartbeat));
+    }
+
+    #[test]
+    fn test_consumed_queues_skip_duplicates() {
+        let routing = EventRouting {
+            assignment_queue: Some("tacoq_relay_updates".to_string()),
+            ..routing()
+        };
+
+        assert_eq!(
+            routing.consumed_queues(&[
+                "tacoq_relay_queue".to_string(),
+                "tacoq_relay_completed".to_string()
+            ]),
+            vec![
+                "tacoq_relay_queue",
+                "tacoq_relay_completed",
+                "tacoq_relay_updates"
+            ]
+        );
+        assert_eq!(
+            routing.bound_event_types("tacoq_relay_updates", false),
+            vec![EventType::Assignment, EventType::Running]
+        );
+        assert!(routing
+            .bound_event_types("tacoq_relay_queue", false)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_relay_queues_are_bound_to_the_event_types_left_to_them() {
+        let routing = routing();
+
+        assert_eq!(
+            routing.bound_event_types("tacoq_relay_queue", true),
+            vec![
+                EventType::Assignment,
+                EventType::Acknowledged,
+                EventType::Heartbeat,
+                EventType::Registration
+            ]
+        );
+        assert_eq!(
+            routing.bound_event_types("tacoq_relay_completed", false),
+            vec![EventType::Completed, EventType::BatchCompleted]
+        );
+
+        // A relay queue that is also dedicated receives both
+        assert_eq!(
+            routing.bound_event_types("tacoq_relay_updates", true),
+            vec![
+                EventType::Assignment,
+                EventType::Acknowledged,
+                EventType::Running,
+                EventType::Heartbeat,
+                EventType::Registration
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binding_arguments_match_the_message_type() {
+        let arguments = binding_arguments(EventType::Completed);
+        let inner = arguments.inner();
+
+        assert_eq!(
+            inner.get("x-match"),
+            Some(&AMQPValue::LongString("all".into()))
+        );
+        assert_eq!(
+            inner.get("message_type"),
+            Some(&AMQPValue::LongString("TaskCompleted".into()))
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::NaiveDateTime;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Identifies a task event independently of the delivery carrying it, so a
+/// redelivered event can be recognized.
+///
+/// # Fields
+/// * `task_id` - The task the event applies to
+/// * `event_type` - The message type of the event
+/// * `occurred_at` - When the event happened according to its publisher
+/// * `is_error` - Whether a completion is an error, as an error may be
+///   reported at the same time as a success and must still win
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventFingerprint {
+    pub task_id: Uuid,
+    pub event_type: &'static str,
+    pub occurred_at: NaiveDateTime,
+    pub is_error: bool,
+}
+
+/// Remembers the fingerprints of the most recently handled events, forgetting
+/// the oldest ones once `window` of them are kept.
+#[derive(Debug)]
+pub struct EventDeduplicator {
+    window: usize,
+    seen: Mutex<SeenEvents>,
+}
+
+#[derive(Debug, Default)]
+struct SeenEvents {
+    fingerprints: HashSet<EventFingerprint>,
+    order: VecDeque<EventFingerprint>,
+}
+
+impl EventDeduplicator {
+    /// Creates a deduplicator remembering up to `window` events. A window of
+    /// 0 disables deduplication.
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            seen: Mutex::new(SeenEvents::default()),
+        }
+    }
+
+    /// Whether an event with this fingerprint was already handled.
+    pub fn is_duplicate(&self, fingerprint: &EventFingerprint) -> bool {
+        let seen = self.seen.lock().expect("Deduplicator lock poisoned");
+        seen.fingerprints.contains(fingerprint)
+    }
+
+    /// Records that an event was handled, evicting the oldest fingerprint if
+    /// the window is full.
+    pub fn record(&self, fingerprint: EventFingerprint) {
+        if self.window == 0 {
+            return;
+        }
+
+        let mut seen = self.seen.lock().expect("Deduplicator lock poisoned");
+        if !seen.fingerprints.insert(fingerprint) {
+            return;
+        }
+        seen.order.push_back(fingerprint);
+        while seen.order.len() > self.window {
+            if let Some(oldest) = seen.order.pop_front() {
+                seen.fingerprints.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    fn fingerprint(event_type: &'static str) -> EventFingerprint {
+        EventFingerprint {
+            task_id: Uuid::new_v4(),
+            event_type,
+            occurred_at: Local::now().naive_local(),
+            is_error: false,
+        }
+    }
+
+    #[test]
+    fn test_deduplicator_window() {
+        let deduplicator = EventDeduplicator::new(2);
+        let first = fingerprint("TaskAssignment");
+        let second = fingerprint("TaskRunning");
+        let third = fingerprint("TaskCompleted");
+
+        assert!(!deduplicator.is_duplicate(&first));
+        deduplicator.record(first);
+        deduplicator.record(second);
+        assert!(deduplicator.is_duplicate(&first));
+        assert!(deduplicator.is_duplicate(&second));
+
+        // The oldest fingerprint is forgotten once the window is full
+        deduplicator.record(third);
+        assert!(!deduplicator.is_duplicate(&first));
+        assert!(deduplicator.is_duplicate(&third));
+    }
+
+    #[test]
+    fn test_deduplicator_disabled() {
+        let deduplicator = EventDeduplicator::new(0);
+        let first = fingerprint("TaskAssignment");
+
+        deduplicator.record(first);
+        assert!(!deduplicator.is_duplicate(&first));
+    }
+}
@@ -1,14 +1,22 @@
 use crate::models::{
-    AvroSerializable, TaskAssignmentUpdate, TaskCompletedUpdate, TaskRunningUpdate,
+    AvroSerializable, TaskAcknowledgedUpdate, TaskAssignmentUpdate, TaskBatchCompletedUpdate,
+    TaskCompletedUpdate, TaskRunningUpdate, WorkerHeartbeatUpdate, WorkerRegistrationUpdate,
 };
+use crate::task_event_consumer::dedup::EventFingerprint;
+use chrono::NaiveDateTime;
 use std::{clone::Clone, fmt::Debug};
-use tracing::error;
 
 /// Errors that can occur when processing a message.
 #[derive(Debug, thiserror::Error)]
 pub enum MessageProcessingError {
     #[error("Error deserializing Avro message: {0}")]
     AvroDeserializationError(String),
+    #[error("Error serializing Avro message: {0}")]
+    AvroSerializationError(String),
+    #[error("Error deserializing JSON message: {0}")]
+    JsonDeserializationError(String),
+    #[error("Error serializing JSON message: {0}")]
+    JsonSerializationError(String),
     #[error("Unknown message type: {0}")]
     UnknownMessageType(String),
 }
@@ -18,8 +26,25 @@ pub enum MessageProcessingError {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EventType {
     Assignment,
+    Acknowledged,
     Completed,
+    BatchCompleted,
     Running,
+    Heartbeat,
+    Registration,
+}
+
+impl EventType {
+    /// Every event type.
+    pub const ALL: [EventType; 7] = [
+        EventType::Assignment,
+        EventType::Acknowledged,
+        EventType::Completed,
+        EventType::BatchCompleted,
+        EventType::Running,
+        EventType::Heartbeat,
+        EventType::Registration,
+    ];
 }
 
 impl TryFrom<String> for EventType {
@@ -28,8 +53,12 @@ impl TryFrom<String> for EventType {
     fn try_from(value: String) -> Result<Self, Self::Error> {
         match value.as_str() {
             "TaskAssignment" => Ok(EventType::Assignment),
+            "TaskAcknowledged" => Ok(EventType::Acknowledged),
             "TaskCompleted" => Ok(EventType::Completed),
+            "TaskBatchCompleted" => Ok(EventType::BatchCompleted),
             "TaskRunning" => Ok(EventType::Running),
+            "WorkerHeartbeat" => Ok(EventType::Heartbeat),
+            "WorkerRegistration" => Ok(EventType::Registration),
             _ => Err(MessageProcessingError::UnknownMessageType(value)),
         }
     }
@@ -39,8 +68,12 @@ impl From<EventType> for &str {
     fn from(value: EventType) -> Self {
         match value {
             EventType::Assignment => "TaskAssignment",
+            EventType::Acknowledged => "TaskAcknowledged",
             EventType::Completed => "TaskCompleted",
+            EventType::BatchCompleted => "TaskBatchCompleted",
             EventType::Running => "TaskRunning",
+            EventType::Heartbeat => "WorkerHeartbeat",
+            EventType::Registration => "WorkerRegistration",
         }
     }
 }
@@ -49,8 +82,116 @@ impl From<EventType> for &str {
 #[derive(Debug)]
 pub enum Event {
     Assignment(TaskAssignmentUpdate),
+    Acknowledged(TaskAcknowledgedUpdate),
     Completed(TaskCompletedUpdate),
+    BatchCompleted(TaskBatchCompletedUpdate),
     Running(TaskRunningUpdate),
+    Heartbeat(WorkerHeartbeatUpdate),
+    Registration(WorkerRegistrationUpdate),
+}
+
+impl Event {
+    /// Returns the type of the event.
+    pub fn event_type(&self) -> EventType {
+        match self {
+            Event::Assignment(_) => EventType::Assignment,
+            Event::Acknowledged(_) => EventType::Acknowledged,
+            Event::Completed(_) => EventType::Completed,
+            Event::BatchCompleted(_) => EventType::BatchCompleted,
+            Event::Running(_) => EventType::Running,
+            Event::Heartbeat(_) => EventType::Heartbeat,
+            Event::Registration(_) => EventType::Registration,
+        }
+    }
+
+    /// Identifies what the event updates, the task or the worker. Events
+    /// with the same key must be handled in the order they were received.
+    /// Completions apply in any order, so a batch of them is keyed by its
+    /// first task only.
+    pub fn ordering_key(&self) -> String {
+        match self {
+            Event::Assignment(assignment) => assignment.id.to_string(),
+            Event::Acknowledged(acknowledged) => acknowledged.id.to_string(),
+            Event::Completed(completed) => completed.id.to_string(),
+            Event::BatchCompleted(batch) => batch
+                .completions
+                .first()
+                .map(|completed| completed.id.to_string())
+                .unwrap_or_default(),
+            Event::Running(running) => running.id.to_string(),
+            Event::Heartbeat(heartbeat) => heartbeat.worker_name.clone(),
+            Event::Registration(registration) => registration.worker_name.clone(),
+        }
+    }
+
+    /// When the event happened. Assignments held back until a scheduled time
+    /// happen at that time, and a batch happens with its latest completion.
+    pub fn occurred_at(&self) -> NaiveDateTime {
+        match self {
+            Event::Assignment(assignment) => {
+                assignment.held_until().unwrap_or(assignment.created_at)
+            }
+            Event::Acknowledged(acknowledged) => acknowledged.acknowledged_at,
+            Event::Completed(completed) => completed.completed_at,
+            Event::BatchCompleted(batch) => batch
+                .completions
+                .iter()
+                .map(|completed| completed.completed_at)
+                .max()
+                .unwrap_or_default(),
+            Event::Running(running) => running.started_at,
+            Event::Heartbeat(heartbeat) => heartbeat.heartbeat_at,
+            Event::Registration(registration) => registration.registered_at,
+        }
+    }
+
+    /// Fingerprint recognizing a redelivery of a task event. Worker events
+    /// are cheap to apply again, so they have none. Batches are recognized
+    /// per completion instead, see [`completion_fingerprint`].
+    pub fn fingerprint(&self) -> Option<EventFingerprint> {
+        let event_type = self.event_type().into();
+        let (task_id, occurred_at, is_error) = match self {
+            Event::Assignment(assignment) => (assignment.id, assignment.created_at, false),
+            Event::Acknowledged(acknowledged) => {
+                (acknowledged.id, acknowledged.acknowledged_at, false)
+            }
+            Event::Completed(completed) => return Some(completion_fingerprint(completed)),
+            Event::Running(running) => (running.id, running.started_at, false),
+            Event::BatchCompleted(_) | Event::Heartbeat(_) | Event::Registration(_) => return None,
+        };
+        Some(EventFingerprint {
+            task_id,
+            event_type,
+            occurred_at,
+            is_error,
+        })
+    }
+
+    /// Serializes the data inside the event into Avro bytes.
+    pub fn try_into_avro_bytes(&self) -> Result<Vec<u8>, MessageProcessingError> {
+        let bytes = match self {
+            Event::Assignment(assignment) => assignment.try_into_avro_bytes(),
+            Event::Acknowledged(acknowledged) => acknowledged.try_into_avro_bytes(),
+            Event::Completed(completed) => completed.try_into_avro_bytes(),
+            Event::BatchCompleted(batch) => batch.try_into_avro_bytes(),
+            Event::Running(running) => running.try_into_avro_bytes(),
+            Event::Heartbeat(heartbeat) => heartbeat.try_into_avro_bytes(),
+            Event::Registration(registration) => registration.try_into_avro_bytes(),
+        };
+        bytes.map_err(|e| MessageProcessingError::AvroSerializationError(e.to_string()))
+    }
+}
+
+/// Fingerprint of a task completion. It is the same whether the completion
+/// was published on its own or in a batch, so either recognizes a redelivery
+/// of the other.
+pub fn completion_fingerprint(completed: &TaskCompletedUpdate) -> EventFingerprint {
+    EventFingerprint {
+        task_id: completed.id,
+        event_type: EventType::Completed.into(),
+        occurred_at: completed.completed_at,
+        is_error: completed.is_error,
+    }
 }
 
 /// Based on the event type, parses raw bytes into an Event with the decoded
@@ -81,6 +222,18 @@ pub fn try_parse_event_from_avro_bytes(
                 .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
             Ok(Event::Assignment(assignment))
         }
+        EventType::Acknowledged => {
+            // Deserialize the message
+            let acknowledged: TaskAcknowledgedUpdate =
+                TaskAcknowledgedUpdate::try_from_avro_bytes(raw_bytes)
+                    .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
+
+            // Validate message integrity
+            acknowledged
+                .validate_update_type()
+                .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
+            Ok(Event::Acknowledged(acknowledged))
+        }
         EventType::Completed => {
             // Deserialize the message
             let completed: TaskCompletedUpdate =
@@ -93,6 +246,18 @@ pub fn try_parse_event_from_avro_bytes(
                 .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
             Ok(Event::Completed(completed))
         }
+        EventType::BatchCompleted => {
+            // Deserialize the message
+            let batch: TaskBatchCompletedUpdate =
+                TaskBatchCompletedUpdate::try_from_avro_bytes(raw_bytes)
+                    .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
+
+            // Validate message integrity
+            batch
+                .validate_update_type()
+                .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
+            Ok(Event::BatchCompleted(batch))
+        }
         EventType::Running => {
             // Deserialize the message
             let running: TaskRunningUpdate = TaskRunningUpdate::try_from_avro_bytes(raw_bytes)
@@ -104,13 +269,40 @@ pub fn try_parse_event_from_avro_bytes(
                 .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
             Ok(Event::Running(running))
         }
+        EventType::Heartbeat => {
+            // Deserialize the message
+            let heartbeat: WorkerHeartbeatUpdate =
+                WorkerHeartbeatUpdate::try_from_avro_bytes(raw_bytes)
+                    .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
+
+            // Validate message integrity
+            heartbeat
+                .validate_update_type()
+                .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
+            Ok(Event::Heartbeat(heartbeat))
+        }
+        EventType::Registration => {
+            // Deserialize the message
+            let registration: WorkerRegistrationUpdate =
+                WorkerRegistrationUpdate::try_from_avro_bytes(raw_bytes)
+                    .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
+
+            // Validate message integrity
+            registration
+                .validate_update_type()
+                .map_err(|e| MessageProcessingError::AvroDeserializationError(e.to_string()))?;
+            Ok(Event::Registration(registration))
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{TaskAssignmentUpdate, TaskCompletedUpdate, TaskRunningUpdate};
+    use crate::models::{
+        TaskAcknowledgedUpdate, TaskAssignmentUpdate, TaskBatchCompletedUpdate,
+        TaskCompletedUpdate, TaskRunningUpdate, WorkerHeartbeatUpdate, WorkerRegistrationUpdate,
+    };
     use chrono::Local;
     use uuid::Uuid;
 
@@ -126,8 +318,10 @@ mod tests {
             created_at: Local::now().naive_local(),
             input_data: vec![1, 2, 3],
             priority: 1,
-            ttl_duration: 3600000000, // 1 hour in microseconds
+            ttl_duration: 3600, // 1 hour in seconds
             otel_ctx_carrier: otel_ctx,
+            input_content_type: None,
+            scheduled_for: None,
             update_type: "Assignment".to_string(),
         }
     }
@@ -137,7 +331,8 @@ mod tests {
             id: Uuid::new_v4(),
             completed_at: Local::now().naive_local(),
             output_data: vec![4, 5, 6],
-            is_error: 0,
+            is_error: false,
+            output_content_type: None,
             update_type: "Completed".to_string(),
         }
     }
@@ -151,6 +346,15 @@ mod tests {
         }
     }
 
+    fn create_test_heartbeat() -> WorkerHeartbeatUpdate {
+        WorkerHeartbeatUpdate {
+            worker_name: "test_worker_1".to_string(),
+            worker_kind: "test_worker".to_string(),
+            heartbeat_at: Local::now().naive_local(),
+            update_type: "Heartbeat".to_string(),
+        }
+    }
+
     #[test]
     fn test_parse_assignment_event() {
         let assignment = create_test_assignment();
@@ -195,6 +399,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_batch_completed_event() {
+        let batch = TaskBatchCompletedUpdate::new(vec![
+            create_test_completed(),
+            create_test_completed(),
+            create_test_completed(),
+        ]);
+        let avro_bytes = batch.try_into_avro_bytes().unwrap();
+
+        let event =
+            try_parse_event_from_avro_bytes(EventType::BatchCompleted, &avro_bytes).unwrap();
+        assert_eq!(
+            event.occurred_at(),
+            batch
+                .completions
+                .iter()
+                .map(|completed| completed.completed_at)
+                .max()
+                .unwrap()
+        );
+        match event {
+            Event::BatchCompleted(parsed) => {
+                let ids: Vec<_> = parsed.completions.iter().map(|c| c.id).collect();
+                let expected: Vec<_> = batch.completions.iter().map(|c| c.id).collect();
+                assert_eq!(ids, expected);
+                assert_eq!(
+                    parsed.completions[0].output_data,
+                    batch.completions[0].output_data
+                );
+            }
+            _ => panic!("Expected BatchCompleted event"),
+        }
+
+        // A single completion isn't a batch
+        let completed = create_test_completed().try_into_avro_bytes().unwrap();
+        assert!(try_parse_event_from_avro_bytes(EventType::BatchCompleted, &completed).is_err());
+    }
+
     #[test]
     fn test_parse_running_event() {
         let running = create_test_running();
@@ -214,6 +456,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_acknowledged_event() {
+        let acknowledged = TaskAcknowledgedUpdate::new(Uuid::new_v4(), Local::now().naive_local());
+        let avro_bytes = acknowledged.try_into_avro_bytes().unwrap();
+
+        let event = try_parse_event_from_avro_bytes(EventType::Acknowledged, &avro_bytes).unwrap();
+        match event {
+            Event::Acknowledged(parsed) => {
+                assert_eq!(acknowledged.id, parsed.id);
+                assert_eq!(
+                    acknowledged.acknowledged_at.and_utc().timestamp_micros(),
+                    parsed.acknowledged_at.and_utc().timestamp_micros()
+                );
+            }
+            _ => panic!("Expected Acknowledged event"),
+        }
+
+        // Acknowledgements are told apart from the running events they resemble
+        assert!(try_parse_event_from_avro_bytes(EventType::Running, &avro_bytes).is_err());
+        assert_eq!(
+            EventType::try_from("TaskAcknowledged".to_string()).unwrap(),
+            EventType::Acknowledged
+        );
+    }
+
+    #[test]
+    fn test_parse_heartbeat_event() {
+        let heartbeat = create_test_heartbeat();
+        let avro_bytes = heartbeat.try_into_avro_bytes().unwrap();
+
+        let event = try_parse_event_from_avro_bytes(EventType::Heartbeat, &avro_bytes).unwrap();
+        match event {
+            Event::Heartbeat(parsed) => {
+                assert_eq!(heartbeat.worker_name, parsed.worker_name);
+                assert_eq!(heartbeat.worker_kind, parsed.worker_kind);
+                assert_eq!(
+                    heartbeat.heartbeat_at.and_utc().timestamp_micros(),
+                    parsed.heartbeat_at.and_utc().timestamp_micros()
+                );
+            }
+            _ => panic!("Expected Heartbeat event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_registration_event() {
+        let registration = WorkerRegistrationUpdate::new(
+            "test_worker_1",
+            "test_worker",
+            &["test_task"],
+            Local::now().naive_local(),
+        );
+        let avro_bytes = registration.try_into_avro_bytes().unwrap();
+
+        let event = try_parse_event_from_avro_bytes(EventType::Registration, &avro_bytes).unwrap();
+        match event {
+            Event::Registration(parsed) => {
+                assert_eq!(registration.worker_name, parsed.worker_name);
+                assert_eq!(registration.worker_kind, parsed.worker_kind);
+                assert_eq!(registration.task_kinds, parsed.task_kinds);
+                assert_eq!(registration.version, parsed.version);
+            }
+            _ => panic!("Expected Registration event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_heartbeat_as_running_event() {
+        let heartbeat = create_test_heartbeat();
+        let avro_bytes = heartbeat.try_into_avro_bytes().unwrap();
+
+        let result = try_parse_event_from_avro_bytes(EventType::Running, &avro_bytes);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_parse_invalid_event_type() {
         let assignment = create_test_assignment();
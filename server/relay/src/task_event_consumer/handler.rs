@@ -1,7 +1,13 @@
-use crate::repo::task_repo::TaskRepository;
-use crate::task_event_consumer::event_parsing::Event;
+use crate::constants::{DEFAULT_DEDUP_WINDOW, DEFAULT_MAX_PAYLOAD_BYTES};
+use crate::models::{TaskCompletedUpdate, TaskLifecycle};
+use crate::repo::{TaskRepository, WorkerRepository};
+use crate::task_event_consumer::dedup::EventDeduplicator;
+use crate::task_event_consumer::event_parsing::{completion_fingerprint, Event};
+use crate::task_event_consumer::lag::ConsumerLag;
 use std::error::Error;
 use std::sync::Arc;
+use tracing::{debug, error, field, info, info_span, warn};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 /// A Task Event Handler handles task events in the consumer.
 ///
@@ -13,14 +19,58 @@ use std::sync::Arc;
 /// would have different repositories, but the handler would remain the same.
 pub struct TaskEventHandler {
     task_repository: Arc<TaskRepository>,
+    worker_repository: Arc<WorkerRepository>,
+    max_payload_bytes: usize,
+    deduplicator: EventDeduplicator,
+    lag: ConsumerLag,
 }
 
 impl TaskEventHandler {
-    pub fn new(task_repository: Arc<TaskRepository>) -> Self {
-        Self { task_repository }
+    pub fn new(
+        task_repository: Arc<TaskRepository>,
+        worker_repository: Arc<WorkerRepository>,
+    ) -> Self {
+        Self {
+            task_repository,
+            worker_repository,
+            max_payload_bytes: DEFAULT_MAX_PAYLOAD_BYTES,
+            deduplicator: EventDeduplicator::new(DEFAULT_DEDUP_WINDOW),
+            lag: ConsumerLag::default(),
+        }
+    }
+
+    /// The lag of the handler, recorded as events are processed.
+    pub fn lag(&self) -> ConsumerLag {
+        self.lag.clone()
+    }
+
+    /// Checks that the repositories can reach the database.
+    pub async fn check_database(&self) -> Result<(), sqlx::Error> {
+        self.task_repository.health_check().await
+    }
+
+    /// Sets the largest task input or output the handler stores.
+    ///
+    /// # Arguments
+    /// * `max_payload_bytes` - The size limit, in bytes
+    pub fn with_max_payload_bytes(mut self, max_payload_bytes: usize) -> Self {
+        self.max_payload_bytes = max_payload_bytes;
+        self
+    }
+
+    /// Sets how many recently handled task events are remembered to skip
+    /// their redeliveries.
+    ///
+    /// # Arguments
+    /// * `dedup_window` - The number of events remembered, 0 to disable
+    pub fn with_dedup_window(mut self, dedup_window: usize) -> Self {
+        self.deduplicator = EventDeduplicator::new(dedup_window);
+        self
     }
 
-    /// Uploads all the received events to the repository.
+    /// Uploads all the received events to the repository. Task events that
+    /// were already handled recently are skipped, so redeliveries aren't
+    /// recorded twice in the task history.
     pub async fn handle_batch_events(
         &self,
         events: Vec<Event>,
@@ -30,15 +80,80 @@ impl TaskEventHandler {
         // in a Postgres transaction and upload them all at once, which requires adding
         // a new method to the TaskRepository that accepts a Vec<Update>
         for event in events {
+            let occurred_at = event.occurred_at();
+            let fingerprint = event.fingerprint();
+            if let Some(fingerprint) = &fingerprint {
+                if self.deduplicator.is_duplicate(fingerprint) {
+                    debug!(
+                        task_id = %fingerprint.task_id,
+                        event_type = fingerprint.event_type,
+                        "Skipping already handled event"
+                    );
+                    self.lag.record(occurred_at);
+                    continue;
+                }
+            }
+
             match event {
                 Event::Assignment(assignment) => {
+                    // Retrying would fail the same way, so the task is dropped.
+                    // TTLs sent in the wrong unit would keep the task forever.
+                    if let Err(e) = assignment.validate_ttl_duration() {
+                        error!(task_id = %assignment.id, error = %e, "Rejecting task assignment");
+                        self.lag.record(occurred_at);
+                        continue;
+                    }
+                    if assignment.input_data.len() > self.max_payload_bytes {
+                        error!(
+                            task_id = %assignment.id,
+                            input_bytes = assignment.input_data.len(),
+                            max_payload_bytes = self.max_payload_bytes,
+                            "Task input exceeds the maximum payload size, rejecting task"
+                        );
+                        self.lag.record(occurred_at);
+                        continue;
+                    }
+                    self.flag_unregistered_worker_kind(&assignment.worker_kind)
+                        .await;
                     self.task_repository
                         .update_task_from_assignment_update(&assignment)
                         .await?;
                 }
                 Event::Completed(completed) => {
-                    self.task_repository
+                    let completed = self.limit_output_size(completed);
+                    if let Some(lifecycle) = self
+                        .task_repository
                         .update_task_from_completed_update(&completed)
+                        .await?
+                    {
+                        log_task_latency(&lifecycle);
+                    }
+                }
+                Event::BatchCompleted(batch) => {
+                    // Completions already handled on their own or in another
+                    // batch are skipped, like redelivered events
+                    let (fingerprints, completions): (Vec<_>, Vec<_>) = batch
+                        .completions
+                        .into_iter()
+                        .map(|completed| (completion_fingerprint(&completed), completed))
+                        .filter(|(fingerprint, _)| !self.deduplicator.is_duplicate(fingerprint))
+                        .map(|(fingerprint, completed)| {
+                            (fingerprint, self.limit_output_size(completed))
+                        })
+                        .unzip();
+                    debug!(count = completions.len(), "Handling batch of completions");
+                    let lifecycles = self
+                        .task_repository
+                        .update_tasks_from_completed_updates(&completions)
+                        .await?;
+                    for fingerprint in fingerprints {
+                        self.deduplicator.record(fingerprint);
+                    }
+                    lifecycles.iter().for_each(log_task_latency);
+                }
+                Event::Acknowledged(acknowledged) => {
+                    self.task_repository
+                        .update_task_from_acknowledged_update(&acknowledged)
                         .await?;
                 }
                 Event::Running(running) => {
@@ -46,8 +161,383 @@ impl TaskEventHandler {
                         .update_task_from_running_update(&running)
                         .await?;
                 }
+                Event::Heartbeat(heartbeat) => {
+                    self.worker_repository.save_heartbeat(&heartbeat).await?;
+                }
+                Event::Registration(registration) => {
+                    info!(
+                        worker_name = %registration.worker_name,
+                        worker_kind = %registration.worker_kind,
+                        task_kinds = ?registration.task_kinds,
+                        version = %registration.version,
+                        "Worker registered"
+                    );
+                    self.worker_repository
+                        .save_registration(&registration)
+                        .await?;
+                }
             }
+
+            if let Some(fingerprint) = fingerprint {
+                self.deduplicator.record(fingerprint);
+            }
+            self.lag.record(occurred_at);
         }
         Ok(())
     }
+
+    /// Warns when a task is assigned to a worker kind no worker has
+    /// registered for, since it may never be picked up. The task is still
+    /// stored, as workers that predate registration never announce
+    /// themselves. Failing to count the workers only loses the warning.
+    async fn flag_unregistered_worker_kind(&self, worker_kind: &str) {
+        match self
+            .worker_repository
+            .count_registered_workers(worker_kind)
+            .await
+        {
+            Ok(0) => warn!(
+                worker_kind = %worker_kind,
+                "Task assigned to a worker kind with no registered workers"
+            ),
+            Ok(_) => {}
+            Err(e) => {
+                warn!(worker_kind = %worker_kind, error = %e, "Failed to count registered workers")
+            }
+        }
+    }
+
+    /// Replaces an output larger than the payload limit with an error, so
+    /// the task still completes but the blob isn't stored.
+    fn limit_output_size(&self, completed: TaskCompletedUpdate) -> TaskCompletedUpdate {
+        let output_bytes = completed.output_data.len();
+        if output_bytes <= self.max_payload_bytes {
+            return completed;
+        }
+
+        error!(
+            task_id = %completed.id,
+            output_bytes,
+            max_payload_bytes = self.max_payload_bytes,
+            "Task output exceeds the maximum payload size, marking task as errored"
+        );
+        let message = format!(
+            "Task output of {} bytes exceeds the maximum payload size of {} bytes",
+            output_bytes, self.max_payload_bytes
+        );
+        TaskCompletedUpdate {
+            output_data: message.into_bytes(),
+            is_error: true,
+            output_content_type: Some("text/plain".to_string()),
+            ..completed
+        }
+    }
+}
+
+/// Logs how long a completed task spent pending and running, in a span
+/// attached to the trace that originated the task.
+fn log_task_latency(task: &TaskLifecycle) {
+    let latency = task.latency();
+
+    let span = info_span!(
+        "task_lifecycle",
+        task_id = %task.id,
+        pending_ms = field::Empty,
+        running_ms = field::Empty,
+        total_ms = latency.total_ms,
+    );
+    span.set_parent(task.context());
+    if let (Some(pending_ms), Some(running_ms)) = (latency.pending_ms, latency.running_ms) {
+        span.record("pending_ms", pending_ms);
+        span.record("running_ms", running_ms);
+    }
+
+    let _entered = span.enter();
+    match (latency.pending_ms, latency.running_ms) {
+        (Some(pending_ms), Some(running_ms)) => info!(
+            task_id = %task.id,
+            pending_ms,
+            running_ms,
+            total_ms = latency.total_ms,
+            "Task completed"
+        ),
+        _ => info!(
+            task_id = %task.id,
+            total_ms = latency.total_ms,
+            "Task completed without a start time"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        TaskAcknowledgedUpdate, TaskAssignmentUpdate, TaskBatchCompletedUpdate, TaskRunningUpdate,
+    };
+    use crate::repo::PgRepositoryCore;
+    use chrono::Local;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    fn get_test_handler(pool: PgPool) -> (TaskEventHandler, Arc<TaskRepository>) {
+        let core = PgRepositoryCore::new(pool);
+        let task_repository = Arc::new(TaskRepository::new(core.clone()));
+        let handler = TaskEventHandler::new(
+            task_repository.clone(),
+            Arc::new(WorkerRepository::new(core)),
+        )
+        .with_max_payload_bytes(8);
+        (handler, task_repository)
+    }
+
+    fn assignment_with_input(input_data: Vec<u8>) -> TaskAssignmentUpdate {
+        TaskAssignmentUpdate {
+            id: Uuid::new_v4(),
+            task_kind: "TaskKindName".to_string(),
+            worker_kind: "WorkerKindName".to_string(),
+            created_at: Local::now().naive_local(),
+            input_data,
+            ..TaskAssignmentUpdate::default()
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_redelivered_event_is_recorded_once(pool: PgPool) {
+        let (handler, repo) = get_test_handler(pool);
+        let assignment = assignment_with_input(vec![1]);
+        let running =
+            TaskRunningUpdate::new(assignment.id, Local::now().naive_local(), "worker".into());
+
+        handler
+            .handle_batch_events(vec![
+                Event::Assignment(assignment.clone()),
+                Event::Assignment(assignment.clone()),
+            ])
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            handler
+                .handle_batch_events(vec![Event::Running(running.clone())])
+                .await
+                .unwrap();
+        }
+
+        let history = repo.get_task_history(&assignment.id).await.unwrap();
+        let event_types: Vec<_> = history
+            .iter()
+            .map(|event| event.event_type.as_str())
+            .collect();
+        assert_eq!(event_types, vec!["TaskAssignment", "TaskRunning"]);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_acknowledged_event_is_recorded(pool: PgPool) {
+        let (handler, repo) = get_test_handler(pool);
+        let assignment = assignment_with_input(vec![1]);
+        let acknowledged_at = chrono::DateTime::from_timestamp_micros(
+            Local::now().naive_local().and_utc().timestamp_micros(),
+        )
+        .unwrap()
+        .naive_utc();
+
+        handler
+            .handle_batch_events(vec![
+                Event::Assignment(assignment.clone()),
+                Event::Acknowledged(TaskAcknowledgedUpdate::new(assignment.id, acknowledged_at)),
+            ])
+            .await
+            .unwrap();
+
+        // Acknowledged but not started yet
+        let task = repo.get_task_by_id(&assignment.id).await.unwrap().unwrap();
+        assert_eq!(task.acknowledged_at, Some(acknowledged_at));
+        assert_eq!(task.started_at, None);
+
+        let history = repo.get_task_history(&assignment.id).await.unwrap();
+        let event_types: Vec<_> = history
+            .iter()
+            .map(|event| event.event_type.as_str())
+            .collect();
+        assert_eq!(event_types, vec!["TaskAssignment", "TaskAcknowledged"]);
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_batch_completed_event_updates_every_task(pool: PgPool) {
+        let (handler, repo) = get_test_handler(pool);
+        let assignments: Vec<_> = (0..3).map(|i| assignment_with_input(vec![i])).collect();
+        let batch = TaskBatchCompletedUpdate::new(
+            assignments
+                .iter()
+                .map(|assignment| {
+                    TaskCompletedUpdate::new(
+                        assignment.id,
+                        Local::now().naive_local(),
+                        vec![1, 2],
+                        false,
+                    )
+                })
+                .collect(),
+        );
+
+        handler
+            .handle_batch_events(assignments.iter().cloned().map(Event::Assignment).collect())
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            handler
+                .handle_batch_events(vec![Event::BatchCompleted(batch.clone())])
+                .await
+                .unwrap();
+        }
+
+        for assignment in &assignments {
+            let task = repo.get_task_by_id(&assignment.id).await.unwrap().unwrap();
+            assert!(task.completed_at.is_some());
+            assert_eq!(task.output_data, Some(vec![1, 2]));
+            assert_eq!(task.is_error, Some(false));
+
+            // The redelivered batch isn't recorded again
+            let history = repo.get_task_history(&assignment.id).await.unwrap();
+            let event_types: Vec<_> = history
+                .iter()
+                .map(|event| event.event_type.as_str())
+                .collect();
+            assert_eq!(event_types, vec!["TaskAssignment", "TaskCompleted"]);
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_handled_events_update_the_lag(pool: PgPool) {
+        let (handler, _) = get_test_handler(pool);
+        let lag = handler.lag();
+        assert_eq!(lag.last_event_at(), None);
+
+        let started_at = chrono::Utc::now().naive_utc() - chrono::Duration::minutes(5);
+        let assignment = assignment_with_input(vec![1]);
+        let running = TaskRunningUpdate::new(assignment.id, started_at, "worker".into());
+        handler
+            .handle_batch_events(vec![Event::Assignment(assignment), Event::Running(running)])
+            .await
+            .unwrap();
+
+        assert_eq!(lag.last_event_at(), Some(started_at));
+        assert!(lag.lag().unwrap() >= std::time::Duration::from_secs(300));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_input_at_payload_limit_is_stored(pool: PgPool) {
+        let (handler, repo) = get_test_handler(pool);
+        let assignment = assignment_with_input(vec![0; 8]);
+
+        handler
+            .handle_batch_events(vec![Event::Assignment(assignment.clone())])
+            .await
+            .unwrap();
+
+        let task = repo.get_task_by_id(&assignment.id).await.unwrap().unwrap();
+        assert_eq!(task.input_data, Some(vec![0; 8]));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_input_over_payload_limit_is_rejected(pool: PgPool) {
+        let (handler, repo) = get_test_handler(pool);
+        let assignment = assignment_with_input(vec![0; 9]);
+
+        handler
+            .handle_batch_events(vec![Event::Assignment(assignment.clone())])
+            .await
+            .unwrap();
+
+        assert!(repo.get_task_by_id(&assignment.id).await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_implausible_ttl_is_rejected(pool: PgPool) {
+        let (handler, repo) = get_test_handler(pool);
+        let assignment = TaskAssignmentUpdate {
+            ttl_duration: 3_600_000_000, // 1 hour in microseconds
+            ..assignment_with_input(vec![])
+        };
+
+        // The event is dropped rather than failed, so it isn't retried
+        handler
+            .handle_batch_events(vec![Event::Assignment(assignment.clone())])
+            .await
+            .unwrap();
+
+        assert!(repo.get_task_by_id(&assignment.id).await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_output_at_payload_limit_is_stored(pool: PgPool) {
+        let (handler, repo) = get_test_handler(pool);
+        let completed = TaskCompletedUpdate::new(
+            Uuid::new_v4(),
+            Local::now().naive_local(),
+            vec![0; 8],
+            false,
+        );
+
+        handler
+            .handle_batch_events(vec![Event::Completed(completed.clone())])
+            .await
+            .unwrap();
+
+        let task = repo.get_task_by_id(&completed.id).await.unwrap().unwrap();
+        assert_eq!(task.output_data, Some(vec![0; 8]));
+        assert_eq!(task.is_error, Some(false));
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_output_over_payload_limit_marks_task_errored(pool: PgPool) {
+        let (handler, repo) = get_test_handler(pool);
+        let completed = TaskCompletedUpdate::new(
+            Uuid::new_v4(),
+            Local::now().naive_local(),
+            vec![0; 9],
+            false,
+        );
+
+        handler
+            .handle_batch_events(vec![Event::Completed(completed.clone())])
+            .await
+            .unwrap();
+
+        let task = repo.get_task_by_id(&completed.id).await.unwrap().unwrap();
+        assert_eq!(task.is_error, Some(true));
+        assert_eq!(
+            String::from_utf8(task.output_data.unwrap()).unwrap(),
+            "Task output of 9 bytes exceeds the maximum payload size of 8 bytes"
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::testing::test::MIGRATOR")]
+    async fn test_repository_failure_is_returned(pool: PgPool) {
+        let core = PgRepositoryCore::new(pool.clone());
+        let handler = TaskEventHandler::new(
+            Arc::new(TaskRepository::new(core.clone())),
+            Arc::new(WorkerRepository::new(core)),
+        );
+
+        // Every query fails once the pool is closed
+        pool.close().await;
+
+        let result = handler
+            .handle_batch_events(vec![Event::Assignment(TaskAssignmentUpdate {
+                id: Uuid::new_v4(),
+                ..TaskAssignmentUpdate::default()
+            })])
+            .await;
+        assert!(result.is_err());
+
+        // The handler is still usable after a failure
+        let result = handler
+            .handle_batch_events(vec![Event::Running(TaskRunningUpdate {
+                id: Uuid::new_v4(),
+                ..TaskRunningUpdate::default()
+            })])
+            .await;
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,100 @@
+use chrono::NaiveDateTime;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How far behind the consumer is, measured as the time between the event it
+/// processed last happening and being processed. A consumer keeping up stays
+/// close to zero, while a growing lag means events pile up faster than they
+/// are handled.
+///
+/// The lag is measured when an event is processed, so it keeps its last
+/// value while the queues are idle instead of growing with the time since
+/// the last event.
+///
+/// Clones share the same measurement, so the handler can record it while
+/// the metrics read it.
+#[derive(Debug, Clone, Default)]
+pub struct ConsumerLag {
+    last_event: Arc<Mutex<Option<ProcessedEvent>>>,
+}
+
+/// The event processed last, along with its lag.
+#[derive(Debug, Clone, Copy)]
+struct ProcessedEvent {
+    occurred_at: NaiveDateTime,
+    lag: Duration,
+}
+
+impl ConsumerLag {
+    /// Records that an event was just processed.
+    ///
+    /// # Arguments
+    /// * `occurred_at` - When the event happened (UTC)
+    pub fn record(&self, occurred_at: NaiveDateTime) {
+        self.record_at(occurred_at, chrono::Utc::now().naive_utc());
+    }
+
+    /// Records that an event was processed at a given time. Events stamped
+    /// ahead of `processed_at`, by a worker whose clock runs fast, count as no
+    /// lag.
+    ///
+    /// # Arguments
+    /// * `occurred_at` - When the event happened (UTC)
+    /// * `processed_at` - When the event was processed (UTC)
+    pub fn record_at(&self, occurred_at: NaiveDateTime, processed_at: NaiveDateTime) {
+        *self.last_event.lock().expect("Consumer lag lock poisoned") = Some(ProcessedEvent {
+            occurred_at,
+            lag: (processed_at - occurred_at).to_std().unwrap_or_default(),
+        });
+    }
+
+    /// When the event processed last happened (UTC), if any was processed.
+    pub fn last_event_at(&self) -> Option<NaiveDateTime> {
+        self.last_event().map(|event| event.occurred_at)
+    }
+
+    /// The lag of the event processed last, `None` until an event was
+    /// processed.
+    pub fn lag(&self) -> Option<Duration> {
+        self.last_event().map(|event| event.lag)
+    }
+
+    fn last_event(&self) -> Option<ProcessedEvent> {
+        *self.last_event.lock().expect("Consumer lag lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consumer_lag() {
+        let lag = ConsumerLag::default();
+        let now = chrono::Utc::now().naive_utc();
+        assert_eq!(lag.lag(), None);
+
+        // Clones share the measurement
+        lag.clone()
+            .record_at(now - chrono::Duration::seconds(30), now);
+        assert_eq!(lag.lag(), Some(Duration::from_secs(30)));
+
+        // The event processed last counts, even if an earlier one was newer
+        lag.record_at(now - chrono::Duration::seconds(45), now);
+        assert_eq!(lag.lag(), Some(Duration::from_secs(45)));
+
+        lag.record_at(now + chrono::Duration::seconds(5), now);
+        assert_eq!(lag.lag(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_consumer_lag_does_not_grow_while_idle() {
+        let lag = ConsumerLag::default();
+        let an_hour_ago = chrono::Utc::now().naive_utc() - chrono::Duration::hours(1);
+
+        // Processed right away an hour ago, and nothing since
+        lag.record_at(an_hour_ago, an_hour_ago + chrono::Duration::seconds(2));
+        assert_eq!(lag.last_event_at(), Some(an_hour_ago));
+        assert_eq!(lag.lag(), Some(Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,179 @@
+use opentelemetry::metrics::{Counter, Histogram, Meter, ObservableGauge};
+use opentelemetry::{global, KeyValue};
+use std::time::Duration;
+
+use crate::task_event_consumer::lag::ConsumerLag;
+
+/// Name of the meter every consumer instrument is registered on
+const METER_NAME: &str = "relay.task_event_consumer";
+
+/// Step of the consume loop an error happened in, recorded as the
+/// `error.kind` attribute of the `consume_errors` counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsumeErrorKind {
+    Receive,
+    Parse,
+    Handler,
+}
+
+impl From<ConsumeErrorKind> for &str {
+    fn from(value: ConsumeErrorKind) -> Self {
+        match value {
+            ConsumeErrorKind::Receive => "receive",
+            ConsumeErrorKind::Parse => "parse",
+            ConsumeErrorKind::Handler => "handler",
+        }
+    }
+}
+
+/// OpenTelemetry instruments recorded by the task event consumer. Every
+/// measurement is tagged with the queue it comes from.
+///
+/// # Fields
+/// * `messages_consumed` - Deliveries received from the broker
+/// * `consume_errors` - Deliveries that could not be received, parsed or handled
+/// * `ack_failures` - Acknowledgements the broker did not accept
+/// * `reconnects` - Reconnections to the broker, to alert on flapping
+/// * `handler_latency` - Time spent handling a batch of events
+/// * `lag` - Time the event processed last waited to be processed, observed on
+///   every export. Not tagged with a queue, as the handler is shared.
+#[derive(Clone)]
+pub struct ConsumerMetrics {
+    messages_consumed: Counter<u64>,
+    consume_errors: Counter<u64>,
+    ack_failures: Counter<u64>,
+    reconnects: Counter<u64>,
+    handler_latency: Histogram<f64>,
+    _lag: ObservableGauge<f64>,
+}
+
+impl ConsumerMetrics {
+    /// Registers the instruments on the global meter provider, which must be
+    /// installed beforehand for the measurements to be exported.
+    ///
+    /// # Arguments
+    ///
+    /// * `lag` - The lag of the event handler, observed by the lag gauge
+    pub fn new(lag: ConsumerLag) -> Self {
+        Self::from_meter(&global::meter(METER_NAME), lag)
+    }
+
+    /// Registers the instruments on a given meter.
+    ///
+    /// # Arguments
+    ///
+    /// * `meter` - The meter to register the instruments on
+    /// * `lag` - The lag of the event handler, observed by the lag gauge
+    pub fn from_meter(meter: &Meter, lag: ConsumerLag) -> Self {
+        Self {
+            messages_consumed: meter
+                .u64_counter("relay.consumer.messages_consumed")
+                .with_description("Task event deliveries received from the broker")
+                .build(),
+            consume_errors: meter
+                .u64_counter("relay.consumer.consume_errors")
+                .with_description("Task event deliveries that could not be consumed")
+                .build(),
+            ack_failures: meter
+                .u64_counter("relay.consumer.ack_failures")
+                .with_description("Acknowledgements rejected by the broker")
+                .build(),
+            reconnects: meter
+                .u64_counter("relay.consumer.reconnects")
+                .with_description("Reconnections to the broker")
+                .build(),
+            handler_latency: meter
+                .f64_histogram("relay.consumer.handler_latency")
+                .with_description("Time spent handling a batch of task events")
+                .with_unit("ms")
+                .build(),
+            _lag: meter
+                .f64_observable_gauge("relay.consumer.lag")
+                .with_description("Time the task event processed last waited to be processed")
+                .with_unit("s")
+                .with_callback(move |observer| {
+                    if let Some(lag) = lag.lag() {
+                        observer.observe(lag.as_secs_f64(), &[]);
+                    }
+                })
+                .build(),
+        }
+    }
+
+    pub fn record_consumed(&self, queue: &str, count: u64) {
+        self.messages_consumed.add(count, &[queue_attribute(queue)]);
+    }
+
+    pub fn record_consume_error(&self, queue: &str, kind: ConsumeErrorKind, count: u64) {
+        let kind: &str = kind.into();
+        self.consume_errors.add(
+            count,
+            &[queue_attribute(queue), KeyValue::new("error.kind", kind)],
+        );
+    }
+
+    pub fn record_ack_failure(&self, queue: &str) {
+        self.ack_failures.add(1, &[queue_attribute(queue)]);
+    }
+
+    pub fn record_reconnect(&self, queue: &str) {
+        self.reconnects.add(1, &[queue_attribute(queue)]);
+    }
+
+    pub fn record_handler_latency(&self, queue: &str, latency: Duration) {
+        self.handler_latency
+            .record(latency.as_secs_f64() * 1000.0, &[queue_attribute(queue)]);
+    }
+}
+
+fn queue_attribute(queue: &str) -> KeyValue {
+    KeyValue::new("queue", queue.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_sdk::metrics::{InMemoryMetricExporter, SdkMeterProvider};
+
+    #[test]
+    fn test_consumer_metrics_are_exported() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(exporter.clone())
+            .build();
+        let lag = ConsumerLag::default();
+        let metrics = ConsumerMetrics::from_meter(&provider.meter(METER_NAME), lag.clone());
+
+        metrics.record_consumed("tacoq_relay_queue", 3);
+        metrics.record_consume_error("tacoq_relay_queue", ConsumeErrorKind::Parse, 1);
+        metrics.record_ack_failure("tacoq_relay_queue");
+        metrics.record_reconnect("tacoq_relay_queue");
+        metrics.record_handler_latency("tacoq_relay_queue", Duration::from_millis(5));
+        lag.record(chrono::Utc::now().naive_utc());
+        provider.force_flush().unwrap();
+
+        let mut names: Vec<String> = exporter
+            .get_finished_metrics()
+            .unwrap()
+            .iter()
+            .flat_map(|resource| &resource.scope_metrics)
+            .flat_map(|scope| &scope.metrics)
+            .map(|metric| metric.name.to_string())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        assert_eq!(
+            names,
+            vec![
+                "relay.consumer.ack_failures",
+                "relay.consumer.consume_errors",
+                "relay.consumer.handler_latency",
+                "relay.consumer.lag",
+                "relay.consumer.messages_consumed",
+                "relay.consumer.reconnects",
+            ]
+        );
+    }
+}
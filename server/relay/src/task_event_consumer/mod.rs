@@ -1,7 +1,17 @@
+mod circuit_breaker;
+mod codec;
 mod consumer;
+mod dedup;
 mod event_parsing;
 mod handler;
+mod lag;
+mod metrics;
 
+pub use circuit_breaker::CircuitBreakerSettings;
+pub use codec::{AvroCodec, MessageCodec, WireFormat};
 pub use consumer::{
-    RabbitMQTaskEventConsumer, RabbitMQTaskEventCore, TaskEventConsumer, TaskEventCore,
+    AckMode, BrokerTlsConfig, ConsumerSettings, EventRouting, QueueArguments, QueueOverflow,
+    QueueType, RabbitMQConnection, RabbitMQTaskEventConsumer, TaskEventConsumer, TaskEventCore,
 };
+pub use event_parsing::Event;
+pub use lag::ConsumerLag;
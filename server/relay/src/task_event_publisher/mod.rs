@@ -0,0 +1,5 @@
+mod publisher_trait;
+mod rabbitmq;
+
+pub use publisher_trait::*;
+pub use rabbitmq::*;
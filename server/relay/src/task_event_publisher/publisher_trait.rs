@@ -0,0 +1,32 @@
+use crate::task_event_consumer::Event;
+use futures::future::BoxFuture;
+use std::error::Error;
+
+/// Routing key of the assignments of a worker kind. Worker queues are bound
+/// to the task exchange with it, and the relay queue with a wildcard.
+///
+/// # Arguments
+///
+/// * `worker_kind` - The worker kind the assignment is for
+pub fn worker_routing_key(worker_kind: &str) -> String {
+    format!("tasks.{}", worker_kind)
+}
+
+/// A Task Event Publisher emits task events to the broker, so the relay can
+/// re-publish events (e.g. to retry an assignment).
+///
+/// The events are encoded exactly like the ones the task event consumer
+/// decodes, so a published event can be consumed by any TacoQ service.
+pub trait TaskEventPublisher: Send + Sync {
+    /// Publishes an event to the broker
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to publish
+    /// * `routing_key` - The routing key the event is published with
+    fn publish<'a>(
+        &'a self,
+        event: &'a Event,
+        routing_key: &'a str,
+    ) -> BoxFuture<'a, Result<(), Box<dyn Error + Send + Sync>>>;
+}
@@ -0,0 +1,364 @@
+use crate::task_event_consumer::{BrokerTlsConfig, Event, MessageCodec, RabbitMQConnection};
+use crate::task_event_publisher::TaskEventPublisher;
+use backoff::ExponentialBackoffBuilder;
+use futures::future::BoxFuture;
+use lapin::options::{BasicPublishOptions, ExchangeDeclareOptions};
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{BasicProperties, Channel, ExchangeKind};
+use std::error::Error;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+/// Delay before the first retry of a failed publish
+const INITIAL_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+/// Longest delay between two retries of a failed publish
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// AMQP delivery mode of messages the broker drops on restart
+const TRANSIENT_DELIVERY_MODE: u8 = 1;
+/// AMQP delivery mode of messages the broker writes to disk, so they survive
+/// a restart when they sit in a durable queue
+const PERSISTENT_DELIVERY_MODE: u8 = 2;
+
+/// Parses the kind of the exchange events are published to. Only the
+/// kinds built into RabbitMQ are accepted.
+pub fn parse_exchange_kind(kind: &str) -> Result<ExchangeKind, String> {
+    match kind {
+        "direct" => Ok(ExchangeKind::Direct),
+        "fanout" => Ok(ExchangeKind::Fanout),
+        "headers" => Ok(ExchangeKind::Headers),
+        "topic" => Ok(ExchangeKind::Topic),
+        _ => Err(format!(
+            "Unknown exchange kind: {} (expected direct, fanout, headers or topic)",
+            kind
+        )),
+    }
+}
+
+/// Builds the properties of a published event. The `message_type` header is
+/// what consumers use to pick the schema of the payload, and the content type
+/// tells them how it was encoded. Assignments keep their priority, which the
+/// worker queues order by.
+///
+/// # Arguments
+///
+/// * `event` - The published event
+/// * `codec` - The codec the event is encoded with
+/// * `persistent` - Whether the broker keeps the message across restarts
+fn event_properties(event: &Event, codec: &dyn MessageCodec, persistent: bool) -> BasicProperties {
+    let message_type: &str = event.event_type().into();
+
+    let mut headers = FieldTable::default();
+    headers.insert(
+        "message_type".into(),
+        AMQPValue::LongString(message_type.to_string().into()),
+    );
+
+    let delivery_mode = if persistent {
+        PERSISTENT_DELIVERY_MODE
+    } else {
+        TRANSIENT_DELIVERY_MODE
+    };
+    let properties = BasicProperties::default()
+        .with_headers(headers)
+        .with_content_type(codec.content_type().into())
+        .with_delivery_mode(delivery_mode);
+
+    match event {
+        Event::Assignment(assignment) => {
+            properties.with_priority(assignment.priority.clamp(0, u8::MAX as i32) as u8)
+        }
+        _ => properties,
+    }
+}
+
+/// Runs a publish, retrying it with exponential backoff until it succeeds or
+/// `max_attempts` attempts failed. The error of the last attempt is returned.
+///
+/// # Arguments
+///
+/// * `max_attempts` - Attempts made before giving up, at least one is made
+/// * `attempt` - Creates the future performing one attempt
+async fn with_publish_retry<F, Fut>(
+    max_attempts: u32,
+    mut attempt: F,
+) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), Box<dyn Error + Send + Sync>>>,
+{
+    let backoff = ExponentialBackoffBuilder::new()
+        .with_initial_interval(INITIAL_RETRY_INTERVAL)
+        .with_max_interval(MAX_RETRY_INTERVAL)
+        .with_max_elapsed_time(None)
+        .build();
+
+    let mut attempts = 0;
+    backoff::future::retry(backoff, || {
+        attempts += 1;
+        let current = attempts;
+        let publish = attempt();
+        async move {
+            publish.await.map_err(|e| {
+                if current < max_attempts {
+                    warn!(attempt = current, max_attempts, error = %e, "Failed to publish task event, retrying");
+                    backoff::Error::transient(e)
+                } else {
+                    backoff::Error::permanent(e)
+                }
+            })
+        }
+    })
+    .await
+}
+
+/// A publisher that emits task events to RabbitMQ.
+pub struct RabbitMQTaskEventPublisher {
+    connection: Mutex<RabbitMQConnection>,
+    channel: Mutex<Option<Channel>>,
+    exchange: String,
+    exchange_kind: ExchangeKind,
+    codec: Arc<dyn MessageCodec>,
+    max_attempts: u32,
+    persistent: bool,
+}
+
+impl RabbitMQTaskEventPublisher {
+    /// Creates a new RabbitMQ task event publisher. The channel is opened
+    /// lazily on the first publish, declaring the exchange. A broker that
+    /// already has the exchange with another kind refuses the declaration.
+    ///
+    /// # Arguments
+    ///
+    /// * `url_string` - The broker URL
+    /// * `tls` - The TLS settings used for `amqps` URLs
+    /// * `exchange` - The exchange events are published to
+    /// * `exchange_kind` - The kind the exchange is declared with
+    /// * `codec` - The codec events are encoded with
+    /// * `max_attempts` - Attempts made to publish an event before giving up
+    /// * `persistent` - Whether events are published as persistent messages,
+    ///   which survive a broker restart at the cost of throughput
+    pub async fn new(
+        url_string: &str,
+        tls: &BrokerTlsConfig,
+        exchange: &str,
+        exchange_kind: ExchangeKind,
+        codec: Arc<dyn MessageCodec>,
+        max_attempts: u32,
+        persistent: bool,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let connection = RabbitMQConnection::new(url_string, tls).await?;
+        info!(
+            exchange = %exchange,
+            exchange_kind = ?exchange_kind,
+            max_attempts,
+            persistent,
+            "RabbitMQ task event publisher created"
+        );
+        Ok(Self {
+            connection: Mutex::new(connection),
+            channel: Mutex::new(None),
+            exchange: exchange.to_string(),
+            exchange_kind,
+            codec,
+            max_attempts,
+            persistent,
+        })
+    }
+
+    /// Returns a connected channel, creating a new one (and reconnecting if
+    /// needed) when the current one is missing or dead.
+    async fn channel(&self) -> Result<Channel, Box<dyn Error + Send + Sync>> {
+        let mut channel = self.channel.lock().await;
+        if let Some(current) = channel.as_ref() {
+            if current.status().connected() {
+                return Ok(current.clone());
+            }
+            warn!("Publisher channel is no longer connected, recreating it");
+        }
+
+        let mut connection = self.connection.lock().await;
+        let new_channel = match connection.create_channel().await {
+            Ok(ch) => ch,
+            Err(e) => {
+                warn!(error = %e, "Failed to create publisher channel, reconnecting");
+                *connection = connection.reconnect().await?;
+                connection.create_channel().await?
+            }
+        };
+
+        new_channel
+            .exchange_declare(
+                &self.exchange,
+                self.exchange_kind.clone(),
+                ExchangeDeclareOptions {
+                    durable: true,
+                    ..ExchangeDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
+        *channel = Some(new_channel.clone());
+        Ok(new_channel)
+    }
+
+    /// Makes a single attempt at publishing an encoded event. On failure the
+    /// channel is dropped, so the next attempt opens a fresh one.
+    async fn publish_once(
+        &self,
+        payload: &[u8],
+        properties: BasicProperties,
+        routing_key: &str,
+        event: &Event,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let channel = self.channel().await?;
+
+        debug!(
+            exchange = %self.exchange,
+            routing_key = %routing_key,
+            event_type = ?event.event_type(),
+            "Publishing task event"
+        );
+        let result = async {
+            channel
+                .basic_publish(
+                    &self.exchange,
+                    routing_key,
+                    BasicPublishOptions::default(),
+                    payload,
+                    properties,
+                )
+                .await?
+                .await
+        }
+        .await;
+
+        if let Err(e) = result {
+            error!(error = %e, routing_key = %routing_key, "Failed to publish task event");
+            *self.channel.lock().await = None;
+            return Err(Box::new(e));
+        }
+        Ok(())
+    }
+}
+
+impl TaskEventPublisher for RabbitMQTaskEventPublisher {
+    fn publish<'a>(
+        &'a self,
+        event: &'a Event,
+        routing_key: &'a str,
+    ) -> BoxFuture<'a, Result<(), Box<dyn Error + Send + Sync>>> {
+        Box::pin(async move {
+            let payload = self.codec.encode(event)?;
+            let properties = event_properties(event, self.codec.as_ref(), self.persistent);
+
+            with_publish_retry(self.max_attempts, || {
+                self.publish_once(&payload, properties.clone(), routing_key, event)
+            })
+            .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TaskRunningUpdate;
+    use crate::task_event_consumer::AvroCodec;
+    use chrono::Local;
+    use lapin::acker::Acker;
+    use lapin::message::Delivery;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_published_event_can_be_consumed() {
+        let running = TaskRunningUpdate::new(
+            Uuid::new_v4(),
+            Local::now().naive_local(),
+            "worker-1".to_string(),
+        );
+        let event = Event::Running(running.clone());
+
+        let delivery = Delivery {
+            delivery_tag: 0,
+            exchange: "".to_string().into(),
+            routing_key: "".to_string().into(),
+            data: AvroCodec.encode(&event).unwrap(),
+            redelivered: false,
+            properties: event_properties(&event, &AvroCodec, true),
+            acker: Acker::default(),
+        };
+
+        match Event::try_from(delivery).unwrap() {
+            Event::Running(parsed) => {
+                assert_eq!(parsed.id, running.id);
+                assert_eq!(parsed.executed_by, running.executed_by);
+            }
+            _ => panic!("Expected Running event"),
+        }
+    }
+
+    #[test]
+    fn test_event_properties_delivery_mode() {
+        let event = Event::Running(TaskRunningUpdate::new(
+            Uuid::new_v4(),
+            Local::now().naive_local(),
+            "worker-1".to_string(),
+        ));
+
+        let persistent = event_properties(&event, &AvroCodec, true);
+        assert_eq!(persistent.delivery_mode(), &Some(PERSISTENT_DELIVERY_MODE));
+
+        let transient = event_properties(&event, &AvroCodec, false);
+        assert_eq!(transient.delivery_mode(), &Some(TRANSIENT_DELIVERY_MODE));
+    }
+
+    #[test]
+    fn test_parse_exchange_kind() {
+        assert_eq!(parse_exchange_kind("topic"), Ok(ExchangeKind::Topic));
+        assert_eq!(parse_exchange_kind("fanout"), Ok(ExchangeKind::Fanout));
+
+        let err = parse_exchange_kind("x-delayed-message").unwrap_err();
+        assert!(
+            err.contains("Unknown exchange kind: x-delayed-message"),
+            "{}",
+            err
+        );
+        assert!(parse_exchange_kind("Topic").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_publish_retry_recovers_from_failed_attempt() {
+        let attempts = AtomicU32::new(0);
+
+        let result = with_publish_retry(3, || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err("channel closed".into()),
+                _ => Ok(()),
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_publish_retry_gives_up_after_max_attempts() {
+        let attempts = AtomicU32::new(0);
+
+        let result = with_publish_retry(2, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err("channel closed".into())
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "channel closed");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,12 +1,28 @@
 #[cfg(test)]
 pub mod test {
     use axum_test::TestServer;
+    use futures::future::BoxFuture;
     use sqlx::PgPool;
+    use std::error::Error;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use uuid::Uuid;
 
+    use crate::constants::DEFAULT_RELAY_QUEUE;
+    use crate::health_probe::{BrokerHealthSource, Readiness};
     use crate::lifecycle::setup_app;
+    use crate::models::QueueDepth;
+    use crate::repo::{PgRepositoryCore, TaskRepository};
+    use crate::server::RequestLimits;
+    use crate::task_event_consumer::Event;
+    use crate::task_event_publisher::TaskEventPublisher;
 
     pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!();
 
+    /// Admin token accepted by the test server
+    pub static TEST_ADMIN_TOKEN: &str = "test-admin-token";
+
     /// Initializes a test logger with debug level output that writes to the test writer.
     /// This should be called at the start of test modules to enable logging during tests.
     pub fn init_test_logger() {
@@ -19,7 +35,92 @@ pub mod test {
     /// Creates and returns a test server instance with the application router.
     /// This provides a way to make test HTTP requests against the API endpoints.
     pub async fn get_test_server(db_pools: PgPool) -> TestServer {
-        let app = setup_app(&db_pools, None).await;
+        let app = setup_app(
+            &db_pools,
+            TaskRepository::new(PgRepositoryCore::new(db_pools.clone())),
+            None,
+            None,
+            &RequestLimits::default(),
+            Some(TEST_ADMIN_TOKEN.to_string()),
+            false,
+            None,
+            Readiness::new(0),
+        )
+        .await;
         TestServer::new(app).unwrap()
     }
+
+    /// Publisher keeping the routing key and task of every published
+    /// assignment instead of sending it to a broker. With `fail_after` set,
+    /// the assignments published after that many fail, like when the broker
+    /// goes away.
+    #[derive(Default)]
+    pub struct RecordingPublisher {
+        pub published: Mutex<Vec<(String, Uuid)>>,
+        pub fail_after: Option<usize>,
+    }
+
+    impl TaskEventPublisher for RecordingPublisher {
+        fn publish<'a>(
+            &'a self,
+            event: &'a Event,
+            routing_key: &'a str,
+        ) -> BoxFuture<'a, Result<(), Box<dyn Error + Send + Sync>>> {
+            let Event::Assignment(assignment) = event else {
+                panic!("Expected an assignment, got {:?}", event.event_type());
+            };
+            let mut published = self.published.lock().unwrap();
+            if self
+                .fail_after
+                .is_some_and(|count| published.len() >= count)
+            {
+                return Box::pin(async { Err("Broker unavailable".into()) });
+            }
+            published.push((routing_key.to_string(), assignment.id));
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    /// Broker whose connection can be dropped and re-established at will.
+    /// While connected, the default relay queue holds three messages. The
+    /// consumer lag is whatever `lag` is set to.
+    #[derive(Default)]
+    pub struct StubBroker {
+        pub connected: AtomicBool,
+        pub lag: Option<Duration>,
+    }
+
+    impl StubBroker {
+        fn connection(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            if self.connected.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("connection closed".into())
+            }
+        }
+    }
+
+    impl BrokerHealthSource for StubBroker {
+        fn check_broker(&self) -> BoxFuture<'_, Result<(), Box<dyn Error + Send + Sync>>> {
+            Box::pin(async move { self.connection() })
+        }
+
+        fn queue_depths(
+            &self,
+        ) -> BoxFuture<'_, Result<Vec<QueueDepth>, Box<dyn Error + Send + Sync>>> {
+            Box::pin(async move {
+                self.connection()?;
+                Ok(vec![QueueDepth {
+                    queue: DEFAULT_RELAY_QUEUE.to_string(),
+                    messages: 3,
+                    consumers: 1,
+                    dead_letter_messages: 0,
+                }])
+            })
+        }
+
+        fn consumer_lag(&self) -> Option<Duration> {
+            self.lag
+        }
+    }
 }